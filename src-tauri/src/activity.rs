@@ -0,0 +1,479 @@
+use crate::database::DatabaseConnection;
+use crate::error::AppError;
+use bson::{doc, Bson, Document};
+use chrono::{SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+pub const DEFAULT_POLL_INTERVAL_MS: u64 = 2000;
+// Polling faster than this puts noticeable load on the server
+pub const MIN_POLL_INTERVAL_MS: u64 = 500;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionInfo {
+    // pid, processlist id or opid, depending on the backend
+    pub id: String,
+    pub user: Option<String>,
+    pub database: Option<String>,
+    pub client: Option<String>,
+    pub application: Option<String>,
+    pub state: Option<String>,
+    pub query: Option<String>,
+    pub query_start: Option<String>,
+    pub duration_ms: Option<i64>,
+    pub wait_event: Option<String>,
+    // Sessions holding locks this one waits for
+    pub blocked_by: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LockInfo {
+    pub session_id: String,
+    pub lock_type: String,
+    pub mode: Option<String>,
+    pub object: Option<String>,
+    pub granted: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlockingChain {
+    // Session at the head of the chain, not itself waiting
+    pub blocker: String,
+    // Every session waiting on the blocker, directly or through others, nearest first
+    pub blocked: Vec<String>,
+    // The sessions wait on each other in a cycle
+    pub deadlock: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActivitySnapshot {
+    pub captured_at: String,
+    pub sessions: Vec<SessionInfo>,
+    pub locks: Vec<LockInfo>,
+    pub blocking_chains: Vec<BlockingChain>,
+    // Views that couldn't be read, e.g. for lack of privileges
+    pub warnings: Vec<String>,
+}
+
+fn text(value: Option<&Value>) -> Option<String> {
+    match value? {
+        Value::Null => None,
+        Value::String(s) => Some(s.clone()),
+        other => Some(other.to_string()),
+    }
+}
+
+fn number(value: Option<&Value>) -> Option<i64> {
+    match value? {
+        Value::Number(n) => n.as_i64().or_else(|| n.as_f64().map(|f| f as i64)),
+        Value::String(s) => s.trim().parse::<f64>().ok().map(|f| f as i64),
+        _ => None,
+    }
+}
+
+fn boolean(value: Option<&Value>) -> bool {
+    match value {
+        Some(Value::Bool(b)) => *b,
+        Some(Value::Number(n)) => n.as_i64() == Some(1),
+        Some(Value::String(s)) => matches!(s.as_str(), "t" | "true" | "1"),
+        _ => false,
+    }
+}
+
+async fn rows(connection: &DatabaseConnection, query: &str, what: &str, warnings: &mut Vec<String>) -> Vec<Vec<Value>> {
+    match connection.execute_query(query).await {
+        Ok(result) => result.rows,
+        Err(e) => {
+            warnings.push(format!("Couldn't read {}: {}", what, e));
+            Vec::new()
+        }
+    }
+}
+
+async fn postgres_activity(connection: &DatabaseConnection, include_idle: bool, warnings: &mut Vec<String>) -> (Vec<SessionInfo>, Vec<LockInfo>) {
+    let query = format!(
+        "SELECT pid::text, usename::text, datname::text, client_addr::text, application_name, state, query, \
+         to_char(query_start AT TIME ZONE 'UTC', 'YYYY-MM-DD\"T\"HH24:MI:SS\"Z\"'), \
+         (EXTRACT(EPOCH FROM (clock_timestamp() - query_start)) * 1000)::bigint, \
+         wait_event_type || ': ' || wait_event, array_to_string(pg_blocking_pids(pid), ',') \
+         FROM pg_stat_activity \
+         WHERE pid <> pg_backend_pid() AND backend_type = 'client backend'{} \
+         ORDER BY query_start NULLS LAST",
+        if include_idle { "" } else { " AND state <> 'idle'" }
+    );
+    let sessions = rows(connection, &query, "pg_stat_activity", warnings)
+        .await
+        .into_iter()
+        .map(|r| SessionInfo {
+            id: text(r.first()).unwrap_or_default(),
+            user: text(r.get(1)),
+            database: text(r.get(2)),
+            client: text(r.get(3)),
+            application: text(r.get(4)).filter(|a| !a.is_empty()),
+            state: text(r.get(5)),
+            query: text(r.get(6)),
+            query_start: text(r.get(7)),
+            duration_ms: number(r.get(8)),
+            wait_event: text(r.get(9)),
+            blocked_by: text(r.get(10))
+                .map(|ids| ids.split(',').filter(|id| !id.is_empty()).map(|id| id.to_string()).collect())
+                .unwrap_or_default(),
+        })
+        .collect();
+
+    let locks = rows(
+        connection,
+        "SELECT l.pid::text, l.locktype, l.mode, l.granted, \
+         COALESCE(c.relname::text, l.transactionid::text, l.virtualxid) \
+         FROM pg_locks l LEFT JOIN pg_class c ON c.oid = l.relation \
+         WHERE l.pid <> pg_backend_pid() AND l.locktype <> 'virtualxid' \
+         ORDER BY l.granted, l.pid",
+        "pg_locks",
+        warnings,
+    )
+    .await
+    .into_iter()
+    .map(|r| LockInfo {
+        session_id: text(r.first()).unwrap_or_default(),
+        lock_type: text(r.get(1)).unwrap_or_default(),
+        mode: text(r.get(2)),
+        granted: boolean(r.get(3)),
+        object: text(r.get(4)),
+    })
+    .collect();
+
+    (sessions, locks)
+}
+
+async fn mysql_activity(connection: &DatabaseConnection, include_idle: bool, warnings: &mut Vec<String>) -> (Vec<SessionInfo>, Vec<LockInfo>) {
+    let query = format!(
+        "SELECT CAST(ID AS CHAR), USER, HOST, DB, COMMAND, STATE, INFO, CAST(TIME AS SIGNED) * 1000 \
+         FROM information_schema.PROCESSLIST WHERE ID <> CONNECTION_ID(){} ORDER BY TIME DESC",
+        if include_idle { "" } else { " AND COMMAND NOT IN ('Sleep', 'Daemon')" }
+    );
+    let mut sessions: Vec<SessionInfo> = rows(connection, &query, "the process list", warnings)
+        .await
+        .into_iter()
+        .map(|r| SessionInfo {
+            id: text(r.first()).unwrap_or_default(),
+            user: text(r.get(1)),
+            client: text(r.get(2)),
+            database: text(r.get(3)),
+            application: None,
+            state: match (text(r.get(4)), text(r.get(5)).filter(|s| !s.is_empty())) {
+                (Some(command), Some(state)) => Some(format!("{}: {}", command, state)),
+                (command, state) => command.or(state),
+            },
+            query: text(r.get(6)),
+            query_start: None,
+            duration_ms: number(r.get(7)),
+            wait_event: None,
+            blocked_by: Vec::new(),
+        })
+        .collect();
+
+    // performance_schema lock views exist from MySQL 8.0
+    let waits = rows(
+        connection,
+        "SELECT CAST(rt.PROCESSLIST_ID AS CHAR), CAST(bt.PROCESSLIST_ID AS CHAR) \
+         FROM performance_schema.data_lock_waits w \
+         JOIN performance_schema.threads rt ON rt.THREAD_ID = w.REQUESTING_THREAD_ID \
+         JOIN performance_schema.threads bt ON bt.THREAD_ID = w.BLOCKING_THREAD_ID",
+        "lock waits (performance_schema.data_lock_waits)",
+        warnings,
+    )
+    .await;
+    for wait in waits {
+        let (Some(waiting), Some(blocking)) = (text(wait.first()), text(wait.get(1))) else { continue };
+        if let Some(session) = sessions.iter_mut().find(|s| s.id == waiting) {
+            if !session.blocked_by.contains(&blocking) {
+                session.blocked_by.push(blocking);
+            }
+        }
+    }
+
+    let locks = rows(
+        connection,
+        "SELECT CAST(t.PROCESSLIST_ID AS CHAR), l.LOCK_TYPE, l.LOCK_MODE, l.LOCK_STATUS = 'GRANTED', \
+         CONCAT_WS('.', l.OBJECT_SCHEMA, l.OBJECT_NAME, l.INDEX_NAME) \
+         FROM performance_schema.data_locks l JOIN performance_schema.threads t ON t.THREAD_ID = l.THREAD_ID \
+         WHERE t.PROCESSLIST_ID IS NOT NULL AND t.PROCESSLIST_ID <> CONNECTION_ID() \
+         ORDER BY l.LOCK_STATUS, t.PROCESSLIST_ID",
+        "locks (performance_schema.data_locks)",
+        warnings,
+    )
+    .await
+    .into_iter()
+    .map(|r| LockInfo {
+        session_id: text(r.first()).unwrap_or_default(),
+        lock_type: text(r.get(1)).unwrap_or_default(),
+        mode: text(r.get(2)),
+        granted: boolean(r.get(3)),
+        object: text(r.get(4)),
+    })
+    .collect();
+
+    (sessions, locks)
+}
+
+fn bson_text(value: Option<&Bson>) -> Option<String> {
+    match value? {
+        Bson::Null => None,
+        Bson::String(s) => Some(s.clone()),
+        other => Some(other.to_string()),
+    }
+}
+
+async fn mongodb_activity(client: &mongodb::Client, include_idle: bool, warnings: &mut Vec<String>) -> (Vec<SessionInfo>, Vec<LockInfo>) {
+    let mut command = doc! { "currentOp": 1 };
+    if include_idle {
+        command.insert("$all", true);
+    }
+    let result = match client.database("admin").run_command(command, None).await {
+        Ok(result) => result,
+        Err(e) => {
+            warnings.push(format!("Couldn't run currentOp: {}", e));
+            return (Vec::new(), Vec::new());
+        }
+    };
+
+    let operations: Vec<Document> = result
+        .get_array("inprog")
+        .map(|ops| ops.iter().filter_map(|op| op.as_document().cloned()).collect())
+        .unwrap_or_default();
+    let mut sessions = Vec::new();
+    let mut locks = Vec::new();
+    for op in operations {
+        // Idle connections have no opid
+        let Some(id) = bson_text(op.get("opid")) else { continue };
+        let waiting = op.get_bool("waitingForLock").unwrap_or(false);
+        let user = op
+            .get_array("effectiveUsers")
+            .ok()
+            .and_then(|users| users.first())
+            .and_then(|user| user.as_document())
+            .and_then(|user| user.get_str("user").ok())
+            .map(|user| user.to_string());
+        let duration_ms = op
+            .get("microsecs_running")
+            .and_then(|v| match v {
+                Bson::Int64(v) => Some(*v / 1000),
+                Bson::Int32(v) => Some(*v as i64 / 1000),
+                _ => None,
+            });
+
+        if let Ok(held) = op.get_document("locks") {
+            for (resource, mode) in held {
+                locks.push(LockInfo {
+                    session_id: id.clone(),
+                    lock_type: resource.clone(),
+                    mode: bson_text(Some(mode)),
+                    object: bson_text(op.get("ns")),
+                    granted: !waiting,
+                });
+            }
+        }
+
+        sessions.push(SessionInfo {
+            id,
+            user,
+            database: bson_text(op.get("ns")).map(|ns| ns.split('.').next().unwrap_or_default().to_string()),
+            client: bson_text(op.get("client")),
+            application: bson_text(op.get("appName")),
+            state: Some(if op.get_bool("active").unwrap_or(false) { "active" } else { "idle" }.to_string()),
+            query: op
+                .get_document("command")
+                .ok()
+                .map(|c| c.to_string())
+                .or_else(|| bson_text(op.get("desc"))),
+            query_start: bson_text(op.get("currentOpTime")),
+            duration_ms,
+            wait_event: waiting.then(|| "waiting for lock".to_string()),
+            // currentOp doesn't say who holds the lock
+            blocked_by: Vec::new(),
+        });
+    }
+    (sessions, locks)
+}
+
+// Chains rooted at sessions that block others without waiting themselves
+fn blocking_chains(sessions: &[SessionInfo]) -> Vec<BlockingChain> {
+    let mut waiters: HashMap<&str, Vec<&str>> = HashMap::new();
+    for session in sessions {
+        for blocker in &session.blocked_by {
+            waiters.entry(blocker.as_str()).or_default().push(session.id.as_str());
+        }
+    }
+    let waiting: HashSet<&str> = sessions.iter().filter(|s| !s.blocked_by.is_empty()).map(|s| s.id.as_str()).collect();
+
+    let collect = |root: &str| -> Vec<String> {
+        let mut blocked = Vec::new();
+        let mut seen = HashSet::from([root]);
+        let mut queue: VecDeque<&str> = VecDeque::from([root]);
+        while let Some(current) = queue.pop_front() {
+            for &waiter in waiters.get(current).into_iter().flatten() {
+                if seen.insert(waiter) {
+                    blocked.push(waiter.to_string());
+                    queue.push_back(waiter);
+                }
+            }
+        }
+        blocked
+    };
+
+    let mut roots: Vec<&str> = waiters.keys().copied().filter(|id| !waiting.contains(id)).collect();
+    roots.sort();
+    let mut chains: Vec<BlockingChain> = roots
+        .into_iter()
+        .map(|root| BlockingChain {
+            blocker: root.to_string(),
+            blocked: collect(root),
+            deadlock: false,
+        })
+        .collect();
+
+    // Waiting sessions no root reaches are stuck in a cycle
+    let mut reached: HashSet<String> = chains.iter().flat_map(|c| c.blocked.iter().cloned()).collect();
+    let mut cyclic: Vec<&str> = waiting.iter().copied().filter(|id| !reached.contains(*id)).collect();
+    cyclic.sort();
+    for id in cyclic {
+        if reached.contains(id) {
+            continue;
+        }
+        let blocked: Vec<String> = collect(id).into_iter().filter(|b| b != id).collect();
+        reached.insert(id.to_string());
+        reached.extend(blocked.iter().cloned());
+        chains.push(BlockingChain {
+            blocker: id.to_string(),
+            blocked,
+            deadlock: true,
+        });
+    }
+    chains
+}
+
+pub async fn activity_snapshot(connection: &DatabaseConnection, include_idle: bool) -> Result<ActivitySnapshot, AppError> {
+    let mut warnings = Vec::new();
+    let (sessions, locks) = match connection {
+        DatabaseConnection::PostgreSQL(..) => postgres_activity(connection, include_idle, &mut warnings).await,
+        DatabaseConnection::MySQL(..) => mysql_activity(connection, include_idle, &mut warnings).await,
+        DatabaseConnection::MongoDB(client, _) => mongodb_activity(client, include_idle, &mut warnings).await,
+        DatabaseConnection::SQLite(..) => {
+            warnings.push("SQLite is an embedded database and has no server sessions".to_string());
+            (Vec::new(), Vec::new())
+        }
+        DatabaseConnection::DuckDB(_) => {
+            warnings.push("DuckDB is an embedded database and has no server sessions".to_string());
+            (Vec::new(), Vec::new())
+        }
+        DatabaseConnection::Redis(_) => {
+            warnings.push("Session monitoring isn't supported for Redis yet".to_string());
+            (Vec::new(), Vec::new())
+        }
+        DatabaseConnection::SQLServer(_) => {
+            warnings.push("Session monitoring isn't supported for SQL Server yet".to_string());
+            (Vec::new(), Vec::new())
+        }
+    };
+
+    Ok(ActivitySnapshot {
+        captured_at: Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+        blocking_chains: blocking_chains(&sessions),
+        sessions,
+        locks,
+        warnings,
+    })
+}
+
+// `cancel_only` stops the running query but keeps the session
+pub async fn terminate_session(connection: &DatabaseConnection, id: &str, cancel_only: bool) -> Result<(), AppError> {
+    let invalid = || AppError::InvalidConfiguration(format!("Invalid session id: {}", id));
+    match connection {
+        DatabaseConnection::PostgreSQL(..) => {
+            let pid: i32 = id.trim().parse().map_err(|_| invalid())?;
+            let function = if cancel_only { "pg_cancel_backend" } else { "pg_terminate_backend" };
+            let result = connection.execute_query(&format!("SELECT {}({})", function, pid)).await?;
+            if !boolean(result.rows.first().and_then(|r| r.first())) {
+                return Err(AppError::QueryExecutionFailed(format!("Session {} could not be signalled", pid)));
+            }
+        }
+        DatabaseConnection::MySQL(..) => {
+            let thread: u64 = id.trim().parse().map_err(|_| invalid())?;
+            let statement = if cancel_only { format!("KILL QUERY {}", thread) } else { format!("KILL {}", thread) };
+            // KILL can't be prepared, so it goes through the plain-text path
+            connection.execute_ddl(&[statement], false).await?;
+        }
+        DatabaseConnection::MongoDB(client, _) => {
+            // opids are numbers on a single server and "shard:number" strings through mongos
+            let op = match id.trim().parse::<i64>() {
+                Ok(number) => Bson::Int64(number),
+                Err(_) => Bson::String(id.trim().to_string()),
+            };
+            client.database("admin").run_command(doc! { "killOp": 1, "op": op }, None).await?;
+        }
+        DatabaseConnection::SQLite(..) => {
+            return Err(AppError::InvalidConfiguration("SQLite has no sessions to terminate".to_string()));
+        }
+        DatabaseConnection::DuckDB(_) => {
+            return Err(AppError::InvalidConfiguration("DuckDB has no sessions to terminate".to_string()));
+        }
+        DatabaseConnection::SQLServer(_) => {
+            let spid: u16 = id.trim().parse().map_err(|_| invalid())?;
+            if cancel_only {
+                return Err(AppError::InvalidConfiguration("SQL Server can only kill whole sessions".to_string()));
+            }
+            connection.execute_ddl(&[format!("KILL {}", spid)], false).await?;
+        }
+        DatabaseConnection::Redis(_) => {
+            let client: u64 = id.trim().parse().map_err(|_| invalid())?;
+            if cancel_only {
+                return Err(AppError::InvalidConfiguration("Redis can only kill whole client connections".to_string()));
+            }
+            connection.execute_query(&format!("CLIENT KILL ID {}", client)).await?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(id: &str, blocked_by: &[&str]) -> SessionInfo {
+        SessionInfo {
+            id: id.to_string(),
+            user: None,
+            database: None,
+            client: None,
+            application: None,
+            state: None,
+            query: None,
+            query_start: None,
+            duration_ms: None,
+            wait_event: None,
+            blocked_by: blocked_by.iter().map(|b| b.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn chains_start_at_the_session_that_is_not_waiting() {
+        let sessions = [session("1", &[]), session("2", &["1"]), session("3", &["2"]), session("4", &["1"]), session("5", &[])];
+        let chains = blocking_chains(&sessions);
+        assert_eq!(chains.len(), 1);
+        assert_eq!(chains[0].blocker, "1");
+        assert_eq!(chains[0].blocked, vec!["2", "4", "3"]);
+        assert!(!chains[0].deadlock);
+    }
+
+    #[test]
+    fn sessions_waiting_on_each_other_are_a_deadlock() {
+        let sessions = [session("5", &["6"]), session("6", &["5"]), session("7", &["6"]), session("8", &[]), session("9", &["8"])];
+        let chains = blocking_chains(&sessions);
+        assert_eq!(chains.len(), 2);
+        assert_eq!((chains[0].blocker.as_str(), chains[0].deadlock), ("8", false));
+        assert_eq!(chains[0].blocked, vec!["9"]);
+        assert_eq!((chains[1].blocker.as_str(), chains[1].deadlock), ("5", true));
+        assert_eq!(chains[1].blocked, vec!["6", "7"]);
+    }
+}
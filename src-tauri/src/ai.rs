@@ -2,6 +2,8 @@ use reqwest;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use anyhow::{Result, anyhow};
+use crate::database::IndexInfo;
+use crate::explain::{PlanNode, QueryPlan};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AIProviderConfig {
@@ -23,6 +25,9 @@ pub struct GenerateSQLRequest {
     pub natural_language_query: String,
     pub schema_description: String,
     pub ai_config: AIProviderConfig,
+    // Filled in from the active connection when the frontend leaves it out
+    #[serde(default)]
+    pub dialect: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -35,9 +40,38 @@ pub struct GenerateDiagramRequest {
 pub struct GenerateInsightsRequest {
     pub query_result: Option<serde_json::Value>,
     pub schema: Option<serde_json::Value>,
+    // Compact column statistics from profile_table
+    #[serde(default)]
+    pub profile: Option<String>,
     pub ai_config: AIProviderConfig,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OptimizeQueryRequest {
+    pub query: String,
+    pub ai_config: AIProviderConfig,
+}
+
+// Live statistics about a table referenced by the query being optimized
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TableOptimizationContext {
+    pub name: String,
+    pub row_count: Option<i64>,
+    pub indexes: Vec<IndexInfo>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct OptimizationSuggestion {
+    #[serde(default)]
+    pub explanation: String,
+    #[serde(default)]
+    pub rewritten_sql: Option<String>,
+    #[serde(default)]
+    pub index_ddl: Vec<String>,
+    #[serde(default)]
+    pub original_plan: Option<QueryPlan>,
+}
+
 pub struct AIService;
 
 impl AIService {
@@ -47,7 +81,8 @@ impl AIService {
     }
 
     pub async fn generate_sql(&self, request: GenerateSQLRequest) -> Result<String> {
-        let prompt = self.create_sql_prompt(&request.natural_language_query, &request.schema_description);
+        let dialect = request.dialect.as_deref().unwrap_or("MySQL");
+        let prompt = self.create_sql_prompt(&request.natural_language_query, &request.schema_description, dialect);
         let response = self.call_ai_provider(&request.ai_config, &prompt).await?;
         self.clean_sql_from_markdown(&response)
     }
@@ -59,10 +94,25 @@ impl AIService {
     }
 
     pub async fn generate_insights(&self, request: GenerateInsightsRequest) -> Result<String> {
-        let prompt = self.create_insights_prompt(&request.query_result, &request.schema);
+        let prompt = self.create_insights_prompt(&request.query_result, &request.schema, &request.profile);
         self.call_ai_provider(&request.ai_config, &prompt).await
     }
 
+    pub async fn optimize_query(
+        &self,
+        config: &AIProviderConfig,
+        query: &str,
+        db_type: &str,
+        plan: &QueryPlan,
+        tables: &[TableOptimizationContext],
+    ) -> Result<OptimizationSuggestion> {
+        let prompt = self.create_optimize_prompt(query, db_type, &plan.root, tables);
+        let response = self.call_ai_provider(config, &prompt).await?;
+        let mut suggestion = self.parse_optimization_response(&response);
+        suggestion.original_plan = Some(plan.clone());
+        Ok(suggestion)
+    }
+
     async fn call_ai_provider(&self, config: &AIProviderConfig, prompt: &str) -> Result<String> {
         match config.provider.as_str() {
             "openai" => self.call_openai(prompt, &config.model, &config.api_key.as_ref().unwrap()).await,
@@ -251,7 +301,26 @@ WAJIB gunakan format markdown berikut:
         )
     }
 
-    fn create_sql_prompt(&self, query: &str, schema: &str) -> String {
+    // Syntax reminders for dialects models tend to get wrong
+    fn dialect_hints(&self, dialect: &str) -> String {
+        let mut hints = Vec::new();
+        if dialect.contains("SQL Server") {
+            hints.push("- Gunakan TOP atau OFFSET ... FETCH NEXT untuk membatasi baris, JANGAN gunakan LIMIT");
+            hints.push("- Gunakan kurung siku [nama] untuk identifier, bukan backtick");
+        }
+        if dialect.contains("MariaDB") {
+            hints.push("- Gunakan JSON_VALUE atau JSON_EXTRACT untuk kolom JSON, JANGAN gunakan operator -> atau ->>");
+        }
+        if dialect.contains("TiDB") {
+            hints.push("- TiDB tidak mendukung stored procedure, trigger, maupun event");
+        }
+        if dialect.contains("CockroachDB") {
+            hints.push("- Hindari ekstensi dan fungsi khusus PostgreSQL (pg_*, tipe dari ekstensi) yang tidak tersedia di CockroachDB");
+        }
+        hints.iter().map(|h| format!("{}\n", h)).collect()
+    }
+
+    fn create_sql_prompt(&self, query: &str, schema: &str, dialect: &str) -> String {
         format!(
             "Anda adalah ahli SQL. Berdasarkan skema database dan query bahasa alami, buatlah query SQL yang valid.
 
@@ -272,11 +341,11 @@ FORMAT OUTPUT:
 - HANYA kembalikan query SQL murni
 - TIDAK boleh menggunakan markdown (backticks atau code blocks)
 - TIDAK boleh ada penjelasan atau teks lain
-- Gunakan sintaks MySQL yang benar
-- Contoh: SELECT * FROM tabel;
+- Gunakan sintaks {} yang benar
+{}- Contoh: SELECT * FROM tabel;
 
 Query SQL:",
-            schema, query
+            schema, query, dialect, self.dialect_hints(dialect)
         )
     }
 
@@ -335,13 +404,24 @@ Diagram Mermaid:",
         )
     }
 
-    fn create_insights_prompt(&self, query_result: &Option<serde_json::Value>, schema: &Option<serde_json::Value>) -> String {
+    fn create_insights_prompt(
+        &self,
+        query_result: &Option<serde_json::Value>,
+        schema: &Option<serde_json::Value>,
+        profile: &Option<String>,
+    ) -> String {
+        // Profile statistics cover the whole table, not just the returned rows
+        let profile_section = profile
+            .as_ref()
+            .map(|p| format!("\n\nStatistik kolom tabel (dihitung di database):\n{}", p))
+            .unwrap_or_default();
+
         if let Some(result) = query_result {
             // QueryResult analysis
             format!(
                 "Analisis hasil query berikut dan berikan wawasan dalam bahasa Indonesia:
 
-Data: {}
+Data: {}{}
 
 Silakan berikan:
 1. Temuan utama dari data
@@ -350,7 +430,24 @@ Silakan berikan:
 4. Potensi masalah atau anomali
 
 Berikan respons yang ringkas dan dapat ditindaklanjuti dalam bahasa Indonesia.",
-                serde_json::to_string_pretty(result).unwrap_or_default()
+                serde_json::to_string_pretty(result).unwrap_or_default(),
+                profile_section
+            )
+        } else if let Some(profile) = profile {
+            // Table profile analysis
+            format!(
+                "Analisis profil data tabel berikut dan berikan wawasan dalam bahasa Indonesia:
+
+{}
+
+Silakan berikan:
+1. Kualitas data (nilai kosong, duplikasi, nilai ekstrem)
+2. Distribusi dan pola yang menonjol
+3. Kolom yang mencurigakan atau tidak konsisten
+4. Rekomendasi perbaikan data atau skema
+
+Berikan respons yang ringkas dan dapat ditindaklanjuti dalam bahasa Indonesia.",
+                profile
             )
         } else if let Some(schema) = schema {
             // Schema analysis
@@ -375,6 +472,63 @@ Berikan respons yang ringkas dan dapat ditindaklanjuti dalam bahasa Indonesia.",
         }
     }
 
+    fn create_optimize_prompt(&self, query: &str, db_type: &str, plan: &PlanNode, tables: &[TableOptimizationContext]) -> String {
+        format!(
+            "Anda adalah ahli optimasi query database {}. Analisis query berikut menggunakan execution plan NYATA dan statistik tabel dari database.
+
+Query:
+{}
+
+Execution Plan (JSON):
+{}
+
+Statistik Tabel (jumlah baris dan index yang sudah ada):
+{}
+
+TUGAS:
+1. Identifikasi bottleneck berdasarkan execution plan (full scan, sort mahal, estimasi baris yang salah, dll)
+2. Tulis ulang query agar lebih efisien dengan hasil yang SAMA PERSIS
+3. Sarankan index baru HANYA jika benar-benar membantu dan belum ada
+4. Gunakan sintaks {} yang benar
+
+FORMAT OUTPUT:
+- HANYA kembalikan satu objek JSON, TANPA markdown dan TANPA teks lain
+- Struktur: {{\"explanation\": \"penjelasan singkat dalam bahasa Indonesia\", \"rewritten_sql\": \"query hasil optimasi atau null\", \"index_ddl\": [\"CREATE INDEX ...\"]}}
+
+JSON:",
+            db_type,
+            query,
+            serde_json::to_string(plan).unwrap_or_default(),
+            serde_json::to_string_pretty(tables).unwrap_or_default(),
+            db_type
+        )
+    }
+
+    fn parse_optimization_response(&self, response: &str) -> OptimizationSuggestion {
+        let cleaned = response
+            .replace("```json", "")
+            .replace("```", "");
+        let json = match (cleaned.find('{'), cleaned.rfind('}')) {
+            (Some(start), Some(end)) if start < end => &cleaned[start..=end],
+            _ => cleaned.as_str(),
+        };
+
+        match serde_json::from_str::<OptimizationSuggestion>(json) {
+            Ok(mut suggestion) => {
+                suggestion.rewritten_sql = suggestion
+                    .rewritten_sql
+                    .map(|sql| sql.trim().to_string())
+                    .filter(|sql| !sql.is_empty());
+                suggestion
+            }
+            // Keep the model's answer readable even when it ignored the format
+            Err(_) => OptimizationSuggestion {
+                explanation: response.trim().to_string(),
+                ..Default::default()
+            },
+        }
+    }
+
     fn clean_sql_from_markdown(&self, sql: &str) -> Result<String> {
         let cleaned = sql
             .replace("```sql", "")
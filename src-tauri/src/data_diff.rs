@@ -0,0 +1,591 @@
+use crate::database::{DatabaseConnection, DatabaseType, RowSink};
+use crate::error::AppError;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::time::Instant;
+
+const DEFAULT_CHUNK_SIZE: usize = 1000;
+const DEFAULT_MAX_DIFFERENCES: usize = 1000;
+
+// One side of a comparison; no connection means the active one
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TableDataSide {
+    pub connection: Option<crate::DatabaseConfig>,
+    pub table: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DataCompareOptions {
+    // Defaults to the source table's primary key
+    pub key_columns: Option<Vec<String>>,
+    pub chunk_size: Option<usize>,
+    pub max_differences: Option<usize>,
+    #[serde(default)]
+    pub generate_sync_sql: bool,
+}
+
+// Relative to the source: Inserted rows exist only in the source, Deleted rows only in the target
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RowChangeKind {
+    Inserted,
+    Deleted,
+    Changed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ColumnDifference {
+    pub column: String,
+    pub source: Value,
+    pub target: Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RowDifference {
+    pub kind: RowChangeKind,
+    pub key: Map<String, Value>,
+    // Changed columns only; empty for inserted and deleted rows
+    pub columns: Vec<ColumnDifference>,
+    // The full row for inserted (source) and deleted (target) rows
+    pub row: Option<Map<String, Value>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DataCompareProgress {
+    pub chunks_done: usize,
+    pub chunks_total: usize,
+    pub differences: u64,
+    pub finished: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DataComparison {
+    pub key_columns: Vec<String>,
+    pub compared_columns: Vec<String>,
+    pub source_only_columns: Vec<String>,
+    pub target_only_columns: Vec<String>,
+    pub source_rows: u64,
+    pub target_rows: u64,
+    pub chunks: usize,
+    // Chunks whose server-side checksums matched without fetching rows
+    pub chunks_matched: usize,
+    pub inserted: u64,
+    pub deleted: u64,
+    pub changed: u64,
+    pub differences: Vec<RowDifference>,
+    // More differences exist than were reported
+    pub truncated: bool,
+    // Statements that make the target match the source, for reported differences
+    pub sync_sql: Option<Vec<String>>,
+    pub duration_ms: u64,
+}
+
+// Rows collected from stream_query
+#[derive(Default)]
+struct CollectSink {
+    rows: Vec<Vec<Value>>,
+}
+
+impl RowSink for CollectSink {
+    fn columns(&mut self, _columns: &[String]) -> Result<(), AppError> {
+        Ok(())
+    }
+
+    fn row(&mut self, values: Vec<Value>) -> Result<(), AppError> {
+        self.rows.push(values);
+        Ok(())
+    }
+}
+
+// Keeps the first key of every chunk after the first
+struct BoundarySink {
+    chunk_size: usize,
+    seen: usize,
+    boundaries: Vec<Vec<Value>>,
+}
+
+impl RowSink for BoundarySink {
+    fn columns(&mut self, _columns: &[String]) -> Result<(), AppError> {
+        Ok(())
+    }
+
+    fn row(&mut self, values: Vec<Value>) -> Result<(), AppError> {
+        if self.seen > 0 && self.seen.is_multiple_of(self.chunk_size) {
+            self.boundaries.push(values);
+        }
+        self.seen += 1;
+        Ok(())
+    }
+}
+
+// Everything is compared as text so decoding can't lose precision
+fn text_expression(db_type: &DatabaseType, quoted: &str) -> String {
+    match db_type {
+        DatabaseType::PostgreSQL => format!("{}::text", quoted),
+        DatabaseType::MySQL => format!("CAST({} AS CHAR)", quoted),
+        _ => format!("CAST({} AS TEXT)", quoted),
+    }
+}
+
+fn literal(db_type: &DatabaseType, value: &Value) -> String {
+    let text = match value {
+        Value::Null => return "NULL".to_string(),
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    };
+    let escaped = text.replace('\'', "''");
+    match db_type {
+        // MySQL treats backslash as an escape character inside string literals
+        DatabaseType::MySQL => format!("'{}'", escaped.replace('\\', "\\\\")),
+        _ => format!("'{}'", escaped),
+    }
+}
+
+struct Side<'a> {
+    connection: &'a DatabaseConnection,
+    db_type: DatabaseType,
+    table: String,
+    keys: Vec<String>,
+    columns: Vec<String>,
+}
+
+impl Side<'_> {
+    fn quote(&self, name: &str) -> String {
+        self.connection.quote_ident(name)
+    }
+
+    fn key_tuple(&self) -> String {
+        let keys: Vec<String> = self.keys.iter().map(|k| self.quote(k)).collect();
+        if keys.len() == 1 {
+            keys[0].clone()
+        } else {
+            format!("({})", keys.join(", "))
+        }
+    }
+
+    fn literal_tuple(&self, values: &[Value]) -> String {
+        let literals: Vec<String> = values.iter().map(|v| literal(&self.db_type, v)).collect();
+        if literals.len() == 1 {
+            literals[0].clone()
+        } else {
+            format!("({})", literals.join(", "))
+        }
+    }
+
+    fn order_by(&self) -> String {
+        self.keys.iter().map(|k| self.quote(k)).collect::<Vec<_>>().join(", ")
+    }
+
+    // Rows with lower <= key < upper
+    fn range_condition(&self, lower: Option<&[Value]>, upper: Option<&[Value]>) -> String {
+        let mut conditions = Vec::new();
+        if let Some(lower) = lower {
+            conditions.push(format!("{} >= {}", self.key_tuple(), self.literal_tuple(lower)));
+        }
+        if let Some(upper) = upper {
+            conditions.push(format!("{} < {}", self.key_tuple(), self.literal_tuple(upper)));
+        }
+        if conditions.is_empty() {
+            "1 = 1".to_string()
+        } else {
+            conditions.join(" AND ")
+        }
+    }
+
+    fn select_list(&self) -> Vec<String> {
+        self.keys
+            .iter()
+            .chain(self.columns.iter())
+            .map(|c| text_expression(&self.db_type, &self.quote(c)))
+            .collect()
+    }
+
+    // Row count and order-independent checksum computed on the server
+    fn checksum_query(&self, condition: &str) -> Option<String> {
+        let table = self.connection.quote_table(&self.table);
+        match self.db_type {
+            DatabaseType::PostgreSQL => Some(format!(
+                "SELECT COUNT(*) AS row_count, md5(string_agg(md5(ROW({})::text), '' ORDER BY {})) AS checksum FROM {} WHERE {}",
+                self.select_list().join(", "),
+                self.order_by(),
+                table,
+                condition
+            )),
+            DatabaseType::MySQL => {
+                // CONCAT_WS skips NULLs, so null flags keep NULL and '' distinct
+                let values = self.select_list().join(", ");
+                let null_flags: Vec<String> = self
+                    .keys
+                    .iter()
+                    .chain(self.columns.iter())
+                    .map(|c| format!("ISNULL({})", self.quote(c)))
+                    .collect();
+                Some(format!(
+                    "SELECT COUNT(*) AS row_count, CAST(BIT_XOR(CAST(CONV(SUBSTRING(MD5(CONCAT_WS('#', {}, CONCAT({}))), 1, 16), 16, 10) AS UNSIGNED)) AS CHAR) AS checksum FROM {} WHERE {}",
+                    values,
+                    null_flags.join(", "),
+                    table,
+                    condition
+                ))
+            }
+            _ => None,
+        }
+    }
+
+    async fn checksum(&self, condition: &str) -> Result<Option<(i64, Value)>, AppError> {
+        let query = match self.checksum_query(condition) {
+            Some(query) => query,
+            None => return Ok(None),
+        };
+        let mut sink = CollectSink::default();
+        self.connection.stream_query(&query, &mut sink).await?;
+        let row = sink.rows.into_iter().next().unwrap_or_default();
+        let count = row.first().and_then(|v| v.as_i64().or_else(|| v.as_str().and_then(|s| s.parse().ok()))).unwrap_or(0);
+        Ok(Some((count, row.get(1).cloned().unwrap_or(Value::Null))))
+    }
+
+    async fn fetch_rows(&self, condition: &str) -> Result<Vec<Vec<Value>>, AppError> {
+        let query = format!(
+            "SELECT {} FROM {} WHERE {} ORDER BY {}",
+            self.select_list().join(", "),
+            self.connection.quote_table(&self.table),
+            condition,
+            self.order_by()
+        );
+        let mut sink = CollectSink::default();
+        self.connection.stream_query(&query, &mut sink).await?;
+        Ok(sink.rows)
+    }
+
+    async fn boundaries(&self, chunk_size: usize) -> Result<Vec<Vec<Value>>, AppError> {
+        let keys: Vec<String> = self.keys.iter().map(|k| text_expression(&self.db_type, &self.quote(k))).collect();
+        let query = format!("SELECT {} FROM {} ORDER BY {}", keys.join(", "), self.connection.quote_table(&self.table), self.order_by());
+        let mut sink = BoundarySink {
+            chunk_size,
+            seen: 0,
+            boundaries: Vec::new(),
+        };
+        self.connection.stream_query(&query, &mut sink).await?;
+        Ok(sink.boundaries)
+    }
+}
+
+async fn table_columns(connection: &DatabaseConnection, table: &str) -> Result<(Vec<String>, Vec<String>), AppError> {
+    let schema = connection.get_schema().await?;
+    let info = schema
+        .tables
+        .into_iter()
+        .find(|t| t.name == table)
+        .ok_or_else(|| AppError::InvalidConfiguration(format!("Table {} not found", table)))?;
+    let keys = info.columns.iter().filter(|c| c.is_primary_key).map(|c| c.name.clone()).collect();
+    let columns = info.columns.into_iter().map(|c| c.name).collect();
+    Ok((columns, keys))
+}
+
+// Text values from different backends: numbers and booleans are compared by meaning
+fn same_value(a: &Value, b: &Value) -> bool {
+    if a == b {
+        return true;
+    }
+    let (a, b) = match (a.as_str(), b.as_str()) {
+        (Some(a), Some(b)) => (a, b),
+        _ => return false,
+    };
+    if let (Ok(x), Ok(y)) = (a.parse::<f64>(), b.parse::<f64>()) {
+        return x == y;
+    }
+    let boolean = |s: &str| match s.to_lowercase().as_str() {
+        "t" | "true" | "1" => Some(true),
+        "f" | "false" | "0" => Some(false),
+        _ => None,
+    };
+    matches!((boolean(a), boolean(b)), (Some(x), Some(y)) if x == y)
+}
+
+struct DiffState {
+    keys: Vec<String>,
+    columns: Vec<String>,
+    max_differences: usize,
+    inserted: u64,
+    deleted: u64,
+    changed: u64,
+    differences: Vec<RowDifference>,
+    // Rows seen on one side only so far; a collation difference can place
+    // the matching row in a different chunk on the other side
+    pending_source: HashMap<String, Vec<Value>>,
+    pending_target: HashMap<String, Vec<Value>>,
+}
+
+impl DiffState {
+    fn key_of(&self, row: &[Value]) -> String {
+        serde_json::to_string(&row[..self.keys.len()]).unwrap_or_default()
+    }
+
+    fn key_map(&self, row: &[Value]) -> Map<String, Value> {
+        self.keys.iter().cloned().zip(row.iter().cloned()).collect()
+    }
+
+    fn row_map(&self, row: &[Value]) -> Map<String, Value> {
+        self.keys.iter().chain(self.columns.iter()).cloned().zip(row.iter().cloned()).collect()
+    }
+
+    fn push(&mut self, difference: RowDifference) {
+        match difference.kind {
+            RowChangeKind::Inserted => self.inserted += 1,
+            RowChangeKind::Deleted => self.deleted += 1,
+            RowChangeKind::Changed => self.changed += 1,
+        }
+        if self.differences.len() < self.max_differences {
+            self.differences.push(difference);
+        }
+    }
+
+    fn compare(&mut self, source: &[Value], target: &[Value]) {
+        let offset = self.keys.len();
+        let columns: Vec<ColumnDifference> = self
+            .columns
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| !same_value(&source[offset + i], &target[offset + i]))
+            .map(|(i, column)| ColumnDifference {
+                column: column.clone(),
+                source: source[offset + i].clone(),
+                target: target[offset + i].clone(),
+            })
+            .collect();
+        if !columns.is_empty() {
+            let key = self.key_map(source);
+            self.push(RowDifference {
+                kind: RowChangeKind::Changed,
+                key,
+                columns,
+                row: None,
+            });
+        }
+    }
+
+    fn diff_chunk(&mut self, source: Vec<Vec<Value>>, target: Vec<Vec<Value>>) {
+        let mut target_by_key: HashMap<String, Vec<Value>> = target.into_iter().map(|row| (self.key_of(&row), row)).collect();
+        for row in source {
+            let key = self.key_of(&row);
+            if let Some(other) = target_by_key.remove(&key).or_else(|| self.pending_target.remove(&key)) {
+                self.compare(&row, &other);
+            } else {
+                self.pending_source.insert(key, row);
+            }
+        }
+        for (key, row) in target_by_key {
+            if let Some(other) = self.pending_source.remove(&key) {
+                self.compare(&other, &row);
+            } else {
+                self.pending_target.insert(key, row);
+            }
+        }
+    }
+
+    fn finish(&mut self) {
+        let mut inserted: Vec<Vec<Value>> = self.pending_source.drain().map(|(_, row)| row).collect();
+        let mut deleted: Vec<Vec<Value>> = self.pending_target.drain().map(|(_, row)| row).collect();
+        inserted.sort_by_key(|row| self.key_of(row));
+        deleted.sort_by_key(|row| self.key_of(row));
+        for row in inserted {
+            let (key, row) = (self.key_map(&row), self.row_map(&row));
+            self.push(RowDifference {
+                kind: RowChangeKind::Inserted,
+                key,
+                columns: Vec::new(),
+                row: Some(row),
+            });
+        }
+        for row in deleted {
+            let (key, row) = (self.key_map(&row), self.row_map(&row));
+            self.push(RowDifference {
+                kind: RowChangeKind::Deleted,
+                key,
+                columns: Vec::new(),
+                row: Some(row),
+            });
+        }
+    }
+}
+
+fn sync_statements(target: &Side, differences: &[RowDifference]) -> Vec<String> {
+    let table = target.connection.quote_table(&target.table);
+    let where_clause = |key: &Map<String, Value>| {
+        key.iter()
+            .map(|(column, value)| match value {
+                Value::Null => format!("{} IS NULL", target.quote(column)),
+                _ => format!("{} = {}", target.quote(column), literal(&target.db_type, value)),
+            })
+            .collect::<Vec<_>>()
+            .join(" AND ")
+    };
+
+    differences
+        .iter()
+        .map(|difference| match difference.kind {
+            RowChangeKind::Inserted => {
+                let row = difference.row.clone().unwrap_or_default();
+                let columns: Vec<String> = row.keys().map(|c| target.quote(c)).collect();
+                let values: Vec<String> = row.values().map(|v| literal(&target.db_type, v)).collect();
+                format!("INSERT INTO {} ({}) VALUES ({});", table, columns.join(", "), values.join(", "))
+            }
+            RowChangeKind::Deleted => format!("DELETE FROM {} WHERE {};", table, where_clause(&difference.key)),
+            RowChangeKind::Changed => {
+                let assignments: Vec<String> = difference
+                    .columns
+                    .iter()
+                    .map(|c| format!("{} = {}", target.quote(&c.column), literal(&target.db_type, &c.source)))
+                    .collect();
+                format!("UPDATE {} SET {} WHERE {};", table, assignments.join(", "), where_clause(&difference.key))
+            }
+        })
+        .collect()
+}
+
+pub async fn compare_table_data<F>(
+    source: &DatabaseConnection,
+    source_table: &str,
+    target: &DatabaseConnection,
+    target_table: &str,
+    options: &DataCompareOptions,
+    mut on_progress: F,
+) -> Result<DataComparison, AppError>
+where
+    F: FnMut(DataCompareProgress) + Send,
+{
+    if matches!(source, DatabaseConnection::MongoDB(..)) || matches!(target, DatabaseConnection::MongoDB(..)) {
+        return Err(AppError::InvalidConfiguration("Data comparison is only supported for SQL databases".to_string()));
+    }
+
+    let started = Instant::now();
+    let (source_columns, primary_key) = table_columns(source, source_table).await?;
+    let (target_columns, _) = table_columns(target, target_table).await?;
+
+    let keys = options.key_columns.clone().unwrap_or(primary_key);
+    if keys.is_empty() {
+        return Err(AppError::InvalidConfiguration(format!(
+            "Table {} has no primary key; pass key_columns to compare it",
+            source_table
+        )));
+    }
+    for key in &keys {
+        if !source_columns.contains(key) || !target_columns.contains(key) {
+            return Err(AppError::InvalidConfiguration(format!("Key column {} must exist in both tables", key)));
+        }
+    }
+
+    let compared: Vec<String> = source_columns
+        .iter()
+        .filter(|c| !keys.contains(c) && target_columns.contains(c))
+        .cloned()
+        .collect();
+    let source_only_columns = source_columns.iter().filter(|c| !target_columns.contains(c)).cloned().collect();
+    let target_only_columns = target_columns.iter().filter(|c| !source_columns.contains(c)).cloned().collect();
+
+    let source_side = Side {
+        connection: source,
+        db_type: source.db_type(),
+        table: source_table.to_string(),
+        keys: keys.clone(),
+        columns: compared.clone(),
+    };
+    let target_side = Side {
+        connection: target,
+        db_type: target.db_type(),
+        table: target_table.to_string(),
+        keys: keys.clone(),
+        columns: compared.clone(),
+    };
+
+    // Checksums are only comparable when both servers render values the same way
+    let use_checksums = source_side.db_type.as_str() == target_side.db_type.as_str();
+    let chunk_size = options.chunk_size.unwrap_or(DEFAULT_CHUNK_SIZE).max(1);
+    let boundaries = source_side.boundaries(chunk_size).await?;
+    let chunks_total = boundaries.len() + 1;
+
+    let mut state = DiffState {
+        keys: keys.clone(),
+        columns: compared.clone(),
+        max_differences: options.max_differences.unwrap_or(DEFAULT_MAX_DIFFERENCES),
+        inserted: 0,
+        deleted: 0,
+        changed: 0,
+        differences: Vec::new(),
+        pending_source: HashMap::new(),
+        pending_target: HashMap::new(),
+    };
+    let mut source_rows = 0u64;
+    let mut target_rows = 0u64;
+    let mut chunks_matched = 0;
+
+    for chunk in 0..chunks_total {
+        let lower = chunk.checked_sub(1).map(|i| boundaries[i].as_slice());
+        let upper = boundaries.get(chunk).map(|b| b.as_slice());
+        let source_condition = source_side.range_condition(lower, upper);
+        let target_condition = target_side.range_condition(lower, upper);
+
+        let mut matched = false;
+        if use_checksums {
+            if let (Some((source_count, source_sum)), Some((target_count, target_sum))) = (
+                source_side.checksum(&source_condition).await?,
+                target_side.checksum(&target_condition).await?,
+            ) {
+                if source_count == target_count && source_sum == target_sum {
+                    source_rows += source_count as u64;
+                    target_rows += target_count as u64;
+                    chunks_matched += 1;
+                    matched = true;
+                }
+            }
+        }
+
+        if !matched {
+            let source_chunk = source_side.fetch_rows(&source_condition).await?;
+            let target_chunk = target_side.fetch_rows(&target_condition).await?;
+            source_rows += source_chunk.len() as u64;
+            target_rows += target_chunk.len() as u64;
+            state.diff_chunk(source_chunk, target_chunk);
+        }
+
+        on_progress(DataCompareProgress {
+            chunks_done: chunk + 1,
+            chunks_total,
+            differences: state.inserted + state.deleted + state.changed,
+            finished: false,
+        });
+    }
+    state.finish();
+
+    let total = state.inserted + state.deleted + state.changed;
+    on_progress(DataCompareProgress {
+        chunks_done: chunks_total,
+        chunks_total,
+        differences: total,
+        finished: true,
+    });
+
+    let sync_sql = if options.generate_sync_sql {
+        Some(sync_statements(&target_side, &state.differences))
+    } else {
+        None
+    };
+
+    Ok(DataComparison {
+        key_columns: keys,
+        compared_columns: compared,
+        source_only_columns,
+        target_only_columns,
+        source_rows,
+        target_rows,
+        chunks: chunks_total,
+        chunks_matched,
+        inserted: state.inserted,
+        deleted: state.deleted,
+        changed: state.changed,
+        truncated: total > state.differences.len() as u64,
+        differences: state.differences,
+        sync_sql,
+        duration_ms: started.elapsed().as_millis() as u64,
+    })
+}
@@ -1,10 +1,22 @@
 use crate::error::AppError;
 use serde::{Deserialize, Serialize};
 use sqlx::{MySqlPool, PgPool, SqlitePool, Row};
-use sqlx::Column;
+use sqlx::{Column, Executor};
+use sqlx::mysql::MySqlPoolOptions;
+use sqlx::postgres::PgPoolOptions;
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
 use mongodb::{Client, Database as MongoDatabase};
 use bson::doc;
 use chrono::{DateTime, Utc, NaiveDateTime, NaiveDate, NaiveTime};
+use futures::TryStreamExt;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::net::TcpStream;
+use tokio_util::compat::{Compat, TokioAsyncWriteCompatExt};
+use crate::explain::{self, QueryPlan};
+use crate::values::{self, SqlValue};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum DatabaseType {
@@ -12,6 +24,9 @@ pub enum DatabaseType {
     PostgreSQL,
     SQLite,
     MongoDB,
+    SQLServer,
+    Redis,
+    DuckDB,
 }
 
 impl DatabaseType {
@@ -21,8 +36,123 @@ impl DatabaseType {
             DatabaseType::PostgreSQL => "postgresql",
             DatabaseType::SQLite => "sqlite",
             DatabaseType::MongoDB => "mongodb",
+            DatabaseType::SQLServer => "mssql",
+            DatabaseType::Redis => "redis",
+            DatabaseType::DuckDB => "duckdb",
         }
     }
+
+    // Dialect named in AI prompts
+    pub fn dialect_name(&self) -> &'static str {
+        match self {
+            DatabaseType::MySQL => "MySQL",
+            DatabaseType::PostgreSQL => "PostgreSQL",
+            DatabaseType::SQLite => "SQLite",
+            DatabaseType::MongoDB => "MongoDB",
+            DatabaseType::SQLServer => "Microsoft SQL Server (T-SQL)",
+            DatabaseType::Redis => "Redis",
+            DatabaseType::DuckDB => "DuckDB",
+        }
+    }
+}
+
+// Server actually answering on the MySQL or PostgreSQL wire protocol
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ServerFlavor {
+    MySQL,
+    MariaDB,
+    TiDB,
+    PostgreSQL,
+    CockroachDB,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServerInfo {
+    pub flavor: ServerFlavor,
+    // The flavor's own version, e.g. 10.11.6 for MariaDB or 7.5.0 for TiDB rather than
+    // the MySQL version it reports for compatibility
+    pub version: String,
+    // Full string from VERSION()/version()
+    pub version_string: String,
+}
+
+impl ServerInfo {
+    // "8.0.35-0ubuntu0.22.04.1", "10.11.6-MariaDB-1:10.11.6+maria~ubu2204" or "8.0.11-TiDB-v7.5.0"
+    fn from_mysql_version(version_string: &str) -> Self {
+        let (flavor, version) = if let Some(tidb) = version_string.split("-TiDB-v").nth(1) {
+            (ServerFlavor::TiDB, tidb.split('-').next().unwrap_or_default())
+        } else if version_string.contains("MariaDB") {
+            // Old clients needed a fake "5.5.5-" prefix to accept 10.x versions
+            let version = version_string.strip_prefix("5.5.5-").unwrap_or(version_string);
+            (ServerFlavor::MariaDB, version.split('-').next().unwrap_or_default())
+        } else {
+            (ServerFlavor::MySQL, version_string.split('-').next().unwrap_or_default())
+        };
+        ServerInfo {
+            flavor,
+            version: version.to_string(),
+            version_string: version_string.to_string(),
+        }
+    }
+
+    // "PostgreSQL 16.1 (Debian 16.1-1.pgdg120+1) on x86_64-pc-linux-gnu, ..." or
+    // "CockroachDB CCL v23.1.11 (x86_64-pc-linux-gnu, built 2023/09/27 ...)"
+    fn from_postgres_version(version_string: &str) -> Self {
+        let mut words = version_string.split_whitespace();
+        let flavor = if version_string.starts_with("CockroachDB") {
+            ServerFlavor::CockroachDB
+        } else {
+            ServerFlavor::PostgreSQL
+        };
+        let version = match flavor {
+            ServerFlavor::CockroachDB => words.find(|w| w.starts_with('v')).map(|w| w.trim_start_matches('v')),
+            _ => words.nth(1),
+        };
+        ServerInfo {
+            flavor,
+            version: version.unwrap_or_default().trim_end_matches(',').to_string(),
+            version_string: version_string.to_string(),
+        }
+    }
+
+    // Dialect named in AI prompts
+    pub fn dialect_name(&self) -> String {
+        match self.flavor {
+            ServerFlavor::MySQL => format!("MySQL {}", self.version),
+            ServerFlavor::MariaDB => format!("MariaDB {}", self.version),
+            ServerFlavor::TiDB => format!("TiDB {} (MySQL-compatible)", self.version),
+            ServerFlavor::PostgreSQL => format!("PostgreSQL {}", self.version),
+            ServerFlavor::CockroachDB => format!("CockroachDB {} (PostgreSQL-compatible)", self.version),
+        }
+    }
+
+    pub fn version_at_least(&self, major: u32, minor: u32) -> bool {
+        // Pre-release versions such as "17beta1" compare by their leading number
+        let mut parts = self.version.split('.').map(|p| {
+            let digits: String = p.chars().take_while(|c| c.is_ascii_digit()).collect();
+            digits.parse::<u32>().unwrap_or(0)
+        });
+        (parts.next().unwrap_or(0), parts.next().unwrap_or(0)) >= (major, minor)
+    }
+}
+
+// An extra SQLite file made visible as `alias.table`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttachedDatabase {
+    pub path: String,
+    pub alias: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SqliteOptions {
+    // Create the database file when it doesn't exist yet
+    #[serde(default)]
+    pub create_if_missing: bool,
+    #[serde(default)]
+    pub attach: Vec<AttachedDatabase>,
+    // Paths of loadable extensions, e.g. mod_spatialite
+    #[serde(default)]
+    pub extensions: Vec<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -30,33 +160,540 @@ pub struct QueryResult {
     pub columns: Vec<String>,
     pub rows: Vec<Vec<serde_json::Value>>,
     pub row_count: usize,
+    // Time until the server produced the first row (or finished, for empty results)
+    #[serde(default)]
+    pub execution_time_ms: f64,
+    // Time spent streaming and decoding the remaining rows
+    #[serde(default)]
+    pub fetch_time_ms: f64,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+struct QueryTimer {
+    started: Instant,
+    first_row: Option<Duration>,
+}
+
+impl QueryTimer {
+    fn start() -> Self {
+        QueryTimer {
+            started: Instant::now(),
+            first_row: None,
+        }
+    }
+
+    fn mark_row(&mut self) {
+        if self.first_row.is_none() {
+            self.first_row = Some(self.started.elapsed());
+        }
+    }
+
+    fn finish(&self) -> (f64, f64) {
+        let total = self.started.elapsed();
+        let execution = self.first_row.unwrap_or(total);
+        (
+            execution.as_secs_f64() * 1000.0,
+            total.saturating_sub(execution).as_secs_f64() * 1000.0,
+        )
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DatabaseSchema {
     pub tables: Vec<TableInfo>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TableInfo {
     pub name: String,
     pub columns: Vec<ColumnInfo>,
+    #[serde(default)]
+    pub indexes: Vec<IndexInfo>,
+    #[serde(default)]
+    pub foreign_keys: Vec<ForeignKeyInfo>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ColumnInfo {
     pub name: String,
     pub data_type: String,
     pub is_nullable: bool,
     pub is_primary_key: bool,
+    // Full declared type including length/precision, e.g. varchar(255)
+    #[serde(default)]
+    pub column_type: Option<String>,
+    // Default as an SQL expression
+    #[serde(default)]
+    pub default_value: Option<String>,
+    // MySQL column attributes: EXTRA (auto_increment, on update ...), comment and collation
+    #[serde(default)]
+    pub extra: Option<String>,
+    #[serde(default)]
+    pub comment: Option<String>,
+    #[serde(default)]
+    pub collation: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ForeignKeyInfo {
+    pub name: String,
+    pub columns: Vec<String>,
+    pub referenced_table: String,
+    pub referenced_columns: Vec<String>,
+    pub on_delete: Option<String>,
+    pub on_update: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexInfo {
+    pub name: String,
+    pub table: String,
+    pub columns: Vec<String>,
+    pub is_unique: bool,
+    pub is_primary: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PlanComparison {
+    pub before: QueryPlan,
+    pub after: QueryPlan,
+    // False when the backend can't apply DDL in a scratch transaction (MySQL)
+    pub indexes_applied: bool,
+}
+
+// Column names come from the first row, so empty results describe the statement instead
+async fn describe_columns<'p, DB: sqlx::Database>(pool: &'p sqlx::Pool<DB>, query: &str, sink: &mut dyn RowSink) -> Result<(), AppError>
+where
+    &'p sqlx::Pool<DB>: Executor<'p, Database = DB>,
+{
+    // Statements sqlx can't describe simply export without a header
+    if let Ok(description) = pool.describe(query).await {
+        sink.columns(&description.columns().iter().map(|c| c.name().to_string()).collect::<Vec<_>>())?;
+    }
+    Ok(())
+}
+
+// Documents written before an ordered MongoDB insert stopped, and why it stopped
+#[derive(Debug)]
+pub struct PartialInsert {
+    pub inserted: u64,
+    pub error: AppError,
+}
+
+// PostgreSQL "schema.table" names; unqualified names resolve against current_schema()
+pub fn pg_table_ref(table: &str) -> (Option<&str>, &str) {
+    match table.split_once('.') {
+        Some((schema, name)) => (Some(schema), name),
+        None => (None, table),
+    }
+}
+
+// Group per-column catalog rows into one IndexInfo per index
+fn push_index_column(indexes: &mut Vec<IndexInfo>, table: &str, name: &str, column: Option<String>, is_unique: bool, is_primary: bool) {
+    // Expression index members have no column name
+    let column = column.unwrap_or_else(|| "<expression>".to_string());
+    match indexes.iter_mut().find(|i| i.name == name) {
+        Some(index) => index.columns.push(column),
+        None => indexes.push(IndexInfo {
+            name: name.to_string(),
+            table: table.to_string(),
+            columns: vec![column],
+            is_unique,
+            is_primary,
+        }),
+    }
+}
+
+// Catalog rows belong to the requested table, or to the table named in the row for schema-wide loads
+fn table_key(table: Option<&str>, row_table: String) -> String {
+    table.map(str::to_string).unwrap_or(row_table)
+}
+
+fn push_foreign_key_column(foreign_keys: &mut Vec<ForeignKeyInfo>, name: String, column: String, referenced_table: String, referenced_column: String, on_delete: Option<String>, on_update: Option<String>) {
+    match foreign_keys.iter_mut().find(|fk| fk.name == name) {
+        Some(fk) => {
+            fk.columns.push(column);
+            fk.referenced_columns.push(referenced_column);
+        }
+        None => foreign_keys.push(ForeignKeyInfo {
+            name,
+            columns: vec![column],
+            referenced_table,
+            referenced_columns: vec![referenced_column],
+            on_delete,
+            on_update,
+        }),
+    }
+}
+
+// MongoDB queries are command documents, e.g. {"find": "users", "filter": {"age": {"$gt": 30}}}
+pub fn parse_mongodb_command(query: &str) -> Result<bson::Document, AppError> {
+    let command: serde_json::Value = serde_json::from_str(query)
+        .map_err(|e| AppError::QueryExecutionFailed(format!("MongoDB query must be a JSON command: {}", e)))?;
+    bson::to_document(&command)
+        .map_err(|e| AppError::QueryExecutionFailed(format!("Invalid MongoDB command: {}", e)))
+}
+
+// Receives rows one at a time while a query result is streamed
+pub trait RowSink: Send {
+    fn columns(&mut self, columns: &[String]) -> Result<(), AppError>;
+    fn row(&mut self, values: Vec<serde_json::Value>) -> Result<(), AppError>;
+}
+
+pub fn quote_identifier(name: &str, quote: char) -> String {
+    let escaped = name.replace(quote, &format!("{}{}", quote, quote));
+    format!("{}{}{}", quote, escaped, quote)
+}
+
+// SQL Server bracket quoting; double quotes also work since sessions enable QUOTED_IDENTIFIER
+pub fn quote_mssql_identifier(name: &str) -> String {
+    format!("[{}]", name.replace(']', "]]"))
+}
+
+// tiberius has no pool, so one client is shared and its requests are serialized
+pub type MssqlClient = Arc<tokio::sync::Mutex<tiberius::Client<Compat<TcpStream>>>>;
+
+// Object id of a table in the user's default schema
+const MSSQL_OBJECT_ID: &str = "OBJECT_ID(QUOTENAME(SCHEMA_NAME()) + '.' + QUOTENAME(@P1))";
+
+fn mssql_failed(e: tiberius::error::Error) -> AppError {
+    AppError::QueryExecutionFailed(format!("SQL Server query failed: {}", e))
+}
+
+// Exact decimal text; Numeric's own Display mangles negative values
+fn mssql_numeric(value: tiberius::numeric::Numeric) -> String {
+    let scale = value.scale() as usize;
+    let digits = value.value().unsigned_abs().to_string();
+    let text = if scale == 0 {
+        digits
+    } else {
+        let padded = format!("{:0>width$}", digits, width = scale + 1);
+        let (integer, fraction) = padded.split_at(padded.len() - scale);
+        format!("{}.{}", integer, fraction)
+    };
+    if value.value() < 0 { format!("-{}", text) } else { text }
+}
+
+// Column defaults are stored wrapped in parentheses, e.g. ((0)) or (getdate())
+fn mssql_default(default: &str) -> String {
+    let mut default = default.trim();
+    while default.starts_with('(') && default.ends_with(')') && default.len() >= 2 {
+        let inner = &default[1..default.len() - 1];
+        let mut depth = 0i32;
+        let balanced = inner.chars().all(|c| {
+            match c {
+                '(' => depth += 1,
+                ')' => depth -= 1,
+                _ => {}
+            }
+            depth >= 0
+        }) && depth == 0;
+        if !balanced {
+            break;
+        }
+        default = inner.trim();
+    }
+    default.to_string()
+}
+
+async fn mssql_rows(client: &MssqlClient, sql: &str, params: &[&dyn tiberius::ToSql]) -> Result<Vec<tiberius::Row>, AppError> {
+    let mut client = client.lock().await;
+    let stream = client.query(sql, params).await.map_err(mssql_failed)?;
+    stream.into_first_result().await.map_err(mssql_failed)
+}
+
+fn mssql_text(row: &tiberius::Row, column: &str) -> Option<String> {
+    row.try_get::<&str, _>(column).ok().flatten().map(|s| s.to_string())
+}
+
+// Multiplexed Redis connection that reconnects on its own; cloning it is cheap
+#[derive(Clone)]
+pub struct RedisClient {
+    pub manager: redis::aio::ConnectionManager,
+    pub db: i64,
+}
+
+// ConnectionManager has no Debug impl
+impl std::fmt::Debug for RedisClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RedisClient").field("db", &self.db).finish()
+    }
+}
+
+pub fn redis_failed(e: redis::RedisError) -> AppError {
+    AppError::QueryExecutionFailed(format!("Redis command failed: {}", e))
+}
+
+// Redis databases are numbered; "3" and "db3" both select database 3
+fn redis_database_index(name: &str) -> Result<i64, AppError> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Ok(0);
+    }
+    name.trim_start_matches("db")
+        .parse()
+        .map_err(|_| AppError::InvalidConfiguration(format!("Invalid Redis database: {}", name)))
+}
+
+// Splits a command line the way redis-cli does: whitespace separated words, with
+// double quotes supporting escapes and single quotes taken literally
+pub fn split_redis_command(line: &str) -> Result<Vec<String>, AppError> {
+    let mut words = Vec::new();
+    let mut chars = line.trim().trim_end_matches(';').chars().peekable();
+    loop {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        let Some(&first) = chars.peek() else { break };
+        let mut word = String::new();
+        if first == '"' || first == '\'' {
+            chars.next();
+            let mut closed = false;
+            while let Some(c) = chars.next() {
+                if c == first {
+                    closed = true;
+                    break;
+                }
+                if c == '\\' {
+                    match (first, chars.next()) {
+                        ('"', Some('n')) => word.push('\n'),
+                        ('"', Some('r')) => word.push('\r'),
+                        ('"', Some('t')) => word.push('\t'),
+                        ('\'', Some('\'')) => word.push('\''),
+                        ('\'', Some(other)) => {
+                            word.push('\\');
+                            word.push(other);
+                        }
+                        (_, Some(other)) => word.push(other),
+                        (_, None) => {}
+                    }
+                } else {
+                    word.push(c);
+                }
+            }
+            if !closed {
+                return Err(AppError::QueryExecutionFailed("Unbalanced quotes in Redis command".to_string()));
+            }
+        } else {
+            while let Some(c) = chars.next_if(|c| !c.is_whitespace()) {
+                word.push(c);
+            }
+        }
+        words.push(word);
+    }
+    Ok(words)
+}
+
+pub fn redis_value_to_json(value: redis::Value) -> serde_json::Value {
+    match value {
+        redis::Value::Nil => serde_json::Value::Null,
+        redis::Value::Int(n) => serde_json::Value::Number(n.into()),
+        redis::Value::Data(bytes) => serde_json::Value::String(String::from_utf8_lossy(&bytes).into_owned()),
+        redis::Value::Status(status) => serde_json::Value::String(status),
+        redis::Value::Okay => serde_json::Value::String("OK".to_string()),
+        redis::Value::Bulk(items) => serde_json::Value::Array(items.into_iter().map(redis_value_to_json).collect()),
+    }
+}
+
+// Lays a reply out as a table; replies that alternate names and values get two columns
+fn redis_reply_table(args: &[String], reply: redis::Value) -> (Vec<String>, Vec<Vec<serde_json::Value>>) {
+    let command = args.iter().take(2).map(|a| a.to_uppercase()).collect::<Vec<_>>();
+    let pair_columns = match command.iter().map(String::as_str).collect::<Vec<_>>().as_slice() {
+        ["HGETALL", ..] => Some(["field", "value"]),
+        ["CONFIG", "GET"] => Some(["parameter", "value"]),
+        [c, ..] if c.starts_with("ZRANGE") || c.starts_with("ZREVRANGE") || *c == "ZPOPMIN" || *c == "ZPOPMAX" => {
+            args.iter().any(|a| a.eq_ignore_ascii_case("WITHSCORES")).then_some(["member", "score"])
+        }
+        _ => None,
+    };
+    match reply {
+        redis::Value::Bulk(items) => {
+            let values: Vec<serde_json::Value> = items.into_iter().map(redis_value_to_json).collect();
+            match pair_columns {
+                Some(columns) => (
+                    columns.iter().map(|c| c.to_string()).collect(),
+                    values.chunks(2).map(|pair| pair.to_vec()).collect(),
+                ),
+                None => (vec!["value".to_string()], values.into_iter().map(|v| vec![v]).collect()),
+            }
+        }
+        other => (vec!["value".to_string()], vec![vec![redis_value_to_json(other)]]),
+    }
+}
+
+async fn redis_command(client: &RedisClient, query: &str) -> Result<(Vec<String>, redis::Value), AppError> {
+    let args = split_redis_command(query)?;
+    let Some((name, rest)) = args.split_first() else {
+        return Err(AppError::QueryExecutionFailed("Empty Redis command".to_string()));
+    };
+    let mut con = client.manager.clone();
+    let reply = redis::cmd(name).arg(rest).query_async(&mut con).await.map_err(redis_failed)?;
+    Ok((args, reply))
+}
+
+// duckdb::Connection isn't Sync; queries run on the blocking pool with the connection locked
+pub type DuckDbClient = Arc<std::sync::Mutex<duckdb::Connection>>;
+
+pub fn duckdb_failed(e: duckdb::Error) -> AppError {
+    AppError::QueryExecutionFailed(format!("DuckDB query failed: {}", e))
+}
+
+async fn duckdb_blocking<T, F>(client: &DuckDbClient, f: F) -> Result<T, AppError>
+where
+    T: Send + 'static,
+    F: FnOnce(&duckdb::Connection) -> Result<T, AppError> + Send + 'static,
+{
+    let client = client.clone();
+    tokio::task::spawn_blocking(move || {
+        let conn = client
+            .lock()
+            .map_err(|_| AppError::InternalError("DuckDB connection lock poisoned".to_string()))?;
+        f(&conn)
+    })
+    .await
+    .map_err(|e| AppError::InternalError(format!("DuckDB task failed: {}", e)))?
+}
+
+fn duckdb_micros(unit: duckdb::types::TimeUnit, value: i64) -> i64 {
+    match unit {
+        duckdb::types::TimeUnit::Second => value.saturating_mul(1_000_000),
+        duckdb::types::TimeUnit::Millisecond => value.saturating_mul(1_000),
+        duckdb::types::TimeUnit::Microsecond => value,
+        duckdb::types::TimeUnit::Nanosecond => value / 1_000,
+    }
+}
+
+// Nested LIST/ARRAY values become JSON arrays, STRUCTs objects and MAPs objects when
+// every key is text, otherwise arrays of {key, value} entries
+pub fn duckdb_value_to_json(value: duckdb::types::Value) -> serde_json::Value {
+    use duckdb::types::Value as V;
+    match value {
+        V::Null => serde_json::Value::Null,
+        V::Boolean(v) => serde_json::Value::Bool(v),
+        V::TinyInt(v) => v.into(),
+        V::SmallInt(v) => v.into(),
+        V::Int(v) => v.into(),
+        V::BigInt(v) => v.into(),
+        V::UTinyInt(v) => v.into(),
+        V::USmallInt(v) => v.into(),
+        V::UInt(v) => v.into(),
+        V::UBigInt(v) => v.into(),
+        // Beyond 64 bits JSON numbers lose precision
+        V::HugeInt(v) => i64::try_from(v).map(serde_json::Value::from).unwrap_or_else(|_| serde_json::Value::String(v.to_string())),
+        V::Float(v) => serde_json::Number::from_f64(v as f64).map(serde_json::Value::Number).unwrap_or(serde_json::Value::Null),
+        V::Double(v) => serde_json::Number::from_f64(v).map(serde_json::Value::Number).unwrap_or(serde_json::Value::Null),
+        V::Decimal(v) => serde_json::Value::String(v.to_string()),
+        V::Text(v) | V::Enum(v) => serde_json::Value::String(v),
+        V::Blob(bytes) => match String::from_utf8(bytes) {
+            Ok(s) => serde_json::Value::String(s),
+            Err(_) => serde_json::Value::String("BLOB_DATA".to_string()),
+        },
+        V::Date32(days) => NaiveDate::from_ymd_opt(1970, 1, 1)
+            .and_then(|epoch| epoch.checked_add_signed(chrono::Duration::days(days as i64)))
+            .map(|d| serde_json::Value::String(d.format("%Y-%m-%d").to_string()))
+            .unwrap_or(serde_json::Value::Null),
+        V::Time64(unit, value) => {
+            let micros = duckdb_micros(unit, value);
+            NaiveTime::from_num_seconds_from_midnight_opt((micros / 1_000_000) as u32, ((micros % 1_000_000) * 1_000) as u32)
+                .map(|t| serde_json::Value::String(t.format("%H:%M:%S%.f").to_string()))
+                .unwrap_or(serde_json::Value::Null)
+        }
+        V::Timestamp(unit, value) => DateTime::<Utc>::from_timestamp_micros(duckdb_micros(unit, value))
+            .map(|t| serde_json::Value::String(t.naive_utc().format("%Y-%m-%d %H:%M:%S%.f").to_string()))
+            .unwrap_or(serde_json::Value::Null),
+        V::Interval { months, days, nanos } => serde_json::Value::String(format!("{} months {} days {} us", months, days, nanos / 1_000)),
+        V::List(items) | V::Array(items) => serde_json::Value::Array(items.into_iter().map(duckdb_value_to_json).collect()),
+        V::Struct(fields) => serde_json::Value::Object(
+            fields.iter().map(|(name, value)| (name.clone(), duckdb_value_to_json(value.clone()))).collect(),
+        ),
+        V::Map(entries) => {
+            let entries: Vec<(serde_json::Value, serde_json::Value)> = entries
+                .iter()
+                .map(|(key, value)| (duckdb_value_to_json(key.clone()), duckdb_value_to_json(value.clone())))
+                .collect();
+            if entries.iter().all(|(key, _)| key.is_string()) {
+                serde_json::Value::Object(
+                    entries.into_iter().map(|(key, value)| (key.as_str().unwrap_or_default().to_string(), value)).collect(),
+                )
+            } else {
+                serde_json::Value::Array(
+                    entries.into_iter().map(|(key, value)| serde_json::json!({ "key": key, "value": value })).collect(),
+                )
+            }
+        }
+        V::Union(inner) => duckdb_value_to_json(*inner),
+    }
+}
+
+// Column names are only known once the statement has run
+fn duckdb_query(conn: &duckdb::Connection, sql: &str, params: Vec<duckdb::types::Value>) -> Result<QueryResult, AppError> {
+    let mut timer = QueryTimer::start();
+    let mut statement = conn.prepare(sql).map_err(duckdb_failed)?;
+    let mut rows = statement.query(duckdb::params_from_iter(params)).map_err(duckdb_failed)?;
+    let columns = rows.as_ref().map(|s| s.column_names()).unwrap_or_default();
+    let mut result_rows = Vec::new();
+    while let Some(row) = rows.next().map_err(duckdb_failed)? {
+        timer.mark_row();
+        let mut values = Vec::with_capacity(columns.len());
+        for i in 0..columns.len() {
+            values.push(duckdb_value_to_json(row.get::<_, duckdb::types::Value>(i).map_err(duckdb_failed)?));
+        }
+        result_rows.push(values);
+    }
+    let row_count = result_rows.len();
+    let (execution_time_ms, fetch_time_ms) = timer.finish();
+    Ok(QueryResult {
+        columns,
+        rows: result_rows,
+        row_count,
+        execution_time_ms,
+        fetch_time_ms,
+    })
+}
+
+async fn duckdb_rows(client: &DuckDbClient, sql: &'static str, params: Vec<duckdb::types::Value>) -> Result<Vec<Vec<serde_json::Value>>, AppError> {
+    duckdb_blocking(client, move |conn| duckdb_query(conn, sql, params)).await.map(|r| r.rows)
+}
+
+// Parameters for "(CAST(? AS VARCHAR) IS NULL OR table_name = ?)"
+fn duckdb_table_filter(table: Option<&str>) -> Vec<duckdb::types::Value> {
+    let value = match table {
+        Some(table) => duckdb::types::Value::Text(table.to_string()),
+        None => duckdb::types::Value::Null,
+    };
+    vec![value.clone(), value]
+}
+
+fn json_text(value: Option<&serde_json::Value>) -> Option<String> {
+    match value? {
+        serde_json::Value::Null => None,
+        serde_json::Value::String(s) => Some(s.clone()),
+        other => Some(other.to_string()),
+    }
+}
+
+// Splits "a, b" from the parenthesised part of CREATE INDEX ... ON t(a, b) or a constraint text
+fn duckdb_column_list(text: &str) -> Vec<String> {
+    text.split(',')
+        .map(|c| c.trim().trim_matches('"').to_string())
+        .filter(|c| !c.is_empty())
+        .collect()
+}
+
+// Text between the first '(' after `from` and its closing ')'
+fn duckdb_parenthesised(text: &str, from: usize) -> Option<(&str, usize)> {
+    let open = from + text[from..].find('(')?;
+    let close = open + text[open..].find(')')?;
+    Some((&text[open + 1..close], close + 1))
 }
 
 #[derive(Clone, Debug)]
 pub enum DatabaseConnection {
-    MySQL(MySqlPool),
-    PostgreSQL(PgPool),
-    SQLite(SqlitePool),
+    MySQL(MySqlPool, ServerInfo),
+    PostgreSQL(PgPool, ServerInfo),
+    // Aliases of attached databases, whose tables are named "alias.table"
+    SQLite(SqlitePool, Vec<String>),
     MongoDB(Client, MongoDatabase),
+    SQLServer(MssqlClient),
+    Redis(RedisClient),
+    DuckDB(DuckDbClient),
 }
 
 impl DatabaseConnection {
@@ -339,6 +976,154 @@ impl DatabaseConnection {
         serde_json::Value::Null
     }
 
+    // Extract value from SQL Server row
+    fn extract_value_from_mssql_row(&self, row: &tiberius::Row, index: usize) -> serde_json::Value {
+        use tiberius::ColumnData;
+        let Some((_, data)) = row.cells().nth(index) else { return serde_json::Value::Null };
+        let number = |value: f64| serde_json::Number::from_f64(value).map(serde_json::Value::Number);
+        let value = match data {
+            ColumnData::U8(v) => v.map(serde_json::Value::from),
+            ColumnData::I16(v) => v.map(serde_json::Value::from),
+            ColumnData::I32(v) => v.map(serde_json::Value::from),
+            ColumnData::I64(v) => v.map(serde_json::Value::from),
+            ColumnData::F32(v) => v.and_then(|v| number(v as f64)),
+            ColumnData::F64(v) => v.and_then(number),
+            ColumnData::Bit(v) => v.map(serde_json::Value::Bool),
+            ColumnData::String(v) => v.as_ref().map(|s| serde_json::Value::String(s.to_string())),
+            // SQL Server shows uniqueidentifiers in upper case
+            ColumnData::Guid(v) => v.map(|g| serde_json::Value::String(g.to_string().to_uppercase())),
+            ColumnData::Binary(v) => v.as_ref().map(|bytes| {
+                serde_json::Value::String(format!("0x{}", bytes.iter().map(|b| format!("{:02X}", b)).collect::<String>()))
+            }),
+            // Kept as text so money and decimal values don't lose precision
+            ColumnData::Numeric(v) => v.map(|n| serde_json::Value::String(mssql_numeric(n))),
+            ColumnData::Xml(v) => v.as_ref().map(|xml| serde_json::Value::String(xml.to_string())),
+            ColumnData::DateTime(_) | ColumnData::SmallDateTime(_) | ColumnData::DateTime2(_) => row
+                .try_get::<NaiveDateTime, _>(index)
+                .ok()
+                .flatten()
+                .map(|v| serde_json::Value::String(v.format("%Y-%m-%d %H:%M:%S").to_string())),
+            ColumnData::Date(_) => row
+                .try_get::<NaiveDate, _>(index)
+                .ok()
+                .flatten()
+                .map(|v| serde_json::Value::String(v.format("%Y-%m-%d").to_string())),
+            ColumnData::Time(_) => row
+                .try_get::<NaiveTime, _>(index)
+                .ok()
+                .flatten()
+                .map(|v| serde_json::Value::String(v.format("%H:%M:%S").to_string())),
+            ColumnData::DateTimeOffset(_) => row
+                .try_get::<DateTime<chrono::FixedOffset>, _>(index)
+                .ok()
+                .flatten()
+                .map(|v| serde_json::Value::String(v.to_rfc3339())),
+        };
+        value.unwrap_or(serde_json::Value::Null)
+    }
+
+    async fn connect_mssql(config: &crate::DatabaseConfig) -> Result<MssqlClient, AppError> {
+        let mut tds = tiberius::Config::new();
+        tds.host(&config.host);
+        tds.port(if config.port == 0 { 1433 } else { config.port });
+        if !config.database.is_empty() {
+            tds.database(&config.database);
+        }
+        tds.authentication(tiberius::AuthMethod::sql_server(&config.username, &config.password));
+        // Default installations use a self-signed certificate
+        tds.trust_cert();
+
+        let tcp = TcpStream::connect(tds.get_addr())
+            .await
+            .map_err(|e| AppError::DatabaseConnectionFailed(e.to_string()))?;
+        tcp.set_nodelay(true)?;
+        let mut client = tiberius::Client::connect(tds, tcp.compat_write()).await?;
+        // Other modules quote identifiers with double quotes
+        client
+            .simple_query("SET QUOTED_IDENTIFIER ON; SET ANSI_NULLS ON; SET ANSI_PADDING ON; SET ANSI_WARNINGS ON; SET CONCAT_NULL_YIELDS_NULL ON")
+            .await?
+            .into_results()
+            .await?;
+        if config.read_only {
+            // There is no read-only session mode; batches are checked by safety::check_tsql_read_only
+            println!("SQL Server connection is read-only at the application level only");
+        }
+        Ok(Arc::new(tokio::sync::Mutex::new(client)))
+    }
+
+    async fn connect_redis(config: &crate::DatabaseConfig) -> Result<RedisClient, AppError> {
+        let db = redis_database_index(&config.database)?;
+        let non_empty = |s: &str| (!s.is_empty()).then(|| s.to_string());
+        let info = redis::ConnectionInfo {
+            addr: redis::ConnectionAddr::Tcp(config.host.clone(), if config.port == 0 { 6379 } else { config.port }),
+            redis: redis::RedisConnectionInfo {
+                db,
+                username: non_empty(&config.username),
+                password: non_empty(&config.password),
+            },
+        };
+        let manager = redis::Client::open(info)?.get_connection_manager().await?;
+        if config.read_only {
+            // Redis has no read-only sessions; write commands are refused before they are sent
+            println!("Redis connection is read-only at the application level only");
+        }
+        Ok(RedisClient { manager, db })
+    }
+
+    // An empty path or :memory: opens a private in-memory database
+    async fn connect_duckdb(config: &crate::DatabaseConfig) -> Result<DuckDbClient, AppError> {
+        let path = config.database.trim().to_string();
+        let in_memory = path.is_empty() || path == ":memory:";
+        let read_only = config.read_only && !in_memory;
+        let conn = tokio::task::spawn_blocking(move || {
+            let conn = if in_memory {
+                duckdb::Connection::open_in_memory()?
+            } else if read_only {
+                duckdb::Connection::open_with_flags(&path, duckdb::Config::default().access_mode(duckdb::AccessMode::ReadOnly)?)?
+            } else {
+                duckdb::Connection::open(&path)?
+            };
+            Ok::<_, duckdb::Error>(conn)
+        })
+        .await
+        .map_err(|e| AppError::InternalError(format!("DuckDB task failed: {}", e)))??;
+        if config.read_only && in_memory {
+            println!("In-memory DuckDB databases can't be opened read-only; relying on the statement classifier");
+        }
+        Ok(Arc::new(std::sync::Mutex::new(conn)))
+    }
+
+    // The flavor decides how pooled sessions are set up, so it is read over a
+    // short-lived connection before the pool exists
+    async fn detect_server<DB>(connection_string: &str, version_query: &str) -> Result<String, AppError>
+    where
+        DB: sqlx::Database,
+        for<'c> &'c mut DB::Connection: Executor<'c, Database = DB>,
+        String: sqlx::Type<DB> + for<'r> sqlx::Decode<'r, DB>,
+        usize: sqlx::ColumnIndex<DB::Row>,
+    {
+        let mut conn = <DB::Connection as sqlx::Connection>::connect(connection_string).await?;
+        let row = conn.fetch_one(version_query).await?;
+        let version: String = row.try_get(0)?;
+        let _ = sqlx::Connection::close(conn).await;
+        Ok(version)
+    }
+
+    pub fn server_info(&self) -> Option<&ServerInfo> {
+        match self {
+            DatabaseConnection::MySQL(_, server) | DatabaseConnection::PostgreSQL(_, server) => Some(server),
+            _ => None,
+        }
+    }
+
+    // Exact dialect for AI prompts, including the server flavor and version where known
+    pub fn dialect_name(&self) -> String {
+        match self.server_info() {
+            Some(server) => server.dialect_name(),
+            None => self.db_type().dialect_name().to_string(),
+        }
+    }
+
     pub async fn new(config: &crate::DatabaseConfig) -> Result<Self, AppError> {
         let connection_string = match config.db_type {
             DatabaseType::MySQL => {
@@ -369,9 +1154,9 @@ impl DatabaseConnection {
             }
             DatabaseType::SQLite => {
                 if config.database.is_empty() {
-                    return Err(AppError::InvalidConfiguration("SQLite requires a database file path".to_string()));
+                    return Err(AppError::InvalidConfiguration("SQLite requires a database file path or :memory:".to_string()));
                 }
-                format!("sqlite://{}", config.database)
+                config.database.clone()
             }
             DatabaseType::MongoDB => {
                 if config.database.is_empty() {
@@ -386,20 +1171,60 @@ impl DatabaseConnection {
                     )
                 }
             }
+            // tiberius and redis are configured field by field; DuckDB opens a file path
+            DatabaseType::SQLServer | DatabaseType::Redis | DatabaseType::DuckDB => String::new(),
         };
 
+        let read_only = config.read_only;
+
         match config.db_type {
             DatabaseType::MySQL => {
-                let pool = MySqlPool::connect(&connection_string).await?;
-                Ok(DatabaseConnection::MySQL(pool))
+                let server = Self::detect_server::<sqlx::MySql>(&connection_string, "SELECT VERSION()").await?;
+                let server = ServerInfo::from_mysql_version(&server);
+                println!("Connected to {}", server.dialect_name());
+                // TiDB accepts READ ONLY transactions only as a no-op, and errors unless
+                // tidb_enable_noop_functions is on
+                let session_read_only = read_only && server.flavor != ServerFlavor::TiDB;
+                if read_only && !session_read_only {
+                    println!("TiDB has no read-only sessions; relying on the statement classifier");
+                }
+                // Every pooled session is switched to read-only transactions so the
+                // server rejects writes even if they slip past the statement classifier
+                let pool = MySqlPoolOptions::new()
+                    .after_connect(move |conn, _meta| Box::pin(async move {
+                        if session_read_only {
+                            conn.execute("SET SESSION TRANSACTION READ ONLY").await?;
+                        }
+                        Ok(())
+                    }))
+                    .connect(&connection_string)
+                    .await?;
+                Ok(DatabaseConnection::MySQL(pool, server))
             }
             DatabaseType::PostgreSQL => {
-                let pool = PgPool::connect(&connection_string).await?;
-                Ok(DatabaseConnection::PostgreSQL(pool))
+                let server = Self::detect_server::<sqlx::Postgres>(&connection_string, "SELECT version()").await?;
+                let server = ServerInfo::from_postgres_version(&server);
+                println!("Connected to {}", server.dialect_name());
+                // CockroachDB doesn't support SET SESSION CHARACTERISTICS
+                let read_only_statement = match server.flavor {
+                    ServerFlavor::CockroachDB => "SET default_transaction_read_only = on",
+                    _ => "SET SESSION CHARACTERISTICS AS TRANSACTION READ ONLY",
+                };
+                let pool = PgPoolOptions::new()
+                    .after_connect(move |conn, _meta| Box::pin(async move {
+                        if read_only {
+                            conn.execute(read_only_statement).await?;
+                        }
+                        Ok(())
+                    }))
+                    .connect(&connection_string)
+                    .await?;
+                Ok(DatabaseConnection::PostgreSQL(pool, server))
             }
             DatabaseType::SQLite => {
-                let pool = SqlitePool::connect(&connection_string).await?;
-                Ok(DatabaseConnection::SQLite(pool))
+                let pool = Self::sqlite_pool(&connection_string, read_only, &config.sqlite).await?;
+                let aliases = config.sqlite.attach.iter().map(|a| a.alias.trim().to_string()).collect();
+                Ok(DatabaseConnection::SQLite(pool, aliases))
             }
             DatabaseType::MongoDB => {
                 let client = Client::with_uri_str(&connection_string).await?;
@@ -411,49 +1236,172 @@ impl DatabaseConnection {
                 let database = client.database(database_name);
                 Ok(DatabaseConnection::MongoDB(client, database))
             }
+            DatabaseType::SQLServer => Ok(DatabaseConnection::SQLServer(Self::connect_mssql(config).await?)),
+            DatabaseType::Redis => Ok(DatabaseConnection::Redis(Self::connect_redis(config).await?)),
+            DatabaseType::DuckDB => Ok(DatabaseConnection::DuckDB(Self::connect_duckdb(config).await?)),
+        }
+    }
+
+    async fn sqlite_pool(path: &str, read_only: bool, sqlite: &SqliteOptions) -> Result<SqlitePool, AppError> {
+        let in_memory = path == ":memory:";
+        let mut options = if in_memory {
+            SqliteConnectOptions::from_str("sqlite::memory:")?
+        } else {
+            SqliteConnectOptions::new()
+                .filename(path)
+                .create_if_missing(sqlite.create_if_missing && !read_only)
+                .read_only(read_only)
+        };
+        for extension in sqlite.extensions.iter().filter(|e| !e.trim().is_empty()) {
+            options = options.extension(extension.trim().to_string());
+        }
+
+        let mut attach = Vec::new();
+        for database in &sqlite.attach {
+            let alias = database.alias.trim();
+            if alias.is_empty() || database.path.trim().is_empty() {
+                return Err(AppError::InvalidConfiguration("Attached databases need a path and an alias".to_string()));
+            }
+            if alias.eq_ignore_ascii_case("main") || alias.eq_ignore_ascii_case("temp") {
+                return Err(AppError::InvalidConfiguration(format!("{} is reserved and can't be used as an alias", alias)));
+            }
+            attach.push(format!(
+                "ATTACH DATABASE '{}' AS {}",
+                database.path.trim().replace('\'', "''"),
+                quote_identifier(alias, '"')
+            ));
+        }
+
+        // ATTACH is per connection, so every pooled connection repeats it
+        let mut pool = SqlitePoolOptions::new().after_connect(move |conn, _meta| {
+            let attach = attach.clone();
+            Box::pin(async move {
+                for statement in &attach {
+                    conn.execute(statement.as_str()).await?;
+                }
+                // Also covers attached files and in-memory databases, which mode=ro doesn't
+                if read_only {
+                    conn.execute("PRAGMA query_only = ON").await?;
+                }
+                Ok(())
+            })
+        });
+        // A shared in-memory database lives only while a connection to it is open
+        if in_memory {
+            pool = pool.min_connections(1).idle_timeout(None).max_lifetime(None);
+        }
+        Ok(pool.connect_with(options).await?)
+    }
+
+    // Connection to another database on the same server. MySQL and PostgreSQL pools are bound
+    // to one database, so a new pool is built; MongoDB reuses the client.
+    pub async fn use_database(&self, config: &crate::DatabaseConfig, database: &str) -> Result<Self, AppError> {
+        match self {
+            DatabaseConnection::MongoDB(client, _) => Ok(DatabaseConnection::MongoDB(client.clone(), client.database(database))),
+            DatabaseConnection::SQLite(..) => Err(AppError::InvalidConfiguration(
+                "SQLite has one database per file; connect to the other file instead".to_string(),
+            )),
+            DatabaseConnection::DuckDB(_) => Err(AppError::InvalidConfiguration(
+                "Attached DuckDB databases are switched with USE or qualified names".to_string(),
+            )),
+            _ => {
+                if !self.list_databases().await?.iter().any(|d| d == database) {
+                    return Err(AppError::InvalidConfiguration(format!("Database {} does not exist", database)));
+                }
+                let mut config = config.clone();
+                config.database = database.to_string();
+                let connection = DatabaseConnection::new(&config).await?;
+                connection.test_connection().await?;
+                Ok(connection)
+            }
+        }
+    }
+
+    // Close pooled connections; used for short-lived secondary connections
+    pub async fn close(&self) {
+        match self {
+            DatabaseConnection::MySQL(pool, _) => pool.close().await,
+            DatabaseConnection::PostgreSQL(pool, _) => pool.close().await,
+            DatabaseConnection::SQLite(pool, _) => pool.close().await,
+            // The client closes when the last handle is dropped
+            DatabaseConnection::MongoDB(..)
+            | DatabaseConnection::SQLServer(_)
+            | DatabaseConnection::Redis(_)
+            | DatabaseConnection::DuckDB(_) => {}
         }
     }
 
     pub async fn test_connection(&self) -> Result<(), AppError> {
         match self {
-            DatabaseConnection::MySQL(pool) => {
+            DatabaseConnection::MySQL(pool, _) => {
                 sqlx::query("SELECT 1").fetch_one(pool).await?;
             }
-            DatabaseConnection::PostgreSQL(pool) => {
+            DatabaseConnection::PostgreSQL(pool, _) => {
                 sqlx::query("SELECT 1").fetch_one(pool).await?;
             }
-            DatabaseConnection::SQLite(pool) => {
+            DatabaseConnection::SQLite(pool, _) => {
                 sqlx::query("SELECT 1").fetch_one(pool).await?;
             }
             DatabaseConnection::MongoDB(_, database) => {
                 // Test MongoDB connection by listing collections
                 let _collections = database.list_collection_names(None).await?;
             }
+            DatabaseConnection::SQLServer(client) => {
+                mssql_rows(client, "SELECT 1", &[]).await?;
+            }
+            DatabaseConnection::Redis(client) => {
+                redis_command(client, "PING").await?;
+            }
+            DatabaseConnection::DuckDB(client) => {
+                duckdb_rows(client, "SELECT 1", Vec::new()).await?;
+            }
         }
         Ok(())
     }
 
     pub async fn get_schema(&self) -> Result<DatabaseSchema, AppError> {
         let tables = match self {
-            DatabaseConnection::MySQL(pool) => {
-                self.get_mysql_schema(pool).await?
+            DatabaseConnection::MySQL(pool, server) => {
+                self.get_mysql_schema(pool, server).await?
             }
-            DatabaseConnection::PostgreSQL(pool) => {
-                self.get_postgresql_schema(pool).await?
+            DatabaseConnection::PostgreSQL(pool, server) => {
+                self.get_postgresql_schema(pool, server).await?
             }
-            DatabaseConnection::SQLite(pool) => {
+            DatabaseConnection::SQLite(pool, _) => {
                 self.get_sqlite_schema(pool).await?
             }
             DatabaseConnection::MongoDB(client, database) => {
                 self.get_mongodb_schema(client, database).await?
             }
+            DatabaseConnection::SQLServer(client) => {
+                self.get_mssql_schema(client).await?
+            }
+            // Keys have no schema; they are browsed through redis_browser instead
+            DatabaseConnection::Redis(_) => Vec::new(),
+            DatabaseConnection::DuckDB(client) => {
+                self.get_duckdb_schema(client).await?
+            }
         };
 
+        let mut tables = tables;
+        let mut indexes = self.load_indexes(None).await?;
+        let mut foreign_keys = self.load_foreign_keys(None).await?;
+        for table in tables.iter_mut() {
+            table.indexes = indexes.remove(&table.name).unwrap_or_default();
+            table.foreign_keys = foreign_keys.remove(&table.name).unwrap_or_default();
+        }
+
         Ok(DatabaseSchema { tables })
     }
 
-    async fn get_mysql_schema(&self, pool: &MySqlPool) -> Result<Vec<TableInfo>, AppError> {
-        let tables_query = "SELECT TABLE_NAME FROM INFORMATION_SCHEMA.TABLES WHERE TABLE_SCHEMA = DATABASE()";
+    async fn get_mysql_schema(&self, pool: &MySqlPool, server: &ServerInfo) -> Result<Vec<TableInfo>, AppError> {
+        // MariaDB and TiDB list sequences as tables
+        let tables_query = match server.flavor {
+            ServerFlavor::MySQL => "SELECT TABLE_NAME FROM INFORMATION_SCHEMA.TABLES WHERE TABLE_SCHEMA = DATABASE()",
+            _ => "SELECT TABLE_NAME FROM INFORMATION_SCHEMA.TABLES WHERE TABLE_SCHEMA = DATABASE() AND TABLE_TYPE <> 'SEQUENCE'",
+        };
+        // MariaDB 10.2.7+ reports defaults as SQL expressions, with literals already quoted
+        let quoted_defaults = server.flavor == ServerFlavor::MariaDB && server.version_at_least(10, 2);
         let table_rows = sqlx::query(tables_query).fetch_all(pool).await?;
         
         let mut tables = Vec::new();
@@ -466,7 +1414,12 @@ impl DatabaseConnection {
                     COLUMN_NAME,
                     DATA_TYPE,
                     IS_NULLABLE,
-                    COLUMN_KEY
+                    COLUMN_KEY,
+                    COLUMN_TYPE,
+                    COLUMN_DEFAULT,
+                    EXTRA,
+                    COLUMN_COMMENT,
+                    COLLATION_NAME
                 FROM INFORMATION_SCHEMA.COLUMNS 
                 WHERE TABLE_SCHEMA = DATABASE() AND TABLE_NAME = ?
                 ORDER BY ORDINAL_POSITION
@@ -483,25 +1436,54 @@ impl DatabaseConnection {
                 let data_type: String = col_row.get("DATA_TYPE");
                 let is_nullable: String = col_row.get("IS_NULLABLE");
                 let column_key: String = col_row.get("COLUMN_KEY");
+                let column_type: String = col_row.get("COLUMN_TYPE");
+                let column_default: Option<String> = col_row.get("COLUMN_DEFAULT");
+                let extra: String = col_row.get("EXTRA");
+                let comment: String = col_row.get("COLUMN_COMMENT");
+                
+                // Literal defaults are reported unquoted; expressions are flagged in EXTRA.
+                // Numeric columns never need quotes, but '42' on a varchar does
+                let numeric = matches!(
+                    data_type.as_str(),
+                    "tinyint" | "smallint" | "mediumint" | "int" | "integer" | "bigint" | "decimal" | "numeric"
+                        | "float" | "double" | "real" | "bit" | "year"
+                );
+                let default_value = column_default.map(|d| {
+                    if quoted_defaults {
+                        return d;
+                    }
+                    let is_expression = extra.contains("DEFAULT_GENERATED")
+                        || numeric
+                        || d.eq_ignore_ascii_case("NULL")
+                        || d.to_uppercase().starts_with("CURRENT_TIMESTAMP");
+                    if is_expression { d } else { format!("'{}'", d.replace('\'', "''")) }
+                });
                 
                 columns.push(ColumnInfo {
                     name: column_name,
                     data_type,
                     is_nullable: is_nullable == "YES",
                     is_primary_key: column_key == "PRI",
+                    column_type: Some(column_type),
+                    default_value,
+                    extra: Some(extra).filter(|e| !e.is_empty()),
+                    comment: Some(comment).filter(|c| !c.is_empty()),
+                    collation: col_row.get("COLLATION_NAME"),
                 });
             }
             
             tables.push(TableInfo {
                 name: table_name,
                 columns,
+                indexes: Vec::new(),
+                foreign_keys: Vec::new(),
             });
         }
         
         Ok(tables)
     }
 
-    async fn get_postgresql_schema(&self, pool: &PgPool) -> Result<Vec<TableInfo>, AppError> {
+    async fn get_postgresql_schema(&self, pool: &PgPool, server: &ServerInfo) -> Result<Vec<TableInfo>, AppError> {
         let tables_query = r#"
             SELECT table_name 
             FROM information_schema.tables 
@@ -520,7 +1502,14 @@ impl DatabaseConnection {
                     column_name,
                     data_type,
                     is_nullable,
-                    CASE WHEN pk.column_name IS NOT NULL THEN true ELSE false END as is_primary_key
+                    CASE WHEN pk.column_name IS NOT NULL THEN true ELSE false END as is_primary_key,
+                    column_default,
+                    (
+                        SELECT format_type(a.atttypid, a.atttypmod)
+                        FROM pg_attribute a
+                        WHERE a.attrelid = format('%I.%I', c.table_schema, c.table_name)::regclass
+                          AND a.attname = c.column_name
+                    ) AS column_type
                 FROM information_schema.columns c
                 LEFT JOIN (
                     SELECT ku.table_name, ku.column_name
@@ -533,6 +1522,33 @@ impl DatabaseConnection {
                 WHERE c.table_name = $1
                 ORDER BY c.ordinal_position
             "#;
+            // CockroachDB can't cast format() output to regclass and lists the hidden rowid
+            // column of tables without a primary key; crdb_sql_type has the full type instead
+            let cockroach_columns_query = r#"
+                SELECT 
+                    c.column_name,
+                    c.data_type,
+                    c.is_nullable,
+                    EXISTS (
+                        SELECT 1
+                        FROM information_schema.table_constraints tc
+                        JOIN information_schema.key_column_usage ku
+                            ON tc.constraint_name = ku.constraint_name
+                            AND tc.table_schema = ku.table_schema
+                            AND tc.table_name = ku.table_name
+                        WHERE tc.constraint_type = 'PRIMARY KEY' AND tc.table_schema = c.table_schema
+                            AND tc.table_name = c.table_name AND ku.column_name = c.column_name
+                    ) AS is_primary_key,
+                    c.column_default,
+                    c.crdb_sql_type AS column_type
+                FROM information_schema.columns c
+                WHERE c.table_schema = 'public' AND c.table_name = $1 AND c.is_hidden = 'NO'
+                ORDER BY c.ordinal_position
+            "#;
+            let columns_query = match server.flavor {
+                ServerFlavor::CockroachDB => cockroach_columns_query,
+                _ => columns_query,
+            };
             
             let column_rows = sqlx::query(columns_query)
                 .bind(&table_name)
@@ -551,12 +1567,19 @@ impl DatabaseConnection {
                     data_type,
                     is_nullable: is_nullable == "YES",
                     is_primary_key,
+                    column_type: col_row.get("column_type"),
+                    default_value: col_row.get("column_default"),
+                    extra: None,
+                    comment: None,
+                    collation: None,
                 });
             }
             
             tables.push(TableInfo {
                 name: table_name,
                 columns,
+                indexes: Vec::new(),
+                foreign_keys: Vec::new(),
             });
         }
         
@@ -564,15 +1587,36 @@ impl DatabaseConnection {
     }
 
     async fn get_sqlite_schema(&self, pool: &SqlitePool) -> Result<Vec<TableInfo>, AppError> {
-        let tables_query = "SELECT name FROM sqlite_master WHERE type='table' AND name NOT LIKE 'sqlite_%'";
-        let table_rows = sqlx::query(tables_query).fetch_all(pool).await?;
+        // main plus attached databases; tables of attached ones are named "alias.table"
+        let schemas: Vec<String> = sqlx::query("PRAGMA database_list")
+            .fetch_all(pool)
+            .await?
+            .iter()
+            .map(|row| row.get::<String, _>("name"))
+            .filter(|name| name != "temp")
+            .collect();
+        let mut table_names = Vec::new();
+        for schema in &schemas {
+            let tables_query = format!(
+                "SELECT name FROM {}.sqlite_master WHERE type='table' AND name NOT LIKE 'sqlite_%'",
+                quote_identifier(schema, '"')
+            );
+            for row in sqlx::query(&tables_query).fetch_all(pool).await? {
+                let name: String = row.get("name");
+                let attached = (schema != "main").then(|| schema.clone());
+                table_names.push((attached, name));
+            }
+        }
         
         let mut tables = Vec::new();
         
-        for row in table_rows {
-            let table_name: String = row.get("name");
+        for (schema, name) in table_names {
+            let table_name = match &schema {
+                Some(schema) => format!("{}.{}", schema, name),
+                None => name.clone(),
+            };
             
-            let columns_query = format!("PRAGMA table_info({})", table_name);
+            let columns_query = sqlite_pragma("table_info", &schema, &name);
             let column_rows = sqlx::query(&columns_query).fetch_all(pool).await?;
             
             let mut columns = Vec::new();
@@ -582,57 +1626,237 @@ impl DatabaseConnection {
                 let data_type: String = col_row.get("type");
                 let not_null: i32 = col_row.get("notnull");
                 let pk: i32 = col_row.get("pk");
+                let default_value: Option<String> = col_row.get("dflt_value");
                 
                 columns.push(ColumnInfo {
                     name: column_name,
+                    column_type: Some(data_type.clone()),
                     data_type,
                     is_nullable: not_null == 0,
-                    is_primary_key: pk == 1,
+                    is_primary_key: pk > 0,
+                    default_value,
+                    extra: None,
+                    comment: None,
+                    collation: None,
                 });
             }
             
             tables.push(TableInfo {
                 name: table_name,
                 columns,
+                indexes: Vec::new(),
+                foreign_keys: Vec::new(),
             });
         }
         
         Ok(tables)
     }
 
+    async fn get_mssql_schema(&self, client: &MssqlClient) -> Result<Vec<TableInfo>, AppError> {
+        let rows = mssql_rows(client, r#"
+            SELECT c.TABLE_NAME, c.COLUMN_NAME, c.DATA_TYPE, c.IS_NULLABLE, c.COLUMN_DEFAULT,
+                   CAST(c.CHARACTER_MAXIMUM_LENGTH AS int) AS max_length,
+                   CAST(c.NUMERIC_PRECISION AS int) AS numeric_precision,
+                   CAST(c.NUMERIC_SCALE AS int) AS numeric_scale,
+                   CAST(CASE WHEN EXISTS (
+                       SELECT 1 FROM INFORMATION_SCHEMA.TABLE_CONSTRAINTS tc
+                       JOIN INFORMATION_SCHEMA.KEY_COLUMN_USAGE k
+                           ON k.CONSTRAINT_SCHEMA = tc.CONSTRAINT_SCHEMA AND k.CONSTRAINT_NAME = tc.CONSTRAINT_NAME
+                       WHERE tc.CONSTRAINT_TYPE = 'PRIMARY KEY' AND tc.TABLE_SCHEMA = c.TABLE_SCHEMA
+                           AND tc.TABLE_NAME = c.TABLE_NAME AND k.COLUMN_NAME = c.COLUMN_NAME
+                   ) THEN 1 ELSE 0 END AS bit) AS is_primary_key
+            FROM INFORMATION_SCHEMA.COLUMNS c
+            JOIN INFORMATION_SCHEMA.TABLES t ON t.TABLE_SCHEMA = c.TABLE_SCHEMA AND t.TABLE_NAME = c.TABLE_NAME
+            WHERE t.TABLE_SCHEMA = SCHEMA_NAME() AND t.TABLE_TYPE = 'BASE TABLE'
+            ORDER BY c.TABLE_NAME, c.ORDINAL_POSITION
+        "#, &[]).await?;
+
+        let mut tables: Vec<TableInfo> = Vec::new();
+        for row in rows {
+            let table_name = mssql_text(&row, "TABLE_NAME").unwrap_or_default();
+            let data_type = mssql_text(&row, "DATA_TYPE").unwrap_or_default();
+            let max_length = row.try_get::<i32, _>("max_length").ok().flatten();
+            let precision = row.try_get::<i32, _>("numeric_precision").ok().flatten();
+            let scale = row.try_get::<i32, _>("numeric_scale").ok().flatten();
+            let column_type = match (data_type.as_str(), max_length, precision, scale) {
+                (_, Some(-1), _, _) => format!("{}(max)", data_type),
+                (_, Some(length), _, _) => format!("{}({})", data_type, length),
+                ("decimal" | "numeric", _, Some(precision), Some(scale)) => format!("{}({},{})", data_type, precision, scale),
+                _ => data_type.clone(),
+            };
+            let column = ColumnInfo {
+                name: mssql_text(&row, "COLUMN_NAME").unwrap_or_default(),
+                is_nullable: mssql_text(&row, "IS_NULLABLE").as_deref() == Some("YES"),
+                is_primary_key: row.try_get::<bool, _>("is_primary_key").ok().flatten().unwrap_or(false),
+                column_type: Some(column_type),
+                default_value: mssql_text(&row, "COLUMN_DEFAULT").map(|d| mssql_default(&d)),
+                data_type,
+                extra: None,
+                comment: None,
+                collation: None,
+            };
+            match tables.last_mut().filter(|t| t.name == table_name) {
+                Some(table) => table.columns.push(column),
+                None => tables.push(TableInfo {
+                    name: table_name,
+                    columns: vec![column],
+                    indexes: Vec::new(),
+                    foreign_keys: Vec::new(),
+                }),
+            }
+        }
+        
+        Ok(tables)
+    }
+
+    // Files read with read_parquet()/read_csv() or 'file.parquet' aren't listed; only tables and views are
+    async fn get_duckdb_schema(&self, client: &DuckDbClient) -> Result<Vec<TableInfo>, AppError> {
+        let rows = duckdb_rows(client, r#"
+            SELECT c.table_name, c.column_name, c.data_type, c.is_nullable, c.column_default,
+                   EXISTS (
+                       SELECT 1 FROM duckdb_constraints() k
+                       WHERE k.constraint_type = 'PRIMARY KEY' AND k.schema_name = c.table_schema
+                           AND k.table_name = c.table_name AND list_contains(k.constraint_column_names, c.column_name)
+                   ) AS is_primary_key
+            FROM information_schema.columns c
+            WHERE c.table_catalog = current_database() AND c.table_schema = current_schema()
+            ORDER BY c.table_name, c.ordinal_position
+        "#, Vec::new()).await?;
+
+        let mut tables: Vec<TableInfo> = Vec::new();
+        for row in rows {
+            let table_name = json_text(row.first()).unwrap_or_default();
+            let data_type = json_text(row.get(2)).unwrap_or_default();
+            let column = ColumnInfo {
+                name: json_text(row.get(1)).unwrap_or_default(),
+                is_nullable: json_text(row.get(3)).as_deref() == Some("YES"),
+                is_primary_key: row.get(5).and_then(|v| v.as_bool()).unwrap_or(false),
+                column_type: Some(data_type.clone()),
+                default_value: json_text(row.get(4)),
+                data_type,
+                extra: None,
+                comment: None,
+                collation: None,
+            };
+            match tables.last_mut().filter(|t| t.name == table_name) {
+                Some(table) => table.columns.push(column),
+                None => tables.push(TableInfo {
+                    name: table_name,
+                    columns: vec![column],
+                    indexes: Vec::new(),
+                    foreign_keys: Vec::new(),
+                }),
+            }
+        }
+        
+        Ok(tables)
+    }
+
     pub async fn execute_query(&self, query: &str) -> Result<QueryResult, AppError> {
         match self {
-            DatabaseConnection::MySQL(pool) => {
+            DatabaseConnection::MySQL(pool, _) => {
                 self.execute_mysql_query(pool, query).await
             }
-            DatabaseConnection::PostgreSQL(pool) => {
+            DatabaseConnection::PostgreSQL(pool, _) => {
                 self.execute_postgresql_query(pool, query).await
             }
-            DatabaseConnection::SQLite(pool) => {
+            DatabaseConnection::SQLite(pool, _) => {
                 self.execute_sqlite_query(pool, query).await
             }
             DatabaseConnection::MongoDB(client, database) => {
                 self.execute_mongodb_query(client, database, query).await
             }
+            DatabaseConnection::SQLServer(client) => {
+                self.execute_mssql_query(client, query).await
+            }
+            DatabaseConnection::Redis(client) => {
+                self.execute_redis_query(client, query).await
+            }
+            // Parquet, CSV and JSON files can be queried in place, e.g. SELECT * FROM 'data.parquet'
+            DatabaseConnection::DuckDB(client) => {
+                let query = query.to_string();
+                duckdb_blocking(client, move |conn| duckdb_query(conn, &query, Vec::new())).await
+            }
         }
     }
 
-    async fn execute_mysql_query(&self, pool: &MySqlPool, query: &str) -> Result<QueryResult, AppError> {
-        println!("Executing MySQL query: {}", query);
-        
-        let rows = match sqlx::query(query).fetch_all(pool).await {
-            Ok(rows) => rows,
-            Err(e) => {
-                println!("MySQL query error: {}", e);
-                return Err(AppError::QueryExecutionFailed(format!("MySQL query failed: {}", e)));
-            }
-        };
-        
-        if rows.is_empty() {
-            return Ok(QueryResult {
-                columns: vec![],
-                rows: vec![],
+    async fn execute_redis_query(&self, client: &RedisClient, query: &str) -> Result<QueryResult, AppError> {
+        let mut timer = QueryTimer::start();
+        let (args, reply) = redis_command(client, query).await?;
+        timer.mark_row();
+        let (columns, rows) = redis_reply_table(&args, reply);
+
+        let row_count = rows.len();
+        let (execution_time_ms, fetch_time_ms) = timer.finish();
+        Ok(QueryResult {
+            columns,
+            rows,
+            row_count,
+            execution_time_ms,
+            fetch_time_ms,
+        })
+    }
+
+    async fn execute_mssql_query(&self, client: &MssqlClient, query: &str) -> Result<QueryResult, AppError> {
+        let mut timer = QueryTimer::start();
+        let mut client = client.lock().await;
+        let mut stream = client.simple_query(query).await.map_err(mssql_failed)?;
+        let mut columns = Vec::new();
+        let mut result_rows = Vec::new();
+        // Only the first result set is returned, as for the other backends
+        while let Some(item) = stream.try_next().await.map_err(mssql_failed)? {
+            match item {
+                tiberius::QueryItem::Metadata(meta) if meta.result_index() == 0 => {
+                    columns = meta.columns().iter().map(|c| c.name().to_string()).collect();
+                }
+                tiberius::QueryItem::Row(row) if row.result_index() == 0 => {
+                    timer.mark_row();
+                    result_rows.push((0..row.len()).map(|i| self.extract_value_from_mssql_row(&row, i)).collect());
+                }
+                _ => {}
+            }
+        }
+        
+        let row_count = result_rows.len();
+        let (execution_time_ms, fetch_time_ms) = timer.finish();
+        Ok(QueryResult {
+            columns,
+            rows: result_rows,
+            row_count,
+            execution_time_ms,
+            fetch_time_ms,
+        })
+    }
+
+    async fn execute_mysql_query(&self, pool: &MySqlPool, query: &str) -> Result<QueryResult, AppError> {
+        println!("Executing MySQL query: {}", query);
+        
+        let mut timer = QueryTimer::start();
+        let mut stream = sqlx::query(query).fetch(pool);
+        let mut rows = Vec::new();
+        loop {
+            match stream.try_next().await {
+                Ok(Some(row)) => {
+                    timer.mark_row();
+                    rows.push(row);
+                }
+                Ok(None) => break,
+                Err(e) => {
+                    println!("MySQL query error: {}", e);
+                    return Err(AppError::QueryExecutionFailed(format!("MySQL query failed: {}", e)));
+                }
+            }
+        }
+        drop(stream);
+        
+        if rows.is_empty() {
+            let (execution_time_ms, fetch_time_ms) = timer.finish();
+            return Ok(QueryResult {
+                columns: vec![],
+                rows: vec![],
                 row_count: 0,
+                execution_time_ms,
+                fetch_time_ms,
             });
         }
         
@@ -650,29 +1874,45 @@ impl DatabaseConnection {
         }
         
         println!("MySQL query executed successfully, {} rows returned", row_count);
+        let (execution_time_ms, fetch_time_ms) = timer.finish();
         Ok(QueryResult {
             columns,
             rows: result_rows,
             row_count,
+            execution_time_ms,
+            fetch_time_ms,
         })
     }
 
     async fn execute_postgresql_query(&self, pool: &PgPool, query: &str) -> Result<QueryResult, AppError> {
         println!("Executing PostgreSQL query: {}", query);
         
-        let rows = match sqlx::query(query).fetch_all(pool).await {
-            Ok(rows) => rows,
-            Err(e) => {
-                println!("PostgreSQL query error: {}", e);
-                return Err(AppError::QueryExecutionFailed(format!("PostgreSQL query failed: {}", e)));
+        let mut timer = QueryTimer::start();
+        let mut stream = sqlx::query(query).fetch(pool);
+        let mut rows = Vec::new();
+        loop {
+            match stream.try_next().await {
+                Ok(Some(row)) => {
+                    timer.mark_row();
+                    rows.push(row);
+                }
+                Ok(None) => break,
+                Err(e) => {
+                    println!("PostgreSQL query error: {}", e);
+                    return Err(AppError::QueryExecutionFailed(format!("PostgreSQL query failed: {}", e)));
+                }
             }
-        };
+        }
+        drop(stream);
         
         if rows.is_empty() {
+            let (execution_time_ms, fetch_time_ms) = timer.finish();
             return Ok(QueryResult {
                 columns: vec![],
                 rows: vec![],
                 row_count: 0,
+                execution_time_ms,
+                fetch_time_ms,
             });
         }
         
@@ -689,29 +1929,45 @@ impl DatabaseConnection {
             result_rows.push(row_data);
         }
         
+        let (execution_time_ms, fetch_time_ms) = timer.finish();
         Ok(QueryResult {
             columns,
             rows: result_rows,
             row_count,
+            execution_time_ms,
+            fetch_time_ms,
         })
     }
 
     async fn execute_sqlite_query(&self, pool: &SqlitePool, query: &str) -> Result<QueryResult, AppError> {
         println!("Executing SQLite query: {}", query);
         
-        let rows = match sqlx::query(query).fetch_all(pool).await {
-            Ok(rows) => rows,
-            Err(e) => {
-                println!("SQLite query error: {}", e);
-                return Err(AppError::QueryExecutionFailed(format!("SQLite query failed: {}", e)));
+        let mut timer = QueryTimer::start();
+        let mut stream = sqlx::query(query).fetch(pool);
+        let mut rows = Vec::new();
+        loop {
+            match stream.try_next().await {
+                Ok(Some(row)) => {
+                    timer.mark_row();
+                    rows.push(row);
+                }
+                Ok(None) => break,
+                Err(e) => {
+                    println!("SQLite query error: {}", e);
+                    return Err(AppError::QueryExecutionFailed(format!("SQLite query failed: {}", e)));
+                }
             }
-        };
+        }
+        drop(stream);
         
         if rows.is_empty() {
+            let (execution_time_ms, fetch_time_ms) = timer.finish();
             return Ok(QueryResult {
                 columns: vec![],
                 rows: vec![],
                 row_count: 0,
+                execution_time_ms,
+                fetch_time_ms,
             });
         }
         
@@ -728,10 +1984,13 @@ impl DatabaseConnection {
             result_rows.push(row_data);
         }
         
+        let (execution_time_ms, fetch_time_ms) = timer.finish();
         Ok(QueryResult {
             columns,
             rows: result_rows,
             row_count,
+            execution_time_ms,
+            fetch_time_ms,
         })
     }
 
@@ -765,6 +2024,11 @@ impl DatabaseConnection {
                         data_type: data_type.to_string(),
                         is_primary_key: key == "_id",
                         is_nullable: true,
+                        column_type: None,
+                        default_value: None,
+                        extra: None,
+                        comment: None,
+                        collation: None,
                     });
                 }
             }
@@ -772,50 +2036,1089 @@ impl DatabaseConnection {
             tables.push(TableInfo {
                 name: collection_name,
                 columns,
+                indexes: Vec::new(),
+                foreign_keys: Vec::new(),
             });
         }
 
         Ok(tables)
     }
 
-    async fn execute_mongodb_query(&self, _client: &Client, database: &MongoDatabase, _query: &str) -> Result<QueryResult, AppError> {
-        // Simple MongoDB query execution
-        // For now, just return a basic result
-        // In a real implementation, you'd parse the query and execute it
-        
-        let collections = database.list_collection_names(None).await?;
-        
-        let columns = vec!["collection".to_string(), "count".to_string()];
+    async fn execute_mongodb_query(&self, _client: &Client, database: &MongoDatabase, query: &str) -> Result<QueryResult, AppError> {
+        // The query is a database command document, the same one check_mongodb_command guards
+        let command = parse_mongodb_command(query)?;
+        let mut timer = QueryTimer::start();
+        let result = database.run_command(command, None).await?;
+
+        // Cursor commands (find, aggregate, ...) return their first batch of documents;
+        // anything else is shown as the single reply document
+        let documents = match result.get_document("cursor").and_then(|c| c.get_array("firstBatch")) {
+            Ok(batch) => batch.iter().filter_map(|d| d.as_document().cloned()).collect(),
+            Err(_) => vec![result],
+        };
+
+        let mut columns: Vec<String> = Vec::new();
+        for document in &documents {
+            for key in document.keys() {
+                if !columns.contains(key) {
+                    columns.push(key.clone());
+                }
+            }
+        }
 
         let mut rows = Vec::new();
-        for collection_name in collections {
-            let collection = database.collection::<bson::Document>(&collection_name);
-            let count = collection.count_documents(None, None).await?;
-            
-            let mut row = Vec::new();
-            row.push(serde_json::Value::String(collection_name));
-            row.push(serde_json::Value::Number(serde_json::Number::from(count)));
-            rows.push(row);
+        for document in documents {
+            timer.mark_row();
+            rows.push(columns.iter()
+                .map(|c| document.get(c).cloned().map(|v| v.into_relaxed_extjson()).unwrap_or(serde_json::Value::Null))
+                .collect());
         }
 
         let row_count = rows.len();
+        let (execution_time_ms, fetch_time_ms) = timer.finish();
         Ok(QueryResult {
             columns,
             rows,
             row_count,
+            execution_time_ms,
+            fetch_time_ms,
         })
     }
 
+    pub async fn explain_query(&self, query: &str, analyze: bool) -> Result<QueryPlan, AppError> {
+        let query = query.trim().trim_end_matches(';');
+        match self {
+            DatabaseConnection::MySQL(pool, _) => {
+                let explain_sql = format!("EXPLAIN FORMAT=JSON {}", query);
+                let row = sqlx::query(&explain_sql).fetch_one(pool).await
+                    .map_err(|e| AppError::QueryExecutionFailed(format!("MySQL explain failed: {}", e)))?;
+                let plan: String = row.try_get(0)?;
+                let raw: serde_json::Value = serde_json::from_str(&plan)
+                    .map_err(|e| AppError::QueryExecutionFailed(format!("Invalid MySQL plan: {}", e)))?;
+                Ok(explain::normalize_mysql_plan(raw))
+            }
+            DatabaseConnection::PostgreSQL(pool, _) => {
+                // ANALYZE really executes the statement, so it runs in a transaction
+                // that is always rolled back
+                let mut tx = pool.begin().await?;
+                let plan = Self::explain_postgresql(&mut tx, query, analyze).await;
+                tx.rollback().await?;
+                plan
+            }
+            DatabaseConnection::SQLite(pool, _) => {
+                let mut conn = pool.acquire().await?;
+                Self::explain_sqlite(&mut conn, query).await
+            }
+            DatabaseConnection::MongoDB(_client, database) => {
+                let command = parse_mongodb_command(query)?;
+                let verbosity = if analyze { "executionStats" } else { "queryPlanner" };
+                let result = database
+                    .run_command(doc! { "explain": command, "verbosity": verbosity }, None)
+                    .await?;
+                Ok(explain::normalize_mongodb_plan(bson::Bson::Document(result).into_relaxed_extjson()))
+            }
+            DatabaseConnection::SQLServer(_) => {
+                Err(AppError::QueryExecutionFailed("Query plans are not supported for SQL Server yet".to_string()))
+            }
+            DatabaseConnection::Redis(_) => {
+                Err(AppError::QueryExecutionFailed("Redis commands have no query plans".to_string()))
+            }
+            DatabaseConnection::DuckDB(_) => {
+                Err(AppError::QueryExecutionFailed("Query plans are not supported for DuckDB yet".to_string()))
+            }
+        }
+    }
+
+    async fn explain_postgresql(conn: &mut sqlx::PgConnection, query: &str, analyze: bool) -> Result<QueryPlan, AppError> {
+        let explain_sql = if analyze {
+            format!("EXPLAIN (ANALYZE, FORMAT JSON) {}", query)
+        } else {
+            format!("EXPLAIN (FORMAT JSON) {}", query)
+        };
+        let row = sqlx::query(&explain_sql).fetch_one(&mut *conn).await
+            .map_err(|e| AppError::QueryExecutionFailed(format!("PostgreSQL explain failed: {}", e)))?;
+        let raw: serde_json::Value = row.try_get(0)?;
+        Ok(explain::normalize_postgresql_plan(raw))
+    }
+
+    async fn explain_sqlite(conn: &mut sqlx::SqliteConnection, query: &str) -> Result<QueryPlan, AppError> {
+        let explain_sql = format!("EXPLAIN QUERY PLAN {}", query);
+        let rows = sqlx::query(&explain_sql).fetch_all(&mut *conn).await
+            .map_err(|e| AppError::QueryExecutionFailed(format!("SQLite explain failed: {}", e)))?;
+        let steps = rows.iter()
+            .map(|row| Ok((row.try_get::<i64, _>("id")?, row.try_get::<i64, _>("parent")?, row.try_get::<String, _>("detail")?)))
+            .collect::<Result<Vec<_>, sqlx::Error>>()
+            .map_err(|e| AppError::QueryExecutionFailed(format!("Unexpected SQLite plan row: {}", e)))?;
+        Ok(explain::normalize_sqlite_plan(steps))
+    }
+
+    // Explain the original query, apply the suggested index DDL inside a scratch
+    // transaction, explain the rewritten query, then roll everything back
+    pub async fn compare_plans(&self, original: &str, rewritten: &str, index_ddl: &[String]) -> Result<PlanComparison, AppError> {
+        let original = original.trim().trim_end_matches(';');
+        let rewritten = rewritten.trim().trim_end_matches(';');
+        match self {
+            DatabaseConnection::PostgreSQL(pool, _) => {
+                let mut tx = pool.begin().await?;
+                let result = async {
+                    let before = Self::explain_postgresql(&mut tx, original, true).await?;
+                    for ddl in index_ddl {
+                        // CONCURRENTLY is not allowed inside a transaction block
+                        let ddl = ddl.replace("CONCURRENTLY ", "").replace("concurrently ", "");
+                        sqlx::query(&ddl).execute(&mut *tx).await
+                            .map_err(|e| AppError::QueryExecutionFailed(format!("Failed to apply index: {}", e)))?;
+                    }
+                    let after = Self::explain_postgresql(&mut tx, rewritten, true).await?;
+                    Ok::<_, AppError>((before, after))
+                }.await;
+                tx.rollback().await?;
+                let (before, after) = result?;
+                Ok(PlanComparison { before, after, indexes_applied: true })
+            }
+            DatabaseConnection::SQLite(pool, _) => {
+                let mut tx = pool.begin().await?;
+                let result = async {
+                    let before = Self::explain_sqlite(&mut tx, original).await?;
+                    for ddl in index_ddl {
+                        sqlx::query(ddl).execute(&mut *tx).await
+                            .map_err(|e| AppError::QueryExecutionFailed(format!("Failed to apply index: {}", e)))?;
+                    }
+                    let after = Self::explain_sqlite(&mut tx, rewritten).await?;
+                    Ok::<_, AppError>((before, after))
+                }.await;
+                tx.rollback().await?;
+                let (before, after) = result?;
+                Ok(PlanComparison { before, after, indexes_applied: true })
+            }
+            DatabaseConnection::MySQL(..) => {
+                // MySQL DDL commits implicitly, so indexes can't be tried out in a
+                // transaction; only the rewritten query is compared
+                let before = self.explain_query(original, false).await?;
+                let after = self.explain_query(rewritten, false).await?;
+                Ok(PlanComparison { before, after, indexes_applied: false })
+            }
+            DatabaseConnection::MongoDB(..) => {
+                Err(AppError::QueryExecutionFailed("Plan comparison is not supported for MongoDB".to_string()))
+            }
+            DatabaseConnection::SQLServer(_) => {
+                Err(AppError::QueryExecutionFailed("Plan comparison is not supported for SQL Server yet".to_string()))
+            }
+            DatabaseConnection::Redis(_) => {
+                Err(AppError::QueryExecutionFailed("Plan comparison is not supported for Redis".to_string()))
+            }
+            DatabaseConnection::DuckDB(_) => {
+                Err(AppError::QueryExecutionFailed("Plan comparison is not supported for DuckDB yet".to_string()))
+            }
+        }
+    }
+
+    pub async fn get_table_indexes(&self, table: &str) -> Result<Vec<IndexInfo>, AppError> {
+        Ok(self.load_indexes(Some(table)).await?.remove(table).unwrap_or_default())
+    }
+
+    pub async fn get_foreign_keys(&self, table: &str) -> Result<Vec<ForeignKeyInfo>, AppError> {
+        Ok(self.load_foreign_keys(Some(table)).await?.remove(table).unwrap_or_default())
+    }
+
+    // Indexes keyed by table, for one table or the whole schema in a single catalog query
+    async fn load_indexes(&self, table: Option<&str>) -> Result<HashMap<String, Vec<IndexInfo>>, AppError> {
+        let mut indexes: HashMap<String, Vec<IndexInfo>> = HashMap::new();
+        match self {
+            DatabaseConnection::MySQL(pool, _) => {
+                let rows = sqlx::query(r#"
+                    SELECT TABLE_NAME, INDEX_NAME, COLUMN_NAME, NON_UNIQUE
+                    FROM INFORMATION_SCHEMA.STATISTICS
+                    WHERE TABLE_SCHEMA = DATABASE() AND (? IS NULL OR TABLE_NAME = ?)
+                    ORDER BY TABLE_NAME, INDEX_NAME, SEQ_IN_INDEX
+                "#)
+                    .bind(table)
+                    .bind(table)
+                    .fetch_all(pool)
+                    .await?;
+                for row in rows {
+                    let key = table_key(table, row.get("TABLE_NAME"));
+                    let name: String = row.get("INDEX_NAME");
+                    let column: Option<String> = row.get("COLUMN_NAME");
+                    let non_unique: i64 = row.get("NON_UNIQUE");
+                    push_index_column(indexes.entry(key.clone()).or_default(), &key, &name, column, non_unique == 0, name == "PRIMARY");
+                }
+            }
+            // pg_index.indkey can't be cast to an array on CockroachDB, but it offers MySQL's
+            // information_schema.statistics; storing and implicit columns aren't index keys
+            DatabaseConnection::PostgreSQL(pool, server) if server.flavor == ServerFlavor::CockroachDB => {
+                let (schema, name) = table.map(pg_table_ref).unzip();
+                let rows = sqlx::query(r#"
+                    SELECT s.table_name, s.index_name, s.column_name, s.non_unique::STRING IN ('NO', 'false') AS is_unique,
+                           EXISTS (
+                               SELECT 1 FROM information_schema.table_constraints tc
+                               WHERE tc.table_schema = s.table_schema AND tc.table_name = s.table_name
+                                   AND tc.constraint_name = s.index_name AND tc.constraint_type = 'PRIMARY KEY'
+                           ) AS is_primary
+                    FROM information_schema.statistics s
+                    WHERE s.table_schema = COALESCE($2, current_schema()) AND ($1::STRING IS NULL OR s.table_name = $1)
+                        AND s.storing::STRING IN ('NO', 'false') AND s.implicit::STRING IN ('NO', 'false')
+                    ORDER BY s.table_name, s.index_name, s.seq_in_index
+                "#)
+                    .bind(name)
+                    .bind(schema.flatten())
+                    .fetch_all(pool)
+                    .await?;
+                for row in rows {
+                    let key = table_key(table, row.get("table_name"));
+                    let name: String = row.get("index_name");
+                    let column: Option<String> = row.get("column_name");
+                    push_index_column(indexes.entry(key.clone()).or_default(), &key, &name, column, row.get("is_unique"), row.get("is_primary"));
+                }
+            }
+            DatabaseConnection::PostgreSQL(pool, _) => {
+                let (schema, name) = table.map(pg_table_ref).unzip();
+                let rows = sqlx::query(r#"
+                    SELECT t.relname AS table_name, i.relname AS index_name, a.attname AS column_name,
+                           ix.indisunique AS is_unique, ix.indisprimary AS is_primary
+                    FROM pg_class t
+                    JOIN pg_namespace n ON n.oid = t.relnamespace
+                    JOIN pg_index ix ON ix.indrelid = t.oid
+                    JOIN pg_class i ON i.oid = ix.indexrelid
+                    CROSS JOIN LATERAL unnest(ix.indkey::int2[]) WITH ORDINALITY AS k(attnum, ord)
+                    LEFT JOIN pg_attribute a ON a.attrelid = t.oid AND a.attnum = k.attnum
+                    WHERE n.nspname = COALESCE($2, current_schema()) AND ($1::text IS NULL OR t.relname = $1)
+                    ORDER BY t.relname, i.relname, k.ord
+                "#)
+                    .bind(name)
+                    .bind(schema.flatten())
+                    .fetch_all(pool)
+                    .await?;
+                for row in rows {
+                    let key = table_key(table, row.get("table_name"));
+                    let name: String = row.get("index_name");
+                    let column: Option<String> = row.get("column_name");
+                    push_index_column(indexes.entry(key.clone()).or_default(), &key, &name, column, row.get("is_unique"), row.get("is_primary"));
+                }
+            }
+            DatabaseConnection::SQLite(pool, _) => {
+                // PRAGMA table-valued functions list every table's indexes in one query per database
+                for (schema, name) in sqlite_table_scopes(pool, table).await? {
+                    let sql = format!(r#"
+                        SELECT m.name AS table_name, il.name AS index_name, il."unique", il.origin, ii.name AS column_name
+                        FROM {}.sqlite_master m
+                        JOIN pragma_index_list(m.name, ?1) il
+                        JOIN pragma_index_info(il.name, ?1) ii
+                        WHERE m.type = 'table' AND (?2 IS NULL OR m.name = ?2)
+                        ORDER BY m.name, il.seq, ii.seqno
+                    "#, quote_identifier(schema.as_deref().unwrap_or("main"), '"'));
+                    let rows = sqlx::query(&sql)
+                        .bind(schema.as_deref().unwrap_or("main"))
+                        .bind(&name)
+                        .fetch_all(pool)
+                        .await?;
+                    for row in rows {
+                        let key = table_key(table, sqlite_table_name(&schema, row.get("table_name")));
+                        let name: String = row.get("index_name");
+                        let unique: i64 = row.get("unique");
+                        let origin: String = row.get("origin");
+                        let column: Option<String> = row.get("column_name");
+                        push_index_column(indexes.entry(key.clone()).or_default(), &key, &name, column, unique == 1, origin == "pk");
+                    }
+                }
+            }
+            DatabaseConnection::SQLServer(client) => {
+                let filter = match table {
+                    Some(_) => format!("i.object_id = {}", MSSQL_OBJECT_ID),
+                    None => "t.schema_id = SCHEMA_ID()".to_string(),
+                };
+                let sql = format!(r#"
+                    SELECT t.name AS table_name, i.name AS index_name, c.name AS column_name, i.is_unique, i.is_primary_key
+                    FROM sys.indexes i
+                    JOIN sys.tables t ON t.object_id = i.object_id
+                    JOIN sys.index_columns ic ON ic.object_id = i.object_id AND ic.index_id = i.index_id
+                    JOIN sys.columns c ON c.object_id = ic.object_id AND c.column_id = ic.column_id
+                    WHERE {} AND i.name IS NOT NULL AND ic.is_included_column = 0
+                    ORDER BY t.name, i.name, ic.key_ordinal
+                "#, filter);
+                let params: Vec<&dyn tiberius::ToSql> = table.iter().map(|t| t as &dyn tiberius::ToSql).collect();
+                for row in mssql_rows(client, &sql, &params).await? {
+                    let key = table_key(table, mssql_text(&row, "table_name").unwrap_or_default());
+                    let name = mssql_text(&row, "index_name").unwrap_or_default();
+                    let unique = row.try_get::<bool, _>("is_unique").ok().flatten().unwrap_or(false);
+                    let primary = row.try_get::<bool, _>("is_primary_key").ok().flatten().unwrap_or(false);
+                    push_index_column(indexes.entry(key.clone()).or_default(), &key, &name, mssql_text(&row, "column_name"), unique, primary);
+                }
+            }
+            DatabaseConnection::Redis(_) => {}
+            DatabaseConnection::DuckDB(client) => {
+                // PRIMARY KEY and UNIQUE constraints are backed by indexes that duckdb_indexes() doesn't list
+                let constraints = duckdb_rows(client, r#"
+                    SELECT table_name, constraint_type, constraint_index, constraint_column_names
+                    FROM duckdb_constraints()
+                    WHERE schema_name = current_schema() AND (CAST(? AS VARCHAR) IS NULL OR table_name = ?)
+                        AND constraint_type IN ('PRIMARY KEY', 'UNIQUE')
+                    ORDER BY table_name, constraint_index
+                "#, duckdb_table_filter(table)).await?;
+                for row in constraints {
+                    let key = table_key(table, json_text(row.first()).unwrap_or_default());
+                    let primary = json_text(row.get(1)).as_deref() == Some("PRIMARY KEY");
+                    let name = if primary {
+                        format!("{}_pkey", key)
+                    } else {
+                        format!("{}_unique_{}", key, json_text(row.get(2)).unwrap_or_default())
+                    };
+                    let columns = row.get(3).and_then(|v| v.as_array()).cloned().unwrap_or_default();
+                    let table_indexes = indexes.entry(key.clone()).or_default();
+                    for column in columns {
+                        push_index_column(table_indexes, &key, &name, json_text(Some(&column)), true, primary);
+                    }
+                }
+                let rows = duckdb_rows(client, r#"
+                    SELECT table_name, index_name, is_unique, is_primary, sql
+                    FROM duckdb_indexes()
+                    WHERE schema_name = current_schema() AND (CAST(? AS VARCHAR) IS NULL OR table_name = ?)
+                    ORDER BY table_name, index_name
+                "#, duckdb_table_filter(table)).await?;
+                for row in rows {
+                    let key = table_key(table, json_text(row.first()).unwrap_or_default());
+                    let name = json_text(row.get(1)).unwrap_or_default();
+                    let unique = row.get(2).and_then(|v| v.as_bool()).unwrap_or(false);
+                    let primary = row.get(3).and_then(|v| v.as_bool()).unwrap_or(false);
+                    // Only the CREATE INDEX statement records the indexed columns
+                    let sql = json_text(row.get(4)).unwrap_or_default();
+                    let columns = sql
+                        .to_uppercase()
+                        .find(" ON ")
+                        .and_then(|on| duckdb_parenthesised(&sql, on))
+                        .map(|(list, _)| duckdb_column_list(list))
+                        .unwrap_or_default();
+                    let table_indexes = indexes.entry(key.clone()).or_default();
+                    if columns.is_empty() {
+                        push_index_column(table_indexes, &key, &name, None, unique, primary);
+                    }
+                    for column in columns {
+                        push_index_column(table_indexes, &key, &name, Some(column), unique, primary);
+                    }
+                }
+            }
+            DatabaseConnection::MongoDB(_client, database) => {
+                // There is no catalog query across collections, so each one is listed separately
+                let collections = match table {
+                    Some(table) => vec![table.to_string()],
+                    None => database.list_collection_names(None).await?,
+                };
+                for collection_name in collections {
+                    let collection = database.collection::<bson::Document>(&collection_name);
+                    let models: Vec<mongodb::IndexModel> = collection.list_indexes(None).await?.try_collect().await?;
+                    let table_indexes = indexes.entry(collection_name.clone()).or_default();
+                    for model in models {
+                        let name = model.options.as_ref().and_then(|o| o.name.clone()).unwrap_or_default();
+                        let unique = model.options.as_ref().and_then(|o| o.unique).unwrap_or(false);
+                        table_indexes.push(IndexInfo {
+                            is_primary: name == "_id_",
+                            name,
+                            table: collection_name.clone(),
+                            columns: model.keys.keys().cloned().collect(),
+                            is_unique: unique,
+                        });
+                    }
+                }
+            }
+        }
+        Ok(indexes)
+    }
+
+    // Foreign keys keyed by table, for one table or the whole schema in a single catalog query
+    async fn load_foreign_keys(&self, table: Option<&str>) -> Result<HashMap<String, Vec<ForeignKeyInfo>>, AppError> {
+        let mut foreign_keys: HashMap<String, Vec<ForeignKeyInfo>> = HashMap::new();
+        match self {
+            DatabaseConnection::MySQL(pool, _) => {
+                let rows = sqlx::query(r#"
+                    SELECT k.TABLE_NAME, k.CONSTRAINT_NAME, k.COLUMN_NAME, k.REFERENCED_TABLE_NAME, k.REFERENCED_COLUMN_NAME,
+                           r.DELETE_RULE, r.UPDATE_RULE
+                    FROM INFORMATION_SCHEMA.KEY_COLUMN_USAGE k
+                    JOIN INFORMATION_SCHEMA.REFERENTIAL_CONSTRAINTS r
+                        ON r.CONSTRAINT_SCHEMA = k.CONSTRAINT_SCHEMA AND r.CONSTRAINT_NAME = k.CONSTRAINT_NAME
+                    WHERE k.TABLE_SCHEMA = DATABASE() AND (? IS NULL OR k.TABLE_NAME = ?) AND k.REFERENCED_TABLE_NAME IS NOT NULL
+                    ORDER BY k.TABLE_NAME, k.CONSTRAINT_NAME, k.ORDINAL_POSITION
+                "#)
+                    .bind(table)
+                    .bind(table)
+                    .fetch_all(pool)
+                    .await?;
+                for row in rows {
+                    push_foreign_key_column(
+                        foreign_keys.entry(table_key(table, row.get("TABLE_NAME"))).or_default(),
+                        row.get("CONSTRAINT_NAME"),
+                        row.get("COLUMN_NAME"),
+                        row.get("REFERENCED_TABLE_NAME"),
+                        row.get("REFERENCED_COLUMN_NAME"),
+                        row.get("DELETE_RULE"),
+                        row.get("UPDATE_RULE"),
+                    );
+                }
+            }
+            DatabaseConnection::PostgreSQL(pool, server) if server.flavor == ServerFlavor::CockroachDB => {
+                let (schema, name) = table.map(pg_table_ref).unzip();
+                // Constraint names are only unique per table, so both sides are matched on the table too
+                let rows = sqlx::query(r#"
+                    SELECT r.table_name, k.constraint_name AS name, k.column_name, rk.table_name AS referenced_table,
+                           rk.column_name AS referenced_column, r.delete_rule AS on_delete, r.update_rule AS on_update
+                    FROM information_schema.referential_constraints r
+                    JOIN information_schema.key_column_usage k
+                        ON k.constraint_schema = r.constraint_schema AND k.constraint_name = r.constraint_name
+                        AND k.table_name = r.table_name
+                    JOIN information_schema.key_column_usage rk
+                        ON rk.constraint_schema = r.unique_constraint_schema AND rk.constraint_name = r.unique_constraint_name
+                        AND rk.table_name = r.referenced_table_name AND rk.ordinal_position = k.position_in_unique_constraint
+                    WHERE r.constraint_schema = COALESCE($2, current_schema()) AND ($1::STRING IS NULL OR r.table_name = $1)
+                    ORDER BY r.table_name, k.constraint_name, k.ordinal_position
+                "#)
+                    .bind(name)
+                    .bind(schema.flatten())
+                    .fetch_all(pool)
+                    .await?;
+                for row in rows {
+                    push_foreign_key_column(
+                        foreign_keys.entry(table_key(table, row.get("table_name"))).or_default(),
+                        row.get("name"),
+                        row.get("column_name"),
+                        row.get("referenced_table"),
+                        row.get("referenced_column"),
+                        row.get("on_delete"),
+                        row.get("on_update"),
+                    );
+                }
+            }
+            DatabaseConnection::PostgreSQL(pool, _) => {
+                let (schema, name) = table.map(pg_table_ref).unzip();
+                let rows = sqlx::query(r#"
+                    SELECT t.relname AS table_name, co.conname AS name, a.attname AS column_name, rt.relname AS referenced_table,
+                           ra.attname AS referenced_column,
+                           CASE co.confdeltype WHEN 'c' THEN 'CASCADE' WHEN 'n' THEN 'SET NULL' WHEN 'd' THEN 'SET DEFAULT'
+                                WHEN 'r' THEN 'RESTRICT' ELSE 'NO ACTION' END AS on_delete,
+                           CASE co.confupdtype WHEN 'c' THEN 'CASCADE' WHEN 'n' THEN 'SET NULL' WHEN 'd' THEN 'SET DEFAULT'
+                                WHEN 'r' THEN 'RESTRICT' ELSE 'NO ACTION' END AS on_update
+                    FROM pg_constraint co
+                    JOIN pg_class t ON t.oid = co.conrelid
+                    JOIN pg_namespace n ON n.oid = t.relnamespace
+                    JOIN pg_class rt ON rt.oid = co.confrelid
+                    CROSS JOIN LATERAL unnest(co.conkey, co.confkey) WITH ORDINALITY AS k(attnum, refattnum, ord)
+                    JOIN pg_attribute a ON a.attrelid = co.conrelid AND a.attnum = k.attnum
+                    JOIN pg_attribute ra ON ra.attrelid = co.confrelid AND ra.attnum = k.refattnum
+                    WHERE co.contype = 'f' AND n.nspname = COALESCE($2, current_schema()) AND ($1::text IS NULL OR t.relname = $1)
+                    ORDER BY t.relname, co.conname, k.ord
+                "#)
+                    .bind(name)
+                    .bind(schema.flatten())
+                    .fetch_all(pool)
+                    .await?;
+                for row in rows {
+                    push_foreign_key_column(
+                        foreign_keys.entry(table_key(table, row.get("table_name"))).or_default(),
+                        row.get("name"),
+                        row.get("column_name"),
+                        row.get("referenced_table"),
+                        row.get("referenced_column"),
+                        row.get("on_delete"),
+                        row.get("on_update"),
+                    );
+                }
+            }
+            DatabaseConnection::SQLite(pool, _) => {
+                for (schema, name) in sqlite_table_scopes(pool, table).await? {
+                    let sql = format!(r#"
+                        SELECT m.name AS table_name, fk.id, fk."table", fk."from", fk."to", fk.on_delete, fk.on_update
+                        FROM {}.sqlite_master m
+                        JOIN pragma_foreign_key_list(m.name, ?1) fk
+                        WHERE m.type = 'table' AND (?2 IS NULL OR m.name = ?2)
+                        ORDER BY m.name, fk.id, fk.seq
+                    "#, quote_identifier(schema.as_deref().unwrap_or("main"), '"'));
+                    let rows = sqlx::query(&sql)
+                        .bind(schema.as_deref().unwrap_or("main"))
+                        .bind(&name)
+                        .fetch_all(pool)
+                        .await?;
+                    for row in rows {
+                        let key = table_key(table, sqlite_table_name(&schema, row.get("table_name")));
+                        // SQLite constraints are unnamed; the id groups multi-column keys
+                        let id: i64 = row.get("id");
+                        // Foreign keys of attached tables point into the same database
+                        let referenced_table = sqlite_table_name(&schema, row.get("table"));
+                        // A missing target column means the referenced table's primary key
+                        let referenced_column: Option<String> = row.get("to");
+                        push_foreign_key_column(
+                            foreign_keys.entry(key.clone()).or_default(),
+                            format!("{}_fk{}", key, id),
+                            row.get("from"),
+                            referenced_table,
+                            referenced_column.unwrap_or_default(),
+                            row.get("on_delete"),
+                            row.get("on_update"),
+                        );
+                    }
+                }
+            }
+            DatabaseConnection::SQLServer(client) => {
+                let filter = match table {
+                    Some(_) => format!("fk.parent_object_id = {}", MSSQL_OBJECT_ID),
+                    None => "pt.schema_id = SCHEMA_ID()".to_string(),
+                };
+                let sql = format!(r#"
+                    SELECT pt.name AS table_name, fk.name, pc.name AS column_name, rt.name AS referenced_table,
+                           rc.name AS referenced_column,
+                           REPLACE(fk.delete_referential_action_desc, '_', ' ') AS on_delete,
+                           REPLACE(fk.update_referential_action_desc, '_', ' ') AS on_update
+                    FROM sys.foreign_keys fk
+                    JOIN sys.tables pt ON pt.object_id = fk.parent_object_id
+                    JOIN sys.foreign_key_columns fkc ON fkc.constraint_object_id = fk.object_id
+                    JOIN sys.columns pc ON pc.object_id = fkc.parent_object_id AND pc.column_id = fkc.parent_column_id
+                    JOIN sys.tables rt ON rt.object_id = fkc.referenced_object_id
+                    JOIN sys.columns rc ON rc.object_id = fkc.referenced_object_id AND rc.column_id = fkc.referenced_column_id
+                    WHERE {}
+                    ORDER BY pt.name, fk.name, fkc.constraint_column_id
+                "#, filter);
+                let params: Vec<&dyn tiberius::ToSql> = table.iter().map(|t| t as &dyn tiberius::ToSql).collect();
+                for row in mssql_rows(client, &sql, &params).await? {
+                    push_foreign_key_column(
+                        foreign_keys.entry(table_key(table, mssql_text(&row, "table_name").unwrap_or_default())).or_default(),
+                        mssql_text(&row, "name").unwrap_or_default(),
+                        mssql_text(&row, "column_name").unwrap_or_default(),
+                        mssql_text(&row, "referenced_table").unwrap_or_default(),
+                        mssql_text(&row, "referenced_column").unwrap_or_default(),
+                        mssql_text(&row, "on_delete"),
+                        mssql_text(&row, "on_update"),
+                    );
+                }
+            }
+            DatabaseConnection::DuckDB(client) => {
+                // constraint_text reads "FOREIGN KEY (a, b) REFERENCES parent(x, y)"
+                let rows = duckdb_rows(client, r#"
+                    SELECT table_name, constraint_index, constraint_text
+                    FROM duckdb_constraints()
+                    WHERE schema_name = current_schema() AND (CAST(? AS VARCHAR) IS NULL OR table_name = ?)
+                        AND constraint_type = 'FOREIGN KEY'
+                    ORDER BY table_name, constraint_index
+                "#, duckdb_table_filter(table)).await?;
+                for row in rows {
+                    let key = table_key(table, json_text(row.first()).unwrap_or_default());
+                    let text = json_text(row.get(2)).unwrap_or_default();
+                    let Some((columns, end)) = duckdb_parenthesised(&text, 0) else { continue };
+                    let Some(references) = text.to_uppercase()[end..].find("REFERENCES").map(|i| end + i + "REFERENCES".len()) else { continue };
+                    let Some((referenced_columns, _)) = duckdb_parenthesised(&text, references) else { continue };
+                    let referenced_table = text[references..]
+                        .split('(')
+                        .next()
+                        .unwrap_or_default()
+                        .trim()
+                        .trim_matches('"')
+                        .to_string();
+                    let name = format!("{}_fk{}", key, json_text(row.get(1)).unwrap_or_default());
+                    let table_foreign_keys = foreign_keys.entry(key).or_default();
+                    for (column, referenced_column) in duckdb_column_list(columns).into_iter().zip(duckdb_column_list(referenced_columns)) {
+                        // DuckDB only supports the default NO ACTION behaviour
+                        push_foreign_key_column(table_foreign_keys, name.clone(), column, referenced_table.clone(), referenced_column, None, None);
+                    }
+                }
+            }
+            DatabaseConnection::MongoDB(..) | DatabaseConnection::Redis(_) => {}
+        }
+        Ok(foreign_keys)
+    }
+
+    // Cheap row count from catalog statistics where available
+    pub async fn estimate_row_count(&self, table: &str) -> Result<Option<i64>, AppError> {
+        match self {
+            DatabaseConnection::MySQL(pool, _) => {
+                let row = sqlx::query("SELECT CAST(TABLE_ROWS AS SIGNED) AS row_count FROM INFORMATION_SCHEMA.TABLES WHERE TABLE_SCHEMA = DATABASE() AND TABLE_NAME = ?")
+                    .bind(table)
+                    .fetch_optional(pool)
+                    .await?;
+                Ok(row.and_then(|r| r.get("row_count")))
+            }
+            DatabaseConnection::PostgreSQL(pool, _) => {
+                let (schema, name) = pg_table_ref(table);
+                let row = sqlx::query("SELECT c.reltuples::bigint AS row_count FROM pg_class c JOIN pg_namespace n ON n.oid = c.relnamespace WHERE n.nspname = COALESCE($2, current_schema()) AND c.relname = $1")
+                    .bind(name)
+                    .bind(schema)
+                    .fetch_optional(pool)
+                    .await?;
+                Ok(row.map(|r| r.get("row_count")))
+            }
+            DatabaseConnection::SQLite(pool, _) => {
+                let (schema, name) = sqlite_table_ref(pool, table).await?;
+                let table = match schema {
+                    Some(schema) => format!("{}.{}", quote_identifier(&schema, '"'), quote_identifier(&name, '"')),
+                    None => quote_identifier(&name, '"'),
+                };
+                let row = sqlx::query(&format!("SELECT COUNT(*) AS row_count FROM {}", table))
+                    .fetch_one(pool)
+                    .await?;
+                Ok(Some(row.get("row_count")))
+            }
+            DatabaseConnection::MongoDB(_client, database) => {
+                let count = database.collection::<bson::Document>(table).estimated_document_count(None).await?;
+                Ok(Some(count as i64))
+            }
+            DatabaseConnection::SQLServer(client) => {
+                let sql = format!(
+                    "SELECT CAST(SUM(p.rows) AS bigint) AS row_count FROM sys.partitions p WHERE p.object_id = {} AND p.index_id IN (0, 1)",
+                    MSSQL_OBJECT_ID
+                );
+                let rows = mssql_rows(client, &sql, &[&table]).await?;
+                Ok(rows.first().and_then(|r| r.try_get::<i64, _>("row_count").ok().flatten()))
+            }
+            DatabaseConnection::Redis(_) => Ok(None),
+            DatabaseConnection::DuckDB(client) => {
+                let rows = duckdb_rows(
+                    client,
+                    "SELECT estimated_size FROM duckdb_tables() WHERE schema_name = current_schema() AND table_name = ?",
+                    vec![duckdb::types::Value::Text(table.to_string())],
+                ).await?;
+                Ok(rows.first().and_then(|r| r.first()).and_then(|v| v.as_i64()))
+            }
+        }
+    }
+
+    // Run a query and hand every row to the sink without buffering the result set
+    pub async fn stream_query(&self, query: &str, sink: &mut dyn RowSink) -> Result<u64, AppError> {
+        let mut count = 0u64;
+        match self {
+            DatabaseConnection::MySQL(pool, _) => {
+                let mut stream = sqlx::query(query).fetch(pool);
+                while let Some(row) = stream.try_next().await
+                    .map_err(|e| AppError::QueryExecutionFailed(format!("MySQL query failed: {}", e)))? {
+                    if count == 0 {
+                        sink.columns(&row.columns().iter().map(|c| c.name().to_string()).collect::<Vec<_>>())?;
+                    }
+                    sink.row((0..row.columns().len()).map(|i| self.extract_value_from_mysql_row(&row, i)).collect())?;
+                    count += 1;
+                }
+                drop(stream);
+                if count == 0 {
+                    describe_columns(pool, query, sink).await?;
+                }
+            }
+            DatabaseConnection::PostgreSQL(pool, _) => {
+                let mut stream = sqlx::query(query).fetch(pool);
+                while let Some(row) = stream.try_next().await
+                    .map_err(|e| AppError::QueryExecutionFailed(format!("PostgreSQL query failed: {}", e)))? {
+                    if count == 0 {
+                        sink.columns(&row.columns().iter().map(|c| c.name().to_string()).collect::<Vec<_>>())?;
+                    }
+                    sink.row((0..row.columns().len()).map(|i| self.extract_value_from_postgresql_row(&row, i)).collect())?;
+                    count += 1;
+                }
+                drop(stream);
+                if count == 0 {
+                    describe_columns(pool, query, sink).await?;
+                }
+            }
+            DatabaseConnection::SQLite(pool, _) => {
+                let mut stream = sqlx::query(query).fetch(pool);
+                while let Some(row) = stream.try_next().await
+                    .map_err(|e| AppError::QueryExecutionFailed(format!("SQLite query failed: {}", e)))? {
+                    if count == 0 {
+                        sink.columns(&row.columns().iter().map(|c| c.name().to_string()).collect::<Vec<_>>())?;
+                    }
+                    sink.row((0..row.columns().len()).map(|i| self.extract_value_from_sqlite_row(&row, i)).collect())?;
+                    count += 1;
+                }
+                drop(stream);
+                if count == 0 {
+                    describe_columns(pool, query, sink).await?;
+                }
+            }
+            DatabaseConnection::MongoDB(_client, database) => {
+                let command = parse_mongodb_command(query)?;
+                let collection_name = command.get_str("find")
+                    .map_err(|_| AppError::QueryExecutionFailed("MongoDB query needs a \"find\" collection".to_string()))?;
+                let options = mongodb::options::FindOptions::builder()
+                    .projection(command.get_document("projection").ok().cloned())
+                    .sort(command.get_document("sort").ok().cloned())
+                    .limit(command.get_i64("limit").ok().or_else(|| command.get_i32("limit").ok().map(i64::from)))
+                    .build();
+                let collection = database.collection::<bson::Document>(collection_name);
+                let mut cursor = collection.find(command.get_document("filter").ok().cloned(), options).await?;
+
+                // Documents are flattened onto the keys of the first one
+                let mut columns: Vec<String> = Vec::new();
+                while let Some(document) = cursor.try_next().await? {
+                    if count == 0 {
+                        columns = document.keys().cloned().collect();
+                        sink.columns(&columns)?;
+                    }
+                    sink.row(columns.iter()
+                        .map(|c| document.get(c).cloned().map(|v| v.into_relaxed_extjson()).unwrap_or(serde_json::Value::Null))
+                        .collect())?;
+                    count += 1;
+                }
+            }
+            DatabaseConnection::SQLServer(client) => {
+                let mut client = client.lock().await;
+                let mut stream = client.simple_query(query).await.map_err(mssql_failed)?;
+                while let Some(item) = stream.try_next().await.map_err(mssql_failed)? {
+                    match item {
+                        tiberius::QueryItem::Metadata(meta) if meta.result_index() == 0 => {
+                            sink.columns(&meta.columns().iter().map(|c| c.name().to_string()).collect::<Vec<_>>())?;
+                        }
+                        tiberius::QueryItem::Row(row) if row.result_index() == 0 => {
+                            sink.row((0..row.len()).map(|i| self.extract_value_from_mssql_row(&row, i)).collect())?;
+                            count += 1;
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            DatabaseConnection::Redis(client) => {
+                let (args, reply) = redis_command(client, query).await?;
+                let (columns, rows) = redis_reply_table(&args, reply);
+                sink.columns(&columns)?;
+                for row in rows {
+                    sink.row(row)?;
+                    count += 1;
+                }
+            }
+            // The sink is borrowed, so rows are pulled on this worker thread instead of the blocking pool
+            DatabaseConnection::DuckDB(client) => {
+                tokio::task::block_in_place(|| -> Result<(), AppError> {
+                    let conn = client
+                        .lock()
+                        .map_err(|_| AppError::InternalError("DuckDB connection lock poisoned".to_string()))?;
+                    let mut statement = conn.prepare(query).map_err(duckdb_failed)?;
+                    let mut rows = statement.query([]).map_err(duckdb_failed)?;
+                    let columns = rows.as_ref().map(|s| s.column_names()).unwrap_or_default();
+                    sink.columns(&columns)?;
+                    while let Some(row) = rows.next().map_err(duckdb_failed)? {
+                        let mut values = Vec::with_capacity(columns.len());
+                        for i in 0..columns.len() {
+                            values.push(duckdb_value_to_json(row.get::<_, duckdb::types::Value>(i).map_err(duckdb_failed)?));
+                        }
+                        sink.row(values)?;
+                        count += 1;
+                    }
+                    Ok(())
+                })?;
+            }
+        }
+        Ok(count)
+    }
+
+    pub fn db_type(&self) -> DatabaseType {
+        match self {
+            DatabaseConnection::MySQL(..) => DatabaseType::MySQL,
+            DatabaseConnection::PostgreSQL(..) => DatabaseType::PostgreSQL,
+            DatabaseConnection::SQLite(..) => DatabaseType::SQLite,
+            DatabaseConnection::MongoDB(..) => DatabaseType::MongoDB,
+            DatabaseConnection::SQLServer(_) => DatabaseType::SQLServer,
+            DatabaseConnection::Redis(_) => DatabaseType::Redis,
+            DatabaseConnection::DuckDB(_) => DatabaseType::DuckDB,
+        }
+    }
+
+    // Quote an identifier for this backend's SQL dialect
+    pub fn quote_ident(&self, name: &str) -> String {
+        match self {
+            DatabaseConnection::MySQL(..) => quote_identifier(name, '`'),
+            DatabaseConnection::SQLServer(_) => quote_mssql_identifier(name),
+            _ => quote_identifier(name, '"'),
+        }
+    }
+
+    // Quote a table name; "alias.table" names of attached SQLite databases become "alias"."table"
+    pub fn quote_table(&self, table: &str) -> String {
+        if let DatabaseConnection::SQLite(_, aliases) = self {
+            if let Some((alias, name)) = table.split_once('.') {
+                if aliases.iter().any(|a| a == alias) {
+                    return format!("{}.{}", quote_identifier(alias, '"'), quote_identifier(name, '"'));
+                }
+            }
+        }
+        self.quote_ident(table)
+    }
+
+    fn placeholder(&self, index: usize) -> String {
+        match self {
+            DatabaseConnection::PostgreSQL(..) => format!("${}", index),
+            DatabaseConnection::SQLServer(_) => format!("@P{}", index),
+            _ => "?".to_string(),
+        }
+    }
+
+    // Bind parameter limit per statement for each backend
+    fn max_parameters(&self) -> usize {
+        match self {
+            DatabaseConnection::SQLite(..) => 999,
+            // The hard limit is 2100, including parameters SQL Server adds itself
+            DatabaseConnection::SQLServer(_) => 2000,
+            _ => 65535,
+        }
+    }
+
+    fn insert_statement(&self, table: &str, columns: &[ColumnInfo], rows: usize) -> String {
+        let column_list: Vec<String> = columns.iter().map(|c| self.quote_ident(&c.name)).collect();
+        let mut index = 0;
+        let tuples: Vec<String> = (0..rows)
+            .map(|_| {
+                let placeholders: Vec<String> = columns
+                    .iter()
+                    .map(|column| {
+                        index += 1;
+                        match (self, &column.column_type) {
+                            // Text and decimals are bound as text; cast so enum, uuid, numeric and
+                            // similar columns accept it
+                            (DatabaseConnection::PostgreSQL(..), Some(column_type))
+                                if matches!(
+                                    values::categorize(&column.data_type),
+                                    values::ValueCategory::Text | values::ValueCategory::Decimal
+                                ) =>
+                            {
+                                format!("{}::{}", self.placeholder(index), column_type)
+                            }
+                            _ => self.placeholder(index),
+                        }
+                    })
+                    .collect();
+                format!("({})", placeholders.join(", "))
+            })
+            .collect();
+        format!(
+            "INSERT INTO {} ({}) VALUES {}",
+            self.quote_table(table),
+            column_list.join(", "),
+            tuples.join(", ")
+        )
+    }
+
+    // MongoDB has no multi-document atomicity outside replica-set transactions, so documents
+    // are inserted in order and the insert stops at the first one that fails. The rows before
+    // it stay inserted; the error says how many so callers can resume after the failing row.
+    pub async fn insert_documents(&self, collection: &str, columns: &[ColumnInfo], rows: &[Vec<serde_json::Value>]) -> Result<u64, PartialInsert> {
+        let DatabaseConnection::MongoDB(_client, database) = self else {
+            return Err(PartialInsert {
+                inserted: 0,
+                error: AppError::InvalidConfiguration("Documents can only be inserted into MongoDB".to_string()),
+            });
+        };
+        if rows.is_empty() {
+            return Ok(0);
+        }
+
+        let categories: Vec<values::ValueCategory> = columns.iter().map(|c| values::categorize(&c.data_type)).collect();
+        let documents: Vec<bson::Document> = rows
+            .iter()
+            .map(|row| {
+                columns
+                    .iter()
+                    .zip(&categories)
+                    .enumerate()
+                    .map(|(i, (column, category))| (column, values::coerce(row.get(i).unwrap_or(&serde_json::Value::Null), *category)))
+                    .filter(|(_, value)| !matches!(value, SqlValue::Null))
+                    .map(|(column, value)| (column.name.clone(), values::to_bson(value)))
+                    .collect()
+            })
+            .collect();
+        let options = mongodb::options::InsertManyOptions::builder().ordered(true).build();
+        match database.collection::<bson::Document>(collection).insert_many(documents, options).await {
+            Ok(result) => Ok(result.inserted_ids.len() as u64),
+            Err(e) => {
+                // With ordered inserts the first write error is where the insert stopped
+                let inserted = match e.kind.as_ref() {
+                    mongodb::error::ErrorKind::BulkWrite(failure) => failure
+                        .write_errors
+                        .as_ref()
+                        .and_then(|errors| errors.iter().map(|w| w.index).min())
+                        .unwrap_or(0),
+                    _ => 0,
+                };
+                Err(PartialInsert {
+                    inserted: inserted as u64,
+                    error: AppError::QueryExecutionFailed(format!(
+                        "Insert into {} failed at row {} ({} rows before it were inserted): {}",
+                        collection,
+                        inserted + 1,
+                        inserted,
+                        e
+                    )),
+                })
+            }
+        }
+    }
+
+    // Insert rows in a single transaction, converting each value to the type of its
+    // target column. Either all rows are inserted or none are, except on MongoDB where
+    // insert_documents keeps the rows before the first failure.
+    pub async fn insert_rows(&self, table: &str, columns: &[ColumnInfo], rows: &[Vec<serde_json::Value>]) -> Result<u64, AppError> {
+        if rows.is_empty() || columns.is_empty() {
+            return Ok(0);
+        }
+
+        let categories: Vec<values::ValueCategory> = columns.iter().map(|c| values::categorize(&c.data_type)).collect();
+        let coerce_row = |row: &Vec<serde_json::Value>| -> Vec<SqlValue> {
+            categories
+                .iter()
+                .enumerate()
+                .map(|(i, category)| values::coerce(row.get(i).unwrap_or(&serde_json::Value::Null), *category))
+                .collect()
+        };
+        let rows_per_statement = (self.max_parameters() / columns.len()).clamp(1, 1000);
+        let insert_failed = |e: sqlx::Error| AppError::QueryExecutionFailed(format!("Insert into {} failed: {}", table, e));
+
+        match self {
+            DatabaseConnection::MySQL(pool, _) => {
+                let mut tx = pool.begin().await?;
+                for chunk in rows.chunks(rows_per_statement) {
+                    let sql = self.insert_statement(table, columns, chunk.len());
+                    let mut query = sqlx::query(&sql);
+                    for value in chunk.iter().flat_map(coerce_row) {
+                        query = values::bind_value(query, value);
+                    }
+                    query.execute(&mut *tx).await.map_err(insert_failed)?;
+                }
+                tx.commit().await?;
+            }
+            DatabaseConnection::PostgreSQL(pool, _) => {
+                let mut tx = pool.begin().await?;
+                for chunk in rows.chunks(rows_per_statement) {
+                    let sql = self.insert_statement(table, columns, chunk.len());
+                    let mut query = sqlx::query(&sql);
+                    for value in chunk.iter().flat_map(coerce_row) {
+                        query = values::bind_value(query, value);
+                    }
+                    query.execute(&mut *tx).await.map_err(insert_failed)?;
+                }
+                tx.commit().await?;
+            }
+            DatabaseConnection::SQLite(pool, _) => {
+                let mut tx = pool.begin().await?;
+                for chunk in rows.chunks(rows_per_statement) {
+                    let sql = self.insert_statement(table, columns, chunk.len());
+                    let mut query = sqlx::query(&sql);
+                    for value in chunk.iter().flat_map(coerce_row) {
+                        query = values::bind_value(query, value);
+                    }
+                    query.execute(&mut *tx).await.map_err(insert_failed)?;
+                }
+                tx.commit().await?;
+            }
+            DatabaseConnection::MongoDB(..) => {
+                self.insert_documents(table, columns, rows).await.map_err(|partial| partial.error)?;
+            }
+            DatabaseConnection::SQLServer(client) => {
+                let mut client = client.lock().await;
+                client.simple_query("BEGIN TRANSACTION").await?.into_results().await?;
+                for chunk in rows.chunks(rows_per_statement) {
+                    let mut query = tiberius::Query::new(self.insert_statement(table, columns, chunk.len()));
+                    for value in chunk.iter().flat_map(coerce_row) {
+                        values::bind_mssql(&mut query, value);
+                    }
+                    if let Err(e) = query.execute(&mut client).await {
+                        let _ = client.simple_query("IF @@TRANCOUNT > 0 ROLLBACK TRANSACTION").await;
+                        return Err(AppError::QueryExecutionFailed(format!("Insert into {} failed: {}", table, e)));
+                    }
+                }
+                client.simple_query("COMMIT TRANSACTION").await?.into_results().await?;
+            }
+            DatabaseConnection::Redis(_) => {
+                return Err(AppError::InvalidConfiguration("Rows can't be inserted into Redis; write keys instead".to_string()));
+            }
+            DatabaseConnection::DuckDB(client) => {
+                let statements: Vec<(String, Vec<duckdb::types::Value>)> = rows
+                    .chunks(rows_per_statement)
+                    .map(|chunk| {
+                        let values = chunk.iter().flat_map(coerce_row).map(values::to_duckdb).collect();
+                        (self.insert_statement(table, columns, chunk.len()), values)
+                    })
+                    .collect();
+                let table = table.to_string();
+                duckdb_blocking(client, move |conn| {
+                    conn.execute_batch("BEGIN TRANSACTION").map_err(duckdb_failed)?;
+                    for (sql, values) in statements {
+                        if let Err(e) = conn.execute(&sql, duckdb::params_from_iter(values)) {
+                            let _ = conn.execute_batch("ROLLBACK");
+                            return Err(AppError::QueryExecutionFailed(format!("Insert into {} failed: {}", table, e)));
+                        }
+                    }
+                    conn.execute_batch("COMMIT").map_err(duckdb_failed)
+                }).await?;
+            }
+        }
+
+        Ok(rows.len() as u64)
+    }
+
+    // Run DDL statements in order on a single connection. With `transactional`, PostgreSQL and
+    // SQLite run them in one transaction unless the statements open their own; MySQL commits
+    // DDL implicitly either way.
+    pub async fn execute_ddl(&self, statements: &[String], transactional: bool) -> Result<(), AppError> {
+        let wrap = transactional && !statements.iter().any(|s| is_statement(s, "BEGIN"));
+        match self {
+            DatabaseConnection::MySQL(pool, _) => {
+                let mut conn = pool.acquire().await?;
+                run_ddl::<sqlx::MySql>(&mut conn, statements, false).await
+            }
+            DatabaseConnection::PostgreSQL(pool, _) => {
+                let mut conn = pool.acquire().await?;
+                run_ddl::<sqlx::Postgres>(&mut conn, statements, wrap).await
+            }
+            DatabaseConnection::SQLite(pool, _) => {
+                let mut conn = pool.acquire().await?;
+                run_ddl::<sqlx::Sqlite>(&mut conn, statements, wrap).await
+            }
+            DatabaseConnection::MongoDB(..) => Err(AppError::InvalidConfiguration("DDL is not supported for MongoDB".to_string())),
+            DatabaseConnection::Redis(_) => Err(AppError::InvalidConfiguration("DDL is not supported for Redis".to_string())),
+            // DuckDB DDL is transactional
+            DatabaseConnection::DuckDB(client) => {
+                let statements = statements.to_vec();
+                duckdb_blocking(client, move |conn| {
+                    if wrap {
+                        conn.execute_batch("BEGIN TRANSACTION").map_err(duckdb_failed)?;
+                    }
+                    for statement in &statements {
+                        if let Err(e) = conn.execute_batch(statement) {
+                            if wrap {
+                                let _ = conn.execute_batch("ROLLBACK");
+                            }
+                            return Err(AppError::QueryExecutionFailed(format!("{} failed: {}", statement.trim(), e)));
+                        }
+                    }
+                    if wrap {
+                        conn.execute_batch("COMMIT").map_err(duckdb_failed)?;
+                    }
+                    Ok(())
+                }).await
+            }
+            // SQL Server DDL is transactional, like PostgreSQL's
+            DatabaseConnection::SQLServer(client) => {
+                let mut client = client.lock().await;
+                if wrap {
+                    client.simple_query("BEGIN TRANSACTION").await?.into_results().await?;
+                }
+                for statement in statements {
+                    let result = match client.simple_query(statement.as_str()).await {
+                        Ok(stream) => stream.into_results().await.map(|_| ()),
+                        Err(e) => Err(e),
+                    };
+                    if let Err(e) = result {
+                        if wrap {
+                            let _ = client.simple_query("IF @@TRANCOUNT > 0 ROLLBACK TRANSACTION").await;
+                        }
+                        return Err(AppError::QueryExecutionFailed(format!("{} failed: {}", statement.trim(), e)));
+                    }
+                }
+                if wrap {
+                    client.simple_query("COMMIT TRANSACTION").await?.into_results().await?;
+                }
+                Ok(())
+            }
+        }
+    }
+
     pub async fn list_databases(&self) -> Result<Vec<String>, AppError> {
         match self {
-            DatabaseConnection::MySQL(pool) => {
+            DatabaseConnection::MySQL(pool, _) => {
                 let rows = sqlx::query("SHOW DATABASES").fetch_all(pool).await?;
                 let databases: Vec<String> = rows.iter()
                     .map(|row| row.get::<String, _>("Database"))
                     .collect();
                 Ok(databases)
             }
-            DatabaseConnection::PostgreSQL(pool) => {
+            DatabaseConnection::PostgreSQL(pool, _) => {
                 let rows = sqlx::query("SELECT datname FROM pg_database WHERE datistemplate = false")
                     .fetch_all(pool).await?;
                 let databases: Vec<String> = rows.iter()
@@ -823,20 +3126,44 @@ impl DatabaseConnection {
                     .collect();
                 Ok(databases)
             }
-            DatabaseConnection::SQLite(_pool) => {
-                // SQLite doesn't have multiple databases concept
-                Ok(vec!["main".to_string()])
+            DatabaseConnection::SQLite(pool, _) => {
+                // main plus any attached databases
+                let rows = sqlx::query("PRAGMA database_list").fetch_all(pool).await?;
+                Ok(rows.iter()
+                    .map(|row| row.get::<String, _>("name"))
+                    .filter(|name| name != "temp")
+                    .collect())
             }
             DatabaseConnection::MongoDB(client, _database) => {
                 let databases = client.list_database_names(None, None).await?;
                 Ok(databases)
             }
+            DatabaseConnection::SQLServer(client) => {
+                let rows = mssql_rows(client, "SELECT name FROM sys.databases WHERE HAS_DBACCESS(name) = 1 ORDER BY name", &[]).await?;
+                Ok(rows.iter().filter_map(|row| mssql_text(row, "name")).collect())
+            }
+            DatabaseConnection::Redis(client) => {
+                // CONFIG can be renamed or disabled on managed servers; 16 is the default
+                let count = match redis_command(client, "CONFIG GET databases").await {
+                    Ok((_, redis::Value::Bulk(items))) => items
+                        .get(1)
+                        .and_then(|v| redis::from_redis_value::<i64>(v).ok())
+                        .unwrap_or(16),
+                    _ => 16,
+                };
+                Ok((0..count.max(client.db + 1)).map(|db| db.to_string()).collect())
+            }
+            // The main file plus any ATTACHed databases
+            DatabaseConnection::DuckDB(client) => {
+                let rows = duckdb_rows(client, "SELECT database_name FROM duckdb_databases() WHERE NOT internal ORDER BY database_name", Vec::new()).await?;
+                Ok(rows.iter().filter_map(|row| json_text(row.first())).collect())
+            }
         }
     }
 
     pub async fn list_collections(&self) -> Result<Vec<String>, AppError> {
         match self {
-            DatabaseConnection::MySQL(pool) => {
+            DatabaseConnection::MySQL(pool, _) => {
                 let rows = sqlx::query("SHOW TABLES").fetch_all(pool).await?;
                 let tables: Vec<String> = rows.iter()
                     .map(|row| {
@@ -846,7 +3173,7 @@ impl DatabaseConnection {
                     .collect();
                 Ok(tables)
             }
-            DatabaseConnection::PostgreSQL(pool) => {
+            DatabaseConnection::PostgreSQL(pool, _) => {
                 let rows = sqlx::query("SELECT tablename FROM pg_tables WHERE schemaname = 'public'")
                     .fetch_all(pool).await?;
                 let tables: Vec<String> = rows.iter()
@@ -854,7 +3181,7 @@ impl DatabaseConnection {
                     .collect();
                 Ok(tables)
             }
-            DatabaseConnection::SQLite(pool) => {
+            DatabaseConnection::SQLite(pool, _) => {
                 let rows = sqlx::query("SELECT name FROM sqlite_master WHERE type='table'")
                     .fetch_all(pool).await?;
                 let tables: Vec<String> = rows.iter()
@@ -866,6 +3193,159 @@ impl DatabaseConnection {
                 let collections = database.list_collection_names(None).await?;
                 Ok(collections)
             }
+            DatabaseConnection::SQLServer(client) => {
+                let rows = mssql_rows(
+                    client,
+                    "SELECT TABLE_NAME FROM INFORMATION_SCHEMA.TABLES WHERE TABLE_SCHEMA = SCHEMA_NAME() ORDER BY TABLE_NAME",
+                    &[],
+                ).await?;
+                Ok(rows.iter().filter_map(|row| mssql_text(row, "TABLE_NAME")).collect())
+            }
+            DatabaseConnection::Redis(_) => Ok(Vec::new()),
+            DatabaseConnection::DuckDB(client) => {
+                let rows = duckdb_rows(
+                    client,
+                    "SELECT table_name FROM information_schema.tables WHERE table_catalog = current_database() AND table_schema = current_schema() ORDER BY table_name",
+                    Vec::new(),
+                ).await?;
+                Ok(rows.iter().filter_map(|row| json_text(row.first())).collect())
+            }
+        }
+    }
+}
+
+// Tables of attached SQLite databases are named "alias.table"; split off the alias when it
+// names an attached database so PRAGMAs can target it
+async fn sqlite_table_ref(pool: &SqlitePool, table: &str) -> Result<(Option<String>, String), AppError> {
+    if let Some((schema, name)) = table.split_once('.') {
+        let attached = sqlx::query("PRAGMA database_list").fetch_all(pool).await?;
+        if schema != "main" && attached.iter().any(|row| row.get::<String, _>("name") == schema) {
+            return Ok((Some(schema.to_string()), name.to_string()));
+        }
+    }
+    Ok((None, table.to_string()))
+}
+
+// Databases to read catalog rows from, with the table to filter on: the one holding the
+// requested table, or main plus every attached database for schema-wide loads
+async fn sqlite_table_scopes(pool: &SqlitePool, table: Option<&str>) -> Result<Vec<(Option<String>, Option<String>)>, AppError> {
+    if let Some(table) = table {
+        let (schema, name) = sqlite_table_ref(pool, table).await?;
+        return Ok(vec![(schema, Some(name))]);
+    }
+    Ok(sqlx::query("PRAGMA database_list")
+        .fetch_all(pool)
+        .await?
+        .iter()
+        .map(|row| row.get::<String, _>("name"))
+        .filter(|name| name != "temp")
+        .map(|name| ((name != "main").then_some(name), None))
+        .collect())
+}
+
+fn sqlite_table_name(schema: &Option<String>, name: String) -> String {
+    match schema {
+        Some(schema) => format!("{}.{}", schema, name),
+        None => name,
+    }
+}
+
+fn sqlite_pragma(pragma: &str, schema: &Option<String>, argument: &str) -> String {
+    match schema {
+        Some(schema) => format!("PRAGMA {}.{}({})", quote_identifier(schema, '"'), pragma, quote_identifier(argument, '"')),
+        None => format!("PRAGMA {}({})", pragma, quote_identifier(argument, '"')),
+    }
+}
+
+fn is_statement(sql: &str, keyword: &str) -> bool {
+    sql.trim().trim_end_matches(';').trim().eq_ignore_ascii_case(keyword)
+}
+
+async fn run_ddl<DB: sqlx::Database>(conn: &mut DB::Connection, statements: &[String], wrap: bool) -> Result<(), AppError>
+where
+    for<'e> &'e mut DB::Connection: Executor<'e, Database = DB>,
+{
+    let mut open = false;
+    if wrap {
+        conn.execute("BEGIN").await?;
+        open = true;
+    }
+    for (i, statement) in statements.iter().enumerate() {
+        // A foreign key check reports violations as rows instead of failing
+        let result = if is_statement(statement, "PRAGMA foreign_key_check") {
+            match conn.fetch_optional(statement.as_str()).await {
+                Ok(Some(_)) => Err("rows violate foreign key constraints".to_string()),
+                Ok(None) => Ok(()),
+                Err(e) => Err(e.to_string()),
+            }
+        } else {
+            conn.execute(statement.as_str()).await.map(|_| ()).map_err(|e| e.to_string())
+        };
+        if let Err(e) = result {
+            if open {
+                let _ = conn.execute("ROLLBACK").await;
+            }
+            // Trailing PRAGMAs restore connection settings, e.g. after a SQLite table rebuild
+            for pragma in statements[i + 1..].iter().filter(|s| s.trim_start().to_uppercase().starts_with("PRAGMA")) {
+                let _ = conn.execute(pragma.as_str()).await;
+            }
+            return Err(AppError::QueryExecutionFailed(format!("{} failed: {}", statement.trim(), e)));
+        }
+        if is_statement(statement, "BEGIN") {
+            open = true;
+        } else if is_statement(statement, "COMMIT") || is_statement(statement, "ROLLBACK") {
+            open = false;
         }
     }
+    if wrap {
+        conn.execute("COMMIT").await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mysql_flavors_are_detected_from_the_version() {
+        let mysql = ServerInfo::from_mysql_version("8.0.35-0ubuntu0.22.04.1");
+        assert_eq!((mysql.flavor, mysql.version.as_str()), (ServerFlavor::MySQL, "8.0.35"));
+
+        let mariadb = ServerInfo::from_mysql_version("10.11.6-MariaDB-1:10.11.6+maria~ubu2204");
+        assert_eq!((mariadb.flavor, mariadb.version.as_str()), (ServerFlavor::MariaDB, "10.11.6"));
+        assert!(mariadb.version_at_least(10, 2));
+
+        let legacy = ServerInfo::from_mysql_version("5.5.5-10.4.32-MariaDB-log");
+        assert_eq!((legacy.flavor, legacy.version.as_str()), (ServerFlavor::MariaDB, "10.4.32"));
+
+        let tidb = ServerInfo::from_mysql_version("8.0.11-TiDB-v7.5.0");
+        assert_eq!((tidb.flavor, tidb.version.as_str()), (ServerFlavor::TiDB, "7.5.0"));
+    }
+
+    #[test]
+    fn postgres_flavors_are_detected_from_the_version() {
+        let postgres = ServerInfo::from_postgres_version(
+            "PostgreSQL 16.1 (Debian 16.1-1.pgdg120+1) on x86_64-pc-linux-gnu, compiled by gcc",
+        );
+        assert_eq!((postgres.flavor, postgres.version.as_str()), (ServerFlavor::PostgreSQL, "16.1"));
+
+        let windows = ServerInfo::from_postgres_version("PostgreSQL 15.4, compiled by Visual C++ build 1914, 64-bit");
+        assert_eq!(windows.version, "15.4");
+
+        let cockroach = ServerInfo::from_postgres_version("CockroachDB CCL v23.1.11 (x86_64-pc-linux-gnu, built 2023/09/27)");
+        assert_eq!((cockroach.flavor, cockroach.version.as_str()), (ServerFlavor::CockroachDB, "23.1.11"));
+    }
+
+    #[test]
+    fn versions_compare_by_major_and_minor() {
+        let beta = ServerInfo::from_postgres_version("PostgreSQL 17beta1 on x86_64-pc-linux-gnu");
+        assert!(beta.version_at_least(17, 0));
+        assert!(!beta.version_at_least(17, 1));
+
+        let mariadb = ServerInfo::from_mysql_version("10.1.48-MariaDB");
+        assert!(mariadb.version_at_least(10, 1));
+        assert!(!mariadb.version_at_least(10, 2));
+        assert!(mariadb.version_at_least(5, 7));
+    }
 }
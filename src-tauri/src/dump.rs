@@ -0,0 +1,732 @@
+use crate::database::{pg_table_ref, quote_identifier, DatabaseConnection, RowSink};
+use crate::error::AppError;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use futures::TryStreamExt;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::{MySqlPool, PgPool, Row, SqlitePool};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::time::Instant;
+
+const DEFAULT_ROWS_PER_INSERT: usize = 100;
+const PROGRESS_INTERVAL: u64 = 1000;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DumpOptions {
+    // Output file; for MongoDB a directory that receives one file per collection
+    pub path: String,
+    // Defaults to every table in the database
+    pub tables: Option<Vec<String>>,
+    #[serde(default)]
+    pub schema_only: bool,
+    #[serde(default)]
+    pub data_only: bool,
+    #[serde(default)]
+    pub gzip: bool,
+    #[serde(default)]
+    pub drop_tables: bool,
+    pub rows_per_insert: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DumpProgress {
+    pub table: String,
+    pub rows_written: u64,
+    pub tables_done: usize,
+    pub tables_total: usize,
+    pub finished: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TableDumpSummary {
+    pub name: String,
+    pub rows: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DumpSummary {
+    pub files: Vec<String>,
+    pub tables: Vec<TableDumpSummary>,
+    pub duration_ms: u64,
+}
+
+fn dump_error(err: impl std::fmt::Display) -> AppError {
+    AppError::FileOperationFailed(format!("Dump failed: {}", err))
+}
+
+enum DumpOutput {
+    Plain(BufWriter<File>),
+    Gzip(GzEncoder<BufWriter<File>>),
+}
+
+impl DumpOutput {
+    fn create(path: &Path, gzip: bool) -> Result<Self, AppError> {
+        let file = BufWriter::new(File::create(path)?);
+        Ok(if gzip {
+            DumpOutput::Gzip(GzEncoder::new(file, Compression::default()))
+        } else {
+            DumpOutput::Plain(file)
+        })
+    }
+
+    fn finish(self) -> Result<(), AppError> {
+        match self {
+            DumpOutput::Plain(mut file) => file.flush()?,
+            DumpOutput::Gzip(encoder) => encoder.finish()?.flush()?,
+        }
+        Ok(())
+    }
+}
+
+impl Write for DumpOutput {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            DumpOutput::Plain(file) => file.write(buf),
+            DumpOutput::Gzip(encoder) => encoder.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            DumpOutput::Plain(file) => file.flush(),
+            DumpOutput::Gzip(encoder) => encoder.flush(),
+        }
+    }
+}
+
+// Everything needed to recreate one table and reload its rows
+struct TableDefinition {
+    name: String,
+    // Quoted table to read rows from when it isn't `name` itself (attached SQLite databases)
+    source: Option<String>,
+    // Statements that must run before CREATE TABLE (sequences)
+    pre_create: Vec<String>,
+    create: String,
+    // Indexes and foreign keys, applied after all data is loaded
+    post_schema: Vec<String>,
+    // Statements that fix up state after the data load (sequence positions)
+    post_data: Vec<String>,
+    // Insertable column names with a SELECT expression that renders a SQL literal
+    columns: Vec<(String, String)>,
+    insert_modifier: &'static str,
+}
+
+async fn mysql_table(pool: &MySqlPool, table: &str) -> Result<TableDefinition, AppError> {
+    let row = sqlx::query(&format!("SHOW CREATE TABLE {}", quote_identifier(table, '`')))
+        .fetch_one(pool)
+        .await?;
+    let create: String = row.try_get(1)?;
+
+    let column_rows = sqlx::query(
+        r#"
+        SELECT COLUMN_NAME, DATA_TYPE, EXTRA
+        FROM INFORMATION_SCHEMA.COLUMNS
+        WHERE TABLE_SCHEMA = DATABASE() AND TABLE_NAME = ?
+        ORDER BY ORDINAL_POSITION
+        "#,
+    )
+    .bind(table)
+    .fetch_all(pool)
+    .await?;
+
+    let mut columns = Vec::new();
+    for row in column_rows {
+        let name: String = row.get("COLUMN_NAME");
+        let data_type: String = row.get::<String, _>("DATA_TYPE").to_lowercase();
+        let extra: String = row.get("EXTRA");
+        if extra.to_uppercase().contains("GENERATED") {
+            continue;
+        }
+        let expression = mysql_column_expression(&name, &data_type);
+        columns.push((name, expression));
+    }
+
+    Ok(TableDefinition {
+        name: table.to_string(),
+        source: None,
+        pre_create: Vec::new(),
+        create,
+        post_schema: Vec::new(),
+        post_data: Vec::new(),
+        columns,
+        insert_modifier: "",
+    })
+}
+
+// Server-side expression rendering a MySQL column as an SQL literal. Binary values use X'..'
+// so that empty ones stay valid, and BIT values are written as b'..'
+fn mysql_column_expression(name: &str, data_type: &str) -> String {
+    let quoted = quote_identifier(name, '`');
+    if data_type == "bit" {
+        format!("IF({0} IS NULL, 'NULL', CONCAT('b''', BIN({0}), ''''))", quoted)
+    } else if data_type.contains("blob") || data_type.contains("binary") {
+        format!("IF({0} IS NULL, 'NULL', CONCAT('X''', HEX({0}), ''''))", quoted)
+    } else {
+        format!("QUOTE({})", quoted)
+    }
+}
+
+// Tables of other schemas ("schema.table") are read from that schema and dumped under their
+// own name, like attached SQLite databases
+async fn postgresql_table(pool: &PgPool, table: &str) -> Result<TableDefinition, AppError> {
+    let (schema, table) = pg_table_ref(table);
+    let quoted_table = quote_identifier(table, '"');
+
+    let column_rows = sqlx::query(
+        r#"
+        SELECT a.attname AS column_name,
+               format_type(a.atttypid, a.atttypmod) AS data_type,
+               a.attnotnull AS not_null,
+               pg_get_expr(d.adbin, d.adrelid) AS default_value,
+               a.attidentity::text AS identity,
+               a.attgenerated::text AS generated
+        FROM pg_attribute a
+        JOIN pg_class c ON c.oid = a.attrelid
+        JOIN pg_namespace n ON n.oid = c.relnamespace
+        LEFT JOIN pg_attrdef d ON d.adrelid = a.attrelid AND d.adnum = a.attnum
+        WHERE n.nspname = COALESCE($2, current_schema()) AND c.relname = $1 AND a.attnum > 0 AND NOT a.attisdropped
+        ORDER BY a.attnum
+        "#,
+    )
+    .bind(table)
+    .bind(schema)
+    .fetch_all(pool)
+    .await?;
+
+    let mut definitions = Vec::new();
+    let mut columns = Vec::new();
+    let mut insert_modifier = "";
+    for row in column_rows {
+        let name: String = row.get("column_name");
+        let data_type: String = row.get("data_type");
+        let not_null: bool = row.get("not_null");
+        let default_value: Option<String> = row.get("default_value");
+        let identity: String = row.get("identity");
+        let generated: String = row.get("generated");
+        let quoted = quote_identifier(&name, '"');
+
+        let mut definition = format!("    {} {}", quoted, data_type);
+        match (identity.as_str(), generated.as_str()) {
+            ("a", _) => {
+                definition.push_str(" GENERATED ALWAYS AS IDENTITY");
+                insert_modifier = " OVERRIDING SYSTEM VALUE";
+            }
+            ("d", _) => definition.push_str(" GENERATED BY DEFAULT AS IDENTITY"),
+            (_, "s") => {
+                if let Some(expression) = &default_value {
+                    definition.push_str(&format!(" GENERATED ALWAYS AS ({}) STORED", expression));
+                }
+            }
+            _ => {
+                if let Some(default_value) = &default_value {
+                    definition.push_str(&format!(" DEFAULT {}", default_value));
+                }
+            }
+        }
+        if not_null {
+            definition.push_str(" NOT NULL");
+        }
+        definitions.push(definition);
+
+        if generated != "s" {
+            columns.push((name, format!("quote_nullable({})", quoted)));
+        }
+    }
+
+    let constraint_rows = sqlx::query(
+        r#"
+        SELECT co.conname AS name, co.contype::text AS kind, pg_get_constraintdef(co.oid) AS definition
+        FROM pg_constraint co
+        JOIN pg_class c ON c.oid = co.conrelid
+        JOIN pg_namespace n ON n.oid = c.relnamespace
+        WHERE n.nspname = COALESCE($2, current_schema()) AND c.relname = $1
+        ORDER BY co.contype, co.conname
+        "#,
+    )
+    .bind(table)
+    .bind(schema)
+    .fetch_all(pool)
+    .await?;
+
+    let mut post_schema = Vec::new();
+    for row in constraint_rows {
+        let name: String = row.get("name");
+        let kind: String = row.get("kind");
+        let definition: String = row.get("definition");
+        let constraint = format!("CONSTRAINT {} {}", quote_identifier(&name, '"'), definition);
+        // Foreign keys are added after every table exists and is loaded
+        if kind == "f" {
+            post_schema.push(format!("ALTER TABLE {} ADD {};", quoted_table, constraint));
+        } else {
+            definitions.push(format!("    {}", constraint));
+        }
+    }
+
+    let index_rows = sqlx::query(
+        r#"
+        SELECT pg_get_indexdef(i.indexrelid) AS definition
+        FROM pg_index i
+        JOIN pg_class c ON c.oid = i.indrelid
+        JOIN pg_namespace n ON n.oid = c.relnamespace
+        WHERE n.nspname = COALESCE($2, current_schema()) AND c.relname = $1
+          AND NOT EXISTS (SELECT 1 FROM pg_constraint co WHERE co.conindid = i.indexrelid)
+        "#,
+    )
+    .bind(table)
+    .bind(schema)
+    .fetch_all(pool)
+    .await?;
+    for row in index_rows {
+        let definition: String = row.get("definition");
+        post_schema.push(format!("{};", definition));
+    }
+
+    // Sequences behind serial and identity columns
+    let sequence_rows = sqlx::query(
+        r#"
+        SELECT s.relname AS sequence_name, a.attname AS column_name, d.deptype::text AS dependency
+        FROM pg_class s
+        JOIN pg_depend d ON d.objid = s.oid AND d.deptype IN ('a', 'i')
+        JOIN pg_class t ON t.oid = d.refobjid
+        JOIN pg_attribute a ON a.attrelid = t.oid AND a.attnum = d.refobjsubid
+        JOIN pg_namespace n ON n.oid = t.relnamespace
+        WHERE s.relkind = 'S' AND n.nspname = COALESCE($2, current_schema()) AND t.relname = $1
+        "#,
+    )
+    .bind(table)
+    .bind(schema)
+    .fetch_all(pool)
+    .await?;
+
+    let mut pre_create = Vec::new();
+    let mut post_data = Vec::new();
+    for row in sequence_rows {
+        let sequence: String = row.get("sequence_name");
+        let column: String = row.get("column_name");
+        let dependency: String = row.get("dependency");
+        // Identity sequences are created by the column definition itself
+        if dependency == "a" {
+            pre_create.push(format!("CREATE SEQUENCE IF NOT EXISTS {};", quote_identifier(&sequence, '"')));
+        }
+        post_data.push(format!(
+            "SELECT setval(pg_get_serial_sequence('{}', '{}'), COALESCE((SELECT MAX({}) FROM {}), 0) + 1, false);",
+            quoted_table.replace('\'', "''"),
+            column.replace('\'', "''"),
+            quote_identifier(&column, '"'),
+            quoted_table
+        ));
+    }
+
+    Ok(TableDefinition {
+        name: table.to_string(),
+        source: schema.map(|s| format!("{}.{}", quote_identifier(s, '"'), quoted_table)),
+        pre_create,
+        create: format!("CREATE TABLE {} (\n{}\n)", quoted_table, definitions.join(",\n")),
+        post_schema,
+        post_data,
+        columns,
+        insert_modifier,
+    })
+}
+
+// Tables of attached databases ("alias.table") are read from the alias and dumped under
+// their own name, so the dump restores into the main database
+async fn sqlite_table(pool: &SqlitePool, aliases: &[String], table: &str) -> Result<TableDefinition, AppError> {
+    let (schema, table) = match table.split_once('.') {
+        Some((alias, name)) if aliases.iter().any(|a| a == alias) => (Some(quote_identifier(alias, '"')), name),
+        _ => (None, table),
+    };
+    let prefix = schema.as_ref().map(|s| format!("{}.", s)).unwrap_or_default();
+
+    let create: String = sqlx::query(&format!("SELECT sql FROM {}sqlite_master WHERE type = 'table' AND name = ?", prefix))
+        .bind(table)
+        .fetch_one(pool)
+        .await?
+        .get("sql");
+
+    let extra_rows = sqlx::query(&format!(
+        "SELECT sql FROM {}sqlite_master WHERE type IN ('index', 'trigger') AND tbl_name = ? AND sql IS NOT NULL",
+        prefix
+    ))
+    .bind(table)
+    .fetch_all(pool)
+    .await?;
+    let post_schema = extra_rows
+        .iter()
+        .map(|row| format!("{};", row.get::<String, _>("sql")))
+        .collect();
+
+    // table_xinfo marks generated columns with hidden = 2 or 3
+    let column_rows = sqlx::query(&format!("PRAGMA {}table_xinfo({})", prefix, quote_identifier(table, '"')))
+        .fetch_all(pool)
+        .await?;
+    let columns = column_rows
+        .iter()
+        .filter(|row| row.get::<i64, _>("hidden") == 0)
+        .map(|row| {
+            let name: String = row.get("name");
+            let expression = format!("quote({})", quote_identifier(&name, '"'));
+            (name, expression)
+        })
+        .collect();
+
+    Ok(TableDefinition {
+        name: table.to_string(),
+        source: schema.map(|s| format!("{}.{}", s, quote_identifier(table, '"'))),
+        pre_create: Vec::new(),
+        create,
+        post_schema,
+        post_data: Vec::new(),
+        columns,
+        insert_modifier: "",
+    })
+}
+
+// Values arrive already rendered as literals by the SELECT; quote() hands numbers back as
+// numbers rather than literal text
+fn value_literal(value: &Value) -> String {
+    match value {
+        Value::String(literal) => literal.clone(),
+        Value::Number(number) => number.to_string(),
+        Value::Bool(flag) => if *flag { "1" } else { "0" }.to_string(),
+        _ => "NULL".to_string(),
+    }
+}
+
+// Collects pre-rendered literal rows and writes multi-row INSERT statements
+struct InsertSink<'a, F: FnMut(DumpProgress) + Send> {
+    out: &'a mut DumpOutput,
+    insert_prefix: String,
+    rows_per_insert: usize,
+    batch: Vec<String>,
+    rows: u64,
+    table: String,
+    tables_done: usize,
+    tables_total: usize,
+    on_progress: &'a mut F,
+}
+
+impl<F: FnMut(DumpProgress) + Send> InsertSink<'_, F> {
+    fn flush_batch(&mut self) -> Result<(), AppError> {
+        if self.batch.is_empty() {
+            return Ok(());
+        }
+        writeln!(self.out, "{}\n{};", self.insert_prefix, self.batch.join(",\n"))?;
+        self.batch.clear();
+        Ok(())
+    }
+}
+
+impl<F: FnMut(DumpProgress) + Send> RowSink for InsertSink<'_, F> {
+    fn columns(&mut self, _columns: &[String]) -> Result<(), AppError> {
+        Ok(())
+    }
+
+    fn row(&mut self, values: Vec<Value>) -> Result<(), AppError> {
+        let literals: Vec<String> = values.iter().map(value_literal).collect();
+        self.batch.push(format!("({})", literals.join(", ")));
+        self.rows += 1;
+        if self.batch.len() >= self.rows_per_insert {
+            self.flush_batch()?;
+        }
+        if self.rows.is_multiple_of(PROGRESS_INTERVAL) {
+            (self.on_progress)(DumpProgress {
+                table: self.table.clone(),
+                rows_written: self.rows,
+                tables_done: self.tables_done,
+                tables_total: self.tables_total,
+                finished: false,
+            });
+        }
+        Ok(())
+    }
+}
+
+pub async fn dump_database<F>(
+    connection: &DatabaseConnection,
+    options: &DumpOptions,
+    mut on_progress: F,
+) -> Result<DumpSummary, AppError>
+where
+    F: FnMut(DumpProgress) + Send,
+{
+    if options.schema_only && options.data_only {
+        return Err(AppError::InvalidConfiguration("schema_only and data_only are mutually exclusive".to_string()));
+    }
+
+    let started = Instant::now();
+    let tables: Vec<String> = match &options.tables {
+        Some(tables) if !tables.is_empty() => tables.clone(),
+        _ => connection
+            .list_collections()
+            .await?
+            .into_iter()
+            .filter(|t| !t.starts_with("sqlite_"))
+            .collect(),
+    };
+
+    if let DatabaseConnection::MongoDB(..) = connection {
+        return dump_mongodb(connection, options, &tables, started, on_progress).await;
+    }
+
+    let mut definitions = Vec::new();
+    for table in &tables {
+        let definition = match connection {
+            DatabaseConnection::MySQL(pool, _) => mysql_table(pool, table).await?,
+            DatabaseConnection::PostgreSQL(pool, _) => postgresql_table(pool, table).await?,
+            DatabaseConnection::SQLite(pool, aliases) => sqlite_table(pool, aliases, table).await?,
+            DatabaseConnection::MongoDB(..) => unreachable!(),
+            DatabaseConnection::SQLServer(_) => {
+                return Err(AppError::InvalidConfiguration("Dumps aren't supported for SQL Server yet".to_string()))
+            }
+            DatabaseConnection::DuckDB(_) => {
+                return Err(AppError::InvalidConfiguration("Dumps aren't supported for DuckDB yet; use EXPORT DATABASE".to_string()))
+            }
+            DatabaseConnection::Redis(_) => {
+                return Err(AppError::InvalidConfiguration("Redis has no tables to dump; use BGSAVE for an RDB snapshot".to_string()))
+            }
+        };
+        definitions.push(definition);
+    }
+
+    let mut out = DumpOutput::create(Path::new(&options.path), options.gzip)?;
+    let db_type = connection.db_type();
+
+    writeln!(out, "-- Titania-Q SQL dump")?;
+    writeln!(out, "-- Database type: {}", db_type.as_str())?;
+    writeln!(out, "-- Generated at: {}", chrono::Utc::now().to_rfc3339())?;
+    writeln!(out)?;
+    match connection {
+        DatabaseConnection::MySQL(..) => writeln!(out, "SET FOREIGN_KEY_CHECKS = 0;\nSET NAMES utf8mb4;\n")?,
+        DatabaseConnection::PostgreSQL(..) => writeln!(out, "BEGIN;\n")?,
+        _ => writeln!(out, "PRAGMA foreign_keys = OFF;\nBEGIN TRANSACTION;\n")?,
+    }
+
+    if !options.data_only {
+        if options.drop_tables {
+            for definition in definitions.iter().rev() {
+                let cascade = if matches!(connection, DatabaseConnection::PostgreSQL(..)) { " CASCADE" } else { "" };
+                writeln!(out, "DROP TABLE IF EXISTS {}{};", connection.quote_ident(&definition.name), cascade)?;
+            }
+            writeln!(out)?;
+        }
+        for definition in &definitions {
+            writeln!(out, "-- Table structure for {}", definition.name)?;
+            for statement in &definition.pre_create {
+                writeln!(out, "{}", statement)?;
+            }
+            writeln!(out, "{};\n", definition.create.trim_end_matches(';'))?;
+        }
+    }
+
+    let rows_per_insert = options.rows_per_insert.unwrap_or(DEFAULT_ROWS_PER_INSERT).max(1);
+    let mut summaries = Vec::new();
+    for (index, definition) in definitions.iter().enumerate() {
+        let mut rows = 0;
+        if !options.schema_only && !definition.columns.is_empty() {
+            writeln!(out, "-- Data for {}", definition.name)?;
+            let column_list: Vec<String> = definition.columns.iter().map(|(name, _)| connection.quote_ident(name)).collect();
+            let select_list: Vec<&str> = definition.columns.iter().map(|(_, expression)| expression.as_str()).collect();
+            let source = definition.source.clone().unwrap_or_else(|| connection.quote_ident(&definition.name));
+            let select = format!("SELECT {} FROM {}", select_list.join(", "), source);
+
+            let mut sink = InsertSink {
+                out: &mut out,
+                insert_prefix: format!(
+                    "INSERT INTO {} ({}){} VALUES",
+                    connection.quote_ident(&definition.name),
+                    column_list.join(", "),
+                    definition.insert_modifier
+                ),
+                rows_per_insert,
+                batch: Vec::new(),
+                rows: 0,
+                table: definition.name.clone(),
+                tables_done: index,
+                tables_total: definitions.len(),
+                on_progress: &mut on_progress,
+            };
+            connection.stream_query(&select, &mut sink).await?;
+            sink.flush_batch()?;
+            rows = sink.rows;
+            writeln!(out)?;
+
+            for statement in &definition.post_data {
+                writeln!(out, "{}", statement)?;
+            }
+        }
+        summaries.push(TableDumpSummary {
+            name: definition.name.clone(),
+            rows,
+        });
+        on_progress(DumpProgress {
+            table: definition.name.clone(),
+            rows_written: rows,
+            tables_done: index + 1,
+            tables_total: definitions.len(),
+            finished: false,
+        });
+    }
+
+    if !options.data_only {
+        for definition in &definitions {
+            for statement in &definition.post_schema {
+                writeln!(out, "{}", statement)?;
+            }
+        }
+        writeln!(out)?;
+    }
+
+    match connection {
+        DatabaseConnection::MySQL(..) => writeln!(out, "SET FOREIGN_KEY_CHECKS = 1;")?,
+        DatabaseConnection::PostgreSQL(..) => writeln!(out, "COMMIT;")?,
+        _ => writeln!(out, "COMMIT;\nPRAGMA foreign_keys = ON;")?,
+    }
+    out.finish()?;
+
+    on_progress(DumpProgress {
+        table: String::new(),
+        rows_written: summaries.iter().map(|s| s.rows).sum(),
+        tables_done: definitions.len(),
+        tables_total: definitions.len(),
+        finished: true,
+    });
+
+    Ok(DumpSummary {
+        files: vec![options.path.clone()],
+        tables: summaries,
+        duration_ms: started.elapsed().as_millis() as u64,
+    })
+}
+
+// One canonical extended-JSON document per line and collection, like mongoexport
+async fn dump_mongodb<F>(
+    connection: &DatabaseConnection,
+    options: &DumpOptions,
+    collections: &[String],
+    started: Instant,
+    mut on_progress: F,
+) -> Result<DumpSummary, AppError>
+where
+    F: FnMut(DumpProgress) + Send,
+{
+    let database = match connection {
+        DatabaseConnection::MongoDB(_client, database) => database,
+        _ => unreachable!(),
+    };
+
+    let directory = Path::new(&options.path);
+    std::fs::create_dir_all(directory)?;
+
+    let mut files = Vec::new();
+    let mut summaries = Vec::new();
+    for (index, name) in collections.iter().enumerate() {
+        let extension = if options.gzip { "json.gz" } else { "json" };
+        let path = directory.join(format!("{}.{}", name, extension));
+        let mut out = DumpOutput::create(&path, options.gzip)?;
+
+        let collection = database.collection::<bson::Document>(name);
+        let mut rows = 0u64;
+        if !options.schema_only {
+            let mut cursor = collection.find(None, None).await?;
+            while let Some(document) = cursor.try_next().await? {
+                let json = bson::Bson::Document(document).into_canonical_extjson();
+                writeln!(out, "{}", serde_json::to_string(&json).map_err(dump_error)?)?;
+                rows += 1;
+                if rows.is_multiple_of(PROGRESS_INTERVAL) {
+                    on_progress(DumpProgress {
+                        table: name.clone(),
+                        rows_written: rows,
+                        tables_done: index,
+                        tables_total: collections.len(),
+                        finished: false,
+                    });
+                }
+            }
+        }
+        out.finish()?;
+
+        // Index definitions next to the data so the collection can be rebuilt
+        if !options.data_only {
+            let indexes: Vec<mongodb::IndexModel> = collection.list_indexes(None).await?.try_collect().await?;
+            let indexes: Vec<Value> = indexes
+                .into_iter()
+                .map(|index| {
+                    serde_json::json!({
+                        "name": index.options.as_ref().and_then(|o| o.name.clone()),
+                        "unique": index.options.as_ref().and_then(|o| o.unique),
+                        "key": bson::Bson::Document(index.keys).into_canonical_extjson(),
+                    })
+                })
+                .collect();
+            let index_path = directory.join(format!("{}.indexes.json", name));
+            std::fs::write(&index_path, serde_json::to_string_pretty(&indexes).map_err(dump_error)?)?;
+            files.push(index_path.to_string_lossy().to_string());
+        }
+
+        files.push(path.to_string_lossy().to_string());
+        summaries.push(TableDumpSummary { name: name.clone(), rows });
+        on_progress(DumpProgress {
+            table: name.clone(),
+            rows_written: rows,
+            tables_done: index + 1,
+            tables_total: collections.len(),
+            finished: false,
+        });
+    }
+
+    on_progress(DumpProgress {
+        table: String::new(),
+        rows_written: summaries.iter().map(|s| s.rows).sum(),
+        tables_done: collections.len(),
+        tables_total: collections.len(),
+        finished: true,
+    });
+
+    Ok(DumpSummary {
+        files,
+        tables: summaries,
+        duration_ms: started.elapsed().as_millis() as u64,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn rendered_values_are_written_verbatim() {
+        assert_eq!(value_literal(&json!("'O''Brien'")), "'O''Brien'");
+        assert_eq!(value_literal(&json!("X''")), "X''");
+        assert_eq!(value_literal(&json!(42)), "42");
+        assert_eq!(value_literal(&json!(-1.5)), "-1.5");
+        assert_eq!(value_literal(&json!(true)), "1");
+        assert_eq!(value_literal(&json!(false)), "0");
+        assert_eq!(value_literal(&Value::Null), "NULL");
+    }
+
+    #[test]
+    fn mysql_binary_columns_dump_as_hex_strings() {
+        assert_eq!(
+            mysql_column_expression("data", "blob"),
+            "IF(`data` IS NULL, 'NULL', CONCAT('X''', HEX(`data`), ''''))"
+        );
+        assert_eq!(
+            mysql_column_expression("flags", "bit"),
+            "IF(`flags` IS NULL, 'NULL', CONCAT('b''', BIN(`flags`), ''''))"
+        );
+        assert_eq!(mysql_column_expression("na`me", "varchar"), "QUOTE(`na``me`)");
+    }
+
+    #[test]
+    fn identifiers_escape_their_quote_character() {
+        assert_eq!(quote_identifier("order", '`'), "`order`");
+        assert_eq!(quote_identifier("a\"b", '"'), "\"a\"\"b\"");
+        assert_eq!(pg_table_ref("sales.orders"), (Some("sales"), "orders"));
+        assert_eq!(pg_table_ref("orders"), (None, "orders"));
+    }
+}
@@ -24,6 +24,15 @@ pub enum AppError {
     #[error("AI error: {0}")]
     AIError(String),
     
+    #[error("Read-only connection: {0}")]
+    ReadOnlyViolation(String),
+    
+    #[error("Confirmation required: {0}")]
+    ConfirmationRequired(String),
+    
+    #[error("File operation failed: {0}")]
+    FileOperationFailed(String),
+    
     #[error("Internal error: {0}")]
     InternalError(String),
 }
@@ -34,6 +43,12 @@ impl From<sqlx::Error> for AppError {
     }
 }
 
+impl From<std::io::Error> for AppError {
+    fn from(err: std::io::Error) -> Self {
+        AppError::FileOperationFailed(err.to_string())
+    }
+}
+
 impl From<anyhow::Error> for AppError {
     fn from(err: anyhow::Error) -> Self {
         AppError::InternalError(err.to_string())
@@ -45,3 +60,21 @@ impl From<mongodb::error::Error> for AppError {
         AppError::DatabaseConnectionFailed(err.to_string())
     }
 }
+
+impl From<tiberius::error::Error> for AppError {
+    fn from(err: tiberius::error::Error) -> Self {
+        AppError::DatabaseConnectionFailed(err.to_string())
+    }
+}
+
+impl From<redis::RedisError> for AppError {
+    fn from(err: redis::RedisError) -> Self {
+        AppError::DatabaseConnectionFailed(err.to_string())
+    }
+}
+
+impl From<duckdb::Error> for AppError {
+    fn from(err: duckdb::Error) -> Self {
+        AppError::DatabaseConnectionFailed(err.to_string())
+    }
+}
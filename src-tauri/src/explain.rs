@@ -0,0 +1,324 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+// Backend-independent execution plan node the UI renders as a tree
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PlanNode {
+    pub node_type: String,
+    pub relation: Option<String>,
+    pub index: Option<String>,
+    pub detail: Option<String>,
+    pub startup_cost: Option<f64>,
+    pub total_cost: Option<f64>,
+    pub estimated_rows: Option<f64>,
+    pub actual_rows: Option<f64>,
+    pub actual_time_ms: Option<f64>,
+    pub loops: Option<f64>,
+    pub children: Vec<PlanNode>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueryPlan {
+    pub db_type: String,
+    pub root: PlanNode,
+    pub planning_time_ms: Option<f64>,
+    pub execution_time_ms: Option<f64>,
+    pub raw: Value,
+}
+
+impl PlanNode {
+    fn new(node_type: &str) -> Self {
+        PlanNode {
+            node_type: node_type.to_string(),
+            ..Default::default()
+        }
+    }
+}
+
+// Catalog values are sometimes numbers and sometimes numeric strings (MySQL)
+fn number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.parse().ok(),
+        _ => None,
+    }
+}
+
+fn string(value: &Value) -> Option<String> {
+    value.as_str().map(|s| s.to_string())
+}
+
+// PostgreSQL: EXPLAIN (ANALYZE, FORMAT JSON) returns [{"Plan": {...}, "Planning Time": .., "Execution Time": ..}]
+pub fn normalize_postgresql_plan(raw: Value) -> QueryPlan {
+    let top = raw.get(0).cloned().unwrap_or(Value::Null);
+    let root = top
+        .get("Plan")
+        .map(postgresql_node)
+        .unwrap_or_else(|| PlanNode::new("Unknown"));
+
+    QueryPlan {
+        db_type: "postgresql".to_string(),
+        root,
+        planning_time_ms: top.get("Planning Time").and_then(number),
+        execution_time_ms: top.get("Execution Time").and_then(number),
+        raw,
+    }
+}
+
+fn postgresql_node(plan: &Value) -> PlanNode {
+    let mut details = Vec::new();
+    for key in ["Join Type", "Index Cond", "Hash Cond", "Merge Cond", "Filter", "Sort Key"] {
+        match plan.get(key) {
+            Some(Value::String(s)) => details.push(format!("{}: {}", key, s)),
+            Some(Value::Array(items)) => {
+                let items: Vec<String> = items.iter().filter_map(string).collect();
+                details.push(format!("{}: {}", key, items.join(", ")));
+            }
+            _ => {}
+        }
+    }
+
+    PlanNode {
+        node_type: plan.get("Node Type").and_then(string).unwrap_or_default(),
+        relation: plan.get("Relation Name").and_then(string),
+        index: plan.get("Index Name").and_then(string),
+        detail: if details.is_empty() { None } else { Some(details.join("; ")) },
+        startup_cost: plan.get("Startup Cost").and_then(number),
+        total_cost: plan.get("Total Cost").and_then(number),
+        estimated_rows: plan.get("Plan Rows").and_then(number),
+        actual_rows: plan.get("Actual Rows").and_then(number),
+        actual_time_ms: plan.get("Actual Total Time").and_then(number),
+        loops: plan.get("Actual Loops").and_then(number),
+        children: plan
+            .get("Plans")
+            .and_then(|p| p.as_array())
+            .map(|plans| plans.iter().map(postgresql_node).collect())
+            .unwrap_or_default(),
+    }
+}
+
+// MySQL: EXPLAIN FORMAT=JSON returns {"query_block": {...}} with nested operation objects
+pub fn normalize_mysql_plan(raw: Value) -> QueryPlan {
+    let root = raw
+        .get("query_block")
+        .map(mysql_query_block)
+        .unwrap_or_else(|| PlanNode::new("Unknown"));
+
+    QueryPlan {
+        db_type: "mysql".to_string(),
+        root,
+        planning_time_ms: None,
+        execution_time_ms: None,
+        raw,
+    }
+}
+
+fn mysql_query_block(block: &Value) -> PlanNode {
+    let mut node = PlanNode::new("Query Block");
+    node.total_cost = block.pointer("/cost_info/query_cost").and_then(number);
+    node.detail = block.get("select_id").map(|id| format!("select_id: {}", id));
+    node.children = mysql_operations(block);
+    node
+}
+
+fn mysql_operations(value: &Value) -> Vec<PlanNode> {
+    let mut children = Vec::new();
+    let object = match value.as_object() {
+        Some(object) => object,
+        None => return children,
+    };
+
+    for (key, inner) in object {
+        match key.as_str() {
+            "table" => children.push(mysql_table(inner)),
+            "nested_loop" => {
+                let mut node = PlanNode::new("Nested Loop");
+                if let Some(items) = inner.as_array() {
+                    for item in items {
+                        node.children.extend(mysql_operations(item));
+                    }
+                }
+                children.push(node);
+            }
+            "ordering_operation" | "grouping_operation" | "duplicates_removal" | "windowing" => {
+                let node_type = match key.as_str() {
+                    "ordering_operation" => "Sort",
+                    "grouping_operation" => "Group",
+                    "duplicates_removal" => "Distinct",
+                    _ => "Window",
+                };
+                let mut node = PlanNode::new(node_type);
+                if inner.get("using_filesort").and_then(|v| v.as_bool()) == Some(true) {
+                    node.detail = Some("using filesort".to_string());
+                }
+                node.children = mysql_operations(inner);
+                children.push(node);
+            }
+            "union_result" => {
+                let mut node = PlanNode::new("Union");
+                node.relation = inner.get("table_name").and_then(string);
+                if let Some(specs) = inner.get("query_specifications").and_then(|v| v.as_array()) {
+                    for spec in specs {
+                        if let Some(block) = spec.get("query_block") {
+                            node.children.push(mysql_query_block(block));
+                        }
+                    }
+                }
+                children.push(node);
+            }
+            "query_block" => children.push(mysql_query_block(inner)),
+            "attached_subqueries" | "optimized_away_subqueries" => {
+                if let Some(items) = inner.as_array() {
+                    for item in items {
+                        children.extend(mysql_operations(item));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    children
+}
+
+fn mysql_table(table: &Value) -> PlanNode {
+    let access_type = table.get("access_type").and_then(string).unwrap_or_default();
+    let node_type = match access_type.as_str() {
+        "ALL" => "Full Table Scan".to_string(),
+        "index" => "Full Index Scan".to_string(),
+        "range" => "Index Range Scan".to_string(),
+        "ref" | "eq_ref" | "const" | "system" => "Index Lookup".to_string(),
+        "" => "Table".to_string(),
+        other => format!("Table ({})", other),
+    };
+
+    let mut details = Vec::new();
+    if !access_type.is_empty() {
+        details.push(format!("access_type: {}", access_type));
+    }
+    if let Some(condition) = table.get("attached_condition").and_then(string) {
+        details.push(format!("condition: {}", condition));
+    }
+
+    let mut node = PlanNode::new(&node_type);
+    node.relation = table.get("table_name").and_then(string);
+    node.index = table.get("key").and_then(string);
+    node.detail = if details.is_empty() { None } else { Some(details.join("; ")) };
+    node.total_cost = table.pointer("/cost_info/prefix_cost").and_then(number);
+    node.estimated_rows = table.get("rows_produced_per_join").and_then(number);
+
+    // Derived tables carry their own query block
+    if let Some(subquery) = table.get("materialized_from_subquery") {
+        node.children = mysql_operations(subquery);
+    }
+    if let Some(subqueries) = table.get("attached_subqueries").and_then(|v| v.as_array()) {
+        for subquery in subqueries {
+            node.children.extend(mysql_operations(subquery));
+        }
+    }
+
+    node
+}
+
+// SQLite: EXPLAIN QUERY PLAN returns flat (id, parent, detail) rows
+pub fn normalize_sqlite_plan(rows: Vec<(i64, i64, String)>) -> QueryPlan {
+    fn children_of(parent: i64, rows: &[(i64, i64, String)]) -> Vec<PlanNode> {
+        rows.iter()
+            .filter(|(_, p, _)| *p == parent)
+            .map(|(id, _, detail)| {
+                let node_type = detail
+                    .split_whitespace()
+                    .take_while(|w| w.chars().all(|c| c.is_ascii_uppercase()))
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                let mut node = PlanNode::new(if node_type.is_empty() { "Step" } else { &node_type });
+                node.relation = sqlite_relation(detail);
+                node.index = detail
+                    .split(" INDEX ")
+                    .nth(1)
+                    .and_then(|rest| rest.split_whitespace().next())
+                    .map(|s| s.to_string());
+                node.detail = Some(detail.clone());
+                node.children = children_of(*id, rows);
+                node
+            })
+            .collect()
+    }
+
+    let raw = Value::Array(
+        rows.iter()
+            .map(|(id, parent, detail)| serde_json::json!({ "id": id, "parent": parent, "detail": detail }))
+            .collect(),
+    );
+
+    let mut root = PlanNode::new("Query Plan");
+    root.children = children_of(0, &rows);
+
+    QueryPlan {
+        db_type: "sqlite".to_string(),
+        root,
+        planning_time_ms: None,
+        execution_time_ms: None,
+        raw,
+    }
+}
+
+fn sqlite_relation(detail: &str) -> Option<String> {
+    let mut words = detail.split_whitespace();
+    while let Some(word) = words.next() {
+        if word == "SCAN" || word == "SEARCH" {
+            let next = words.next()?;
+            // Older SQLite versions print "SCAN TABLE name"
+            let name = if next == "TABLE" { words.next()? } else { next };
+            return Some(name.to_string());
+        }
+    }
+    None
+}
+
+// MongoDB: explain output with queryPlanner.winningPlan and optional executionStats
+pub fn normalize_mongodb_plan(raw: Value) -> QueryPlan {
+    let stages = raw
+        .pointer("/executionStats/executionStages")
+        .or_else(|| raw.pointer("/queryPlanner/winningPlan/queryPlan"))
+        .or_else(|| raw.pointer("/queryPlanner/winningPlan"));
+
+    let root = stages
+        .map(mongodb_stage)
+        .unwrap_or_else(|| PlanNode::new("Unknown"));
+
+    QueryPlan {
+        db_type: "mongodb".to_string(),
+        root,
+        planning_time_ms: None,
+        execution_time_ms: raw.pointer("/executionStats/executionTimeMillis").and_then(number),
+        raw,
+    }
+}
+
+fn mongodb_stage(stage: &Value) -> PlanNode {
+    let mut node = PlanNode::new(&stage.get("stage").and_then(string).unwrap_or_default());
+    node.index = stage.get("indexName").and_then(string);
+    node.actual_rows = stage.get("nReturned").and_then(number);
+    node.actual_time_ms = stage.get("executionTimeMillisEstimate").and_then(number);
+
+    let mut details = Vec::new();
+    if let Some(filter) = stage.get("filter") {
+        details.push(format!("filter: {}", filter));
+    }
+    for key in ["keysExamined", "docsExamined"] {
+        if let Some(count) = stage.get(key).and_then(number) {
+            details.push(format!("{}: {}", key, count));
+        }
+    }
+    node.detail = if details.is_empty() { None } else { Some(details.join("; ")) };
+
+    if let Some(input) = stage.get("inputStage") {
+        node.children.push(mongodb_stage(input));
+    }
+    if let Some(inputs) = stage.get("inputStages").and_then(|v| v.as_array()) {
+        node.children.extend(inputs.iter().map(mongodb_stage));
+    }
+
+    node
+}
@@ -0,0 +1,489 @@
+use crate::database::{DatabaseConnection, RowSink};
+use crate::error::AppError;
+use arrow_array::builder::{BooleanBuilder, Float64Builder, Int64Builder, StringBuilder};
+use arrow_array::{ArrayRef, RecordBatch};
+use arrow_schema::{DataType, Field, Schema, SchemaRef};
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+use rust_xlsxwriter::Workbook;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::sync::Arc;
+use std::time::Instant;
+
+// Rows between two progress events
+const PROGRESS_INTERVAL: u64 = 1000;
+// Rows buffered per Parquet row group
+const PARQUET_BATCH_SIZE: usize = 8192;
+// Excel's hard sheet limit, including the header row
+const XLSX_MAX_ROWS: u32 = 1_048_576;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum ExportFormat {
+    Csv,
+    Tsv,
+    Json,
+    Ndjson,
+    Xlsx,
+    Parquet,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum CsvQuoteStyle {
+    Always,
+    Necessary,
+    NonNumeric,
+    Never,
+}
+
+fn default_include_header() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportOptions {
+    pub format: ExportFormat,
+    pub path: String,
+    // CSV/TSV only; defaults to ',' for CSV and '\t' for TSV
+    pub delimiter: Option<char>,
+    pub quote_style: Option<CsvQuoteStyle>,
+    #[serde(default = "default_include_header")]
+    pub include_header: bool,
+    pub sheet_name: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportProgress {
+    pub path: String,
+    pub rows_written: u64,
+    pub finished: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExportSummary {
+    pub path: String,
+    pub format: ExportFormat,
+    pub rows_written: u64,
+    pub duration_ms: u64,
+}
+
+fn export_error(err: impl std::fmt::Display) -> AppError {
+    AppError::FileOperationFailed(format!("Export failed: {}", err))
+}
+
+// Text form used by the delimited and spreadsheet writers
+fn value_to_text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+trait FormatWriter: Send {
+    fn begin(&mut self, columns: &[String]) -> Result<(), AppError>;
+    fn write_row(&mut self, row: &[Value]) -> Result<(), AppError>;
+    fn finish(self: Box<Self>) -> Result<(), AppError>;
+}
+
+struct DelimitedWriter {
+    writer: csv::Writer<BufWriter<File>>,
+    include_header: bool,
+}
+
+impl FormatWriter for DelimitedWriter {
+    fn begin(&mut self, columns: &[String]) -> Result<(), AppError> {
+        if self.include_header {
+            self.writer.write_record(columns).map_err(export_error)?;
+        }
+        Ok(())
+    }
+
+    fn write_row(&mut self, row: &[Value]) -> Result<(), AppError> {
+        self.writer
+            .write_record(row.iter().map(value_to_text))
+            .map_err(export_error)
+    }
+
+    fn finish(mut self: Box<Self>) -> Result<(), AppError> {
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+struct JsonWriter {
+    out: BufWriter<File>,
+    columns: Vec<String>,
+    rows_written: u64,
+    // One object per line instead of a single array
+    lines: bool,
+}
+
+impl JsonWriter {
+    // Objects are written by hand so keys keep the column order
+    fn object(&self, row: &[Value]) -> Result<String, AppError> {
+        let mut fields = Vec::with_capacity(row.len());
+        for (column, value) in self.columns.iter().zip(row) {
+            fields.push(format!(
+                "{}:{}",
+                serde_json::to_string(column).map_err(export_error)?,
+                serde_json::to_string(value).map_err(export_error)?
+            ));
+        }
+        Ok(format!("{{{}}}", fields.join(",")))
+    }
+}
+
+impl FormatWriter for JsonWriter {
+    fn begin(&mut self, columns: &[String]) -> Result<(), AppError> {
+        self.columns = columns.to_vec();
+        Ok(())
+    }
+
+    fn write_row(&mut self, row: &[Value]) -> Result<(), AppError> {
+        let object = self.object(row)?;
+        if self.lines {
+            writeln!(self.out, "{}", object)?;
+        } else {
+            let separator = if self.rows_written == 0 { "[\n" } else { ",\n" };
+            write!(self.out, "{}{}", separator, object)?;
+        }
+        self.rows_written += 1;
+        Ok(())
+    }
+
+    fn finish(mut self: Box<Self>) -> Result<(), AppError> {
+        if !self.lines {
+            let closing = if self.rows_written == 0 { "[]\n" } else { "\n]\n" };
+            self.out.write_all(closing.as_bytes())?;
+        }
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+struct XlsxWriter {
+    workbook: Workbook,
+    path: String,
+    include_header: bool,
+    next_row: u32,
+}
+
+impl FormatWriter for XlsxWriter {
+    fn begin(&mut self, columns: &[String]) -> Result<(), AppError> {
+        if self.include_header {
+            let worksheet = self.workbook.worksheet_from_index(0).map_err(export_error)?;
+            for (col, name) in columns.iter().enumerate() {
+                worksheet.write_string(0, col as u16, name).map_err(export_error)?;
+            }
+            self.next_row = 1;
+        }
+        Ok(())
+    }
+
+    fn write_row(&mut self, row: &[Value]) -> Result<(), AppError> {
+        if self.next_row >= XLSX_MAX_ROWS {
+            return Err(export_error("result exceeds the Excel limit of 1,048,576 rows"));
+        }
+
+        let worksheet = self.workbook.worksheet_from_index(0).map_err(export_error)?;
+        for (col, value) in row.iter().enumerate() {
+            let col = col as u16;
+            match value {
+                Value::Null => {}
+                Value::Bool(b) => {
+                    worksheet.write_boolean(self.next_row, col, *b).map_err(export_error)?;
+                }
+                Value::Number(n) => {
+                    worksheet
+                        .write_number(self.next_row, col, n.as_f64().unwrap_or_default())
+                        .map_err(export_error)?;
+                }
+                other => {
+                    worksheet
+                        .write_string(self.next_row, col, value_to_text(other))
+                        .map_err(export_error)?;
+                }
+            }
+        }
+        self.next_row += 1;
+        Ok(())
+    }
+
+    fn finish(mut self: Box<Self>) -> Result<(), AppError> {
+        self.workbook.save(&self.path).map_err(export_error)
+    }
+}
+
+struct ParquetWriter {
+    file: Option<File>,
+    writer: Option<ArrowWriter<File>>,
+    schema: Option<SchemaRef>,
+    columns: Vec<String>,
+    buffer: Vec<Vec<Value>>,
+    // Rows in batches already written, for error messages
+    rows_flushed: usize,
+}
+
+impl ParquetWriter {
+    // Column types are inferred from the first buffered batch and fixed once the file
+    // is started; later values that don't fit are reported by check_batch
+    fn infer_schema(&self) -> SchemaRef {
+        let fields: Vec<Field> = self
+            .columns
+            .iter()
+            .enumerate()
+            .map(|(i, name)| {
+                let values: Vec<&Value> = self
+                    .buffer
+                    .iter()
+                    .filter_map(|row| row.get(i))
+                    .filter(|v| !v.is_null())
+                    .collect();
+                let data_type = if values.is_empty() {
+                    DataType::Utf8
+                } else if values.iter().all(|v| v.is_boolean()) {
+                    DataType::Boolean
+                } else if values.iter().all(|v| v.is_i64()) {
+                    DataType::Int64
+                } else if values.iter().all(|v| v.is_number()) {
+                    DataType::Float64
+                } else {
+                    DataType::Utf8
+                };
+                Field::new(name, data_type, true)
+            })
+            .collect();
+        Arc::new(Schema::new(fields))
+    }
+
+    fn check_batch(&self, schema: &Schema) -> Result<(), AppError> {
+        for (row_index, row) in self.buffer.iter().enumerate() {
+            for (field, value) in schema.fields().iter().zip(row) {
+                let fits = match field.data_type() {
+                    DataType::Boolean => value.is_null() || value.is_boolean(),
+                    DataType::Int64 => value.is_null() || value.is_i64(),
+                    DataType::Float64 => value.is_null() || value.is_number(),
+                    _ => true,
+                };
+                if !fits {
+                    return Err(export_error(format!(
+                        "row {} has {} in column {}, which holds {} values in the first {} rows; export as CSV or cast the column to text",
+                        self.rows_flushed + row_index + 1,
+                        value,
+                        field.name(),
+                        field.data_type(),
+                        PARQUET_BATCH_SIZE
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn flush_batch(&mut self) -> Result<(), AppError> {
+        if self.writer.is_none() {
+            let schema = self.infer_schema();
+            let file = self.file.take().ok_or_else(|| export_error("output file already closed"))?;
+            let properties = WriterProperties::builder()
+                .set_compression(Compression::SNAPPY)
+                .build();
+            self.writer = Some(ArrowWriter::try_new(file, schema.clone(), Some(properties)).map_err(export_error)?);
+            self.schema = Some(schema);
+        }
+
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+
+        let schema = self.schema.clone().unwrap_or_else(|| Arc::new(Schema::empty()));
+        self.check_batch(&schema)?;
+        let mut arrays: Vec<ArrayRef> = Vec::with_capacity(schema.fields().len());
+        for (i, field) in schema.fields().iter().enumerate() {
+            let values = self.buffer.iter().map(|row| row.get(i).unwrap_or(&Value::Null));
+            let array: ArrayRef = match field.data_type() {
+                DataType::Boolean => {
+                    let mut builder = BooleanBuilder::new();
+                    values.for_each(|v| builder.append_option(v.as_bool()));
+                    Arc::new(builder.finish())
+                }
+                DataType::Int64 => {
+                    let mut builder = Int64Builder::new();
+                    values.for_each(|v| builder.append_option(v.as_i64()));
+                    Arc::new(builder.finish())
+                }
+                DataType::Float64 => {
+                    let mut builder = Float64Builder::new();
+                    values.for_each(|v| builder.append_option(v.as_f64()));
+                    Arc::new(builder.finish())
+                }
+                _ => {
+                    let mut builder = StringBuilder::new();
+                    values.for_each(|v| match v {
+                        Value::Null => builder.append_null(),
+                        other => builder.append_value(value_to_text(other)),
+                    });
+                    Arc::new(builder.finish())
+                }
+            };
+            arrays.push(array);
+        }
+
+        let batch = RecordBatch::try_new(schema, arrays).map_err(export_error)?;
+        if let Some(writer) = self.writer.as_mut() {
+            writer.write(&batch).map_err(export_error)?;
+        }
+        self.rows_flushed += self.buffer.len();
+        self.buffer.clear();
+        Ok(())
+    }
+}
+
+impl FormatWriter for ParquetWriter {
+    fn begin(&mut self, columns: &[String]) -> Result<(), AppError> {
+        self.columns = columns.to_vec();
+        Ok(())
+    }
+
+    fn write_row(&mut self, row: &[Value]) -> Result<(), AppError> {
+        self.buffer.push(row.to_vec());
+        if self.buffer.len() >= PARQUET_BATCH_SIZE {
+            self.flush_batch()?;
+        }
+        Ok(())
+    }
+
+    fn finish(mut self: Box<Self>) -> Result<(), AppError> {
+        self.flush_batch()?;
+        if let Some(writer) = self.writer.take() {
+            writer.close().map_err(export_error)?;
+        }
+        Ok(())
+    }
+}
+
+fn create_writer(options: &ExportOptions) -> Result<Box<dyn FormatWriter>, AppError> {
+    let writer: Box<dyn FormatWriter> = match options.format {
+        ExportFormat::Csv | ExportFormat::Tsv => {
+            let default_delimiter = if matches!(options.format, ExportFormat::Tsv) { '\t' } else { ',' };
+            let delimiter = options.delimiter.unwrap_or(default_delimiter);
+            if !delimiter.is_ascii() {
+                return Err(AppError::InvalidConfiguration("Delimiter must be a single ASCII character".to_string()));
+            }
+            let quote_style = match options.quote_style.unwrap_or(CsvQuoteStyle::Necessary) {
+                CsvQuoteStyle::Always => csv::QuoteStyle::Always,
+                CsvQuoteStyle::Necessary => csv::QuoteStyle::Necessary,
+                CsvQuoteStyle::NonNumeric => csv::QuoteStyle::NonNumeric,
+                CsvQuoteStyle::Never => csv::QuoteStyle::Never,
+            };
+            let writer = csv::WriterBuilder::new()
+                .delimiter(delimiter as u8)
+                .quote_style(quote_style)
+                .flexible(true)
+                .from_writer(BufWriter::new(File::create(&options.path)?));
+            Box::new(DelimitedWriter {
+                writer,
+                include_header: options.include_header,
+            })
+        }
+        ExportFormat::Json | ExportFormat::Ndjson => Box::new(JsonWriter {
+            out: BufWriter::new(File::create(&options.path)?),
+            columns: Vec::new(),
+            rows_written: 0,
+            lines: matches!(options.format, ExportFormat::Ndjson),
+        }),
+        ExportFormat::Xlsx => {
+            let mut workbook = Workbook::new();
+            let worksheet = workbook.add_worksheet_with_constant_memory();
+            if let Some(name) = &options.sheet_name {
+                worksheet.set_name(name).map_err(export_error)?;
+            }
+            Box::new(XlsxWriter {
+                workbook,
+                path: options.path.clone(),
+                include_header: options.include_header,
+                next_row: 0,
+            })
+        }
+        ExportFormat::Parquet => Box::new(ParquetWriter {
+            file: Some(File::create(&options.path)?),
+            writer: None,
+            schema: None,
+            columns: Vec::new(),
+            buffer: Vec::new(),
+            rows_flushed: 0,
+        }),
+    };
+    Ok(writer)
+}
+
+struct ExportSink<F: FnMut(ExportProgress) + Send> {
+    writer: Box<dyn FormatWriter>,
+    path: String,
+    rows_written: u64,
+    on_progress: F,
+}
+
+impl<F: FnMut(ExportProgress) + Send> RowSink for ExportSink<F> {
+    fn columns(&mut self, columns: &[String]) -> Result<(), AppError> {
+        self.writer.begin(columns)
+    }
+
+    fn row(&mut self, values: Vec<Value>) -> Result<(), AppError> {
+        self.writer.write_row(&values)?;
+        self.rows_written += 1;
+        if self.rows_written.is_multiple_of(PROGRESS_INTERVAL) {
+            (self.on_progress)(ExportProgress {
+                path: self.path.clone(),
+                rows_written: self.rows_written,
+                finished: false,
+            });
+        }
+        Ok(())
+    }
+}
+
+// Re-run the query and stream its rows straight into the output file
+pub async fn export_query<F>(
+    connection: &DatabaseConnection,
+    query: &str,
+    options: &ExportOptions,
+    on_progress: F,
+) -> Result<ExportSummary, AppError>
+where
+    F: FnMut(ExportProgress) + Send,
+{
+    let started = Instant::now();
+    let mut sink = ExportSink {
+        writer: create_writer(options)?,
+        path: options.path.clone(),
+        rows_written: 0,
+        on_progress,
+    };
+
+    connection.stream_query(query, &mut sink).await?;
+
+    let ExportSink {
+        writer,
+        rows_written,
+        mut on_progress,
+        ..
+    } = sink;
+    writer.finish()?;
+
+    on_progress(ExportProgress {
+        path: options.path.clone(),
+        rows_written,
+        finished: true,
+    });
+
+    Ok(ExportSummary {
+        path: options.path.clone(),
+        format: options.format,
+        rows_written,
+        duration_ms: started.elapsed().as_millis() as u64,
+    })
+}
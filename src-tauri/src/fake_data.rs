@@ -0,0 +1,875 @@
+use crate::database::{ColumnInfo, DatabaseConnection, DatabaseType, RowSink, TableInfo};
+use crate::error::AppError;
+use crate::values::{self, ValueCategory};
+use chrono::{Duration, NaiveDate, NaiveDateTime};
+use futures::TryStreamExt;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+use std::time::Instant;
+
+const DEFAULT_BATCH_SIZE: usize = 500;
+const DEFAULT_NULL_RATIO: f64 = 0.1;
+// Parent keys sampled per foreign key
+const MAX_PARENT_KEYS: usize = 10_000;
+// Attempts at producing a row that doesn't collide with a unique key
+const MAX_UNIQUE_ATTEMPTS: usize = 50;
+
+const FIRST_NAMES: &[&str] = &[
+    "Andi", "Budi", "Citra", "Dewi", "Eko", "Fitri", "Gilang", "Hana", "Indra", "Joko", "Kartika", "Lestari",
+    "Maya", "Nanda", "Oscar", "Putri", "Rizky", "Sari", "Taufik", "Wulan", "Alice", "Bob", "Carol", "David",
+    "Emma", "Frank", "Grace", "Henry", "Olivia", "James",
+];
+const LAST_NAMES: &[&str] = &[
+    "Santoso", "Wijaya", "Pratama", "Saputra", "Hidayat", "Nugroho", "Kurniawan", "Setiawan", "Halim", "Gunawan",
+    "Smith", "Johnson", "Brown", "Taylor", "Miller", "Wilson", "Moore", "Clark", "Lewis", "Walker",
+];
+const CITIES: &[&str] = &[
+    "Jakarta", "Bandung", "Surabaya", "Yogyakarta", "Medan", "Semarang", "Makassar", "Denpasar", "Singapore",
+    "Kuala Lumpur", "Tokyo", "Sydney", "London", "Berlin", "Amsterdam", "New York", "Toronto", "Paris",
+];
+const COUNTRIES: &[&str] = &[
+    "Indonesia", "Singapore", "Malaysia", "Japan", "Australia", "United Kingdom", "Germany", "Netherlands",
+    "United States", "Canada", "France", "India", "Brazil", "Thailand", "Vietnam",
+];
+const STREETS: &[&str] = &[
+    "Jl. Sudirman", "Jl. Thamrin", "Jl. Gatot Subroto", "Jl. Diponegoro", "Jl. Merdeka", "Main Street",
+    "Oak Avenue", "Park Lane", "High Street", "Maple Road",
+];
+const COMPANY_WORDS: &[&str] = &[
+    "Nusantara", "Global", "Digital", "Mitra", "Sejahtera", "Prima", "Solusi", "Teknologi", "Karya", "Abadi",
+    "Data", "Cloud", "Logistics", "Systems", "Media",
+];
+const COMPANY_SUFFIXES: &[&str] = &["PT", "CV", "Inc", "Ltd", "Group"];
+const DOMAINS: &[&str] = &["example.com", "example.org", "example.net", "mail.test", "demo.test"];
+const WORDS: &[&str] = &[
+    "lorem", "ipsum", "dolor", "sit", "amet", "consectetur", "adipiscing", "elit", "sed", "do", "eiusmod",
+    "tempor", "incididunt", "ut", "labore", "et", "dolore", "magna", "aliqua", "enim", "ad", "minim", "veniam",
+    "quis", "nostrud", "exercitation", "ullamco", "laboris", "nisi", "aliquip",
+];
+
+// How a column's values are produced; columns without an override get one
+// picked from their name and type
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ColumnGenerator {
+    FirstName,
+    LastName,
+    FullName,
+    Email,
+    Username,
+    Phone,
+    Company,
+    Address,
+    City,
+    Country,
+    Url,
+    Uuid,
+    Word,
+    Sentence,
+    Paragraph,
+    Boolean,
+    Integer { min: i64, max: i64 },
+    Float { min: f64, max: f64, decimals: Option<u32> },
+    // Dates as YYYY-MM-DD, date-times as YYYY-MM-DD HH:MM:SS
+    Date { start: String, end: String },
+    DateTime { start: String, end: String },
+    OneOf { values: Vec<Value> },
+    Constant { value: Value },
+    Sequence { start: i64, step: Option<i64> },
+    Null,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FakeTableRequest {
+    pub table: String,
+    pub rows: u64,
+    #[serde(default)]
+    pub overrides: HashMap<String, ColumnGenerator>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FakeDataOptions {
+    pub tables: Vec<FakeTableRequest>,
+    // Same seed and schema produce the same rows; a random seed is picked and reported otherwise
+    pub seed: Option<u64>,
+    // Share of NULLs in nullable columns without an override
+    pub null_ratio: Option<f64>,
+    pub batch_size: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FakeDataProgress {
+    pub table: String,
+    pub rows_inserted: u64,
+    pub total_rows: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FakeTableSummary {
+    pub table: String,
+    pub rows_inserted: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FakeDataSummary {
+    pub seed: u64,
+    // In insertion order, parents before children
+    pub tables: Vec<FakeTableSummary>,
+    pub warnings: Vec<String>,
+    pub duration_ms: u64,
+}
+
+enum Source {
+    Generate(ColumnGenerator),
+    // Filled from a parent row, position within the foreign key
+    ForeignKey(usize),
+}
+
+struct ColumnPlan {
+    column: ColumnInfo,
+    source: Source,
+    null_ratio: f64,
+    max_length: Option<usize>,
+    decimals: Option<u32>,
+}
+
+struct ForeignKeyPlan {
+    // Indexes into the column plans
+    columns: Vec<usize>,
+    parent_rows: Vec<Vec<Value>>,
+    nullable: bool,
+}
+
+struct TablePlan {
+    columns: Vec<ColumnPlan>,
+    foreign_keys: Vec<ForeignKeyPlan>,
+    // Column indexes of each primary/unique key that is fully generated here
+    unique_keys: Vec<Vec<usize>>,
+}
+
+fn pick<'a>(rng: &mut ChaCha8Rng, items: &[&'a str]) -> &'a str {
+    items.choose(rng).copied().unwrap_or_default()
+}
+
+fn words(rng: &mut ChaCha8Rng, count: usize) -> String {
+    (0..count).map(|_| pick(rng, WORDS)).collect::<Vec<_>>().join(" ")
+}
+
+fn capitalize(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+fn slug(text: &str) -> String {
+    text.to_lowercase()
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == ' ')
+        .collect::<String>()
+        .replace(' ', ".")
+}
+
+fn parse_date_bound(text: &str) -> Result<NaiveDateTime, AppError> {
+    values::parse_datetime(text)
+        .or_else(|| values::parse_date(text).and_then(|d| d.and_hms_opt(0, 0, 0)))
+        .ok_or_else(|| AppError::InvalidConfiguration(format!("Invalid date: {}", text)))
+}
+
+fn random_datetime(rng: &mut ChaCha8Rng, start: NaiveDateTime, end: NaiveDateTime) -> NaiveDateTime {
+    let span = (end - start).num_seconds().max(0);
+    start + Duration::seconds(rng.gen_range(0..=span))
+}
+
+fn default_range() -> (NaiveDateTime, NaiveDateTime) {
+    let start = NaiveDate::from_ymd_opt(2020, 1, 1).unwrap_or_default();
+    let end = NaiveDate::from_ymd_opt(2025, 12, 31).unwrap_or_default();
+    (start.and_hms_opt(0, 0, 0).unwrap_or_default(), end.and_hms_opt(23, 59, 59).unwrap_or_default())
+}
+
+// Length argument of char/varchar/binary types, e.g. varchar(50)
+fn declared_length(column_type: &str) -> Option<usize> {
+    let lower = column_type.to_lowercase();
+    if !(lower.contains("char") || lower.contains("binary")) {
+        return None;
+    }
+    let start = lower.find('(')?;
+    let end = lower[start..].find(')')? + start;
+    lower[start + 1..end].trim().parse().ok()
+}
+
+// Precision and scale of numeric/decimal types
+fn numeric_precision(column_type: &str) -> Option<(u32, u32)> {
+    let lower = column_type.to_lowercase();
+    if !(lower.starts_with("numeric") || lower.starts_with("decimal")) {
+        return None;
+    }
+    let start = lower.find('(')?;
+    let end = lower[start..].find(')')? + start;
+    let mut parts = lower[start + 1..end].split(',').map(|p| p.trim().parse::<u32>());
+    let precision = parts.next()?.ok()?;
+    let scale = parts.next().and_then(|s| s.ok()).unwrap_or(0);
+    Some((precision, scale))
+}
+
+// Labels of a MySQL enum('a','b') column type
+fn mysql_enum_values(column_type: &str) -> Option<Vec<String>> {
+    let inner = column_type.strip_prefix("enum(")?.strip_suffix(')')?;
+    let mut values = Vec::new();
+    let mut current = String::new();
+    let mut chars = inner.chars().peekable();
+    let mut quoted = false;
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' if quoted && chars.peek() == Some(&'\'') => {
+                chars.next();
+                current.push('\'');
+            }
+            '\'' if quoted => {
+                quoted = false;
+                values.push(std::mem::take(&mut current));
+            }
+            '\'' => quoted = true,
+            '\\' if quoted => current.extend(chars.next()),
+            _ if quoted => current.push(c),
+            _ => {}
+        }
+    }
+    Some(values)
+}
+
+fn integer_range(column: &ColumnInfo) -> (i64, i64) {
+    let declared = column.column_type.as_deref().unwrap_or(&column.data_type).to_lowercase();
+    let unsigned = declared.contains("unsigned");
+    if declared.starts_with("tinyint(1)") {
+        (0, 1)
+    } else if declared.starts_with("tinyint") {
+        if unsigned { (0, 255) } else { (0, 127) }
+    } else if declared.starts_with("smallint") || declared.starts_with("int2") || declared == "smallserial" {
+        (0, 32_767)
+    } else {
+        (1, 100_000)
+    }
+}
+
+// Declared types that accept generated text; SQLite columns may have no type at all
+fn is_text_type(declared: &str) -> bool {
+    let base = declared.split('(').next().unwrap_or_default().trim();
+    base.is_empty()
+        || ["char", "text", "clob", "string"].iter().any(|t| base.contains(t))
+        || ["uuid", "uniqueidentifier", "name", "enum", "set"].contains(&base)
+}
+
+// Generator for a column without an override, guessed from its name then its type
+fn infer_generator(column: &ColumnInfo, enum_values: Option<&Vec<String>>) -> Result<ColumnGenerator, AppError> {
+    if let Some(labels) = enum_values {
+        return Ok(ColumnGenerator::OneOf {
+            values: labels.iter().map(|l| Value::String(l.clone())).collect(),
+        });
+    }
+
+    let name = column.name.to_lowercase();
+    let category = values::categorize(&column.data_type);
+    let declared = column.column_type.as_deref().unwrap_or(&column.data_type).to_lowercase();
+
+    if category == ValueCategory::Text && is_text_type(&declared) {
+        let by_name = if declared == "uuid" || name == "uuid" || name.ends_with("_uuid") || name == "guid" {
+            Some(ColumnGenerator::Uuid)
+        } else if name.contains("email") {
+            Some(ColumnGenerator::Email)
+        } else if name.contains("first_name") || name.contains("firstname") || name == "given_name" {
+            Some(ColumnGenerator::FirstName)
+        } else if name.contains("last_name") || name.contains("lastname") || name.contains("surname") {
+            Some(ColumnGenerator::LastName)
+        } else if name.contains("username") || name == "login" || name == "handle" {
+            Some(ColumnGenerator::Username)
+        } else if name.contains("company") || name.contains("organization") {
+            Some(ColumnGenerator::Company)
+        } else if name == "name" || name.contains("full_name") || name.contains("fullname") || name.ends_with("_name") {
+            Some(ColumnGenerator::FullName)
+        } else if name.contains("phone") || name.contains("mobile") {
+            Some(ColumnGenerator::Phone)
+        } else if name.contains("address") || name.contains("street") {
+            Some(ColumnGenerator::Address)
+        } else if name.contains("city") {
+            Some(ColumnGenerator::City)
+        } else if name.contains("country") {
+            Some(ColumnGenerator::Country)
+        } else if name.contains("url") || name.contains("website") || name.contains("link") {
+            Some(ColumnGenerator::Url)
+        } else if ["description", "bio", "body", "content", "comment", "notes", "text"].iter().any(|n| name.contains(n)) {
+            Some(ColumnGenerator::Paragraph)
+        } else if ["title", "subject", "summary"].iter().any(|n| name.contains(n)) {
+            Some(ColumnGenerator::Sentence)
+        } else {
+            None
+        };
+        return Ok(by_name.unwrap_or(if declared_length(&declared).map(|l| l <= 20).unwrap_or(false) {
+            ColumnGenerator::Word
+        } else {
+            ColumnGenerator::Sentence
+        }));
+    }
+
+    let (start, end) = default_range();
+    let generator = match category {
+        ValueCategory::Integer => {
+            let (min, max) = integer_range(column);
+            ColumnGenerator::Integer { min, max }
+        }
+        ValueCategory::Float | ValueCategory::Decimal => {
+            let (max, decimals) = match numeric_precision(&declared) {
+                Some((precision, scale)) => ((10f64.powi(precision.saturating_sub(scale).min(6) as i32) - 1.0).max(1.0), Some(scale)),
+                None => (10_000.0, Some(2)),
+            };
+            ColumnGenerator::Float { min: 0.0, max, decimals }
+        }
+        ValueCategory::Boolean => ColumnGenerator::Boolean,
+        ValueCategory::Date => ColumnGenerator::Date {
+            start: start.format("%Y-%m-%d").to_string(),
+            end: end.format("%Y-%m-%d").to_string(),
+        },
+        ValueCategory::DateTime | ValueCategory::TimestampTz => ColumnGenerator::DateTime {
+            start: start.format("%Y-%m-%d %H:%M:%S").to_string(),
+            end: end.format("%Y-%m-%d %H:%M:%S").to_string(),
+        },
+        ValueCategory::Json => ColumnGenerator::Constant { value: json!({}) },
+        // Binary, geometry and other types nothing here can produce
+        _ if column.is_nullable => ColumnGenerator::Null,
+        _ => {
+            return Err(AppError::InvalidConfiguration(format!(
+                "Can't generate values for column {} of type {}; give it an override",
+                column.name, declared
+            )))
+        }
+    };
+    Ok(generator)
+}
+
+fn validate(generator: &ColumnGenerator, column: &str) -> Result<(), AppError> {
+    let invalid = |message: String| Err(AppError::InvalidConfiguration(format!("Column {}: {}", column, message)));
+    match generator {
+        ColumnGenerator::Integer { min, max } if min > max => invalid(format!("min {} is greater than max {}", min, max)),
+        ColumnGenerator::Float { min, max, .. } if min > max => invalid(format!("min {} is greater than max {}", min, max)),
+        ColumnGenerator::Date { start, end } | ColumnGenerator::DateTime { start, end } => {
+            if parse_date_bound(start)? > parse_date_bound(end)? {
+                invalid(format!("start {} is after end {}", start, end))
+            } else {
+                Ok(())
+            }
+        }
+        ColumnGenerator::OneOf { values } if values.is_empty() => invalid("one_of needs at least one value".to_string()),
+        _ => Ok(()),
+    }
+}
+
+fn generate(generator: &ColumnGenerator, rng: &mut ChaCha8Rng, row: u64, decimals: Option<u32>) -> Value {
+    match generator {
+        ColumnGenerator::FirstName => json!(pick(rng, FIRST_NAMES)),
+        ColumnGenerator::LastName => json!(pick(rng, LAST_NAMES)),
+        ColumnGenerator::FullName => json!(format!("{} {}", pick(rng, FIRST_NAMES), pick(rng, LAST_NAMES))),
+        ColumnGenerator::Email => {
+            let name = slug(&format!("{} {}", pick(rng, FIRST_NAMES), pick(rng, LAST_NAMES)));
+            json!(format!("{}{}@{}", name, rng.gen_range(1..1000), pick(rng, DOMAINS)))
+        }
+        ColumnGenerator::Username => {
+            json!(format!("{}_{}", slug(pick(rng, FIRST_NAMES)), rng.gen_range(1..10_000)))
+        }
+        ColumnGenerator::Phone => json!(format!(
+            "+62 8{}{} {:04} {:04}",
+            rng.gen_range(1..10),
+            rng.gen_range(0..10),
+            rng.gen_range(0..10_000),
+            rng.gen_range(0..10_000)
+        )),
+        ColumnGenerator::Company => json!(format!(
+            "{} {} {}",
+            pick(rng, COMPANY_SUFFIXES),
+            pick(rng, COMPANY_WORDS),
+            pick(rng, COMPANY_WORDS)
+        )),
+        ColumnGenerator::Address => json!(format!("{} No. {}, {}", pick(rng, STREETS), rng.gen_range(1..300), pick(rng, CITIES))),
+        ColumnGenerator::City => json!(pick(rng, CITIES)),
+        ColumnGenerator::Country => json!(pick(rng, COUNTRIES)),
+        ColumnGenerator::Url => json!(format!("https://{}/{}", pick(rng, DOMAINS), pick(rng, WORDS))),
+        ColumnGenerator::Uuid => {
+            let bytes: u128 = rng.gen();
+            // Version 4, RFC 4122 variant
+            let bytes = (bytes & !(0xf << 76) & !(0x3 << 62)) | (0x4 << 76) | (0x2 << 62);
+            let hex = format!("{:032x}", bytes);
+            json!(format!("{}-{}-{}-{}-{}", &hex[0..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..32]))
+        }
+        ColumnGenerator::Word => json!(pick(rng, WORDS)),
+        ColumnGenerator::Sentence => {
+            let count = rng.gen_range(3..9);
+            json!(format!("{}.", capitalize(&words(rng, count))))
+        }
+        ColumnGenerator::Paragraph => {
+            let sentences: Vec<String> = (0..rng.gen_range(2..5))
+                .map(|_| {
+                    let count = rng.gen_range(5..12);
+                    format!("{}.", capitalize(&words(rng, count)))
+                })
+                .collect();
+            json!(sentences.join(" "))
+        }
+        ColumnGenerator::Boolean => json!(rng.gen_bool(0.5)),
+        ColumnGenerator::Integer { min, max } => json!(rng.gen_range(*min..=*max)),
+        ColumnGenerator::Float { min, max, decimals: wanted } => {
+            let value = if max > min { rng.gen_range(*min..=*max) } else { *min };
+            let factor = 10f64.powi(wanted.or(decimals).unwrap_or(2) as i32);
+            json!((value * factor).round() / factor)
+        }
+        ColumnGenerator::Date { start, end } => {
+            let (start, end) = (parse_date_bound(start).unwrap_or_default(), parse_date_bound(end).unwrap_or_default());
+            json!(random_datetime(rng, start, end).format("%Y-%m-%d").to_string())
+        }
+        ColumnGenerator::DateTime { start, end } => {
+            let (start, end) = (parse_date_bound(start).unwrap_or_default(), parse_date_bound(end).unwrap_or_default());
+            json!(random_datetime(rng, start, end).format("%Y-%m-%d %H:%M:%S").to_string())
+        }
+        ColumnGenerator::OneOf { values } => values.choose(rng).cloned().unwrap_or(Value::Null),
+        ColumnGenerator::Constant { value } => value.clone(),
+        ColumnGenerator::Sequence { start, step } => json!(start + row as i64 * step.unwrap_or(1)),
+        ColumnGenerator::Null => Value::Null,
+    }
+}
+
+fn sql_string(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "''"))
+}
+
+// Columns whose values the database assigns: auto increment, identity, serial and generated columns
+async fn database_generated_columns(connection: &DatabaseConnection, table: &TableInfo) -> Result<HashSet<String>, AppError> {
+    let query = match connection {
+        DatabaseConnection::MySQL(..) => format!(
+            "SELECT COLUMN_NAME FROM information_schema.COLUMNS WHERE TABLE_SCHEMA = DATABASE() AND TABLE_NAME = {} \
+             AND (EXTRA LIKE '%auto_increment%' OR EXTRA LIKE '%VIRTUAL GENERATED%' OR EXTRA LIKE '%STORED GENERATED%')",
+            sql_string(&table.name)
+        ),
+        DatabaseConnection::PostgreSQL(..) => format!(
+            "SELECT column_name FROM information_schema.columns WHERE table_schema = current_schema() AND table_name = {} \
+             AND (is_identity = 'YES' OR is_generated = 'ALWAYS' OR column_default LIKE 'nextval(%')",
+            sql_string(&table.name)
+        ),
+        DatabaseConnection::SQLite(..) => {
+            // A lone INTEGER PRIMARY KEY is an alias for the rowid
+            let keys: Vec<&ColumnInfo> = table.columns.iter().filter(|c| c.is_primary_key).collect();
+            return Ok(match keys.as_slice() {
+                [key] if key.data_type.eq_ignore_ascii_case("integer") => HashSet::from([key.name.clone()]),
+                _ => HashSet::new(),
+            });
+        }
+        DatabaseConnection::MongoDB(..) => return Ok(HashSet::from(["_id".to_string()])),
+        DatabaseConnection::Redis(_) => return Ok(HashSet::new()),
+        DatabaseConnection::DuckDB(_) => format!(
+            "SELECT column_name FROM information_schema.columns WHERE table_schema = current_schema() AND table_name = {} \
+             AND column_default LIKE 'nextval(%'",
+            sql_string(&table.name)
+        ),
+        DatabaseConnection::SQLServer(_) => format!(
+            "SELECT name FROM sys.columns WHERE object_id = OBJECT_ID(QUOTENAME(SCHEMA_NAME()) + '.' + QUOTENAME({})) \
+             AND (is_identity = 1 OR is_computed = 1 OR system_type_id = 189)",
+            sql_string(&table.name)
+        ),
+    };
+    let rows = connection.execute_query(&query).await?.rows;
+    Ok(rows
+        .into_iter()
+        .filter_map(|r| r.into_iter().next().and_then(|v| v.as_str().map(|s| s.to_string())))
+        .collect())
+}
+
+// Labels of PostgreSQL enum types keyed by type name
+async fn postgres_enum_types(connection: &DatabaseConnection) -> Result<HashMap<String, Vec<String>>, AppError> {
+    let rows = connection
+        .execute_query(
+            "SELECT t.typname, e.enumlabel FROM pg_enum e JOIN pg_type t ON t.oid = e.enumtypid \
+             ORDER BY t.typname, e.enumsortorder",
+        )
+        .await?
+        .rows;
+    let mut types: HashMap<String, Vec<String>> = HashMap::new();
+    for row in rows {
+        if let (Some(Value::String(name)), Some(Value::String(label))) = (row.first(), row.get(1)) {
+            types.entry(name.clone()).or_default().push(label.clone());
+        }
+    }
+    Ok(types)
+}
+
+fn enum_labels(db_type: &DatabaseType, column: &ColumnInfo, pg_enums: &HashMap<String, Vec<String>>) -> Option<Vec<String>> {
+    let column_type = column.column_type.as_deref()?;
+    match db_type {
+        DatabaseType::MySQL => mysql_enum_values(column_type),
+        DatabaseType::PostgreSQL => {
+            // format_type schema-qualifies types outside the search path
+            let name = column_type.rsplit('.').next().unwrap_or(column_type).trim_matches('"');
+            pg_enums.get(name).cloned()
+        }
+        _ => None,
+    }
+}
+
+async fn sample_parent_keys(
+    connection: &DatabaseConnection,
+    table: &str,
+    columns: &[String],
+) -> Result<Vec<Vec<Value>>, AppError> {
+    let quoted: Vec<String> = columns.iter().map(|c| connection.quote_ident(c)).collect();
+    let not_null: Vec<String> = quoted.iter().map(|c| format!("{} IS NOT NULL", c)).collect();
+    // Ordered so the same seed picks the same parents
+    let query = format!(
+        "SELECT {cols} FROM {table} WHERE {filter} ORDER BY {cols} LIMIT {limit}",
+        cols = quoted.join(", "),
+        table = connection.quote_table(table),
+        filter = not_null.join(" AND "),
+        limit = MAX_PARENT_KEYS
+    );
+    Ok(connection.execute_query(&query).await?.rows)
+}
+
+async fn next_sequence_value(connection: &DatabaseConnection, table: &str, column: &str) -> Result<i64, AppError> {
+    let query = format!("SELECT MAX({}) FROM {}", connection.quote_ident(column), connection.quote_table(table));
+    let rows = connection.execute_query(&query).await?.rows;
+    let max = rows
+        .first()
+        .and_then(|r| r.first())
+        .and_then(|v| v.as_i64().or_else(|| v.as_str().and_then(|s| s.parse().ok())))
+        .unwrap_or(0);
+    Ok(max + 1)
+}
+
+// Text form of a key used to detect collisions, the same for generated and stored values
+fn key_text<'a>(values: impl Iterator<Item = &'a Value>) -> String {
+    values
+        .map(|v| match v {
+            Value::String(s) => s.clone(),
+            other => other.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("\u{1f}")
+}
+
+// Collects the key text of every stored row without a NULL in the key
+struct KeySink<'a> {
+    keys: &'a mut HashSet<String>,
+}
+
+impl RowSink for KeySink<'_> {
+    fn columns(&mut self, _columns: &[String]) -> Result<(), AppError> {
+        Ok(())
+    }
+
+    fn row(&mut self, values: Vec<Value>) -> Result<(), AppError> {
+        if !values.iter().any(|v| v.is_null()) {
+            self.keys.insert(key_text(values.iter()));
+        }
+        Ok(())
+    }
+}
+
+// Unique key values already in the table, so generated rows avoid them instead of failing
+// part-way through after earlier batches committed. Keys continuing a sequence can't collide
+async fn existing_unique_keys(connection: &DatabaseConnection, table: &str, plan: &TablePlan) -> Result<Vec<HashSet<String>>, AppError> {
+    let mut existing = vec![HashSet::new(); plan.unique_keys.len()];
+    for (key, seen) in plan.unique_keys.iter().zip(existing.iter_mut()) {
+        if let [column] = key.as_slice() {
+            if matches!(plan.columns[*column].source, Source::Generate(ColumnGenerator::Sequence { .. })) {
+                continue;
+            }
+        }
+        let names: Vec<&str> = key.iter().map(|&c| plan.columns[c].column.name.as_str()).collect();
+        match connection {
+            DatabaseConnection::MongoDB(_client, database) => {
+                let mut projection = bson::Document::new();
+                for name in &names {
+                    projection.insert(*name, 1);
+                }
+                let options = mongodb::options::FindOptions::builder().projection(projection).build();
+                let mut cursor = database.collection::<bson::Document>(table).find(None, options).await?;
+                while let Some(document) = cursor.try_next().await? {
+                    let values: Vec<Value> = names
+                        .iter()
+                        .map(|n| document.get(n).cloned().map(|v| v.into_relaxed_extjson()).unwrap_or(Value::Null))
+                        .collect();
+                    KeySink { keys: seen }.row(values)?;
+                }
+            }
+            _ => {
+                let quoted: Vec<String> = names.iter().map(|n| connection.quote_ident(n)).collect();
+                let query = format!("SELECT DISTINCT {} FROM {}", quoted.join(", "), connection.quote_table(table));
+                connection.stream_query(&query, &mut KeySink { keys: seen }).await?;
+            }
+        }
+    }
+    Ok(existing)
+}
+
+async fn plan_table(
+    connection: &DatabaseConnection,
+    table: &TableInfo,
+    request: &FakeTableRequest,
+    pg_enums: &HashMap<String, Vec<String>>,
+    null_ratio: f64,
+) -> Result<TablePlan, AppError> {
+    let db_type = connection.db_type();
+    let generated = database_generated_columns(connection, table).await?;
+
+    for name in request.overrides.keys() {
+        if !table.columns.iter().any(|c| &c.name == name) {
+            return Err(AppError::InvalidConfiguration(format!("Table {} has no column {}", table.name, name)));
+        }
+    }
+
+    let mut columns: Vec<ColumnPlan> = Vec::new();
+    for column in &table.columns {
+        let overridden = request.overrides.get(&column.name);
+        if overridden.is_none() && generated.contains(&column.name) {
+            continue;
+        }
+        let declared = column.column_type.as_deref().unwrap_or(&column.data_type);
+        let source = match overridden {
+            Some(generator) => {
+                validate(generator, &column.name)?;
+                Source::Generate(generator.clone())
+            }
+            None => Source::Generate(infer_generator(column, enum_labels(&db_type, column, pg_enums).as_ref())?),
+        };
+        columns.push(ColumnPlan {
+            column: column.clone(),
+            source,
+            null_ratio: if column.is_nullable && overridden.is_none() { null_ratio } else { 0.0 },
+            max_length: declared_length(declared),
+            decimals: numeric_precision(declared).map(|(_, scale)| scale),
+        });
+    }
+
+    let position = |columns: &[ColumnPlan], name: &str| columns.iter().position(|p| p.column.name == name);
+
+    let mut foreign_keys = Vec::new();
+    for fk in &table.foreign_keys {
+        let indexes: Option<Vec<usize>> = fk.columns.iter().map(|c| position(&columns, c)).collect();
+        let Some(indexes) = indexes else { continue };
+        // Overridden columns keep their generator
+        if indexes.iter().any(|&i| request.overrides.contains_key(&columns[i].column.name)) {
+            continue;
+        }
+        for (offset, &i) in indexes.iter().enumerate() {
+            columns[i].source = Source::ForeignKey(offset);
+            columns[i].null_ratio = 0.0;
+        }
+        let nullable = indexes.iter().all(|&i| columns[i].column.is_nullable);
+        let parent_rows = sample_parent_keys(connection, &fk.referenced_table, &fk.referenced_columns).await?;
+        if parent_rows.is_empty() && !nullable {
+            return Err(AppError::InvalidConfiguration(format!(
+                "Table {} references {} which has no rows; generate rows for {} first",
+                table.name, fk.referenced_table, fk.referenced_table
+            )));
+        }
+        foreign_keys.push(ForeignKeyPlan {
+            columns: indexes,
+            parent_rows,
+            nullable,
+        });
+    }
+
+    // Integer keys the database doesn't assign continue after the current maximum
+    let keys: Vec<usize> = (0..columns.len()).filter(|&i| columns[i].column.is_primary_key).collect();
+    if let [key] = keys.as_slice() {
+        let plan = &columns[*key];
+        if !request.overrides.contains_key(&plan.column.name)
+            && matches!(plan.source, Source::Generate(_))
+            && values::categorize(&plan.column.data_type) == ValueCategory::Integer
+            && !matches!(db_type, DatabaseType::MongoDB)
+        {
+            let start = next_sequence_value(connection, &table.name, &plan.column.name).await?;
+            columns[*key].source = Source::Generate(ColumnGenerator::Sequence { start, step: None });
+        }
+    }
+
+    let mut unique_keys: Vec<Vec<usize>> = Vec::new();
+    if !keys.is_empty() && keys.len() == table.columns.iter().filter(|c| c.is_primary_key).count() {
+        unique_keys.push(keys);
+    }
+    for index in table.indexes.iter().filter(|i| i.is_unique && !i.is_primary) {
+        let indexes: Option<Vec<usize>> = index.columns.iter().map(|c| position(&columns, c)).collect();
+        if let Some(indexes) = indexes {
+            unique_keys.push(indexes);
+        }
+    }
+
+    Ok(TablePlan {
+        columns,
+        foreign_keys,
+        unique_keys,
+    })
+}
+
+fn generate_row(plan: &TablePlan, rng: &mut ChaCha8Rng, row_number: u64) -> Vec<Value> {
+    let mut row: Vec<Value> = plan
+        .columns
+        .iter()
+        .map(|column| match &column.source {
+            Source::ForeignKey(_) => Value::Null,
+            Source::Generate(_) if column.null_ratio > 0.0 && rng.gen_bool(column.null_ratio.min(1.0)) => Value::Null,
+            Source::Generate(generator) => match generate(generator, rng, row_number, column.decimals) {
+                Value::String(text) => match column.max_length {
+                    Some(max) if text.chars().count() > max => Value::String(text.chars().take(max).collect()),
+                    _ => Value::String(text),
+                },
+                other => other,
+            },
+        })
+        .collect();
+
+    for fk in &plan.foreign_keys {
+        let use_null = fk.parent_rows.is_empty() || (fk.nullable && rng.gen_bool(DEFAULT_NULL_RATIO));
+        let parent = if use_null { None } else { fk.parent_rows.choose(rng) };
+        for &i in &fk.columns {
+            if let Source::ForeignKey(offset) = plan.columns[i].source {
+                row[i] = parent.and_then(|p| p.get(offset).cloned()).unwrap_or(Value::Null);
+            }
+        }
+    }
+    row
+}
+
+// Parents first; tables outside the request are assumed to already hold their rows
+fn insertion_order<'a>(tables: &'a [(&'a FakeTableRequest, TableInfo)]) -> Result<Vec<usize>, AppError> {
+    let mut order = Vec::new();
+    let mut placed = vec![false; tables.len()];
+    while order.len() < tables.len() {
+        let ready = (0..tables.len()).find(|&i| {
+            !placed[i]
+                && tables[i].1.foreign_keys.iter().all(|fk| {
+                    fk.referenced_table == tables[i].1.name
+                        || tables
+                            .iter()
+                            .enumerate()
+                            .all(|(j, (_, other))| other.name != fk.referenced_table || placed[j])
+                })
+        });
+        match ready {
+            Some(i) => {
+                placed[i] = true;
+                order.push(i);
+            }
+            None => {
+                let remaining: Vec<&str> = (0..tables.len()).filter(|&i| !placed[i]).map(|i| tables[i].1.name.as_str()).collect();
+                return Err(AppError::InvalidConfiguration(format!(
+                    "Circular foreign keys between {}; generate these tables separately",
+                    remaining.join(", ")
+                )));
+            }
+        }
+    }
+    Ok(order)
+}
+
+pub async fn generate_fake_data(
+    connection: &DatabaseConnection,
+    options: &FakeDataOptions,
+    mut on_progress: impl FnMut(FakeDataProgress),
+) -> Result<FakeDataSummary, AppError> {
+    let started = Instant::now();
+    let seed = options.seed.unwrap_or_else(rand::random);
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    let null_ratio = options.null_ratio.unwrap_or(DEFAULT_NULL_RATIO).clamp(0.0, 1.0);
+    let batch_size = options.batch_size.unwrap_or(DEFAULT_BATCH_SIZE).max(1);
+
+    let schema = connection.get_schema().await?;
+    let mut tables = Vec::new();
+    for request in &options.tables {
+        let info = schema
+            .tables
+            .iter()
+            .find(|t| t.name == request.table)
+            .cloned()
+            .ok_or_else(|| AppError::InvalidConfiguration(format!("Table {} not found", request.table)))?;
+        tables.push((request, info));
+    }
+    let order = insertion_order(&tables)?;
+
+    let pg_enums = if matches!(connection, DatabaseConnection::PostgreSQL(..)) {
+        postgres_enum_types(connection).await?
+    } else {
+        HashMap::new()
+    };
+
+    let mut summaries = Vec::new();
+    let mut warnings = Vec::new();
+    for i in order {
+        let (request, info) = &tables[i];
+        // Planned only now so parent keys include the rows just inserted
+        let plan = plan_table(connection, info, request, &pg_enums, null_ratio).await?;
+        let columns: Vec<ColumnInfo> = plan.columns.iter().map(|p| p.column.clone()).collect();
+        let mut seen = existing_unique_keys(connection, &info.name, &plan).await?;
+        let mut inserted = 0u64;
+        let mut generated = 0u64;
+        let mut exhausted = false;
+        while generated < request.rows && !exhausted {
+            let mut batch = Vec::new();
+            while batch.len() < batch_size && generated < request.rows {
+                let mut accepted = None;
+                for _ in 0..MAX_UNIQUE_ATTEMPTS {
+                    let row = generate_row(&plan, &mut rng, generated);
+                    let keys: Vec<String> = plan
+                        .unique_keys
+                        .iter()
+                        .map(|key| key_text(key.iter().map(|&c| &row[c])))
+                        .collect();
+                    // NULLs never collide in unique indexes
+                    let collides = plan.unique_keys.iter().zip(&keys).enumerate().any(|(k, (key, text))| {
+                        !key.iter().any(|&c| row[c].is_null()) && seen[k].contains(text)
+                    });
+                    if !collides {
+                        for (k, text) in keys.into_iter().enumerate() {
+                            seen[k].insert(text);
+                        }
+                        accepted = Some(row);
+                        break;
+                    }
+                }
+                match accepted {
+                    Some(row) => {
+                        batch.push(row);
+                        generated += 1;
+                    }
+                    None => {
+                        warnings.push(format!(
+                            "{}: stopped after {} rows, unique values ran out; widen the column generators",
+                            info.name, generated
+                        ));
+                        exhausted = true;
+                        break;
+                    }
+                }
+            }
+
+            inserted += connection.insert_rows(&info.name, &columns, &batch).await?;
+            on_progress(FakeDataProgress {
+                table: info.name.clone(),
+                rows_inserted: inserted,
+                total_rows: request.rows,
+            });
+        }
+
+        summaries.push(FakeTableSummary {
+            table: info.name.clone(),
+            rows_inserted: inserted,
+        });
+    }
+
+    Ok(FakeDataSummary {
+        seed,
+        tables: summaries,
+        warnings,
+        duration_ms: started.elapsed().as_millis() as u64,
+    })
+}
@@ -0,0 +1,552 @@
+use crate::error::AppError;
+use chrono::{DateTime, Duration, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
+use sqlx::{QueryBuilder, Row, Sqlite, SqlitePool};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub id: i64,
+    pub connection: String,
+    pub db_type: String,
+    pub query: String,
+    pub executed_at: String,
+    pub duration_ms: i64,
+    pub row_count: Option<i64>,
+    pub error: Option<String>,
+    pub ai_generated: bool,
+    pub favorite: bool,
+}
+
+// Everything needed to record one executed statement
+#[derive(Debug, Clone)]
+pub struct NewHistoryEntry {
+    pub connection: String,
+    pub db_type: String,
+    pub query: String,
+    pub duration_ms: i64,
+    pub row_count: Option<i64>,
+    pub error: Option<String>,
+    pub ai_generated: bool,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct HistoryFilter {
+    pub search: Option<String>,
+    pub connection: Option<String>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub favorites_only: bool,
+    #[serde(default)]
+    pub errors_only: bool,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedSnippet {
+    pub id: Option<i64>,
+    pub name: String,
+    pub query: String,
+    pub description: Option<String>,
+    pub connection: Option<String>,
+    pub created_at: Option<String>,
+    pub updated_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HistoryRetention {
+    // Keep at most this many entries besides favorites, which are never pruned
+    pub max_entries: Option<i64>,
+    // Remove entries older than this many days
+    pub max_age_days: Option<i64>,
+}
+
+#[derive(Debug, Clone)]
+pub struct QueryHistory {
+    pool: SqlitePool,
+    // Inserts since the last prune
+    recorded: Arc<AtomicU64>,
+}
+
+// Retention runs when the history is opened and then once per this many recorded queries
+const RETENTION_INTERVAL: u64 = 100;
+
+// Longest accepted max_age_days, about a century
+const MAX_RETENTION_DAYS: i64 = 36_500;
+
+// Substring pattern for LIKE ... ESCAPE '\'; wildcards in the search text match literally
+fn like_pattern(search: &str) -> String {
+    let escaped = search.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+    format!("%{}%", escaped)
+}
+
+fn now() -> String {
+    timestamp(&Utc::now())
+}
+
+fn timestamp(time: &DateTime<Utc>) -> String {
+    // Fixed-width UTC timestamps so string comparison matches chronological order
+    time.to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+impl QueryHistory {
+    pub async fn open(path: &Path) -> Result<Self, AppError> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| AppError::InternalError(format!("Failed to create history directory: {}", e)))?;
+        }
+
+        let options = SqliteConnectOptions::new()
+            .filename(path)
+            .create_if_missing(true);
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect_with(options)
+            .await?;
+
+        let history = QueryHistory {
+            pool,
+            recorded: Arc::new(AtomicU64::new(0)),
+        };
+        history.migrate().await?;
+        history.apply_retention().await?;
+        Ok(history)
+    }
+
+    async fn migrate(&self) -> Result<(), AppError> {
+        let statements = [
+            r#"
+            CREATE TABLE IF NOT EXISTS query_history (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                connection TEXT NOT NULL,
+                db_type TEXT NOT NULL,
+                query TEXT NOT NULL,
+                executed_at TEXT NOT NULL,
+                duration_ms INTEGER NOT NULL,
+                row_count INTEGER,
+                error TEXT,
+                ai_generated INTEGER NOT NULL DEFAULT 0,
+                favorite INTEGER NOT NULL DEFAULT 0
+            )
+            "#,
+            "CREATE INDEX IF NOT EXISTS idx_query_history_executed_at ON query_history (executed_at)",
+            "CREATE INDEX IF NOT EXISTS idx_query_history_connection ON query_history (connection)",
+            r#"
+            CREATE TABLE IF NOT EXISTS saved_snippets (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                name TEXT NOT NULL,
+                query TEXT NOT NULL,
+                description TEXT,
+                connection TEXT,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            )
+            "#,
+            r#"
+            CREATE TABLE IF NOT EXISTS history_settings (
+                key TEXT PRIMARY KEY,
+                value TEXT
+            )
+            "#,
+        ];
+
+        for statement in statements {
+            sqlx::query(statement).execute(&self.pool).await?;
+        }
+
+        Ok(())
+    }
+
+    pub async fn record(&self, entry: NewHistoryEntry) -> Result<i64, AppError> {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO query_history
+                (connection, db_type, query, executed_at, duration_ms, row_count, error, ai_generated)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&entry.connection)
+        .bind(&entry.db_type)
+        .bind(&entry.query)
+        .bind(now())
+        .bind(entry.duration_ms)
+        .bind(entry.row_count)
+        .bind(&entry.error)
+        .bind(entry.ai_generated)
+        .execute(&self.pool)
+        .await?;
+
+        if self.recorded.fetch_add(1, Ordering::Relaxed) + 1 >= RETENTION_INTERVAL {
+            self.recorded.store(0, Ordering::Relaxed);
+            self.apply_retention().await?;
+        }
+
+        Ok(result.last_insert_rowid())
+    }
+
+    pub async fn search(&self, filter: &HistoryFilter) -> Result<Vec<HistoryEntry>, AppError> {
+        let mut builder: QueryBuilder<Sqlite> = QueryBuilder::new(
+            "SELECT id, connection, db_type, query, executed_at, duration_ms, row_count, error, ai_generated, favorite \
+             FROM query_history WHERE 1 = 1",
+        );
+
+        if let Some(search) = filter.search.as_ref().filter(|s| !s.is_empty()) {
+            builder.push(" AND query LIKE ");
+            builder.push_bind(like_pattern(search));
+            builder.push(" ESCAPE '\\'");
+        }
+        if let Some(connection) = &filter.connection {
+            builder.push(" AND connection = ");
+            builder.push_bind(connection.clone());
+        }
+        if let Some(from) = &filter.from {
+            builder.push(" AND executed_at >= ");
+            builder.push_bind(timestamp(from));
+        }
+        if let Some(to) = &filter.to {
+            builder.push(" AND executed_at <= ");
+            builder.push_bind(timestamp(to));
+        }
+        if filter.favorites_only {
+            builder.push(" AND favorite = 1");
+        }
+        if filter.errors_only {
+            builder.push(" AND error IS NOT NULL");
+        }
+
+        builder.push(" ORDER BY executed_at DESC, id DESC LIMIT ");
+        builder.push_bind(filter.limit.unwrap_or(100));
+        builder.push(" OFFSET ");
+        builder.push_bind(filter.offset.unwrap_or(0));
+
+        let rows = builder.build().fetch_all(&self.pool).await?;
+
+        let entries = rows
+            .iter()
+            .map(|row| HistoryEntry {
+                id: row.get("id"),
+                connection: row.get("connection"),
+                db_type: row.get("db_type"),
+                query: row.get("query"),
+                executed_at: row.get("executed_at"),
+                duration_ms: row.get("duration_ms"),
+                row_count: row.get("row_count"),
+                error: row.get("error"),
+                ai_generated: row.get("ai_generated"),
+                favorite: row.get("favorite"),
+            })
+            .collect();
+
+        Ok(entries)
+    }
+
+    pub async fn set_favorite(&self, id: i64, favorite: bool) -> Result<(), AppError> {
+        sqlx::query("UPDATE query_history SET favorite = ? WHERE id = ?")
+            .bind(favorite)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn delete_entry(&self, id: i64) -> Result<(), AppError> {
+        sqlx::query("DELETE FROM query_history WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    // Remove all non-favorite entries, optionally only for one connection
+    pub async fn clear(&self, connection: Option<&str>) -> Result<u64, AppError> {
+        let result = match connection {
+            Some(connection) => {
+                sqlx::query("DELETE FROM query_history WHERE favorite = 0 AND connection = ?")
+                    .bind(connection)
+                    .execute(&self.pool)
+                    .await?
+            }
+            None => {
+                sqlx::query("DELETE FROM query_history WHERE favorite = 0")
+                    .execute(&self.pool)
+                    .await?
+            }
+        };
+        Ok(result.rows_affected())
+    }
+
+    pub async fn save_snippet(&self, snippet: &SavedSnippet) -> Result<i64, AppError> {
+        let timestamp = now();
+
+        match snippet.id {
+            Some(id) => {
+                sqlx::query(
+                    "UPDATE saved_snippets SET name = ?, query = ?, description = ?, connection = ?, updated_at = ? WHERE id = ?",
+                )
+                .bind(&snippet.name)
+                .bind(&snippet.query)
+                .bind(&snippet.description)
+                .bind(&snippet.connection)
+                .bind(&timestamp)
+                .bind(id)
+                .execute(&self.pool)
+                .await?;
+                Ok(id)
+            }
+            None => {
+                let result = sqlx::query(
+                    "INSERT INTO saved_snippets (name, query, description, connection, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?)",
+                )
+                .bind(&snippet.name)
+                .bind(&snippet.query)
+                .bind(&snippet.description)
+                .bind(&snippet.connection)
+                .bind(&timestamp)
+                .bind(&timestamp)
+                .execute(&self.pool)
+                .await?;
+                Ok(result.last_insert_rowid())
+            }
+        }
+    }
+
+    pub async fn list_snippets(&self, search: Option<&str>) -> Result<Vec<SavedSnippet>, AppError> {
+        let pattern = like_pattern(search.unwrap_or(""));
+        let rows = sqlx::query(
+            r#"
+            SELECT id, name, query, description, connection, created_at, updated_at
+            FROM saved_snippets
+            WHERE name LIKE ? ESCAPE '\' OR query LIKE ? ESCAPE '\'
+            ORDER BY name
+            "#,
+        )
+        .bind(&pattern)
+        .bind(&pattern)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let snippets = rows
+            .iter()
+            .map(|row| SavedSnippet {
+                id: row.get("id"),
+                name: row.get("name"),
+                query: row.get("query"),
+                description: row.get("description"),
+                connection: row.get("connection"),
+                created_at: row.get("created_at"),
+                updated_at: row.get("updated_at"),
+            })
+            .collect();
+
+        Ok(snippets)
+    }
+
+    pub async fn delete_snippet(&self, id: i64) -> Result<(), AppError> {
+        sqlx::query("DELETE FROM saved_snippets WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn get_retention(&self) -> Result<HistoryRetention, AppError> {
+        let rows = sqlx::query("SELECT key, value FROM history_settings")
+            .fetch_all(&self.pool)
+            .await?;
+
+        let mut retention = HistoryRetention::default();
+        for row in rows {
+            let key: String = row.get("key");
+            let value: Option<String> = row.get("value");
+            let value = value.and_then(|v| v.parse::<i64>().ok());
+            match key.as_str() {
+                "max_entries" => retention.max_entries = value,
+                "max_age_days" => retention.max_age_days = value,
+                _ => {}
+            }
+        }
+
+        Ok(retention)
+    }
+
+    pub async fn set_retention(&self, retention: &HistoryRetention) -> Result<(), AppError> {
+        if retention.max_age_days.is_some_and(|days| !(1..=MAX_RETENTION_DAYS).contains(&days)) {
+            return Err(AppError::InvalidConfiguration(format!(
+                "History retention must be between 1 and {} days",
+                MAX_RETENTION_DAYS
+            )));
+        }
+        if retention.max_entries.is_some_and(|entries| entries < 0) {
+            return Err(AppError::InvalidConfiguration("History retention can't keep a negative number of entries".to_string()));
+        }
+
+        let settings = [
+            ("max_entries", retention.max_entries),
+            ("max_age_days", retention.max_age_days),
+        ];
+
+        for (key, value) in settings {
+            sqlx::query("INSERT OR REPLACE INTO history_settings (key, value) VALUES (?, ?)")
+                .bind(key)
+                .bind(value.map(|v| v.to_string()))
+                .execute(&self.pool)
+                .await?;
+        }
+
+        self.apply_retention().await
+    }
+
+    async fn apply_retention(&self) -> Result<(), AppError> {
+        let retention = self.get_retention().await?;
+
+        let cutoff = retention
+            .max_age_days
+            .and_then(Duration::try_days)
+            .and_then(|age| Utc::now().checked_sub_signed(age));
+        if let Some(cutoff) = cutoff {
+            let cutoff = timestamp(&cutoff);
+            sqlx::query("DELETE FROM query_history WHERE favorite = 0 AND executed_at < ?")
+                .bind(cutoff)
+                .execute(&self.pool)
+                .await?;
+        }
+
+        if let Some(max_entries) = retention.max_entries {
+            sqlx::query(
+                r#"
+                DELETE FROM query_history
+                WHERE favorite = 0 AND id NOT IN (
+                    SELECT id FROM query_history WHERE favorite = 0 ORDER BY executed_at DESC, id DESC LIMIT ?
+                )
+                "#,
+            )
+            .bind(max_entries)
+            .execute(&self.pool)
+            .await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn memory_history() -> QueryHistory {
+        let pool = SqlitePoolOptions::new().max_connections(1).connect("sqlite::memory:").await.unwrap();
+        let history = QueryHistory {
+            pool,
+            recorded: Arc::new(AtomicU64::new(0)),
+        };
+        history.migrate().await.unwrap();
+        history
+    }
+
+    async fn record(history: &QueryHistory, query: &str) -> i64 {
+        history
+            .record(NewHistoryEntry {
+                connection: "sqlite://test".to_string(),
+                db_type: "sqlite".to_string(),
+                query: query.to_string(),
+                duration_ms: 1,
+                row_count: Some(0),
+                error: None,
+                ai_generated: false,
+            })
+            .await
+            .unwrap()
+    }
+
+    async fn queries(history: &QueryHistory, search: Option<&str>) -> Vec<String> {
+        let filter = HistoryFilter {
+            search: search.map(str::to_string),
+            limit: Some(1000),
+            ..Default::default()
+        };
+        history.search(&filter).await.unwrap().into_iter().map(|e| e.query).collect()
+    }
+
+    #[tokio::test]
+    async fn search_matches_wildcards_literally() {
+        let history = memory_history().await;
+        for query in ["SELECT 100% FROM t", "SELECT 1000 FROM t", "SELECT a_b FROM t", "SELECT axb FROM t", "SELECT 'C:\\tmp'"] {
+            record(&history, query).await;
+        }
+
+        assert_eq!(queries(&history, Some("100%")).await, vec!["SELECT 100% FROM t"]);
+        assert_eq!(queries(&history, Some("a_b")).await, vec!["SELECT a_b FROM t"]);
+        assert_eq!(queries(&history, Some("C:\\t")).await, vec!["SELECT 'C:\\tmp'"]);
+
+        history
+            .save_snippet(&SavedSnippet {
+                id: None,
+                name: "top_10".to_string(),
+                query: "SELECT 1".to_string(),
+                description: None,
+                connection: None,
+                created_at: None,
+                updated_at: None,
+            })
+            .await
+            .unwrap();
+        assert_eq!(history.list_snippets(Some("p_1")).await.unwrap().len(), 1);
+        assert!(history.list_snippets(Some("px1")).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn retention_keeps_favorites_outside_the_cap() {
+        let history = memory_history().await;
+        let mut ids = Vec::new();
+        for i in 0..6 {
+            ids.push(record(&history, &format!("SELECT {}", i)).await);
+        }
+        history.set_favorite(ids[0], true).await.unwrap();
+        history.set_favorite(ids[1], true).await.unwrap();
+
+        history
+            .set_retention(&HistoryRetention {
+                max_entries: Some(2),
+                max_age_days: None,
+            })
+            .await
+            .unwrap();
+        assert_eq!(queries(&history, None).await, vec!["SELECT 5", "SELECT 4", "SELECT 1", "SELECT 0"]);
+    }
+
+    #[tokio::test]
+    async fn retention_prunes_old_entries_periodically() {
+        let history = memory_history().await;
+        let old = timestamp(&(Utc::now() - Duration::days(40)));
+        sqlx::query(
+            "INSERT INTO query_history (connection, db_type, query, executed_at, duration_ms, favorite) \
+             VALUES ('c', 'sqlite', 'old', ?, 1, 0), ('c', 'sqlite', 'old favorite', ?, 1, 1)",
+        )
+        .bind(&old)
+        .bind(&old)
+        .execute(&history.pool)
+        .await
+        .unwrap();
+        history
+            .set_retention(&HistoryRetention {
+                max_entries: Some(10),
+                max_age_days: Some(30),
+            })
+            .await
+            .unwrap();
+        assert_eq!(queries(&history, None).await, vec!["old favorite"]);
+
+        // Recording doesn't prune on every insert, only once per interval
+        for i in 0..RETENTION_INTERVAL - 1 {
+            record(&history, &format!("SELECT {}", i)).await;
+        }
+        assert_eq!(queries(&history, None).await.len() as u64, RETENTION_INTERVAL);
+        record(&history, "SELECT last").await;
+        assert_eq!(queries(&history, None).await.len(), 11);
+    }
+}
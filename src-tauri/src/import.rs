@@ -0,0 +1,625 @@
+use crate::database::{ColumnInfo, DatabaseConnection};
+use crate::error::AppError;
+use crate::values;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::time::Instant;
+
+// Rows used to infer column types
+const SAMPLE_SIZE: usize = 1000;
+const DEFAULT_BATCH_SIZE: usize = 500;
+// Keep the error report bounded for files that are entirely malformed
+const MAX_REPORTED_ERRORS: usize = 1000;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum ImportFormat {
+    Csv,
+    Json,
+    Ndjson,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum InferredType {
+    Integer,
+    Float,
+    Boolean,
+    Date,
+    DateTime,
+    Text,
+    Json,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ColumnMapping {
+    pub source: String,
+    pub target: String,
+}
+
+fn default_has_header() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportOptions {
+    pub format: ImportFormat,
+    pub path: String,
+    pub table: String,
+    pub delimiter: Option<char>,
+    #[serde(default = "default_has_header")]
+    pub has_header: bool,
+    // Defaults to matching file columns to table columns by name
+    pub column_mapping: Option<Vec<ColumnMapping>>,
+    #[serde(default)]
+    pub create_table: bool,
+    // Declare created columns NOT NULL when the sample has no NULLs; rows past the sample may
+    // still hold NULLs, so this is opt-in
+    #[serde(default)]
+    pub infer_not_null: bool,
+    pub batch_size: Option<usize>,
+    #[serde(default)]
+    pub stop_on_error: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InferredColumn {
+    pub name: String,
+    pub inferred_type: InferredType,
+    pub nullable: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ImportPreview {
+    pub columns: Vec<InferredColumn>,
+    pub sample_rows: Vec<Vec<Value>>,
+    pub table_exists: bool,
+    pub suggested_mapping: Vec<ColumnMapping>,
+    pub create_table_sql: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportRowError {
+    // 1-based record number in the file, not counting the header
+    pub row_number: u64,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportProgress {
+    pub path: String,
+    pub table: String,
+    pub rows_processed: u64,
+    pub rows_inserted: u64,
+    pub rows_failed: u64,
+    pub finished: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ImportSummary {
+    pub table: String,
+    pub table_created: bool,
+    pub rows_processed: u64,
+    pub rows_inserted: u64,
+    pub rows_failed: u64,
+    pub skipped_columns: Vec<String>,
+    pub errors: Vec<ImportRowError>,
+    pub duration_ms: u64,
+}
+
+fn import_error(err: impl std::fmt::Display) -> AppError {
+    AppError::FileOperationFailed(format!("Import failed: {}", err))
+}
+
+type RecordIter = Box<dyn Iterator<Item = Result<Vec<Value>, String>> + Send>;
+
+// Open the file and return its column names plus an iterator over records
+fn open_records(options: &ImportOptions) -> Result<(Vec<String>, RecordIter), AppError> {
+    match options.format {
+        ImportFormat::Csv => {
+            let delimiter = options.delimiter.unwrap_or(',');
+            if !delimiter.is_ascii() {
+                return Err(AppError::InvalidConfiguration("Delimiter must be a single ASCII character".to_string()));
+            }
+            let mut reader = csv::ReaderBuilder::new()
+                .delimiter(delimiter as u8)
+                .has_headers(options.has_header)
+                .flexible(true)
+                .from_path(&options.path)
+                .map_err(import_error)?;
+
+            let columns: Vec<String> = if options.has_header {
+                reader.headers().map_err(import_error)?.iter().map(|h| h.trim().to_string()).collect()
+            } else {
+                // Peek at the first record only to count the columns
+                let mut peek = csv::ReaderBuilder::new()
+                    .delimiter(delimiter as u8)
+                    .has_headers(false)
+                    .from_path(&options.path)
+                    .map_err(import_error)?;
+                let width = peek.records().next().transpose().map_err(import_error)?.map(|r| r.len()).unwrap_or(0);
+                (1..=width).map(|i| format!("column_{}", i)).collect()
+            };
+
+            let records = reader.into_records().map(|record| {
+                record
+                    .map(|r| {
+                        r.iter()
+                            .map(|field| if field.is_empty() { Value::Null } else { Value::String(field.to_string()) })
+                            .collect()
+                    })
+                    .map_err(|e| e.to_string())
+            });
+            Ok((columns, Box::new(records)))
+        }
+        ImportFormat::Ndjson => {
+            let columns = object_keys(
+                BufReader::new(File::open(&options.path)?)
+                    .lines()
+                    .take(SAMPLE_SIZE)
+                    .filter_map(|line| line.ok())
+                    .filter(|line| !line.trim().is_empty())
+                    .filter_map(|line| serde_json::from_str::<Value>(&line).ok())
+                    .collect::<Vec<_>>()
+                    .iter(),
+            );
+
+            let keys = columns.clone();
+            let records = BufReader::new(File::open(&options.path)?)
+                .lines()
+                .filter(|line| line.as_ref().map(|l| !l.trim().is_empty()).unwrap_or(true))
+                .map(move |line| {
+                    let line = line.map_err(|e| e.to_string())?;
+                    let value: Value = serde_json::from_str(&line).map_err(|e| e.to_string())?;
+                    object_values(&value, &keys)
+                });
+            Ok((columns, Box::new(records)))
+        }
+        ImportFormat::Json => {
+            // A top-level array has to be parsed as a whole
+            let value: Value = serde_json::from_reader(BufReader::new(File::open(&options.path)?)).map_err(import_error)?;
+            let items = match value {
+                Value::Array(items) => items,
+                other => vec![other],
+            };
+            let columns = object_keys(items.iter().take(SAMPLE_SIZE));
+            let keys = columns.clone();
+            let records = items.into_iter().map(move |item| object_values(&item, &keys));
+            Ok((columns, Box::new(records)))
+        }
+    }
+}
+
+// Union of object keys in order of first appearance
+fn object_keys<'a>(items: impl Iterator<Item = &'a Value>) -> Vec<String> {
+    let mut seen = HashSet::new();
+    let mut keys = Vec::new();
+    for item in items {
+        if let Some(object) = item.as_object() {
+            for key in object.keys() {
+                if seen.insert(key.clone()) {
+                    keys.push(key.clone());
+                }
+            }
+        }
+    }
+    keys
+}
+
+fn object_values(value: &Value, keys: &[String]) -> Result<Vec<Value>, String> {
+    let object = value.as_object().ok_or_else(|| "record is not a JSON object".to_string())?;
+    Ok(keys.iter().map(|k| object.get(k).cloned().unwrap_or(Value::Null)).collect())
+}
+
+fn infer_value_type(value: &Value) -> Option<InferredType> {
+    match value {
+        Value::Null => None,
+        Value::Bool(_) => Some(InferredType::Boolean),
+        Value::Number(n) if n.is_i64() => Some(InferredType::Integer),
+        Value::Number(_) => Some(InferredType::Float),
+        Value::Array(_) | Value::Object(_) => Some(InferredType::Json),
+        Value::String(s) => {
+            let s = s.trim();
+            // Codes such as ZIPs and phone numbers would lose their leading zeros as numbers
+            let leading_zero = s.len() > 1 && s.starts_with('0') && s[1..].starts_with(|c: char| c.is_ascii_digit());
+            if leading_zero && s.parse::<f64>().is_ok() {
+                Some(InferredType::Text)
+            } else if s.parse::<i64>().is_ok() {
+                Some(InferredType::Integer)
+            } else if s.parse::<f64>().is_ok() {
+                Some(InferredType::Float)
+            } else if matches!(s.to_lowercase().as_str(), "true" | "false") {
+                Some(InferredType::Boolean)
+            } else if values::parse_date(s).is_some() {
+                Some(InferredType::Date)
+            } else if values::parse_datetime(s).is_some() {
+                Some(InferredType::DateTime)
+            } else {
+                Some(InferredType::Text)
+            }
+        }
+    }
+}
+
+// Widen two observed types to one that can hold both
+fn merge_types(a: InferredType, b: InferredType) -> InferredType {
+    use InferredType::*;
+    match (a, b) {
+        (a, b) if a == b => a,
+        (Integer, Float) | (Float, Integer) => Float,
+        (Date, DateTime) | (DateTime, Date) => DateTime,
+        _ => Text,
+    }
+}
+
+pub fn infer_columns(columns: &[String], sample: &[Vec<Value>], infer_not_null: bool) -> Vec<InferredColumn> {
+    columns
+        .iter()
+        .enumerate()
+        .map(|(i, name)| {
+            let mut inferred: Option<InferredType> = None;
+            let mut nullable = !infer_not_null;
+            for row in sample {
+                match row.get(i).and_then(infer_value_type) {
+                    Some(t) => inferred = Some(inferred.map(|current| merge_types(current, t)).unwrap_or(t)),
+                    None => nullable = true,
+                }
+            }
+            InferredColumn {
+                name: name.clone(),
+                inferred_type: inferred.unwrap_or(InferredType::Text),
+                nullable,
+            }
+        })
+        .collect()
+}
+
+fn column_type_sql(connection: &DatabaseConnection, inferred: InferredType) -> &'static str {
+    match connection {
+        DatabaseConnection::MySQL(..) => match inferred {
+            InferredType::Integer => "BIGINT",
+            InferredType::Float => "DOUBLE",
+            InferredType::Boolean => "BOOLEAN",
+            InferredType::Date => "DATE",
+            InferredType::DateTime => "DATETIME",
+            InferredType::Json => "JSON",
+            InferredType::Text => "TEXT",
+        },
+        DatabaseConnection::PostgreSQL(..) => match inferred {
+            InferredType::Integer => "BIGINT",
+            InferredType::Float => "DOUBLE PRECISION",
+            InferredType::Boolean => "BOOLEAN",
+            InferredType::Date => "DATE",
+            InferredType::DateTime => "TIMESTAMP",
+            InferredType::Json => "JSONB",
+            InferredType::Text => "TEXT",
+        },
+        _ => match inferred {
+            InferredType::Integer | InferredType::Boolean => "INTEGER",
+            InferredType::Float => "REAL",
+            _ => "TEXT",
+        },
+    }
+}
+
+// Declared type used when coercing values for a column that only exists in the file
+fn inferred_data_type(inferred: InferredType) -> &'static str {
+    match inferred {
+        InferredType::Integer => "bigint",
+        InferredType::Float => "double",
+        InferredType::Boolean => "boolean",
+        InferredType::Date => "date",
+        InferredType::DateTime => "timestamp",
+        InferredType::Json => "json",
+        InferredType::Text => "text",
+    }
+}
+
+pub fn create_table_sql(connection: &DatabaseConnection, table: &str, columns: &[InferredColumn]) -> String {
+    let definitions: Vec<String> = columns
+        .iter()
+        .map(|c| {
+            format!(
+                "    {} {}{}",
+                connection.quote_ident(&c.name),
+                column_type_sql(connection, c.inferred_type),
+                if c.nullable { "" } else { " NOT NULL" }
+            )
+        })
+        .collect();
+    format!("CREATE TABLE {} (\n{}\n)", connection.quote_table(table), definitions.join(",\n"))
+}
+
+fn default_mapping(file_columns: &[String], table_columns: Option<&[ColumnInfo]>) -> Vec<ColumnMapping> {
+    file_columns
+        .iter()
+        .filter_map(|source| {
+            let target = match table_columns {
+                Some(columns) => columns.iter().find(|c| c.name.eq_ignore_ascii_case(source))?.name.clone(),
+                None => source.clone(),
+            };
+            Some(ColumnMapping { source: source.clone(), target })
+        })
+        .collect()
+}
+
+async fn find_table_columns(connection: &DatabaseConnection, table: &str) -> Result<Option<Vec<ColumnInfo>>, AppError> {
+    if matches!(connection, DatabaseConnection::MongoDB(..)) {
+        // Collections are schemaless; they are created on first insert
+        return Ok(None);
+    }
+    let schema = connection.get_schema().await?;
+    Ok(schema
+        .tables
+        .into_iter()
+        .find(|t| t.name.eq_ignore_ascii_case(table))
+        .map(|t| t.columns))
+}
+
+pub async fn preview_import(connection: &DatabaseConnection, options: &ImportOptions) -> Result<ImportPreview, AppError> {
+    let (columns, records) = open_records(options)?;
+    let sample: Vec<Vec<Value>> = records.take(SAMPLE_SIZE).filter_map(|r| r.ok()).collect();
+    let inferred = infer_columns(&columns, &sample, options.infer_not_null);
+
+    let table_columns = find_table_columns(connection, &options.table).await?;
+    let is_mongodb = matches!(connection, DatabaseConnection::MongoDB(..));
+
+    Ok(ImportPreview {
+        suggested_mapping: default_mapping(&columns, table_columns.as_deref()),
+        create_table_sql: if table_columns.is_none() && !is_mongodb {
+            Some(create_table_sql(connection, &options.table, &inferred))
+        } else {
+            None
+        },
+        table_exists: table_columns.is_some(),
+        columns: inferred,
+        sample_rows: sample.into_iter().take(20).collect(),
+    })
+}
+
+struct ImportState {
+    rows_inserted: u64,
+    rows_failed: u64,
+    errors: Vec<ImportRowError>,
+}
+
+impl ImportState {
+    fn fail(&mut self, row_number: u64, error: String) {
+        self.rows_failed += 1;
+        if self.errors.len() < MAX_REPORTED_ERRORS {
+            self.errors.push(ImportRowError { row_number, error });
+        }
+    }
+}
+
+// Insert a batch in one transaction; when it fails, retry row by row so that only
+// the offending rows are reported. MongoDB batches resume after the failing row instead
+async fn insert_batch(
+    connection: &DatabaseConnection,
+    table: &str,
+    targets: &[ColumnInfo],
+    batch: &mut Vec<(u64, Vec<Value>)>,
+    stop_on_error: bool,
+    state: &mut ImportState,
+) -> Result<(), AppError> {
+    if batch.is_empty() {
+        return Ok(());
+    }
+
+    let rows: Vec<Vec<Value>> = batch.iter().map(|(_, row)| row.clone()).collect();
+    if matches!(connection, DatabaseConnection::MongoDB(..)) {
+        // Rows before a failure are already written, so resume after the failing row
+        // instead of retrying the whole batch
+        let mut start = 0;
+        while start < rows.len() {
+            match connection.insert_documents(table, targets, &rows[start..]).await {
+                Ok(count) => {
+                    state.rows_inserted += count;
+                    break;
+                }
+                Err(partial) => {
+                    state.rows_inserted += partial.inserted;
+                    let failed = start + partial.inserted as usize;
+                    let row_number = batch.get(failed).map(|(n, _)| *n).unwrap_or_default();
+                    if stop_on_error {
+                        return Err(import_error(format!("row {}: {}", row_number, partial.error)));
+                    }
+                    state.fail(row_number, partial.error.to_string());
+                    start = failed + 1;
+                }
+            }
+        }
+        batch.clear();
+        return Ok(());
+    }
+
+    match connection.insert_rows(table, targets, &rows).await {
+        Ok(count) => state.rows_inserted += count,
+        Err(e) if stop_on_error => {
+            let first = batch.first().map(|(n, _)| *n).unwrap_or_default();
+            return Err(import_error(format!("batch starting at row {}: {}", first, e)));
+        }
+        Err(_) => {
+            for (row_number, row) in batch.iter() {
+                match connection.insert_rows(table, targets, std::slice::from_ref(row)).await {
+                    Ok(count) => state.rows_inserted += count,
+                    Err(e) => state.fail(*row_number, e.to_string()),
+                }
+            }
+        }
+    }
+
+    batch.clear();
+    Ok(())
+}
+
+pub async fn import_file<F>(
+    connection: &DatabaseConnection,
+    options: &ImportOptions,
+    mut on_progress: F,
+) -> Result<ImportSummary, AppError>
+where
+    F: FnMut(ImportProgress) + Send,
+{
+    let started = Instant::now();
+
+    // First pass over a sample to infer types for new tables and schemaless targets
+    let (file_columns, sample_records) = open_records(options)?;
+    let sample: Vec<Vec<Value>> = sample_records.take(SAMPLE_SIZE).filter_map(|r| r.ok()).collect();
+    let inferred = infer_columns(&file_columns, &sample, options.infer_not_null);
+
+    let mut table_created = false;
+    let mut table_columns = find_table_columns(connection, &options.table).await?;
+    let is_mongodb = matches!(connection, DatabaseConnection::MongoDB(..));
+
+    if table_columns.is_none() && !is_mongodb {
+        if !options.create_table {
+            return Err(import_error(format!("table {} does not exist", options.table)));
+        }
+        let sql = create_table_sql(connection, &options.table, &inferred);
+        connection.execute_query(&sql).await?;
+        table_created = true;
+        table_columns = find_table_columns(connection, &options.table).await?;
+    }
+
+    let mapping = options
+        .column_mapping
+        .clone()
+        .unwrap_or_else(|| default_mapping(&file_columns, table_columns.as_deref()));
+
+    // Resolve each mapping to a file column index and a target column definition
+    let mut sources = Vec::new();
+    let mut targets = Vec::new();
+    for m in &mapping {
+        let index = file_columns
+            .iter()
+            .position(|c| c == &m.source)
+            .ok_or_else(|| import_error(format!("column {} not found in file", m.source)))?;
+        let target = match &table_columns {
+            Some(columns) => columns
+                .iter()
+                .find(|c| c.name.eq_ignore_ascii_case(&m.target))
+                .cloned()
+                .ok_or_else(|| import_error(format!("column {} not found in table {}", m.target, options.table)))?,
+            None => ColumnInfo {
+                name: m.target.clone(),
+                data_type: inferred_data_type(inferred[index].inferred_type).to_string(),
+                is_nullable: true,
+                is_primary_key: false,
+                column_type: None,
+                default_value: None,
+                extra: None,
+                comment: None,
+                collation: None,
+            },
+        };
+        sources.push(index);
+        targets.push(target);
+    }
+
+    if targets.is_empty() {
+        return Err(import_error("no file columns map to table columns"));
+    }
+
+    let skipped_columns: Vec<String> = file_columns
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| !sources.contains(i))
+        .map(|(_, c)| c.clone())
+        .collect();
+
+    let batch_size = options.batch_size.unwrap_or(DEFAULT_BATCH_SIZE).max(1);
+    let mut state = ImportState {
+        rows_inserted: 0,
+        rows_failed: 0,
+        errors: Vec::new(),
+    };
+    let mut rows_processed = 0u64;
+    let mut batch: Vec<(u64, Vec<Value>)> = Vec::with_capacity(batch_size);
+
+    let (_, records) = open_records(options)?;
+    for record in records {
+        rows_processed += 1;
+        match record {
+            Ok(values) => {
+                let row = sources
+                    .iter()
+                    .map(|i| values.get(*i).cloned().unwrap_or(Value::Null))
+                    .collect();
+                batch.push((rows_processed, row));
+            }
+            Err(e) if options.stop_on_error => {
+                return Err(import_error(format!("row {}: {}", rows_processed, e)));
+            }
+            Err(e) => state.fail(rows_processed, e),
+        }
+
+        if batch.len() >= batch_size {
+            insert_batch(connection, &options.table, &targets, &mut batch, options.stop_on_error, &mut state).await?;
+            on_progress(ImportProgress {
+                path: options.path.clone(),
+                table: options.table.clone(),
+                rows_processed,
+                rows_inserted: state.rows_inserted,
+                rows_failed: state.rows_failed,
+                finished: false,
+            });
+        }
+    }
+    insert_batch(connection, &options.table, &targets, &mut batch, options.stop_on_error, &mut state).await?;
+
+    on_progress(ImportProgress {
+        path: options.path.clone(),
+        table: options.table.clone(),
+        rows_processed,
+        rows_inserted: state.rows_inserted,
+        rows_failed: state.rows_failed,
+        finished: true,
+    });
+
+    Ok(ImportSummary {
+        table: options.table.clone(),
+        table_created,
+        rows_processed,
+        rows_inserted: state.rows_inserted,
+        rows_failed: state.rows_failed,
+        skipped_columns,
+        errors: state.errors,
+        duration_ms: started.elapsed().as_millis() as u64,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|n| n.to_string()).collect()
+    }
+
+    #[test]
+    fn leading_zero_codes_stay_text() {
+        assert_eq!(infer_value_type(&json!("02134")), Some(InferredType::Text));
+        assert_eq!(infer_value_type(&json!("0044 20 7946")), Some(InferredType::Text));
+        assert_eq!(infer_value_type(&json!("007.5")), Some(InferredType::Text));
+        assert_eq!(infer_value_type(&json!("0")), Some(InferredType::Integer));
+        assert_eq!(infer_value_type(&json!("0.5")), Some(InferredType::Float));
+        assert_eq!(infer_value_type(&json!("2134")), Some(InferredType::Integer));
+    }
+
+    #[test]
+    fn columns_are_nullable_unless_requested() {
+        let sample = vec![vec![json!("1"), json!("02134")], vec![json!("2"), json!("10001")]];
+        let inferred = infer_columns(&names(&["id", "zip"]), &sample, false);
+        assert!(inferred.iter().all(|c| c.nullable));
+        assert_eq!(inferred[0].inferred_type, InferredType::Integer);
+        assert_eq!(inferred[1].inferred_type, InferredType::Text);
+
+        let sample = vec![vec![json!(1), Value::Null], vec![json!(2), json!("x")]];
+        let inferred = infer_columns(&names(&["id", "note"]), &sample, true);
+        assert!(!inferred[0].nullable);
+        assert!(inferred[1].nullable);
+    }
+}
@@ -0,0 +1,527 @@
+use crate::database::{pg_table_ref, quote_identifier, quote_mssql_identifier, DatabaseConnection, DatabaseType, IndexInfo};
+use crate::error::AppError;
+use bson::{doc, Bson, Document};
+use futures::TryStreamExt;
+use mongodb::options::IndexOptions;
+use mongodb::IndexModel;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexStats {
+    pub table: String,
+    pub name: String,
+    pub columns: Vec<String>,
+    pub is_unique: bool,
+    pub is_primary: bool,
+    pub size_bytes: Option<i64>,
+    // Index scans since the statistics were last reset (server restart on MySQL and MongoDB)
+    pub scans: Option<i64>,
+    pub rows_read: Option<i64>,
+    // Never scanned and not enforcing uniqueness
+    pub unused: bool,
+    pub definition: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexReport {
+    pub indexes: Vec<IndexStats>,
+    // Statistics that couldn't be read, e.g. for lack of privileges
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexColumn {
+    pub name: String,
+    #[serde(default)]
+    pub descending: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum IndexChange {
+    Create {
+        table: String,
+        name: String,
+        columns: Vec<IndexColumn>,
+        #[serde(default)]
+        unique: bool,
+        // Index method such as btree, hash or gin (PostgreSQL and MySQL)
+        method: Option<String>,
+        // Build without blocking writes where the backend supports it
+        #[serde(default)]
+        online: bool,
+    },
+    Drop {
+        table: String,
+        name: String,
+        #[serde(default)]
+        online: bool,
+    },
+    Rebuild {
+        table: String,
+        name: String,
+        #[serde(default)]
+        online: bool,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexDdl {
+    pub dialect: String,
+    // For MongoDB these are shell commands shown for preview
+    pub statements: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+fn number(value: &Value) -> Option<i64> {
+    match value {
+        Value::Number(n) => n.as_i64().or_else(|| n.as_f64().map(|f| f as i64)),
+        Value::String(s) => s.trim().parse::<f64>().ok().map(|f| f as i64),
+        _ => None,
+    }
+}
+
+fn text(value: &Value) -> Option<String> {
+    value.as_str().map(|s| s.to_string())
+}
+
+fn sql_string(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "''"))
+}
+
+fn bson_number(value: Option<&Bson>) -> Option<i64> {
+    match value? {
+        Bson::Int32(v) => Some(*v as i64),
+        Bson::Int64(v) => Some(*v),
+        Bson::Double(v) => Some(*v as i64),
+        _ => None,
+    }
+}
+
+// Statistics keyed by (table, index)
+#[derive(Default)]
+struct Usage {
+    size_bytes: Option<i64>,
+    scans: Option<i64>,
+    rows_read: Option<i64>,
+    definition: Option<String>,
+}
+
+async fn sql_usage(
+    connection: &DatabaseConnection,
+    table: Option<&str>,
+    warnings: &mut Vec<String>,
+) -> HashMap<(String, String), Usage> {
+    let mut usage: HashMap<(String, String), Usage> = HashMap::new();
+    // (query, what it provides) pairs; each may fail independently
+    let queries: Vec<(String, &str)> = match connection {
+        // Rows are keyed by the table name as requested, so "schema.table" matches its indexes
+        DatabaseConnection::PostgreSQL(..) => {
+            let (schema, _) = table.map(pg_table_ref).unwrap_or((None, ""));
+            let (schema, key) = match (schema, table) {
+                (Some(schema), Some(table)) => (sql_string(schema), sql_string(table)),
+                _ => ("current_schema()".to_string(), "s.relname".to_string()),
+            };
+            vec![(
+                format!(
+                    "SELECT {}, s.indexrelname, pg_relation_size(s.indexrelid) AS size_bytes, s.idx_scan, s.idx_tup_read, \
+                     pg_get_indexdef(s.indexrelid) AS definition \
+                     FROM pg_stat_user_indexes s WHERE s.schemaname = {}",
+                    key, schema
+                ),
+                "index statistics",
+            )]
+        }
+        DatabaseConnection::MySQL(..) => vec![
+            (
+                "SELECT table_name, index_name, CAST(stat_value * @@innodb_page_size AS SIGNED) AS size_bytes, NULL, NULL, NULL \
+                 FROM mysql.innodb_index_stats WHERE database_name = DATABASE() AND stat_name = 'size'".to_string(),
+                "index sizes (mysql.innodb_index_stats)",
+            ),
+            (
+                "SELECT OBJECT_NAME, INDEX_NAME, NULL, CAST(COUNT_STAR AS SIGNED), CAST(COUNT_READ AS SIGNED), NULL \
+                 FROM performance_schema.table_io_waits_summary_by_index_usage \
+                 WHERE OBJECT_SCHEMA = DATABASE() AND INDEX_NAME IS NOT NULL".to_string(),
+                "index usage (performance_schema)",
+            ),
+        ],
+        DatabaseConnection::SQLite(..) => vec![(
+            "SELECT tbl_name, name, (SELECT SUM(pgsize) FROM dbstat d WHERE d.name = m.name), NULL, NULL, sql \
+             FROM sqlite_master m WHERE type = 'index'".to_string(),
+            "index sizes (dbstat)",
+        )],
+        DatabaseConnection::SQLServer(_) => vec![(
+            "SELECT OBJECT_NAME(i.object_id), i.name, \
+             (SELECT CAST(SUM(p.used_page_count) * 8192 AS bigint) FROM sys.dm_db_partition_stats p \
+              WHERE p.object_id = i.object_id AND p.index_id = i.index_id), \
+             CAST(u.user_seeks + u.user_scans + u.user_lookups AS bigint), NULL, NULL \
+             FROM sys.indexes i JOIN sys.tables t ON t.object_id = i.object_id \
+             LEFT JOIN sys.dm_db_index_usage_stats u ON u.database_id = DB_ID() AND u.object_id = i.object_id AND u.index_id = i.index_id \
+             WHERE i.name IS NOT NULL AND t.schema_id = SCHEMA_ID()".to_string(),
+            "index statistics (sys.dm_db_index_usage_stats)",
+        )],
+        DatabaseConnection::MongoDB(..) | DatabaseConnection::Redis(_) | DatabaseConnection::DuckDB(_) => Vec::new(),
+    };
+
+    for (query, description) in queries {
+        match connection.execute_query(&query).await {
+            Ok(result) => {
+                for row in result.rows {
+                    let get = |i: usize| row.get(i).cloned().unwrap_or(Value::Null);
+                    let (Some(table), Some(index)) = (text(&get(0)), text(&get(1))) else { continue };
+                    let entry = usage.entry((table, index)).or_default();
+                    entry.size_bytes = entry.size_bytes.or(number(&get(2)));
+                    entry.scans = entry.scans.or(number(&get(3)));
+                    entry.rows_read = entry.rows_read.or(number(&get(4)));
+                    entry.definition = entry.definition.clone().or(text(&get(5)));
+                }
+            }
+            Err(e) => warnings.push(format!("Couldn't read {}: {}", description, e)),
+        }
+    }
+    usage
+}
+
+async fn mongodb_usage(
+    database: &mongodb::Database,
+    collection: &str,
+    warnings: &mut Vec<String>,
+) -> HashMap<(String, String), Usage> {
+    let mut usage: HashMap<(String, String), Usage> = HashMap::new();
+    let handle = database.collection::<Document>(collection);
+
+    match handle.aggregate(vec![doc! { "$indexStats": {} }], None).await {
+        Ok(cursor) => {
+            let stats: Vec<Document> = cursor.try_collect().await.unwrap_or_default();
+            for stat in stats {
+                let Ok(name) = stat.get_str("name") else { continue };
+                let entry = usage.entry((collection.to_string(), name.to_string())).or_default();
+                entry.scans = stat.get_document("accesses").ok().and_then(|a| bson_number(a.get("ops")));
+                entry.definition = stat.get_document("key").ok().map(|k| k.to_string());
+            }
+        }
+        Err(e) => warnings.push(format!("Couldn't read $indexStats for {}: {}", collection, e)),
+    }
+
+    match handle.aggregate(vec![doc! { "$collStats": { "storageStats": {} } }], None).await {
+        Ok(cursor) => {
+            let stats: Vec<Document> = cursor.try_collect().await.unwrap_or_default();
+            let sizes = stats
+                .first()
+                .and_then(|s| s.get_document("storageStats").ok())
+                .and_then(|s| s.get_document("indexSizes").ok());
+            for (name, size) in sizes.into_iter().flatten() {
+                usage.entry((collection.to_string(), name.clone())).or_default().size_bytes = bson_number(Some(size));
+            }
+        }
+        Err(e) => warnings.push(format!("Couldn't read index sizes for {}: {}", collection, e)),
+    }
+    usage
+}
+
+fn with_usage(index: IndexInfo, usage: &mut HashMap<(String, String), Usage>) -> IndexStats {
+    let stats = usage.remove(&(index.table.clone(), index.name.clone())).unwrap_or_default();
+    IndexStats {
+        unused: stats.scans == Some(0) && !index.is_primary && !index.is_unique,
+        table: index.table,
+        name: index.name,
+        columns: index.columns,
+        is_unique: index.is_unique,
+        is_primary: index.is_primary,
+        size_bytes: stats.size_bytes,
+        scans: stats.scans,
+        rows_read: stats.rows_read,
+        definition: stats.definition,
+    }
+}
+
+pub async fn index_stats(connection: &DatabaseConnection, table: Option<&str>) -> Result<IndexReport, AppError> {
+    let tables = match table {
+        Some(table) => vec![table.to_string()],
+        None => connection.list_collections().await?,
+    };
+    let mut warnings = Vec::new();
+    let mut usage = match connection {
+        DatabaseConnection::MongoDB(..) => HashMap::new(),
+        _ => sql_usage(connection, table, &mut warnings).await,
+    };
+
+    let mut indexes = Vec::new();
+    for table in &tables {
+        if let DatabaseConnection::MongoDB(_client, database) = connection {
+            usage.extend(mongodb_usage(database, table, &mut warnings).await);
+        }
+        for index in connection.get_table_indexes(table).await? {
+            indexes.push(with_usage(index, &mut usage));
+        }
+    }
+
+    Ok(IndexReport { indexes, warnings })
+}
+
+fn quote(db_type: &DatabaseType, name: &str) -> String {
+    match db_type {
+        DatabaseType::MySQL => quote_identifier(name, '`'),
+        DatabaseType::SQLServer => quote_mssql_identifier(name),
+        _ => quote_identifier(name, '"'),
+    }
+}
+
+fn column_list(db_type: &DatabaseType, columns: &[IndexColumn]) -> String {
+    columns
+        .iter()
+        .map(|c| format!("{}{}", quote(db_type, &c.name), if c.descending { " DESC" } else { "" }))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn mongodb_keys(columns: &[IndexColumn]) -> Document {
+    columns
+        .iter()
+        .map(|c| (c.name.clone(), Bson::Int32(if c.descending { -1 } else { 1 })))
+        .collect()
+}
+
+async fn find_index(connection: &DatabaseConnection, table: &str, name: &str) -> Result<IndexInfo, AppError> {
+    connection
+        .get_table_indexes(table)
+        .await?
+        .into_iter()
+        .find(|i| i.name == name)
+        .ok_or_else(|| AppError::InvalidConfiguration(format!("Index {} not found on {}", name, table)))
+}
+
+// Constraint backing a PostgreSQL index; such indexes are dropped with the constraint
+async fn postgres_constraint(connection: &DatabaseConnection, table: &str, index: &str) -> Result<Option<String>, AppError> {
+    let schema = pg_table_ref(table).0.map(sql_string).unwrap_or_else(|| "current_schema()".to_string());
+    let rows = connection
+        .execute_query(&format!(
+            "SELECT c.conname FROM pg_constraint c JOIN pg_class i ON i.oid = c.conindid \
+             JOIN pg_namespace n ON n.oid = i.relnamespace WHERE n.nspname = {} AND i.relname = {}",
+            schema,
+            sql_string(index)
+        ))
+        .await?
+        .rows;
+    Ok(rows.first().and_then(|r| r.first()).and_then(text))
+}
+
+// The method is spliced into the DDL unquoted, so only a bare keyword such as btree is accepted
+fn check_method(change: &IndexChange) -> Result<(), AppError> {
+    let IndexChange::Create { method: Some(method), .. } = change else { return Ok(()) };
+    let method = method.trim();
+    if !method.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return Err(AppError::InvalidConfiguration(format!("Invalid index method: {}", method)));
+    }
+    Ok(())
+}
+
+fn create_sql(db_type: &DatabaseType, change: &IndexChange, warnings: &mut Vec<String>) -> Option<String> {
+    let IndexChange::Create { table, name, columns, unique, method, online } = change else { return None };
+    let unique = if *unique { "UNIQUE " } else { "" };
+    let method = method.as_deref().filter(|m| !m.trim().is_empty());
+    Some(match db_type {
+        DatabaseType::PostgreSQL => format!(
+            "CREATE {}INDEX {}{} ON {}{} ({});",
+            unique,
+            if *online { "CONCURRENTLY " } else { "" },
+            quote(db_type, name),
+            quote(db_type, table),
+            method.map(|m| format!(" USING {}", m)).unwrap_or_default(),
+            column_list(db_type, columns)
+        ),
+        DatabaseType::MySQL => format!(
+            "CREATE {}INDEX {} ON {} ({}){}{};",
+            unique,
+            quote(db_type, name),
+            quote(db_type, table),
+            column_list(db_type, columns),
+            method.map(|m| format!(" USING {}", m.to_uppercase())).unwrap_or_default(),
+            if *online { " ALGORITHM=INPLACE LOCK=NONE" } else { "" }
+        ),
+        DatabaseType::DuckDB => {
+            if method.is_some() || *online {
+                warnings.push("DuckDB always builds ART indexes and locks the table while indexing".to_string());
+            }
+            format!(
+                "CREATE {}INDEX {} ON {} ({});",
+                unique,
+                quote(db_type, name),
+                quote(db_type, table),
+                column_list(db_type, columns)
+            )
+        }
+        DatabaseType::SQLServer => {
+            if method.is_some() {
+                warnings.push("SQL Server picks the index type from CLUSTERED/NONCLUSTERED; the method is ignored".to_string());
+            }
+            format!(
+                "CREATE {}INDEX {} ON {} ({}){};",
+                unique,
+                quote(db_type, name),
+                quote(db_type, table),
+                column_list(db_type, columns),
+                if *online { " WITH (ONLINE = ON)" } else { "" }
+            )
+        }
+        _ => {
+            if method.is_some() || *online {
+                warnings.push("SQLite ignores index methods and always locks the table while indexing".to_string());
+            }
+            format!(
+                "CREATE {}INDEX {} ON {} ({});",
+                unique,
+                quote(db_type, name),
+                quote(db_type, table),
+                column_list(db_type, columns)
+            )
+        }
+    })
+}
+
+// SQL for the change; MongoDB changes are shown as shell commands
+pub async fn index_ddl(connection: &DatabaseConnection, change: &IndexChange) -> Result<IndexDdl, AppError> {
+    let db_type = connection.db_type();
+    if let DatabaseType::Redis = db_type {
+        return Err(AppError::InvalidConfiguration("Redis keys have no indexes".to_string()));
+    }
+    let mut statements = Vec::new();
+    let mut warnings = Vec::new();
+
+    match change {
+        IndexChange::Create { table, name, columns, unique, .. } => {
+            if name.trim().is_empty() || columns.is_empty() {
+                return Err(AppError::InvalidConfiguration("An index needs a name and at least one column".to_string()));
+            }
+            check_method(change)?;
+            match db_type {
+                DatabaseType::MongoDB => statements.push(format!(
+                    "db.{}.createIndex({}, {})",
+                    table,
+                    mongodb_keys(columns),
+                    doc! { "name": name, "unique": *unique }
+                )),
+                _ => statements.extend(create_sql(&db_type, change, &mut warnings)),
+            }
+        }
+        IndexChange::Drop { table, name, online } => {
+            let index = find_index(connection, table, name).await?;
+            if index.is_primary {
+                return Err(AppError::InvalidConfiguration(format!(
+                    "{} is the primary key of {}; change it in the table designer",
+                    name, table
+                )));
+            }
+            match db_type {
+                DatabaseType::MySQL | DatabaseType::SQLServer => {
+                    statements.push(format!("DROP INDEX {} ON {};", quote(&db_type, name), quote(&db_type, table)))
+                }
+                DatabaseType::PostgreSQL => match postgres_constraint(connection, table, name).await? {
+                    Some(constraint) => statements.push(format!(
+                        "ALTER TABLE {} DROP CONSTRAINT {};",
+                        quote(&db_type, table),
+                        quote(&db_type, &constraint)
+                    )),
+                    None => statements.push(format!(
+                        "DROP INDEX {}{};",
+                        if *online { "CONCURRENTLY " } else { "" },
+                        quote(&db_type, name)
+                    )),
+                },
+                DatabaseType::SQLite => {
+                    if name.starts_with("sqlite_autoindex_") {
+                        return Err(AppError::InvalidConfiguration(format!(
+                            "{} backs a UNIQUE constraint; remove the constraint in the table designer",
+                            name
+                        )));
+                    }
+                    statements.push(format!("DROP INDEX {};", quote(&db_type, name)));
+                }
+                DatabaseType::MongoDB => statements.push(format!("db.{}.dropIndex({:?})", table, name)),
+                DatabaseType::DuckDB => statements.push(format!("DROP INDEX {};", quote(&db_type, name))),
+                DatabaseType::Redis => unreachable!(),
+            }
+        }
+        IndexChange::Rebuild { table, name, online } => {
+            let index = find_index(connection, table, name).await?;
+            match db_type {
+                DatabaseType::PostgreSQL => statements.push(format!(
+                    "REINDEX INDEX {}{};",
+                    if *online { "CONCURRENTLY " } else { "" },
+                    quote(&db_type, name)
+                )),
+                DatabaseType::SQLite => statements.push(format!("REINDEX {};", quote(&db_type, name))),
+                DatabaseType::DuckDB => {
+                    return Err(AppError::InvalidConfiguration("DuckDB can't rebuild indexes; drop and recreate it instead".to_string()));
+                }
+                DatabaseType::SQLServer => statements.push(format!(
+                    "ALTER INDEX {} ON {} REBUILD{};",
+                    quote(&db_type, name),
+                    quote(&db_type, table),
+                    if *online { " WITH (ONLINE = ON)" } else { "" }
+                )),
+                // InnoDB can't rebuild a single index; a null rebuild of the table recreates every
+                // index from its own definition, keeping type, prefixes, order, comments and visibility
+                DatabaseType::MySQL => {
+                    warnings.push(format!("{} is rebuilt together with the rest of {}", name, table));
+                    statements.push(format!(
+                        "ALTER TABLE {} FORCE{};",
+                        quote(&db_type, table),
+                        if *online { ", ALGORITHM=INPLACE, LOCK=NONE" } else { "" }
+                    ));
+                }
+                DatabaseType::MongoDB => {
+                    if index.is_primary {
+                        return Err(AppError::InvalidConfiguration("The _id index can't be rebuilt".to_string()));
+                    }
+                    warnings.push(format!("{} is dropped and recreated; it isn't available while it builds", name));
+                    statements.push(format!("db.{}.dropIndex({:?})", table, name));
+                    statements.push(format!("db.{}.createIndex(<existing keys and options of {}>)", table, name));
+                }
+                DatabaseType::Redis => unreachable!(),
+            }
+        }
+    }
+
+    Ok(IndexDdl {
+        dialect: db_type.as_str().to_string(),
+        statements,
+        warnings,
+    })
+}
+
+async fn apply_mongodb(database: &mongodb::Database, change: &IndexChange) -> Result<(), AppError> {
+    match change {
+        IndexChange::Create { table, name, columns, unique, .. } => {
+            let options = IndexOptions::builder().name(name.clone()).unique(*unique).build();
+            let model = IndexModel::builder().keys(mongodb_keys(columns)).options(options).build();
+            database.collection::<Document>(table).create_index(model, None).await?;
+        }
+        IndexChange::Drop { table, name, .. } => {
+            database.collection::<Document>(table).drop_index(name.as_str(), None).await?;
+        }
+        IndexChange::Rebuild { table, name, .. } => {
+            let collection = database.collection::<Document>(table);
+            let models: Vec<IndexModel> = collection.list_indexes(None).await?.try_collect().await?;
+            let model = models
+                .into_iter()
+                .find(|m| m.options.as_ref().and_then(|o| o.name.as_deref()) == Some(name.as_str()))
+                .ok_or_else(|| AppError::InvalidConfiguration(format!("Index {} not found on {}", name, table)))?;
+            collection.drop_index(name.as_str(), None).await?;
+            collection.create_index(model, None).await?;
+        }
+    }
+    Ok(())
+}
+
+pub async fn apply_index_change(connection: &DatabaseConnection, change: &IndexChange) -> Result<IndexDdl, AppError> {
+    let ddl = index_ddl(connection, change).await?;
+    match connection {
+        DatabaseConnection::MongoDB(_client, database) => apply_mongodb(database, change).await?,
+        // CONCURRENTLY can't run inside a transaction, and each change is a single statement anyway
+        _ => connection.execute_ddl(&ddl.statements, false).await?,
+    }
+    Ok(ddl)
+}
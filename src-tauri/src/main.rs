@@ -4,13 +4,55 @@
 mod database;
 mod error;
 mod ai;
+mod safety;
+mod history;
+mod explain;
+mod export;
+mod values;
+mod import;
+mod dump;
+mod splitter;
+mod script;
+mod schema_diff;
+mod snapshots;
+mod data_diff;
+mod profile;
+mod fake_data;
+mod table_designer;
+mod index_manager;
+mod activity;
+mod storage;
+mod users;
+mod sqlite_maintenance;
+mod redis_browser;
 
-use database::{DatabaseConnection, DatabaseType, QueryResult, DatabaseSchema as DbSchema};
+use database::{DatabaseConnection, DatabaseType, QueryResult, DatabaseSchema as DbSchema, PlanComparison, SqliteOptions, ServerInfo};
 use error::AppError;
 use serde::{Deserialize, Serialize};
 use std::sync::Mutex;
-use tauri::State;
-use ai::{AIService, ConsultRequest, GenerateSQLRequest, GenerateDiagramRequest, GenerateInsightsRequest};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager, State};
+use ai::{AIService, ConsultRequest, GenerateSQLRequest, GenerateDiagramRequest, GenerateInsightsRequest, OptimizeQueryRequest, OptimizationSuggestion, TableOptimizationContext};
+use safety::{QueryClassification, StatementRisk};
+use splitter::SqlDialect;
+use explain::QueryPlan;
+use export::{ExportOptions, ExportProgress, ExportSummary};
+use dump::{DumpOptions, DumpProgress, DumpSummary};
+use schema_diff::{SchemaComparison, SchemaSource};
+use data_diff::{DataCompareOptions, DataCompareProgress, DataComparison, TableDataSide};
+use fake_data::{FakeDataOptions, FakeDataProgress, FakeDataSummary};
+use activity::ActivitySnapshot;
+use storage::StorageStats;
+use users::{UserChange, UserDdl, UserReport};
+use sqlite_maintenance::{AnalyzeSummary, BackupProgress, BackupSummary, CheckpointSummary, ForeignKeyReport, IntegrityReport, VacuumSummary};
+use redis_browser::{KeyPage, KeyValue, KeyWrite, RedisKey};
+use index_manager::{IndexChange, IndexDdl, IndexReport};
+use table_designer::{TableDdl, TableDesign};
+use profile::{ProfileOptions, TableProfile};
+use snapshots::{SchemaSnapshot, SchemaSnapshots, SnapshotSummary};
+use script::{RunSqlFileOptions, SqlFileProgress, SqlFileSummary, SqlStatementError};
+use import::{ImportOptions, ImportPreview, ImportProgress, ImportSummary};
+use history::{HistoryEntry, HistoryFilter, HistoryRetention, NewHistoryEntry, QueryHistory, SavedSnippet};
 
 // Application state
 
@@ -22,6 +64,21 @@ pub struct DatabaseConfig {
     pub password: String,
     pub database: String,
     pub db_type: DatabaseType,
+    #[serde(default)]
+    pub read_only: bool,
+    // Only used for SQLite connections
+    #[serde(default)]
+    pub sqlite: SqliteOptions,
+}
+
+impl DatabaseConfig {
+    // Stable identifier used to group history entries and snapshots per connection
+    pub fn connection_label(&self) -> String {
+        format!(
+            "{}://{}@{}:{}/{}",
+            self.db_type.as_str(), self.username, self.host, self.port, self.database
+        )
+    }
 }
 
 // Use the one from database module
@@ -46,6 +103,9 @@ pub struct AppData {
     pub db_connection: Option<DatabaseConnection>,
     pub db_config: Option<DatabaseConfig>,
     pub ai_config: Option<ai::AIProviderConfig>,
+    pub history: Option<QueryHistory>,
+    pub snapshots: Option<SchemaSnapshots>,
+    pub activity_monitor: Option<tauri::async_runtime::JoinHandle<()>>,
 }
 
 // Tauri commands
@@ -80,35 +140,856 @@ async fn get_database_schema(state: State<'_, Mutex<AppData>>) -> Result<DbSchem
     connection.get_schema().await
 }
 
+#[tauri::command]
+fn get_server_info(state: State<'_, Mutex<AppData>>) -> Result<Option<ServerInfo>, AppError> {
+    let app_data = state.lock().unwrap();
+    let connection = app_data
+        .db_connection
+        .as_ref()
+        .ok_or(AppError::DatabaseNotConnected)?;
+    Ok(connection.server_info().cloned())
+}
+
 #[tauri::command]
 async fn execute_query(
     query: String,
+    confirmed: Option<bool>,
+    ai_generated: Option<bool>,
     state: State<'_, Mutex<AppData>>,
 ) -> Result<QueryResult, AppError> {
     println!("Tauri execute_query called with: {}", query);
     
-    let connection = {
+    let (connection, config, history) = {
         let app_data = state.lock().unwrap();
-        app_data
+        let connection = app_data
             .db_connection
             .as_ref()
             .ok_or_else(|| {
                 println!("Database not connected");
                 AppError::DatabaseNotConnected
             })?
-            .clone()
+            .clone();
+        (connection, app_data.db_config.clone(), app_data.history.clone())
     };
+    let read_only = config.as_ref().map(|c| c.read_only).unwrap_or(false);
+    let dialect = SqlDialect::from(&connection.db_type());
+    
+    match connection {
+        DatabaseConnection::MongoDB(..) => safety::check_mongodb_command(&query, read_only)?,
+        DatabaseConnection::Redis(_) => redis_browser::check_command(&connection, &query, read_only, confirmed.unwrap_or(false)).await?,
+        DatabaseConnection::SQLServer(_) => {
+            safety::check_query(&query, dialect, read_only, confirmed.unwrap_or(false))?;
+            if read_only {
+                safety::check_tsql_read_only(&query)?;
+            }
+        }
+        _ => {
+            safety::check_query(&query, dialect, read_only, confirmed.unwrap_or(false))?;
+        }
+    }
     
     println!("Calling connection.execute_query");
+    let started = Instant::now();
     let result = connection.execute_query(&query).await;
+    let duration_ms = started.elapsed().as_millis() as i64;
     match &result {
         Ok(query_result) => println!("Query executed successfully, {} rows returned", query_result.row_count),
         Err(e) => println!("Query execution failed: {}", e),
     }
     
+    if let (Some(history), Some(config)) = (history, config) {
+        let entry = NewHistoryEntry {
+            connection: config.connection_label(),
+            db_type: config.db_type.as_str().to_string(),
+            query: query.clone(),
+            duration_ms,
+            row_count: result.as_ref().ok().map(|r| r.row_count as i64),
+            error: result.as_ref().err().map(|e| e.to_string()),
+            ai_generated: ai_generated.unwrap_or(false),
+        };
+        // A history failure must never hide the query result from the user
+        if let Err(e) = history.record(entry).await {
+            println!("Failed to record query history: {}", e);
+        }
+    }
+    
+    result
+}
+
+// EXPLAIN ANALYZE runs the statement, and SQLite executes every statement handed to
+// EXPLAIN QUERY PLAN, so plans get the same checks as execute_query and one statement only
+fn check_explain(connection: &DatabaseConnection, query: &str, read_only: bool, confirmed: bool) -> Result<(), AppError> {
+    if let DatabaseConnection::MongoDB(..) = connection {
+        return safety::check_mongodb_command(query, read_only);
+    }
+    let dialect = SqlDialect::from(&connection.db_type());
+    if safety::split_statements(query, dialect).len() > 1 {
+        return Err(AppError::QueryExecutionFailed("Only a single statement can be explained".to_string()));
+    }
+    safety::check_query(query, dialect, read_only, confirmed)?;
+    Ok(())
+}
+
+#[tauri::command]
+async fn explain_query(
+    query: String,
+    analyze: Option<bool>,
+    confirmed: Option<bool>,
+    state: State<'_, Mutex<AppData>>,
+) -> Result<QueryPlan, AppError> {
+    let (connection, config) = {
+        let app_data = state.lock().unwrap();
+        let connection = app_data
+            .db_connection
+            .as_ref()
+            .ok_or(AppError::DatabaseNotConnected)?
+            .clone();
+        (connection, app_data.db_config.clone())
+    };
+    let read_only = config.as_ref().map(|c| c.read_only).unwrap_or(false);
+    check_explain(&connection, &query, read_only, confirmed.unwrap_or(false))?;
+    
+    connection.explain_query(&query, analyze.unwrap_or(true)).await
+}
+
+#[tauri::command]
+async fn export_query(
+    query: String,
+    options: ExportOptions,
+    app: AppHandle,
+    state: State<'_, Mutex<AppData>>,
+) -> Result<ExportSummary, AppError> {
+    let connection = {
+        let app_data = state.lock().unwrap();
+        app_data
+            .db_connection
+            .as_ref()
+            .ok_or(AppError::DatabaseNotConnected)?
+            .clone()
+    };
+    
+    // Exports re-run the query, so anything that could modify data is refused
+    if !matches!(connection, DatabaseConnection::MongoDB(..))
+        && safety::classify_query(&query, SqlDialect::from(&connection.db_type())).risk != StatementRisk::ReadOnly
+    {
+        return Err(AppError::QueryExecutionFailed("Only read-only queries can be exported".to_string()));
+    }
+    if matches!(connection, DatabaseConnection::SQLServer(_)) {
+        safety::check_tsql_read_only(&query)
+            .map_err(|_| AppError::QueryExecutionFailed("Only read-only queries can be exported".to_string()))?;
+    }
+    
+    export::export_query(&connection, &query, &options, move |progress: ExportProgress| {
+        let _ = app.emit_all("export-progress", progress);
+    }).await
+}
+
+#[tauri::command]
+async fn preview_import(
+    options: ImportOptions,
+    state: State<'_, Mutex<AppData>>,
+) -> Result<ImportPreview, AppError> {
+    let connection = {
+        let app_data = state.lock().unwrap();
+        app_data
+            .db_connection
+            .as_ref()
+            .ok_or(AppError::DatabaseNotConnected)?
+            .clone()
+    };
+    
+    import::preview_import(&connection, &options).await
+}
+
+#[tauri::command]
+async fn import_file(
+    options: ImportOptions,
+    app: AppHandle,
+    state: State<'_, Mutex<AppData>>,
+) -> Result<ImportSummary, AppError> {
+    let (connection, read_only) = {
+        let app_data = state.lock().unwrap();
+        let connection = app_data
+            .db_connection
+            .as_ref()
+            .ok_or(AppError::DatabaseNotConnected)?
+            .clone();
+        let read_only = app_data.db_config.as_ref().map(|c| c.read_only).unwrap_or(false);
+        (connection, read_only)
+    };
+    
+    if read_only {
+        return Err(AppError::ReadOnlyViolation("imports are not allowed on a read-only connection".to_string()));
+    }
+    
+    import::import_file(&connection, &options, move |progress: ImportProgress| {
+        let _ = app.emit_all("import-progress", progress);
+    }).await
+}
+
+#[tauri::command]
+async fn dump_database(
+    options: DumpOptions,
+    app: AppHandle,
+    state: State<'_, Mutex<AppData>>,
+) -> Result<DumpSummary, AppError> {
+    let connection = {
+        let app_data = state.lock().unwrap();
+        app_data
+            .db_connection
+            .as_ref()
+            .ok_or(AppError::DatabaseNotConnected)?
+            .clone()
+    };
+    
+    dump::dump_database(&connection, &options, move |progress: DumpProgress| {
+        let _ = app.emit_all("dump-progress", progress);
+    }).await
+}
+
+#[tauri::command]
+async fn run_sql_file(
+    options: RunSqlFileOptions,
+    app: AppHandle,
+    state: State<'_, Mutex<AppData>>,
+) -> Result<SqlFileSummary, AppError> {
+    let (connection, read_only) = {
+        let app_data = state.lock().unwrap();
+        let connection = app_data
+            .db_connection
+            .as_ref()
+            .ok_or(AppError::DatabaseNotConnected)?
+            .clone();
+        let read_only = app_data.db_config.as_ref().map(|c| c.read_only).unwrap_or(false);
+        (connection, read_only)
+    };
+    
+    if read_only {
+        return Err(AppError::ReadOnlyViolation("SQL files can't be run on a read-only connection".to_string()));
+    }
+    
+    let error_app = app.clone();
+    script::run_sql_file(
+        &connection,
+        &options,
+        move |progress: SqlFileProgress| {
+            let _ = app.emit_all("sql-file-progress", progress);
+        },
+        move |error: SqlStatementError| {
+            let _ = error_app.emit_all("sql-file-error", error);
+        },
+    ).await
+}
+
+#[tauri::command]
+async fn generate_fake_data(
+    options: FakeDataOptions,
+    app: AppHandle,
+    state: State<'_, Mutex<AppData>>,
+) -> Result<FakeDataSummary, AppError> {
+    let (connection, read_only) = {
+        let app_data = state.lock().unwrap();
+        let connection = app_data
+            .db_connection
+            .as_ref()
+            .ok_or(AppError::DatabaseNotConnected)?
+            .clone();
+        let read_only = app_data.db_config.as_ref().map(|c| c.read_only).unwrap_or(false);
+        (connection, read_only)
+    };
+    
+    if read_only {
+        return Err(AppError::ReadOnlyViolation("Fake data can't be inserted on a read-only connection".to_string()));
+    }
+    
+    fake_data::generate_fake_data(&connection, &options, move |progress: FakeDataProgress| {
+        let _ = app.emit_all("fake-data-progress", progress);
+    }).await
+}
+
+// Table designer commands
+#[tauri::command]
+async fn get_table_design(table: String, state: State<'_, Mutex<AppData>>) -> Result<TableDesign, AppError> {
+    let connection = {
+        let app_data = state.lock().unwrap();
+        app_data.db_connection.as_ref().ok_or(AppError::DatabaseNotConnected)?.clone()
+    };
+    
+    table_designer::get_table_design(&connection, &table).await
+}
+
+#[tauri::command]
+async fn preview_table_ddl(design: TableDesign, state: State<'_, Mutex<AppData>>) -> Result<TableDdl, AppError> {
+    let connection = {
+        let app_data = state.lock().unwrap();
+        app_data.db_connection.as_ref().ok_or(AppError::DatabaseNotConnected)?.clone()
+    };
+    
+    table_designer::generate_ddl(&connection, &design).await
+}
+
+#[tauri::command]
+async fn apply_table_design(design: TableDesign, state: State<'_, Mutex<AppData>>) -> Result<TableDdl, AppError> {
+    let (connection, read_only) = {
+        let app_data = state.lock().unwrap();
+        let connection = app_data
+            .db_connection
+            .as_ref()
+            .ok_or(AppError::DatabaseNotConnected)?
+            .clone();
+        let read_only = app_data.db_config.as_ref().map(|c| c.read_only).unwrap_or(false);
+        (connection, read_only)
+    };
+    
+    if read_only {
+        return Err(AppError::ReadOnlyViolation("Tables can't be changed on a read-only connection".to_string()));
+    }
+    
+    let ddl = table_designer::generate_ddl(&connection, &design).await?;
+    connection.execute_ddl(&ddl.statements, true).await?;
+    Ok(ddl)
+}
+
+// Index management commands
+#[tauri::command]
+async fn get_index_stats(table: Option<String>, state: State<'_, Mutex<AppData>>) -> Result<IndexReport, AppError> {
+    let connection = {
+        let app_data = state.lock().unwrap();
+        app_data.db_connection.as_ref().ok_or(AppError::DatabaseNotConnected)?.clone()
+    };
+    
+    index_manager::index_stats(&connection, table.as_deref()).await
+}
+
+#[tauri::command]
+async fn preview_index_change(change: IndexChange, state: State<'_, Mutex<AppData>>) -> Result<IndexDdl, AppError> {
+    let connection = {
+        let app_data = state.lock().unwrap();
+        app_data.db_connection.as_ref().ok_or(AppError::DatabaseNotConnected)?.clone()
+    };
+    
+    index_manager::index_ddl(&connection, &change).await
+}
+
+#[tauri::command]
+async fn apply_index_change(change: IndexChange, state: State<'_, Mutex<AppData>>) -> Result<IndexDdl, AppError> {
+    let (connection, read_only) = {
+        let app_data = state.lock().unwrap();
+        let connection = app_data
+            .db_connection
+            .as_ref()
+            .ok_or(AppError::DatabaseNotConnected)?
+            .clone();
+        let read_only = app_data.db_config.as_ref().map(|c| c.read_only).unwrap_or(false);
+        (connection, read_only)
+    };
+    
+    if read_only {
+        return Err(AppError::ReadOnlyViolation("Indexes can't be changed on a read-only connection".to_string()));
+    }
+    
+    index_manager::apply_index_change(&connection, &change).await
+}
+
+#[tauri::command]
+async fn get_storage_stats(state: State<'_, Mutex<AppData>>) -> Result<StorageStats, AppError> {
+    let connection = {
+        let app_data = state.lock().unwrap();
+        app_data.db_connection.as_ref().ok_or(AppError::DatabaseNotConnected)?.clone()
+    };
+    
+    storage::storage_stats(&connection).await
+}
+
+// User and privilege commands
+#[tauri::command]
+async fn list_users(state: State<'_, Mutex<AppData>>) -> Result<UserReport, AppError> {
+    let connection = {
+        let app_data = state.lock().unwrap();
+        app_data.db_connection.as_ref().ok_or(AppError::DatabaseNotConnected)?.clone()
+    };
+    
+    users::list_users(&connection).await
+}
+
+#[tauri::command]
+async fn preview_user_change(change: UserChange, state: State<'_, Mutex<AppData>>) -> Result<UserDdl, AppError> {
+    let connection = {
+        let app_data = state.lock().unwrap();
+        app_data.db_connection.as_ref().ok_or(AppError::DatabaseNotConnected)?.clone()
+    };
+    
+    users::user_ddl(&connection, &change).await
+}
+
+#[tauri::command]
+async fn apply_user_change(change: UserChange, state: State<'_, Mutex<AppData>>) -> Result<UserDdl, AppError> {
+    let (connection, read_only) = {
+        let app_data = state.lock().unwrap();
+        let connection = app_data
+            .db_connection
+            .as_ref()
+            .ok_or(AppError::DatabaseNotConnected)?
+            .clone();
+        let read_only = app_data.db_config.as_ref().map(|c| c.read_only).unwrap_or(false);
+        (connection, read_only)
+    };
+    
+    if read_only {
+        return Err(AppError::ReadOnlyViolation("Users and privileges can't be changed on a read-only connection".to_string()));
+    }
+    
+    users::apply_user_change(&connection, &change).await
+}
+
+// SQLite maintenance commands
+#[tauri::command]
+async fn sqlite_integrity_check(
+    schema: Option<String>,
+    quick: Option<bool>,
+    state: State<'_, Mutex<AppData>>,
+) -> Result<IntegrityReport, AppError> {
+    let connection = {
+        let app_data = state.lock().unwrap();
+        app_data.db_connection.as_ref().ok_or(AppError::DatabaseNotConnected)?.clone()
+    };
+    
+    sqlite_maintenance::integrity_check(&connection, schema.as_deref(), quick.unwrap_or(false)).await
+}
+
+#[tauri::command]
+async fn sqlite_foreign_key_check(schema: Option<String>, state: State<'_, Mutex<AppData>>) -> Result<ForeignKeyReport, AppError> {
+    let connection = {
+        let app_data = state.lock().unwrap();
+        app_data.db_connection.as_ref().ok_or(AppError::DatabaseNotConnected)?.clone()
+    };
+    
+    sqlite_maintenance::foreign_key_check(&connection, schema.as_deref()).await
+}
+
+// Without a destination the database is rebuilt in place; VACUUM INTO leaves it untouched
+#[tauri::command]
+async fn sqlite_vacuum(
+    schema: Option<String>,
+    destination: Option<String>,
+    overwrite: Option<bool>,
+    state: State<'_, Mutex<AppData>>,
+) -> Result<VacuumSummary, AppError> {
+    let (connection, read_only) = {
+        let app_data = state.lock().unwrap();
+        let connection = app_data
+            .db_connection
+            .as_ref()
+            .ok_or(AppError::DatabaseNotConnected)?
+            .clone();
+        let read_only = app_data.db_config.as_ref().map(|c| c.read_only).unwrap_or(false);
+        (connection, read_only)
+    };
+    
+    if read_only && destination.is_none() {
+        return Err(AppError::ReadOnlyViolation("VACUUM rewrites the database; use VACUUM INTO on a read-only connection".to_string()));
+    }
+    
+    sqlite_maintenance::vacuum(&connection, schema.as_deref(), destination.as_deref(), overwrite.unwrap_or(false)).await
+}
+
+#[tauri::command]
+async fn sqlite_analyze(target: Option<String>, state: State<'_, Mutex<AppData>>) -> Result<AnalyzeSummary, AppError> {
+    let (connection, read_only) = {
+        let app_data = state.lock().unwrap();
+        let connection = app_data
+            .db_connection
+            .as_ref()
+            .ok_or(AppError::DatabaseNotConnected)?
+            .clone();
+        let read_only = app_data.db_config.as_ref().map(|c| c.read_only).unwrap_or(false);
+        (connection, read_only)
+    };
+    
+    if read_only {
+        return Err(AppError::ReadOnlyViolation("ANALYZE writes statistics tables and isn't allowed on a read-only connection".to_string()));
+    }
+    
+    sqlite_maintenance::analyze(&connection, target.as_deref()).await
+}
+
+#[tauri::command]
+async fn sqlite_wal_checkpoint(mode: Option<String>, state: State<'_, Mutex<AppData>>) -> Result<CheckpointSummary, AppError> {
+    let (connection, read_only) = {
+        let app_data = state.lock().unwrap();
+        let connection = app_data
+            .db_connection
+            .as_ref()
+            .ok_or(AppError::DatabaseNotConnected)?
+            .clone();
+        let read_only = app_data.db_config.as_ref().map(|c| c.read_only).unwrap_or(false);
+        (connection, read_only)
+    };
+    
+    if read_only {
+        return Err(AppError::ReadOnlyViolation("Checkpoints write to the database and aren't allowed on a read-only connection".to_string()));
+    }
+    
+    sqlite_maintenance::wal_checkpoint(&connection, mode.as_deref()).await
+}
+
+#[tauri::command]
+async fn sqlite_backup(
+    destination: String,
+    schema: Option<String>,
+    overwrite: Option<bool>,
+    app: AppHandle,
+    state: State<'_, Mutex<AppData>>,
+) -> Result<BackupSummary, AppError> {
+    let connection = {
+        let app_data = state.lock().unwrap();
+        app_data.db_connection.as_ref().ok_or(AppError::DatabaseNotConnected)?.clone()
+    };
+    
+    sqlite_maintenance::backup(&connection, schema.as_deref(), &destination, overwrite.unwrap_or(false), move |progress: BackupProgress| {
+        let _ = app.emit_all("sqlite-backup-progress", progress);
+    }).await
+}
+
+// Redis key browser commands
+#[tauri::command]
+async fn redis_scan_keys(
+    pattern: Option<String>,
+    key_type: Option<String>,
+    cursor: Option<String>,
+    count: Option<usize>,
+    state: State<'_, Mutex<AppData>>,
+) -> Result<KeyPage, AppError> {
+    let connection = {
+        let app_data = state.lock().unwrap();
+        app_data.db_connection.as_ref().ok_or(AppError::DatabaseNotConnected)?.clone()
+    };
+    
+    redis_browser::scan_keys(&connection, pattern.as_deref(), key_type.as_deref(), cursor.as_deref(), count).await
+}
+
+#[tauri::command]
+async fn redis_read_key(
+    key: String,
+    cursor: Option<String>,
+    page_size: Option<usize>,
+    state: State<'_, Mutex<AppData>>,
+) -> Result<KeyValue, AppError> {
+    let connection = {
+        let app_data = state.lock().unwrap();
+        app_data.db_connection.as_ref().ok_or(AppError::DatabaseNotConnected)?.clone()
+    };
+    
+    redis_browser::read_key(&connection, &key, cursor.as_deref(), page_size).await
+}
+
+#[tauri::command]
+async fn redis_write_key(change: KeyWrite, state: State<'_, Mutex<AppData>>) -> Result<RedisKey, AppError> {
+    let (connection, read_only) = {
+        let app_data = state.lock().unwrap();
+        let connection = app_data
+            .db_connection
+            .as_ref()
+            .ok_or(AppError::DatabaseNotConnected)?
+            .clone();
+        let read_only = app_data.db_config.as_ref().map(|c| c.read_only).unwrap_or(false);
+        (connection, read_only)
+    };
+    
+    if read_only {
+        return Err(AppError::ReadOnlyViolation("Keys can't be written on a read-only connection".to_string()));
+    }
+    
+    redis_browser::write_key(&connection, &change).await
+}
+
+#[tauri::command]
+async fn redis_delete_keys(keys: Vec<String>, state: State<'_, Mutex<AppData>>) -> Result<i64, AppError> {
+    let (connection, read_only) = {
+        let app_data = state.lock().unwrap();
+        let connection = app_data
+            .db_connection
+            .as_ref()
+            .ok_or(AppError::DatabaseNotConnected)?
+            .clone();
+        let read_only = app_data.db_config.as_ref().map(|c| c.read_only).unwrap_or(false);
+        (connection, read_only)
+    };
+    
+    if read_only {
+        return Err(AppError::ReadOnlyViolation("Keys can't be deleted on a read-only connection".to_string()));
+    }
+    
+    redis_browser::delete_keys(&connection, &keys).await
+}
+
+// Without ttl_seconds the expiry is removed
+#[tauri::command]
+async fn redis_set_ttl(key: String, ttl_seconds: Option<i64>, state: State<'_, Mutex<AppData>>) -> Result<RedisKey, AppError> {
+    let (connection, read_only) = {
+        let app_data = state.lock().unwrap();
+        let connection = app_data
+            .db_connection
+            .as_ref()
+            .ok_or(AppError::DatabaseNotConnected)?
+            .clone();
+        let read_only = app_data.db_config.as_ref().map(|c| c.read_only).unwrap_or(false);
+        (connection, read_only)
+    };
+    
+    if read_only {
+        return Err(AppError::ReadOnlyViolation("Expiry times can't be changed on a read-only connection".to_string()));
+    }
+    
+    redis_browser::set_ttl(&connection, &key, ttl_seconds).await
+}
+
+// Activity monitor commands
+#[tauri::command]
+async fn get_activity(include_idle: Option<bool>, state: State<'_, Mutex<AppData>>) -> Result<ActivitySnapshot, AppError> {
+    let connection = {
+        let app_data = state.lock().unwrap();
+        app_data.db_connection.as_ref().ok_or(AppError::DatabaseNotConnected)?.clone()
+    };
+    
+    activity::activity_snapshot(&connection, include_idle.unwrap_or(false)).await
+}
+
+#[tauri::command]
+async fn terminate_session(
+    id: String,
+    cancel_only: Option<bool>,
+    state: State<'_, Mutex<AppData>>,
+) -> Result<(), AppError> {
+    let (connection, read_only) = {
+        let app_data = state.lock().unwrap();
+        let connection = app_data
+            .db_connection
+            .as_ref()
+            .ok_or(AppError::DatabaseNotConnected)?
+            .clone();
+        let read_only = app_data.db_config.as_ref().map(|c| c.read_only).unwrap_or(false);
+        (connection, read_only)
+    };
+    
+    if read_only {
+        return Err(AppError::ReadOnlyViolation("Sessions can't be terminated from a read-only connection".to_string()));
+    }
+    
+    activity::terminate_session(&connection, &id, cancel_only.unwrap_or(false)).await
+}
+
+// Emits "activity-snapshot" every interval until stopped or disconnected
+#[tauri::command]
+async fn start_activity_monitor(
+    interval_ms: Option<u64>,
+    include_idle: Option<bool>,
+    app: AppHandle,
+    state: State<'_, Mutex<AppData>>,
+) -> Result<(), AppError> {
+    let interval = Duration::from_millis(
+        interval_ms
+            .unwrap_or(activity::DEFAULT_POLL_INTERVAL_MS)
+            .max(activity::MIN_POLL_INTERVAL_MS),
+    );
+    let include_idle = include_idle.unwrap_or(false);
+    
+    let monitor = tauri::async_runtime::spawn(async move {
+        loop {
+            let connection = {
+                let state = app.state::<Mutex<AppData>>();
+                let app_data = state.lock().unwrap();
+                app_data.db_connection.clone()
+            };
+            let result = match connection {
+                Some(connection) => activity::activity_snapshot(&connection, include_idle).await,
+                None => Err(AppError::DatabaseNotConnected),
+            };
+            match result {
+                Ok(snapshot) => {
+                    let _ = app.emit_all("activity-snapshot", snapshot);
+                }
+                Err(e) => {
+                    let _ = app.emit_all("activity-error", e.to_string());
+                }
+            }
+            tokio::time::sleep(interval).await;
+        }
+    });
+    
+    let mut app_data = state.lock().unwrap();
+    if let Some(previous) = app_data.activity_monitor.replace(monitor) {
+        previous.abort();
+    }
+    Ok(())
+}
+
+#[tauri::command]
+async fn stop_activity_monitor(state: State<'_, Mutex<AppData>>) -> Result<(), AppError> {
+    let mut app_data = state.lock().unwrap();
+    if let Some(monitor) = app_data.activity_monitor.take() {
+        monitor.abort();
+    }
+    Ok(())
+}
+
+#[tauri::command]
+async fn compare_schemas(
+    from: SchemaSource,
+    to: SchemaSource,
+    dialect: Option<DatabaseType>,
+    state: State<'_, Mutex<AppData>>,
+) -> Result<SchemaComparison, AppError> {
+    let (connection, snapshots) = {
+        let app_data = state.lock().unwrap();
+        (app_data.db_connection.clone(), app_data.snapshots.clone())
+    };
+    
+    schema_diff::compare(&from, &to, dialect, connection.as_ref(), snapshots.as_ref()).await
+}
+
+fn schema_snapshots(state: &State<'_, Mutex<AppData>>) -> Result<SchemaSnapshots, AppError> {
+    let app_data = state.lock().unwrap();
+    app_data
+        .snapshots
+        .clone()
+        .ok_or_else(|| AppError::InternalError("Schema snapshots are not available".to_string()))
+}
+
+// Open the connection for one side of a data comparison; the bool says it must be closed afterwards
+async fn comparison_connection(
+    side: &TableDataSide,
+    current: Option<&DatabaseConnection>,
+) -> Result<(DatabaseConnection, bool), AppError> {
+    match &side.connection {
+        Some(config) => Ok((DatabaseConnection::new(config).await?, true)),
+        None => Ok((current.ok_or(AppError::DatabaseNotConnected)?.clone(), false)),
+    }
+}
+
+#[tauri::command]
+async fn compare_table_data(
+    source: TableDataSide,
+    target: TableDataSide,
+    options: Option<DataCompareOptions>,
+    app: AppHandle,
+    state: State<'_, Mutex<AppData>>,
+) -> Result<DataComparison, AppError> {
+    let current = {
+        let app_data = state.lock().unwrap();
+        app_data.db_connection.clone()
+    };
+    
+    let (source_connection, close_source) = comparison_connection(&source, current.as_ref()).await?;
+    let (target_connection, close_target) = comparison_connection(&target, current.as_ref()).await?;
+    
+    let result = data_diff::compare_table_data(
+        &source_connection,
+        &source.table,
+        &target_connection,
+        &target.table,
+        &options.unwrap_or_default(),
+        move |progress: DataCompareProgress| {
+            let _ = app.emit_all("data-compare-progress", progress);
+        },
+    ).await;
+    
+    if close_source {
+        source_connection.close().await;
+    }
+    if close_target {
+        target_connection.close().await;
+    }
     result
 }
 
+// Schema snapshot commands
+#[tauri::command]
+async fn capture_schema_snapshot(
+    label: Option<String>,
+    state: State<'_, Mutex<AppData>>,
+) -> Result<SnapshotSummary, AppError> {
+    let (connection, config) = {
+        let app_data = state.lock().unwrap();
+        let connection = app_data
+            .db_connection
+            .as_ref()
+            .ok_or(AppError::DatabaseNotConnected)?
+            .clone();
+        let config = app_data.db_config.clone().ok_or(AppError::DatabaseNotConnected)?;
+        (connection, config)
+    };
+    let snapshots = schema_snapshots(&state)?;
+    
+    let schema = connection.get_schema().await?;
+    snapshots
+        .capture(&config.connection_label(), &config.db_type, label.as_deref(), &schema)
+        .await
+}
+
+#[tauri::command]
+async fn list_schema_snapshots(
+    connection: Option<String>,
+    state: State<'_, Mutex<AppData>>,
+) -> Result<Vec<SnapshotSummary>, AppError> {
+    schema_snapshots(&state)?.list(connection.as_deref()).await
+}
+
+#[tauri::command]
+async fn get_schema_snapshot(id: i64, state: State<'_, Mutex<AppData>>) -> Result<SchemaSnapshot, AppError> {
+    schema_snapshots(&state)?.get(id).await
+}
+
+#[tauri::command]
+async fn delete_schema_snapshot(id: i64, state: State<'_, Mutex<AppData>>) -> Result<(), AppError> {
+    schema_snapshots(&state)?.delete(id).await
+}
+
+// Drift since the snapshot: `from` is the snapshot, `to` the live schema
+#[tauri::command]
+async fn diff_schema_snapshot(id: i64, state: State<'_, Mutex<AppData>>) -> Result<SchemaComparison, AppError> {
+    let connection = {
+        let app_data = state.lock().unwrap();
+        app_data
+            .db_connection
+            .as_ref()
+            .ok_or(AppError::DatabaseNotConnected)?
+            .clone()
+    };
+    let snapshots = schema_snapshots(&state)?;
+    
+    schema_diff::compare(
+        &SchemaSource::Snapshot(id),
+        &SchemaSource::Current,
+        None,
+        Some(&connection),
+        Some(&snapshots),
+    ).await
+}
+
+#[tauri::command]
+async fn profile_table(
+    table: String,
+    options: Option<ProfileOptions>,
+    state: State<'_, Mutex<AppData>>,
+) -> Result<TableProfile, AppError> {
+    let connection = {
+        let app_data = state.lock().unwrap();
+        app_data.db_connection.as_ref().ok_or(AppError::DatabaseNotConnected)?.clone()
+    };
+    
+    profile::profile_table(&connection, &table, &options.unwrap_or_default()).await
+}
+
+#[tauri::command]
+async fn classify_query(query: String, state: State<'_, Mutex<AppData>>) -> Result<QueryClassification, AppError> {
+    let dialect = {
+        let app_data = state.lock().unwrap();
+        app_data.db_connection.as_ref().map(|c| SqlDialect::from(&c.db_type())).unwrap_or(SqlDialect::Generic)
+    };
+    Ok(safety::classify_query(&query, dialect))
+}
+
 #[tauri::command]
 async fn set_ai_config(
     config: ai::AIProviderConfig,
@@ -136,6 +1017,9 @@ async fn disconnect_database(state: State<'_, Mutex<AppData>>) -> Result<String,
     let mut app_data = state.lock().unwrap();
     app_data.db_connection = None;
     app_data.db_config = None;
+    if let Some(monitor) = app_data.activity_monitor.take() {
+        monitor.abort();
+    }
     Ok("Database disconnected".to_string())
 }
 
@@ -151,6 +1035,41 @@ async fn list_databases(state: State<'_, Mutex<AppData>>) -> Result<Vec<String>,
     Ok(databases)
 }
 
+// Switch the active database, keeping db_config in sync
+#[tauri::command]
+async fn use_database(database: String, state: State<'_, Mutex<AppData>>) -> Result<String, AppError> {
+    let database = database.trim().to_string();
+    if database.is_empty() {
+        return Err(AppError::InvalidConfiguration("Database name is required".to_string()));
+    }
+    let (connection, config) = {
+        let app_data = state.lock().unwrap();
+        let connection = app_data.db_connection.clone().ok_or(AppError::DatabaseNotConnected)?;
+        let config = app_data.db_config.clone().ok_or(AppError::DatabaseNotConnected)?;
+        (connection, config)
+    };
+    
+    let switched = connection.use_database(&config, &database).await?;
+    {
+        let mut app_data = state.lock().unwrap();
+        // Disconnected or reconnected elsewhere while the new pool was opening
+        let current = app_data.db_config.as_ref().map(|c| c.connection_label());
+        if current != Some(config.connection_label()) {
+            drop(app_data);
+            tauri::async_runtime::spawn(async move { switched.close().await });
+            return Err(AppError::DatabaseNotConnected);
+        }
+        app_data.db_connection = Some(switched);
+        if let Some(config) = app_data.db_config.as_mut() {
+            config.database = database.clone();
+        }
+    }
+    // Let queries still running on the old pool finish before it closes
+    tauri::async_runtime::spawn(async move { connection.close().await });
+    
+    Ok(format!("Using database {}", database))
+}
+
 #[tauri::command]
 async fn list_collections(state: State<'_, Mutex<AppData>>) -> Result<Vec<String>, AppError> {
     let connection = {
@@ -163,6 +1082,85 @@ async fn list_collections(state: State<'_, Mutex<AppData>>) -> Result<Vec<String
     Ok(collections)
 }
 
+fn query_history(state: &State<'_, Mutex<AppData>>) -> Result<QueryHistory, AppError> {
+    let app_data = state.lock().unwrap();
+    app_data
+        .history
+        .clone()
+        .ok_or_else(|| AppError::InternalError("Query history is not available".to_string()))
+}
+
+// Query history commands
+#[tauri::command]
+async fn get_query_history(
+    filter: Option<HistoryFilter>,
+    state: State<'_, Mutex<AppData>>,
+) -> Result<Vec<HistoryEntry>, AppError> {
+    let history = query_history(&state)?;
+    history.search(&filter.unwrap_or_default()).await
+}
+
+#[tauri::command]
+async fn set_history_favorite(
+    id: i64,
+    favorite: bool,
+    state: State<'_, Mutex<AppData>>,
+) -> Result<(), AppError> {
+    let history = query_history(&state)?;
+    history.set_favorite(id, favorite).await
+}
+
+#[tauri::command]
+async fn delete_history_entry(id: i64, state: State<'_, Mutex<AppData>>) -> Result<(), AppError> {
+    let history = query_history(&state)?;
+    history.delete_entry(id).await
+}
+
+#[tauri::command]
+async fn clear_query_history(
+    connection: Option<String>,
+    state: State<'_, Mutex<AppData>>,
+) -> Result<u64, AppError> {
+    let history = query_history(&state)?;
+    history.clear(connection.as_deref()).await
+}
+
+#[tauri::command]
+async fn save_snippet(snippet: SavedSnippet, state: State<'_, Mutex<AppData>>) -> Result<i64, AppError> {
+    let history = query_history(&state)?;
+    history.save_snippet(&snippet).await
+}
+
+#[tauri::command]
+async fn list_snippets(
+    search: Option<String>,
+    state: State<'_, Mutex<AppData>>,
+) -> Result<Vec<SavedSnippet>, AppError> {
+    let history = query_history(&state)?;
+    history.list_snippets(search.as_deref()).await
+}
+
+#[tauri::command]
+async fn delete_snippet(id: i64, state: State<'_, Mutex<AppData>>) -> Result<(), AppError> {
+    let history = query_history(&state)?;
+    history.delete_snippet(id).await
+}
+
+#[tauri::command]
+async fn get_history_retention(state: State<'_, Mutex<AppData>>) -> Result<HistoryRetention, AppError> {
+    let history = query_history(&state)?;
+    history.get_retention().await
+}
+
+#[tauri::command]
+async fn set_history_retention(
+    retention: HistoryRetention,
+    state: State<'_, Mutex<AppData>>,
+) -> Result<(), AppError> {
+    let history = query_history(&state)?;
+    history.set_retention(&retention).await
+}
+
 // AI Commands
 #[tauri::command]
 async fn consult_database(
@@ -176,12 +1174,35 @@ async fn consult_database(
 
 #[tauri::command]
 async fn generate_sql(
-    request: GenerateSQLRequest,
+    mut request: GenerateSQLRequest,
     state: State<'_, Mutex<AppData>>,
 ) -> Result<String, AppError> {
+    let (read_only, sql_dialect) = {
+        let app_data = state.lock().unwrap();
+        if request.dialect.is_none() {
+            // The live connection knows the exact server flavor and version
+            request.dialect = app_data
+                .db_connection
+                .as_ref()
+                .map(|c| c.dialect_name())
+                .or_else(|| app_data.db_config.as_ref().map(|c| c.db_type.dialect_name().to_string()));
+        }
+        let read_only = app_data.db_config.as_ref().map(|c| c.read_only).unwrap_or(false);
+        let sql_dialect = app_data.db_config.as_ref().map(|c| SqlDialect::from(&c.db_type)).unwrap_or(SqlDialect::Generic);
+        (read_only, sql_dialect)
+    };
+    
     let ai_service = AIService;
-    ai_service.generate_sql(request).await
-        .map_err(|e| AppError::AIError(e.to_string()))
+    let sql = ai_service.generate_sql(request).await
+        .map_err(|e| AppError::AIError(e.to_string()))?;
+    
+    // Generated SQL goes through the same guard as hand-written queries; destructive
+    // statements still need confirmation when they are executed
+    if read_only {
+        safety::check_query(&sql, sql_dialect, true, true)?;
+    }
+    
+    Ok(sql)
 }
 
 #[tauri::command]
@@ -204,6 +1225,95 @@ async fn generate_insights(
         .map_err(|e| AppError::AIError(e.to_string()))
 }
 
+#[tauri::command]
+async fn optimize_query(
+    request: OptimizeQueryRequest,
+    state: State<'_, Mutex<AppData>>,
+) -> Result<OptimizationSuggestion, AppError> {
+    let (connection, config) = {
+        let app_data = state.lock().unwrap();
+        let connection = app_data
+            .db_connection
+            .as_ref()
+            .ok_or(AppError::DatabaseNotConnected)?
+            .clone();
+        (connection, app_data.db_config.clone())
+    };
+    let read_only = config.as_ref().map(|c| c.read_only).unwrap_or(false);
+    let db_type = config.map(|c| c.db_type.as_str().to_string()).unwrap_or_default();
+    
+    check_explain(&connection, &request.query, read_only, false)?;
+    let plan = connection.explain_query(&request.query, true).await?;
+    
+    // Only tables mentioned in the query are sent as context
+    let words: Vec<String> = request.query
+        .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .map(|w| w.to_lowercase())
+        .collect();
+    let mut tables = Vec::new();
+    for table in connection.list_collections().await? {
+        if !words.contains(&table.to_lowercase()) {
+            continue;
+        }
+        tables.push(TableOptimizationContext {
+            indexes: connection.get_table_indexes(&table).await?,
+            row_count: connection.estimate_row_count(&table).await?,
+            name: table,
+        });
+    }
+    
+    let ai_service = AIService;
+    ai_service.optimize_query(&request.ai_config, &request.query, &db_type, &plan, &tables).await
+        .map_err(|e| AppError::AIError(e.to_string()))
+}
+
+#[tauri::command]
+async fn apply_query_optimization(
+    original_query: String,
+    rewritten_sql: Option<String>,
+    index_ddl: Vec<String>,
+    state: State<'_, Mutex<AppData>>,
+) -> Result<PlanComparison, AppError> {
+    let (connection, read_only) = {
+        let app_data = state.lock().unwrap();
+        let connection = app_data
+            .db_connection
+            .as_ref()
+            .ok_or(AppError::DatabaseNotConnected)?
+            .clone();
+        let read_only = app_data.db_config.as_ref().map(|c| c.read_only).unwrap_or(false);
+        (connection, read_only)
+    };
+    
+    // Suggestions come from the AI provider, so only plain reads and CREATE INDEX are accepted
+    // Each must be a single statement, so nothing can COMMIT out of the scratch transaction
+    let rewritten = rewritten_sql.unwrap_or_else(|| original_query.clone());
+    let dialect = SqlDialect::from(&connection.db_type());
+    let classification = safety::classify_query(&rewritten, dialect);
+    if classification.statements.len() != 1 || classification.risk != StatementRisk::ReadOnly {
+        return Err(AppError::QueryExecutionFailed("The rewritten query must be a single read-only statement".to_string()));
+    }
+    for ddl in &index_ddl {
+        let statements = safety::split_statements(ddl, dialect);
+        let leading: Vec<String> = statements
+            .first()
+            .map(|s| s.split_whitespace().take(3).map(|w| w.to_uppercase()).collect())
+            .unwrap_or_default();
+        let is_index = matches!(
+            leading.iter().map(String::as_str).collect::<Vec<_>>().as_slice(),
+            ["CREATE", "INDEX", ..] | ["CREATE", "UNIQUE", "INDEX"]
+        );
+        if statements.len() != 1 || !is_index {
+            return Err(AppError::QueryExecutionFailed(format!("Not an index definition: {}", ddl)));
+        }
+    }
+    if read_only && !index_ddl.is_empty() {
+        return Err(AppError::ReadOnlyViolation("indexes can't be tried out on a read-only connection".to_string()));
+    }
+    
+    connection.compare_plans(&original_query, &rewritten, &index_ddl).await
+}
+
 #[tauri::command]
 async fn validate_ai_key(
     config: ai::AIProviderConfig,
@@ -314,20 +1424,107 @@ async fn get_available_models(
 fn main() {
     tauri::Builder::default()
         .manage(Mutex::new(AppData::default()))
+        .setup(|app| {
+            let history_path = app
+                .path_resolver()
+                .app_data_dir()
+                .map(|dir| dir.join("query_history.db"));
+            
+            if let Some(path) = history_path {
+                match tauri::async_runtime::block_on(QueryHistory::open(&path)) {
+                    Ok(history) => {
+                        let state = app.state::<Mutex<AppData>>();
+                        state.lock().unwrap().history = Some(history);
+                    }
+                    Err(e) => println!("Failed to open query history: {}", e),
+                }
+            }
+            
+            let snapshots_path = app
+                .path_resolver()
+                .app_data_dir()
+                .map(|dir| dir.join("schema_snapshots.db"));
+            
+            if let Some(path) = snapshots_path {
+                match tauri::async_runtime::block_on(SchemaSnapshots::open(&path)) {
+                    Ok(snapshots) => {
+                        let state = app.state::<Mutex<AppData>>();
+                        state.lock().unwrap().snapshots = Some(snapshots);
+                    }
+                    Err(e) => println!("Failed to open schema snapshots: {}", e),
+                }
+            }
+            
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             connect_database,
             get_database_schema,
+            get_server_info,
             execute_query,
+            explain_query,
+            export_query,
+            preview_import,
+            import_file,
+            dump_database,
+            run_sql_file,
+            generate_fake_data,
+            get_table_design,
+            preview_table_ddl,
+            apply_table_design,
+            get_index_stats,
+            preview_index_change,
+            apply_index_change,
+            get_activity,
+            terminate_session,
+            start_activity_monitor,
+            stop_activity_monitor,
+            get_storage_stats,
+            list_users,
+            preview_user_change,
+            apply_user_change,
+            sqlite_integrity_check,
+            sqlite_foreign_key_check,
+            sqlite_vacuum,
+            sqlite_analyze,
+            sqlite_wal_checkpoint,
+            sqlite_backup,
+            redis_scan_keys,
+            redis_read_key,
+            redis_write_key,
+            redis_delete_keys,
+            redis_set_ttl,
+            compare_schemas,
+            compare_table_data,
+            capture_schema_snapshot,
+            list_schema_snapshots,
+            get_schema_snapshot,
+            delete_schema_snapshot,
+            diff_schema_snapshot,
+            profile_table,
+            classify_query,
             set_ai_config,
             get_ai_config,
             get_database_config,
             disconnect_database,
             list_databases,
+            use_database,
             list_collections,
+            get_query_history,
+            set_history_favorite,
+            delete_history_entry,
+            clear_query_history,
+            save_snippet,
+            list_snippets,
+            delete_snippet,
+            get_history_retention,
+            set_history_retention,
             consult_database,
             generate_sql,
             generate_diagram,
             generate_insights,
+            optimize_query,
+            apply_query_optimization,
             validate_ai_key,
             get_available_models
         ])
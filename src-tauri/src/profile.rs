@@ -0,0 +1,544 @@
+use crate::database::{DatabaseConnection, DatabaseType};
+use crate::error::AppError;
+use crate::values::{self, ValueCategory};
+use bson::{doc, Bson, Document};
+use futures::TryStreamExt;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::time::Instant;
+
+const DEFAULT_TOP_N: usize = 10;
+const DEFAULT_BUCKETS: usize = 10;
+// Values longer than this are shortened in the AI context
+const CONTEXT_VALUE_LENGTH: usize = 30;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProfileOptions {
+    // Defaults to every column
+    pub columns: Option<Vec<String>>,
+    pub top_n: Option<usize>,
+    pub histogram_buckets: Option<usize>,
+    // Profile only the first N rows instead of the whole table
+    pub sample_rows: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ValueFrequency {
+    pub value: Value,
+    pub count: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistogramBucket {
+    pub lower: f64,
+    pub upper: f64,
+    pub count: i64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ColumnProfile {
+    pub name: String,
+    pub data_type: String,
+    pub non_null_count: i64,
+    pub null_count: i64,
+    pub null_ratio: f64,
+    pub distinct_count: Option<i64>,
+    pub min: Option<Value>,
+    pub max: Option<Value>,
+    pub mean: Option<f64>,
+    pub stddev: Option<f64>,
+    pub min_length: Option<i64>,
+    pub max_length: Option<i64>,
+    pub avg_length: Option<f64>,
+    pub top_values: Vec<ValueFrequency>,
+    // Equal-width buckets between min and max for numeric columns
+    pub histogram: Vec<HistogramBucket>,
+    // Equal-width buckets of string lengths
+    pub length_histogram: Vec<HistogramBucket>,
+    // Set when the column could not be profiled; other columns are unaffected
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TableProfile {
+    pub table: String,
+    pub row_count: i64,
+    pub sampled: bool,
+    pub columns: Vec<ColumnProfile>,
+    // Compact plain-text summary for AI prompts
+    pub ai_context: String,
+    pub duration_ms: u64,
+}
+
+// Catalog values arrive as numbers or numeric strings depending on the backend
+fn number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+fn integer(value: &Value) -> Option<i64> {
+    number(value).map(|n| n as i64)
+}
+
+fn non_null(value: &Value) -> Option<Value> {
+    if value.is_null() {
+        None
+    } else {
+        Some(value.clone())
+    }
+}
+
+fn buckets_from_counts(rows: impl Iterator<Item = (i64, i64)>, min: f64, width: f64) -> Vec<HistogramBucket> {
+    rows.map(|(index, count)| HistogramBucket {
+        lower: min + index as f64 * width,
+        upper: min + (index + 1) as f64 * width,
+        count,
+    })
+    .collect()
+}
+
+// SQL fragments that differ per backend
+struct Dialect {
+    db_type: DatabaseType,
+}
+
+impl Dialect {
+    fn text(&self, expression: &str) -> String {
+        match self.db_type {
+            DatabaseType::PostgreSQL => format!("({})::text", expression),
+            DatabaseType::MySQL => format!("CAST({} AS CHAR)", expression),
+            _ => format!("CAST({} AS TEXT)", expression),
+        }
+    }
+
+    fn length(&self, expression: &str) -> String {
+        match self.db_type {
+            DatabaseType::MySQL => format!("CHAR_LENGTH({})", expression),
+            DatabaseType::PostgreSQL => format!("length(({})::text)", expression),
+            _ => format!("length({})", expression),
+        }
+    }
+
+    // Non-negative bucket position rounded down to an integer
+    fn floor_int(&self, expression: &str) -> String {
+        match self.db_type {
+            DatabaseType::PostgreSQL => format!("FLOOR({})::int", expression),
+            DatabaseType::MySQL => format!("CAST(FLOOR({}) AS SIGNED)", expression),
+            _ => format!("CAST({} AS INTEGER)", expression),
+        }
+    }
+}
+
+async fn query_rows(connection: &DatabaseConnection, query: &str) -> Result<Vec<Vec<Value>>, AppError> {
+    Ok(connection.execute_query(query).await?.rows)
+}
+
+async fn sql_histogram(
+    connection: &DatabaseConnection,
+    dialect: &Dialect,
+    source: &str,
+    expression: &str,
+    min: f64,
+    max: f64,
+    buckets: usize,
+) -> Result<Vec<HistogramBucket>, AppError> {
+    if max <= min {
+        return Ok(Vec::new());
+    }
+    let width = (max - min) / buckets as f64;
+    let position = dialect.floor_int(&format!("(({}) - {}) / {}", expression, min, width));
+    let query = format!(
+        "SELECT CASE WHEN {expr} >= {max} THEN {last} ELSE {position} END AS bucket, COUNT(*) AS n \
+         FROM {source} WHERE {expr} IS NOT NULL GROUP BY 1 ORDER BY 1",
+        expr = expression,
+        max = max,
+        last = buckets - 1,
+        position = position,
+        source = source,
+    );
+    let rows = query_rows(connection, &query).await?;
+    Ok(buckets_from_counts(
+        rows.iter().filter_map(|r| Some((integer(r.first()?)?, integer(r.get(1)?)?))),
+        min,
+        width,
+    ))
+}
+
+async fn profile_sql_column(
+    connection: &DatabaseConnection,
+    dialect: &Dialect,
+    source: &str,
+    column: &mut ColumnProfile,
+    top_n: usize,
+    buckets: usize,
+) -> Result<(), AppError> {
+    let quoted = connection.quote_ident(&column.name);
+    let category = values::categorize(&column.data_type);
+    let is_numeric = matches!(category, ValueCategory::Integer | ValueCategory::Float | ValueCategory::Decimal);
+    let is_text = category == ValueCategory::Text;
+    let text = dialect.text(&quoted);
+
+    let mut aggregates = vec![
+        "COUNT(*) AS total".to_string(),
+        format!("COUNT({}) AS non_null", quoted),
+        format!("COUNT(DISTINCT {}) AS distinct_count", text),
+    ];
+    match category {
+        // Native ordering for numbers and dates, text ordering for everything else
+        ValueCategory::Integer
+        | ValueCategory::Float
+        | ValueCategory::Decimal
+        | ValueCategory::Date
+        | ValueCategory::DateTime
+        | ValueCategory::TimestampTz => {
+            aggregates.push(format!("{} AS min_value", dialect.text(&format!("MIN({})", quoted))));
+            aggregates.push(format!("{} AS max_value", dialect.text(&format!("MAX({})", quoted))));
+        }
+        ValueCategory::Text => {
+            aggregates.push(format!("MIN({}) AS min_value", text));
+            aggregates.push(format!("MAX({}) AS max_value", text));
+        }
+        _ => {
+            aggregates.push("NULL AS min_value".to_string());
+            aggregates.push("NULL AS max_value".to_string());
+        }
+    }
+    if is_numeric {
+        aggregates.push(format!("{} AS mean", dialect.text(&format!("AVG({})", quoted))));
+        match dialect.db_type {
+            DatabaseType::PostgreSQL | DatabaseType::MySQL => {
+                aggregates.push(format!("{} AS stddev", dialect.text(&format!("STDDEV_SAMP({})", quoted))));
+            }
+            // SQLite has no stddev aggregate; it is derived from the mean of squares
+            _ => aggregates.push(format!("AVG(CAST({0} AS REAL) * CAST({0} AS REAL)) AS mean_square", quoted)),
+        }
+    }
+    if is_text {
+        let length = dialect.length(&quoted);
+        aggregates.push(format!("MIN({}) AS min_length", length));
+        aggregates.push(format!("MAX({}) AS max_length", length));
+        aggregates.push(format!("{} AS avg_length", dialect.text(&format!("AVG({})", length))));
+    }
+
+    let query = format!("SELECT {} FROM {}", aggregates.join(", "), source);
+    let row = query_rows(connection, &query).await?.into_iter().next().unwrap_or_default();
+    let get = |i: usize| row.get(i).cloned().unwrap_or(Value::Null);
+
+    let total = integer(&get(0)).unwrap_or(0);
+    column.non_null_count = integer(&get(1)).unwrap_or(0);
+    column.null_count = total - column.non_null_count;
+    column.null_ratio = if total > 0 { column.null_count as f64 / total as f64 } else { 0.0 };
+    column.distinct_count = integer(&get(2));
+    column.min = non_null(&get(3));
+    column.max = non_null(&get(4));
+    let mut next = 5;
+    if is_numeric {
+        column.mean = number(&get(next));
+        column.stddev = match dialect.db_type {
+            DatabaseType::PostgreSQL | DatabaseType::MySQL => number(&get(next + 1)),
+            _ => match (column.mean, number(&get(next + 1))) {
+                (Some(mean), Some(mean_square)) if column.non_null_count > 1 => {
+                    let n = column.non_null_count as f64;
+                    Some(((mean_square - mean * mean) * n / (n - 1.0)).max(0.0).sqrt())
+                }
+                _ => None,
+            },
+        };
+        next += 2;
+    }
+    if is_text {
+        column.min_length = integer(&get(next));
+        column.max_length = integer(&get(next + 1));
+        column.avg_length = number(&get(next + 2));
+    }
+
+    let top_query = format!(
+        "SELECT {} AS value, COUNT(*) AS n FROM {} WHERE {} IS NOT NULL GROUP BY 1 ORDER BY n DESC, 1 LIMIT {}",
+        text, source, quoted, top_n
+    );
+    column.top_values = query_rows(connection, &top_query)
+        .await?
+        .into_iter()
+        .filter_map(|r| {
+            Some(ValueFrequency {
+                count: integer(r.get(1)?)?,
+                value: r.into_iter().next()?,
+            })
+        })
+        .collect();
+
+    if is_numeric {
+        if let (Some(min), Some(max)) = (column.min.as_ref().and_then(number), column.max.as_ref().and_then(number)) {
+            column.histogram = sql_histogram(connection, dialect, source, &quoted, min, max, buckets).await?;
+        }
+    }
+    if is_text {
+        if let (Some(min), Some(max)) = (column.min_length, column.max_length) {
+            let length = dialect.length(&quoted);
+            column.length_histogram = sql_histogram(connection, dialect, source, &length, min as f64, max as f64, buckets).await?;
+        }
+    }
+
+    Ok(())
+}
+
+async fn aggregate(collection: &mongodb::Collection<Document>, prefix: &[Document], stages: Vec<Document>) -> Result<Vec<Document>, AppError> {
+    let pipeline: Vec<Document> = prefix.iter().cloned().chain(stages).collect();
+    let cursor = collection.aggregate(pipeline, None).await?;
+    Ok(cursor.try_collect().await?)
+}
+
+fn bson_number(value: Option<&Bson>) -> Option<f64> {
+    match value? {
+        Bson::Int32(v) => Some(*v as f64),
+        Bson::Int64(v) => Some(*v as f64),
+        Bson::Double(v) => Some(*v),
+        Bson::Decimal128(v) => v.to_string().parse().ok(),
+        _ => None,
+    }
+}
+
+fn bson_json(value: Option<&Bson>) -> Option<Value> {
+    match value {
+        None | Some(Bson::Null) => None,
+        Some(v) => Some(v.clone().into_relaxed_extjson()),
+    }
+}
+
+// Histogram stages shared by numeric values and string lengths
+fn mongo_histogram_stages(expression: Bson, min: f64, max: f64, buckets: usize) -> Vec<Document> {
+    let width = (max - min) / buckets as f64;
+    vec![
+        doc! { "$group": {
+            "_id": { "$min": [
+                { "$floor": { "$divide": [{ "$subtract": [expression, min] }, width] } },
+                (buckets - 1) as i64,
+            ] },
+            "n": { "$sum": 1 },
+        } },
+        doc! { "$sort": { "_id": 1 } },
+    ]
+}
+
+fn mongo_buckets(rows: &[Document], min: f64, max: f64, buckets: usize) -> Vec<HistogramBucket> {
+    let width = (max - min) / buckets as f64;
+    buckets_from_counts(
+        rows.iter().filter_map(|d| Some((bson_number(d.get("_id"))? as i64, bson_number(d.get("n"))? as i64))),
+        min,
+        width,
+    )
+}
+
+async fn profile_mongodb_field(
+    collection: &mongodb::Collection<Document>,
+    prefix: &[Document],
+    total: i64,
+    column: &mut ColumnProfile,
+    top_n: usize,
+    buckets: usize,
+) -> Result<(), AppError> {
+    let field = format!("${}", column.name);
+    let is_string = doc! { "$eq": [{ "$type": &field }, "string"] };
+    let length = doc! { "$cond": [is_string.clone(), { "$strLenCP": &field }, Bson::Null] };
+
+    let stats = aggregate(collection, prefix, vec![doc! { "$group": {
+        "_id": Bson::Null,
+        "non_null": { "$sum": { "$cond": [{ "$eq": [{ "$ifNull": [&field, Bson::Null] }, Bson::Null] }, 0, 1] } },
+        "min": { "$min": &field },
+        "max": { "$max": &field },
+        "mean": { "$avg": &field },
+        "stddev": { "$stdDevSamp": &field },
+        "min_length": { "$min": length.clone() },
+        "max_length": { "$max": length.clone() },
+        "avg_length": { "$avg": length.clone() },
+    } }])
+    .await?;
+    let stats = stats.into_iter().next().unwrap_or_default();
+
+    column.non_null_count = bson_number(stats.get("non_null")).unwrap_or(0.0) as i64;
+    column.null_count = total - column.non_null_count;
+    column.null_ratio = if total > 0 { column.null_count as f64 / total as f64 } else { 0.0 };
+    column.min = bson_json(stats.get("min"));
+    column.max = bson_json(stats.get("max"));
+    column.mean = bson_number(stats.get("mean"));
+    column.stddev = bson_number(stats.get("stddev"));
+    column.min_length = bson_number(stats.get("min_length")).map(|v| v as i64);
+    column.max_length = bson_number(stats.get("max_length")).map(|v| v as i64);
+    column.avg_length = bson_number(stats.get("avg_length"));
+
+    let not_null = doc! { "$match": { &column.name: { "$ne": Bson::Null } } };
+    let distinct = aggregate(collection, prefix, vec![
+        not_null.clone(),
+        doc! { "$group": { "_id": &field } },
+        doc! { "$count": "n" },
+    ])
+    .await?;
+    column.distinct_count = Some(distinct.first().and_then(|d| bson_number(d.get("n"))).unwrap_or(0.0) as i64);
+
+    let top = aggregate(collection, prefix, vec![
+        not_null,
+        doc! { "$group": { "_id": &field, "n": { "$sum": 1 } } },
+        doc! { "$sort": { "n": -1 } },
+        doc! { "$limit": top_n as i64 },
+    ])
+    .await?;
+    column.top_values = top
+        .iter()
+        .filter_map(|d| {
+            Some(ValueFrequency {
+                value: bson_json(d.get("_id"))?,
+                count: bson_number(d.get("n"))? as i64,
+            })
+        })
+        .collect();
+
+    // $avg only sees numbers, so a mean means the field holds numeric values
+    if let (Some(_), Some(min), Some(max)) = (column.mean, column.min.as_ref().and_then(number), column.max.as_ref().and_then(number)) {
+        if max > min {
+            let mut stages = vec![doc! { "$match": { &column.name: { "$type": "number" } } }];
+            stages.extend(mongo_histogram_stages(Bson::String(field.clone()), min, max, buckets));
+            column.histogram = mongo_buckets(&aggregate(collection, prefix, stages).await?, min, max, buckets);
+        }
+    }
+    if let (Some(min), Some(max)) = (column.min_length, column.max_length) {
+        if max > min {
+            let (min, max) = (min as f64, max as f64);
+            let mut stages = vec![doc! { "$match": { &column.name: { "$type": "string" } } }];
+            stages.extend(mongo_histogram_stages(Bson::Document(doc! { "$strLenCP": &field }), min, max, buckets));
+            column.length_histogram = mongo_buckets(&aggregate(collection, prefix, stages).await?, min, max, buckets);
+        }
+    }
+
+    Ok(())
+}
+
+fn short(value: &Value) -> String {
+    let text = match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    };
+    if text.chars().count() > CONTEXT_VALUE_LENGTH {
+        format!("{}...", text.chars().take(CONTEXT_VALUE_LENGTH).collect::<String>())
+    } else {
+        text
+    }
+}
+
+// One line per column, small enough to send with every insights request
+pub fn ai_context(table: &str, row_count: i64, sampled: bool, columns: &[ColumnProfile]) -> String {
+    let mut lines = vec![format!(
+        "Table {}: {} rows{}",
+        table,
+        row_count,
+        if sampled { " (sampled)" } else { "" }
+    )];
+    for column in columns {
+        if let Some(error) = &column.error {
+            lines.push(format!("- {} ({}): not profiled ({})", column.name, column.data_type, error));
+            continue;
+        }
+        let mut parts = vec![format!("nulls {:.1}%", column.null_ratio * 100.0)];
+        if let Some(distinct) = column.distinct_count {
+            parts.push(format!("distinct {}", distinct));
+        }
+        if let (Some(min), Some(max)) = (&column.min, &column.max) {
+            parts.push(format!("range {} .. {}", short(min), short(max)));
+        }
+        if let Some(mean) = column.mean {
+            parts.push(format!("mean {:.2}", mean));
+        }
+        if let Some(stddev) = column.stddev {
+            parts.push(format!("sd {:.2}", stddev));
+        }
+        if let (Some(min), Some(max), Some(avg)) = (column.min_length, column.max_length, column.avg_length) {
+            parts.push(format!("length {}-{} avg {:.1}", min, max, avg));
+        }
+        if !column.top_values.is_empty() {
+            let top: Vec<String> = column
+                .top_values
+                .iter()
+                .take(3)
+                .map(|v| format!("{} ({})", short(&v.value), v.count))
+                .collect();
+            parts.push(format!("top {}", top.join(", ")));
+        }
+        lines.push(format!("- {} ({}): {}", column.name, column.data_type, parts.join("; ")));
+    }
+    lines.join("\n")
+}
+
+pub async fn profile_table(
+    connection: &DatabaseConnection,
+    table: &str,
+    options: &ProfileOptions,
+) -> Result<TableProfile, AppError> {
+    let started = Instant::now();
+    let top_n = options.top_n.unwrap_or(DEFAULT_TOP_N).max(1);
+    let buckets = options.histogram_buckets.unwrap_or(DEFAULT_BUCKETS).max(1);
+
+    let schema = connection.get_schema().await?;
+    let info = schema
+        .tables
+        .into_iter()
+        .find(|t| t.name == table)
+        .ok_or_else(|| AppError::InvalidConfiguration(format!("Table {} not found", table)))?;
+
+    let mut columns: Vec<ColumnProfile> = info
+        .columns
+        .iter()
+        .filter(|c| options.columns.as_ref().map(|wanted| wanted.contains(&c.name)).unwrap_or(true))
+        .map(|c| ColumnProfile {
+            name: c.name.clone(),
+            data_type: c.data_type.clone(),
+            ..Default::default()
+        })
+        .collect();
+
+    let row_count = match connection {
+        DatabaseConnection::MongoDB(_client, database) => {
+            let collection = database.collection::<Document>(table);
+            let prefix: Vec<Document> = options
+                .sample_rows
+                .map(|n| vec![doc! { "$limit": n as i64 }])
+                .unwrap_or_default();
+            let total = collection.count_documents(None, None).await? as i64;
+            let total = options.sample_rows.map(|n| total.min(n as i64)).unwrap_or(total);
+
+            for column in columns.iter_mut() {
+                if let Err(e) = profile_mongodb_field(&collection, &prefix, total, column, top_n, buckets).await {
+                    column.error = Some(e.to_string());
+                }
+            }
+            total
+        }
+        _ => {
+            let dialect = Dialect { db_type: connection.db_type() };
+            let quoted_table = connection.quote_table(table);
+            let source = match options.sample_rows {
+                Some(n) => format!("(SELECT * FROM {} LIMIT {}) sample_rows", quoted_table, n),
+                None => quoted_table,
+            };
+            let count_rows = query_rows(connection, &format!("SELECT COUNT(*) FROM {}", source)).await?;
+            let total = count_rows.first().and_then(|r| r.first()).and_then(integer).unwrap_or(0);
+
+            for column in columns.iter_mut() {
+                if let Err(e) = profile_sql_column(connection, &dialect, &source, column, top_n, buckets).await {
+                    column.error = Some(e.to_string());
+                }
+            }
+            total
+        }
+    };
+
+    Ok(TableProfile {
+        ai_context: ai_context(table, row_count, options.sample_rows.is_some(), &columns),
+        table: table.to_string(),
+        row_count,
+        sampled: options.sample_rows.is_some(),
+        columns,
+        duration_ms: started.elapsed().as_millis() as u64,
+    })
+}
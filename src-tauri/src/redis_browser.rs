@@ -0,0 +1,485 @@
+use crate::database::{redis_failed, redis_value_to_json, split_redis_command, DatabaseConnection, QueryResult, RedisClient};
+use crate::error::AppError;
+use redis::aio::ConnectionManager;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::time::Instant;
+
+// Keys asked for per SCAN call
+const DEFAULT_SCAN_COUNT: usize = 200;
+// Elements of a hash, list, set, sorted set or stream returned per page
+const DEFAULT_PAGE_SIZE: usize = 500;
+// Commands that wipe or stop the whole server and need confirmation
+const DESTRUCTIVE_COMMANDS: &[&str] = &["FLUSHALL", "FLUSHDB", "SHUTDOWN", "SWAPDB", "DEBUG"];
+// Scripts aren't flagged as writes, so only the read-only variants run on read-only connections
+const READ_ONLY_SCRIPT_COMMANDS: &[&str] = &["EVAL_RO", "FCALL_RO"];
+const FLAG_WRITE_COMMANDS: &[&str] = &["write", "admin", "may_replicate"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RedisKey {
+    pub key: String,
+    // string, hash, list, set, zset, stream or a module type
+    pub key_type: String,
+    // None when the key never expires
+    pub ttl_ms: Option<i64>,
+    // Length, element or entry count; None for module types
+    pub size: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyPage {
+    pub keys: Vec<RedisKey>,
+    // "0" once the whole keyspace has been scanned
+    pub cursor: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct KeyValue {
+    pub key: RedisKey,
+    pub value: QueryResult,
+    // Pass back to read the next page; None on the last page
+    pub next_cursor: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FieldValue {
+    pub field: String,
+    pub value: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScoredMember {
+    pub member: String,
+    pub score: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "action")]
+pub enum KeyWrite {
+    SetString { key: String, value: String, ttl_seconds: Option<i64> },
+    HashSet { key: String, fields: Vec<FieldValue> },
+    HashDelete { key: String, fields: Vec<String> },
+    ListPush { key: String, values: Vec<String>, #[serde(default)] prepend: bool },
+    ListSet { key: String, index: i64, value: String },
+    // count follows LREM: 0 removes every match, negative counts from the tail
+    ListRemove { key: String, value: String, #[serde(default)] count: i64 },
+    SetAdd { key: String, members: Vec<String> },
+    SetRemove { key: String, members: Vec<String> },
+    SortedSetAdd { key: String, members: Vec<ScoredMember> },
+    SortedSetRemove { key: String, members: Vec<String> },
+    // id defaults to "*", letting the server assign one
+    StreamAdd { key: String, id: Option<String>, fields: Vec<FieldValue> },
+    StreamDelete { key: String, ids: Vec<String> },
+}
+
+impl KeyWrite {
+    fn key(&self) -> &str {
+        match self {
+            KeyWrite::SetString { key, .. }
+            | KeyWrite::HashSet { key, .. }
+            | KeyWrite::HashDelete { key, .. }
+            | KeyWrite::ListPush { key, .. }
+            | KeyWrite::ListSet { key, .. }
+            | KeyWrite::ListRemove { key, .. }
+            | KeyWrite::SetAdd { key, .. }
+            | KeyWrite::SetRemove { key, .. }
+            | KeyWrite::SortedSetAdd { key, .. }
+            | KeyWrite::SortedSetRemove { key, .. }
+            | KeyWrite::StreamAdd { key, .. }
+            | KeyWrite::StreamDelete { key, .. } => key,
+        }
+    }
+}
+
+fn require_redis(connection: &DatabaseConnection) -> Result<&RedisClient, AppError> {
+    match connection {
+        DatabaseConnection::Redis(client) => Ok(client),
+        _ => Err(AppError::InvalidConfiguration("The key browser is only available for Redis".to_string())),
+    }
+}
+
+fn text(value: &redis::Value) -> String {
+    match redis_value_to_json(value.clone()) {
+        Value::String(s) => s,
+        other => other.to_string(),
+    }
+}
+
+fn result(columns: &[&str], rows: Vec<Vec<Value>>, started: Instant) -> QueryResult {
+    let elapsed = started.elapsed().as_secs_f64() * 1000.0;
+    QueryResult {
+        columns: columns.iter().map(|c| c.to_string()).collect(),
+        row_count: rows.len(),
+        rows,
+        execution_time_ms: elapsed,
+        fetch_time_ms: 0.0,
+    }
+}
+
+// Command that reports how many elements a key of this type holds
+fn size_command(key_type: &str) -> Option<&'static str> {
+    match key_type {
+        "string" => Some("STRLEN"),
+        "hash" => Some("HLEN"),
+        "list" => Some("LLEN"),
+        "set" => Some("SCARD"),
+        "zset" => Some("ZCARD"),
+        "stream" => Some("XLEN"),
+        _ => None,
+    }
+}
+
+// Type, TTL and size of each key in two round trips
+async fn describe_keys(con: &mut ConnectionManager, keys: Vec<String>) -> Result<Vec<RedisKey>, AppError> {
+    if keys.is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut pipe = redis::pipe();
+    for key in &keys {
+        pipe.cmd("TYPE").arg(key).cmd("PTTL").arg(key);
+    }
+    let replies: Vec<redis::Value> = pipe.query_async(con).await.map_err(redis_failed)?;
+    let mut described: Vec<RedisKey> = keys
+        .into_iter()
+        .zip(replies.chunks(2))
+        .map(|(key, reply)| {
+            // PTTL is -1 without an expiry and -2 when the key vanished in between
+            let ttl = reply.get(1).and_then(|v| redis::from_redis_value::<i64>(v).ok()).unwrap_or(-1);
+            RedisKey {
+                key,
+                key_type: reply.first().map(text).unwrap_or_default(),
+                ttl_ms: (ttl >= 0).then_some(ttl),
+                size: None,
+            }
+        })
+        .collect();
+
+    let mut pipe = redis::pipe();
+    let mut sized = Vec::new();
+    for (i, key) in described.iter().enumerate() {
+        if let Some(command) = size_command(&key.key_type) {
+            pipe.cmd(command).arg(&key.key);
+            sized.push(i);
+        }
+    }
+    if !sized.is_empty() {
+        let sizes: Vec<i64> = pipe.query_async(con).await.map_err(redis_failed)?;
+        for (i, size) in sized.into_iter().zip(sizes) {
+            described[i].size = Some(size);
+        }
+    }
+    Ok(described)
+}
+
+async fn describe_key(con: &mut ConnectionManager, key: &str) -> Result<RedisKey, AppError> {
+    let key = describe_keys(con, vec![key.to_string()]).await?.remove(0);
+    if key.key_type == "none" {
+        return Err(AppError::QueryExecutionFailed(format!("Key {} does not exist", key.key)));
+    }
+    Ok(key)
+}
+
+// One SCAN step; the type filter needs Redis 6 or later
+pub async fn scan_keys(
+    connection: &DatabaseConnection,
+    pattern: Option<&str>,
+    key_type: Option<&str>,
+    cursor: Option<&str>,
+    count: Option<usize>,
+) -> Result<KeyPage, AppError> {
+    let mut con = require_redis(connection)?.manager.clone();
+    let mut scan = redis::cmd("SCAN");
+    scan.arg(cursor.unwrap_or("0"))
+        .arg("MATCH")
+        .arg(pattern.filter(|p| !p.is_empty()).unwrap_or("*"))
+        .arg("COUNT")
+        .arg(count.unwrap_or(DEFAULT_SCAN_COUNT));
+    if let Some(key_type) = key_type.filter(|t| !t.is_empty()) {
+        scan.arg("TYPE").arg(key_type);
+    }
+    let (cursor, keys): (String, Vec<String>) = scan.query_async(&mut con).await.map_err(redis_failed)?;
+    Ok(KeyPage {
+        keys: describe_keys(&mut con, keys).await?,
+        cursor,
+    })
+}
+
+// Pairs up a flat [name, value, name, value, ...] reply
+fn pairs(values: &[redis::Value]) -> Vec<Vec<Value>> {
+    values
+        .chunks(2)
+        .map(|pair| pair.iter().cloned().map(redis_value_to_json).collect())
+        .collect()
+}
+
+// Reads a page of a key's contents. Lists and sorted sets page by offset, hashes and sets
+// by SCAN cursor and streams by the last entry ID seen.
+pub async fn read_key(
+    connection: &DatabaseConnection,
+    key: &str,
+    cursor: Option<&str>,
+    page_size: Option<usize>,
+) -> Result<KeyValue, AppError> {
+    let mut con = require_redis(connection)?.manager.clone();
+    let described = describe_key(&mut con, key).await?;
+    let page_size = page_size.unwrap_or(DEFAULT_PAGE_SIZE).max(1);
+    let started = Instant::now();
+    let offset = || cursor.and_then(|c| c.parse::<usize>().ok()).unwrap_or(0);
+    let next_offset = |offset: usize| {
+        let next = offset + page_size;
+        (described.size.unwrap_or(0) > next as i64).then(|| next.to_string())
+    };
+
+    let (value, next_cursor) = match described.key_type.as_str() {
+        "string" => {
+            let value: redis::Value = redis::cmd("GET").arg(key).query_async(&mut con).await.map_err(redis_failed)?;
+            (result(&["value"], vec![vec![redis_value_to_json(value)]], started), None)
+        }
+        "hash" | "set" => {
+            let command = if described.key_type == "hash" { "HSCAN" } else { "SSCAN" };
+            let (next, items): (String, Vec<redis::Value>) = redis::cmd(command)
+                .arg(key)
+                .arg(cursor.unwrap_or("0"))
+                .arg("COUNT")
+                .arg(page_size)
+                .query_async(&mut con)
+                .await
+                .map_err(redis_failed)?;
+            let value = if command == "HSCAN" {
+                result(&["field", "value"], pairs(&items), started)
+            } else {
+                result(&["member"], items.into_iter().map(|m| vec![redis_value_to_json(m)]).collect(), started)
+            };
+            (value, (next != "0").then_some(next))
+        }
+        "list" => {
+            let offset = offset();
+            let items: Vec<redis::Value> = redis::cmd("LRANGE")
+                .arg(key)
+                .arg(offset)
+                .arg(offset + page_size - 1)
+                .query_async(&mut con)
+                .await
+                .map_err(redis_failed)?;
+            let rows = items
+                .into_iter()
+                .enumerate()
+                .map(|(i, item)| vec![Value::from(offset + i), redis_value_to_json(item)])
+                .collect();
+            (result(&["index", "value"], rows, started), next_offset(offset))
+        }
+        "zset" => {
+            let offset = offset();
+            let items: Vec<redis::Value> = redis::cmd("ZRANGE")
+                .arg(key)
+                .arg(offset)
+                .arg(offset + page_size - 1)
+                .arg("WITHSCORES")
+                .query_async(&mut con)
+                .await
+                .map_err(redis_failed)?;
+            (result(&["member", "score"], pairs(&items), started), next_offset(offset))
+        }
+        "stream" => {
+            // An exclusive start ID needs Redis 6.2
+            let start = cursor.map(|id| format!("({}", id)).unwrap_or_else(|| "-".to_string());
+            let entries: Vec<(String, Vec<redis::Value>)> = redis::cmd("XRANGE")
+                .arg(key)
+                .arg(start)
+                .arg("+")
+                .arg("COUNT")
+                .arg(page_size)
+                .query_async(&mut con)
+                .await
+                .map_err(redis_failed)?;
+            let next = if entries.len() == page_size { entries.last().map(|(id, _)| id.clone()) } else { None };
+            let rows = entries
+                .into_iter()
+                .map(|(id, fields)| {
+                    let fields: serde_json::Map<String, Value> = fields
+                        .chunks(2)
+                        .map(|pair| (pair.first().map(text).unwrap_or_default(), pair.get(1).cloned().map(redis_value_to_json).unwrap_or(Value::Null)))
+                        .collect();
+                    vec![Value::String(id), Value::Object(fields)]
+                })
+                .collect();
+            (result(&["id", "fields"], rows, started), next)
+        }
+        other => {
+            return Err(AppError::InvalidConfiguration(format!(
+                "Keys of type {} can only be read with raw commands",
+                other
+            )))
+        }
+    };
+
+    Ok(KeyValue {
+        key: described,
+        value,
+        next_cursor,
+    })
+}
+
+pub async fn write_key(connection: &DatabaseConnection, change: &KeyWrite) -> Result<RedisKey, AppError> {
+    let mut con = require_redis(connection)?.manager.clone();
+    let key = change.key();
+    if key.is_empty() {
+        return Err(AppError::InvalidConfiguration("A key name is required".to_string()));
+    }
+    let cmd = match change {
+        KeyWrite::SetString { value, ttl_seconds, .. } => {
+            let mut cmd = redis::cmd("SET");
+            cmd.arg(key).arg(value);
+            if let Some(ttl) = ttl_seconds.filter(|t| *t > 0) {
+                cmd.arg("EX").arg(ttl);
+            }
+            cmd
+        }
+        KeyWrite::HashSet { fields, .. } => {
+            let mut cmd = redis::cmd("HSET");
+            cmd.arg(key);
+            for field in fields {
+                cmd.arg(&field.field).arg(&field.value);
+            }
+            cmd
+        }
+        KeyWrite::HashDelete { fields, .. } => {
+            let mut cmd = redis::cmd("HDEL");
+            cmd.arg(key).arg(fields);
+            cmd
+        }
+        KeyWrite::ListPush { values, prepend, .. } => {
+            let mut cmd = redis::cmd(if *prepend { "LPUSH" } else { "RPUSH" });
+            cmd.arg(key).arg(values);
+            cmd
+        }
+        KeyWrite::ListSet { index, value, .. } => {
+            let mut cmd = redis::cmd("LSET");
+            cmd.arg(key).arg(index).arg(value);
+            cmd
+        }
+        KeyWrite::ListRemove { value, count, .. } => {
+            let mut cmd = redis::cmd("LREM");
+            cmd.arg(key).arg(count).arg(value);
+            cmd
+        }
+        KeyWrite::SetAdd { members, .. } => {
+            let mut cmd = redis::cmd("SADD");
+            cmd.arg(key).arg(members);
+            cmd
+        }
+        KeyWrite::SetRemove { members, .. } => {
+            let mut cmd = redis::cmd("SREM");
+            cmd.arg(key).arg(members);
+            cmd
+        }
+        KeyWrite::SortedSetAdd { members, .. } => {
+            let mut cmd = redis::cmd("ZADD");
+            cmd.arg(key);
+            for member in members {
+                cmd.arg(member.score).arg(&member.member);
+            }
+            cmd
+        }
+        KeyWrite::SortedSetRemove { members, .. } => {
+            let mut cmd = redis::cmd("ZREM");
+            cmd.arg(key).arg(members);
+            cmd
+        }
+        KeyWrite::StreamAdd { id, fields, .. } => {
+            let mut cmd = redis::cmd("XADD");
+            cmd.arg(key).arg(id.as_deref().filter(|id| !id.is_empty()).unwrap_or("*"));
+            for field in fields {
+                cmd.arg(&field.field).arg(&field.value);
+            }
+            cmd
+        }
+        KeyWrite::StreamDelete { ids, .. } => {
+            let mut cmd = redis::cmd("XDEL");
+            cmd.arg(key).arg(ids);
+            cmd
+        }
+    };
+    let _: redis::Value = cmd.query_async(&mut con).await.map_err(redis_failed)?;
+
+    // Removing the last element deletes the key itself
+    describe_keys(&mut con, vec![key.to_string()])
+        .await
+        .map(|mut keys| keys.remove(0))
+}
+
+// UNLINK frees memory in the background; returns how many keys existed
+pub async fn delete_keys(connection: &DatabaseConnection, keys: &[String]) -> Result<i64, AppError> {
+    if keys.is_empty() {
+        return Ok(0);
+    }
+    let mut con = require_redis(connection)?.manager.clone();
+    let deleted: i64 = redis::cmd("UNLINK").arg(keys).query_async(&mut con).await.map_err(redis_failed)?;
+    Ok(deleted)
+}
+
+// None removes the expiry
+pub async fn set_ttl(connection: &DatabaseConnection, key: &str, ttl_seconds: Option<i64>) -> Result<RedisKey, AppError> {
+    let mut con = require_redis(connection)?.manager.clone();
+    let changed: i64 = match ttl_seconds {
+        Some(ttl) => redis::cmd("EXPIRE").arg(key).arg(ttl).query_async(&mut con).await,
+        None => redis::cmd("PERSIST").arg(key).query_async(&mut con).await,
+    }
+    .map_err(redis_failed)?;
+    if changed == 0 && ttl_seconds.is_some() {
+        return Err(AppError::QueryExecutionFailed(format!("Key {} does not exist", key)));
+    }
+    describe_key(&mut con, key).await
+}
+
+// COMMAND INFO entry for a command or a "container|subcommand": its flags and whether it
+// has subcommands (Redis 7+). None for unknown commands
+async fn command_flags(con: &mut ConnectionManager, name: &str) -> Result<Option<(Vec<String>, bool)>, AppError> {
+    let info: redis::Value = redis::cmd("COMMAND").arg("INFO").arg(name).query_async(con).await.map_err(redis_failed)?;
+    // Reply: [[name, arity, [flags...], first, last, step, categories, tips, key specs, subcommands]] or [nil]
+    let redis::Value::Bulk(entries) = &info else {
+        return Ok(None);
+    };
+    let Some(redis::Value::Bulk(entry)) = entries.first() else {
+        return Ok(None);
+    };
+    let flags: Vec<String> = entry.get(2).and_then(|f| redis::from_redis_value(f).ok()).unwrap_or_default();
+    let has_subcommands = matches!(entry.get(9), Some(redis::Value::Bulk(subcommands)) if !subcommands.is_empty());
+    Ok(Some((flags, has_subcommands)))
+}
+
+// Counterpart of safety::check_query for raw commands. Whether a command writes comes
+// from COMMAND INFO, so module commands are classified too; unknown commands count as writes.
+pub async fn check_command(connection: &DatabaseConnection, query: &str, read_only: bool, confirmed: bool) -> Result<(), AppError> {
+    let client = require_redis(connection)?;
+    let args = split_redis_command(query)?;
+    let Some(name) = args.first().map(|n| n.to_uppercase()) else {
+        return Ok(());
+    };
+
+    if read_only {
+        let is_read = if name.starts_with("EVAL") || name.starts_with("FCALL") {
+            READ_ONLY_SCRIPT_COMMANDS.contains(&name.as_str())
+        } else {
+            let mut con = client.manager.clone();
+            let mut flags = command_flags(&mut con, &name).await?;
+            // Containers such as CONFIG or XGROUP carry no flags of their own
+            if let Some((_, true)) = flags {
+                flags = match args.get(1) {
+                    Some(subcommand) => command_flags(&mut con, &format!("{}|{}", name, subcommand).to_lowercase()).await?,
+                    None => None,
+                };
+            }
+            // Empty flags say nothing about writes, so they count as one
+            flags.is_some_and(|(flags, _)| !flags.is_empty() && !flags.iter().any(|f| FLAG_WRITE_COMMANDS.contains(&f.as_str())))
+        };
+        if !is_read {
+            return Err(AppError::ReadOnlyViolation(format!("{} may modify data and isn't allowed on a read-only connection", name)));
+        }
+    }
+
+    if !confirmed && DESTRUCTIVE_COMMANDS.contains(&name.as_str()) {
+        return Err(AppError::ConfirmationRequired(format!("{} affects the whole server", name)));
+    }
+    Ok(())
+}
@@ -0,0 +1,503 @@
+use crate::database::parse_mongodb_command;
+use crate::error::AppError;
+use crate::splitter::{split_sql, SqlDialect};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum StatementRisk {
+    ReadOnly,
+    Write,
+    Destructive,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatementClassification {
+    pub statement: String,
+    pub keyword: String,
+    pub risk: StatementRisk,
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueryClassification {
+    pub statements: Vec<StatementClassification>,
+    pub risk: StatementRisk,
+    pub requires_confirmation: bool,
+}
+
+// Statements that never modify data or schema
+const READ_ONLY_KEYWORDS: &[&str] = &[
+    "SELECT", "SHOW", "DESCRIBE", "DESC", "EXPLAIN", "VALUES", "TABLE",
+    "BEGIN", "START", "COMMIT", "ROLLBACK", "SAVEPOINT", "RELEASE", "USE",
+];
+
+// Session settings that can't switch off a read-only guard; every other SET counts as a write
+const READ_ONLY_SETTINGS: &[&str] = &["NAMES", "SEARCH_PATH", "SCHEMA", "TIMEZONE", "TIME", "CLIENT_ENCODING"];
+
+// Words between EXPLAIN and the explained statement
+const EXPLAIN_OPTIONS: &[&str] = &["ANALYZE", "ANALYSE", "VERBOSE", "FORMAT", "TREE", "JSON", "TRADITIONAL", "QUERY", "PLAN"];
+
+// SQLite pragmas whose parenthesised argument is the table or index to inspect
+const READ_ONLY_PRAGMAS: &[&str] = &[
+    "TABLE_INFO", "TABLE_XINFO", "TABLE_LIST", "INDEX_LIST", "INDEX_INFO", "INDEX_XINFO",
+    "FOREIGN_KEY_LIST", "FOREIGN_KEY_CHECK", "INTEGRITY_CHECK", "QUICK_CHECK",
+];
+
+// MongoDB commands that only read; aggregate is checked for $out and $merge separately
+const MONGODB_READ_COMMANDS: &[&str] = &[
+    "find", "aggregate", "count", "distinct", "listCollections", "listIndexes", "listDatabases",
+    "collStats", "dbStats", "explain", "ping", "buildInfo", "hello", "isMaster", "serverStatus",
+];
+
+// T-SQL reserved words that can write or run arbitrary code. Being reserved, they can only
+// appear unquoted as keywords, so finding one anywhere in a batch is enough to refuse it
+const TSQL_WRITE_KEYWORDS: &[&str] = &[
+    "INSERT", "UPDATE", "DELETE", "MERGE", "INTO", "CREATE", "ALTER", "DROP", "TRUNCATE",
+    "EXEC", "EXECUTE", "GRANT", "REVOKE", "DENY", "BULK", "BACKUP", "RESTORE", "DBCC", "KILL",
+    "SHUTDOWN", "RECONFIGURE", "CHECKPOINT", "WRITETEXT", "UPDATETEXT", "SETUSER",
+    "OPENROWSET", "OPENDATASOURCE", "OPENQUERY",
+];
+
+// Keywords that start a new T-SQL statement when they appear outside parentheses
+const TSQL_STATEMENT_KEYWORDS: &[&str] = &[
+    "SELECT", "DECLARE", "SET", "PRINT", "IF", "WHILE", "WAITFOR", "USE", "BEGIN", "COMMIT",
+    "ROLLBACK", "SAVE", "RETURN", "THROW", "RAISERROR", "GOTO", "BREAK", "CONTINUE", "OPEN",
+    "CLOSE", "DEALLOCATE", "READTEXT",
+];
+
+// Split a SQL script into individual statements, ignoring semicolons inside
+// string literals, quoted identifiers and comments. MySQL's executable comments
+// (/*! ... */ and MariaDB's /*M! ... */) run as SQL, so their contents are split too
+pub fn split_statements(sql: &str, dialect: SqlDialect) -> Vec<String> {
+    let mut statements = Vec::new();
+    for statement in split_sql(sql, dialect) {
+        if dialect == SqlDialect::MySQL && statement.sql.contains("/*") {
+            let unwrapped = unwrap_executable_comments(&statement.sql);
+            statements.extend(split_sql(&unwrapped, dialect).into_iter().map(|s| s.sql));
+        } else {
+            statements.push(statement.sql);
+        }
+    }
+    statements
+}
+
+// Replace the markers of executable comments with spaces, leaving their contents in place
+fn unwrap_executable_comments(sql: &str) -> String {
+    let mut result = String::with_capacity(sql.len());
+    let mut rest = sql;
+    while let Some(start) = rest.find("/*") {
+        result.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let body = after.strip_prefix('!').or_else(|| after.strip_prefix("M!"));
+        let Some(body) = body else {
+            result.push_str("/*");
+            rest = after;
+            continue;
+        };
+        // An optional version number, e.g. /*!50000 ... */
+        let body = body.trim_start_matches(|c: char| c.is_ascii_digit());
+        result.push(' ');
+        match body.find("*/") {
+            Some(end) => {
+                result.push_str(&body[..end]);
+                result.push(' ');
+                rest = &body[end + 2..];
+            }
+            None => {
+                result.push_str(body);
+                rest = "";
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+// Uppercased words outside string literals, with their parenthesis depth
+fn statement_words(statement: &str) -> Vec<(String, i32)> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut depth = 0i32;
+    let mut quote: Option<char> = None;
+
+    for c in statement.chars() {
+        if let Some(q) = quote {
+            if c == q {
+                quote = None;
+            }
+            continue;
+        }
+
+        if c.is_alphanumeric() || c == '_' {
+            word.push(c.to_ascii_uppercase());
+            continue;
+        }
+
+        if !word.is_empty() {
+            words.push((std::mem::take(&mut word), depth));
+        }
+
+        match c {
+            '\'' | '"' | '`' => quote = Some(c),
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            _ => {}
+        }
+    }
+
+    if !word.is_empty() {
+        words.push((word, depth));
+    }
+
+    words
+}
+
+// Uppercased words that appear outside of parentheses and string literals
+fn top_level_words(statement: &str) -> Vec<String> {
+    statement_words(statement)
+        .into_iter()
+        .filter(|(_, depth)| *depth == 0)
+        .map(|(word, _)| word)
+        .collect()
+}
+
+// Uppercased T-SQL words with their parenthesis depth, skipping literals, [bracketed] and
+// "quoted" identifiers and comments, which nest in T-SQL
+fn tsql_words(batch: &str) -> Vec<(String, i32)> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut depth = 0i32;
+    let mut chars = batch.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c.is_alphanumeric() || c == '_' || c == '@' || c == '#' || c == '$' {
+            word.push(c.to_ascii_uppercase());
+            continue;
+        }
+        if !word.is_empty() {
+            words.push((std::mem::take(&mut word), depth));
+        }
+
+        match c {
+            '\'' | '"' | '[' => {
+                let close = if c == '[' { ']' } else { c };
+                while let Some(inner) = chars.next() {
+                    if inner == close {
+                        // A doubled closing character is escaped
+                        if chars.peek() == Some(&close) {
+                            chars.next();
+                        } else {
+                            break;
+                        }
+                    }
+                }
+            }
+            '-' if chars.peek() == Some(&'-') => {
+                for inner in chars.by_ref() {
+                    if inner == '\n' {
+                        break;
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut nesting = 1;
+                while let Some(inner) = chars.next() {
+                    if inner == '/' && chars.peek() == Some(&'*') {
+                        chars.next();
+                        nesting += 1;
+                    } else if inner == '*' && chars.peek() == Some(&'/') {
+                        chars.next();
+                        nesting -= 1;
+                        if nesting == 0 {
+                            break;
+                        }
+                    }
+                }
+            }
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            _ => {}
+        }
+    }
+
+    if !word.is_empty() {
+        words.push((word, depth));
+    }
+    words
+}
+
+// SQL Server has no read-only session mode and T-SQL doesn't need semicolons between
+// statements, so "SELECT 1 DELETE FROM t" reads as a single SELECT to classify_statement.
+// Read-only connections therefore only run single-statement batches without any writing keyword
+pub fn check_tsql_read_only(batch: &str) -> Result<(), AppError> {
+    let words = tsql_words(batch);
+    if let Some((keyword, _)) = words.iter().find(|(w, _)| TSQL_WRITE_KEYWORDS.contains(&w.as_str())) {
+        return Err(AppError::ReadOnlyViolation(format!(
+            "{} is not allowed on a read-only connection",
+            keyword
+        )));
+    }
+
+    let mut statements = split_sql(batch, SqlDialect::TSql).len();
+    for (index, (word, depth)) in words.iter().enumerate() {
+        if index == 0 || *depth != 0 || !TSQL_STATEMENT_KEYWORDS.contains(&word.as_str()) {
+            continue;
+        }
+        let previous = words[index - 1].0.as_str();
+        if word == "SELECT" && matches!(previous, "UNION" | "ALL" | "EXCEPT" | "INTERSECT") {
+            continue;
+        }
+        statements += 1;
+    }
+    if statements > 1 {
+        return Err(AppError::ReadOnlyViolation(
+            "Read-only SQL Server connections run one statement per query".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+pub fn classify_statement(statement: &str) -> StatementClassification {
+    let all_words: Vec<String> = statement_words(statement).into_iter().map(|(word, _)| word).collect();
+    let words = top_level_words(statement);
+    let mut keyword = words.first().cloned().unwrap_or_default();
+
+    // EXPLAIN ANALYZE actually runs the statement on PostgreSQL and MySQL; the option may be
+    // parenthesised, as in EXPLAIN (ANALYZE, BUFFERS) DELETE ...
+    if keyword == "EXPLAIN" && all_words.iter().any(|w| w == "ANALYZE" || w == "ANALYSE") {
+        if let Some(verb) = words.iter().skip(1).find(|w| !EXPLAIN_OPTIONS.contains(&w.as_str())) {
+            keyword = verb.clone();
+        }
+    }
+
+    // Common table expressions: the real verb follows the CTE list, and PostgreSQL CTEs
+    // may themselves modify data, as in WITH d AS (DELETE FROM t RETURNING *) SELECT ...
+    if keyword == "WITH" {
+        keyword = all_words
+            .iter()
+            .skip(1)
+            .find(|w| matches!(w.as_str(), "INSERT" | "UPDATE" | "DELETE" | "MERGE"))
+            .cloned()
+            .unwrap_or_else(|| "SELECT".to_string());
+    }
+
+    let has_where = words.iter().any(|w| w == "WHERE");
+    // SET [SESSION | LOCAL] name ...; GLOBAL and PERSIST settings are always writes
+    let setting = words
+        .iter()
+        .skip(1)
+        .find(|w| !matches!(w.as_str(), "SESSION" | "LOCAL"))
+        .map(String::as_str)
+        .unwrap_or_default();
+
+    let (risk, reason) = match keyword.as_str() {
+        "DROP" => (StatementRisk::Destructive, Some("DROP permanently removes database objects".to_string())),
+        "TRUNCATE" => (StatementRisk::Destructive, Some("TRUNCATE removes every row from the table".to_string())),
+        "DELETE" if !has_where => (StatementRisk::Destructive, Some("DELETE without WHERE removes every row".to_string())),
+        "UPDATE" if !has_where => (StatementRisk::Destructive, Some("UPDATE without WHERE modifies every row".to_string())),
+        "SELECT" if words.iter().any(|w| w == "INTO") => (StatementRisk::Write, None),
+        // PRAGMA name = value and PRAGMA name(value) both change settings, except for
+        // pragmas whose argument names the object to inspect
+        "PRAGMA" if statement.contains('=') => (StatementRisk::Write, None),
+        "PRAGMA" if statement.contains('(') && !words.last().is_some_and(|w| READ_ONLY_PRAGMAS.contains(&w.as_str())) => {
+            (StatementRisk::Write, None)
+        }
+        "PRAGMA" => (StatementRisk::ReadOnly, None),
+        // MySQL's SET NAMES x, other_variable = y changes more than the character set
+        "SET" if setting == "NAMES" && statement.contains(',') => (StatementRisk::Write, None),
+        "SET" if READ_ONLY_SETTINGS.contains(&setting) => (StatementRisk::ReadOnly, None),
+        "SET" => (StatementRisk::Write, None),
+        // BEGIN READ WRITE / START TRANSACTION READ WRITE override a read-only session
+        "BEGIN" | "START" if words.iter().any(|w| w == "WRITE") => (StatementRisk::Write, None),
+        k if READ_ONLY_KEYWORDS.contains(&k) => (StatementRisk::ReadOnly, None),
+        _ => (StatementRisk::Write, None),
+    };
+
+    StatementClassification {
+        statement: statement.to_string(),
+        keyword,
+        risk,
+        reason,
+    }
+}
+
+// MongoDB commands are JSON documents named by their first key. On read-only connections
+// only known read commands are accepted, and aggregations may not write their output
+pub fn check_mongodb_command(query: &str, read_only: bool) -> Result<(), AppError> {
+    if !read_only {
+        return Ok(());
+    }
+    let command = parse_mongodb_command(query)?;
+    let name = command.keys().next().cloned().unwrap_or_default();
+    if !MONGODB_READ_COMMANDS.contains(&name.as_str()) {
+        return Err(AppError::ReadOnlyViolation(format!(
+            "{} commands are not allowed on a read-only connection",
+            name
+        )));
+    }
+    let writes_output = command.get_array("pipeline").is_ok_and(|stages| {
+        stages.iter().any(|stage| {
+            stage
+                .as_document()
+                .is_some_and(|stage| stage.contains_key("$out") || stage.contains_key("$merge"))
+        })
+    });
+    if writes_output {
+        return Err(AppError::ReadOnlyViolation(
+            "Aggregations with $out or $merge are not allowed on a read-only connection".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+pub fn classify_query(query: &str, dialect: SqlDialect) -> QueryClassification {
+    let statements: Vec<StatementClassification> = split_statements(query, dialect)
+        .iter()
+        .map(|s| classify_statement(s))
+        .collect();
+
+    let risk = statements
+        .iter()
+        .map(|s| s.risk)
+        .max()
+        .unwrap_or(StatementRisk::ReadOnly);
+
+    QueryClassification {
+        requires_confirmation: risk == StatementRisk::Destructive,
+        statements,
+        risk,
+    }
+}
+
+// Validate a query against the connection's safety settings before it is executed
+pub fn check_query(query: &str, dialect: SqlDialect, read_only: bool, confirmed: bool) -> Result<QueryClassification, AppError> {
+    let classification = classify_query(query, dialect);
+
+    if read_only {
+        if let Some(statement) = classification
+            .statements
+            .iter()
+            .find(|s| s.risk != StatementRisk::ReadOnly)
+        {
+            return Err(AppError::ReadOnlyViolation(format!(
+                "{} statements are not allowed on a read-only connection",
+                statement.keyword
+            )));
+        }
+    }
+
+    if classification.requires_confirmation && !confirmed {
+        let reasons: Vec<String> = classification
+            .statements
+            .iter()
+            .filter_map(|s| s.reason.clone())
+            .collect();
+        return Err(AppError::ConfirmationRequired(reasons.join("; ")));
+    }
+
+    Ok(classification)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn risk(statement: &str) -> StatementRisk {
+        classify_statement(statement).risk
+    }
+
+    #[test]
+    fn cte_uses_the_verb_after_the_cte_list() {
+        assert_eq!(risk("WITH x AS (SELECT 1) SELECT * FROM x"), StatementRisk::ReadOnly);
+        assert_eq!(risk("WITH x AS (SELECT id FROM t) DELETE FROM t WHERE id IN (SELECT id FROM x)"), StatementRisk::Write);
+        assert_eq!(risk("WITH x AS (SELECT 1) DELETE FROM t"), StatementRisk::Destructive);
+        // Data-modifying CTEs hide the verb inside parentheses
+        assert_ne!(risk("WITH d AS (DELETE FROM t RETURNING *) SELECT * FROM d"), StatementRisk::ReadOnly);
+    }
+
+    #[test]
+    fn explain_analyze_is_classified_by_the_explained_statement() {
+        assert_eq!(risk("EXPLAIN DELETE FROM t"), StatementRisk::ReadOnly);
+        assert_eq!(risk("EXPLAIN ANALYZE SELECT * FROM t"), StatementRisk::ReadOnly);
+        assert_eq!(risk("EXPLAIN ANALYZE DELETE FROM t"), StatementRisk::Destructive);
+        assert_eq!(risk("EXPLAIN (ANALYZE, BUFFERS) UPDATE t SET a = 1 WHERE id = 2"), StatementRisk::Write);
+        assert_eq!(risk("EXPLAIN ANALYZE VERBOSE INSERT INTO t VALUES (1)"), StatementRisk::Write);
+        assert_ne!(risk("EXPLAIN ANALYZE WITH d AS (DELETE FROM t RETURNING *) SELECT * FROM d"), StatementRisk::ReadOnly);
+    }
+
+    #[test]
+    fn select_into_writes() {
+        assert_eq!(risk("SELECT * INTO backup FROM t"), StatementRisk::Write);
+        assert_eq!(risk("SELECT 'INTO' FROM t"), StatementRisk::ReadOnly);
+    }
+
+    #[test]
+    fn pragma_assignments_write() {
+        assert_eq!(risk("PRAGMA table_info(users)"), StatementRisk::ReadOnly);
+        assert_eq!(risk("PRAGMA main.index_list(users)"), StatementRisk::ReadOnly);
+        assert_eq!(risk("PRAGMA journal_mode"), StatementRisk::ReadOnly);
+        assert_eq!(risk("PRAGMA journal_mode = WAL"), StatementRisk::Write);
+        assert_eq!(risk("PRAGMA query_only(0)"), StatementRisk::Write);
+    }
+
+    #[test]
+    fn only_harmless_session_settings_are_read_only() {
+        assert_eq!(risk("SET search_path TO app, public"), StatementRisk::ReadOnly);
+        assert_eq!(risk("SET LOCAL TIME ZONE 'UTC'"), StatementRisk::ReadOnly);
+        assert_eq!(risk("SET NAMES utf8mb4"), StatementRisk::ReadOnly);
+        assert_eq!(risk("SET NAMES utf8mb4, transaction_read_only = 0"), StatementRisk::Write);
+        assert_eq!(risk("SET SESSION transaction_read_only = 0"), StatementRisk::Write);
+        assert_eq!(risk("SET @@global.read_only = 0"), StatementRisk::Write);
+        assert_eq!(risk("SET default_transaction_read_only = off"), StatementRisk::Write);
+        assert_eq!(risk("BEGIN READ WRITE"), StatementRisk::Write);
+        assert_eq!(risk("START TRANSACTION READ ONLY"), StatementRisk::ReadOnly);
+    }
+
+    #[test]
+    fn read_only_check_covers_every_statement() {
+        let generic = SqlDialect::Generic;
+        assert!(check_query("SELECT 1; SELECT 2", generic, true, false).is_ok());
+        assert!(check_query("SELECT 1; DELETE FROM t WHERE id = 1", generic, true, false).is_err());
+        assert!(matches!(check_query("DROP TABLE t", generic, false, false), Err(AppError::ConfirmationRequired(_))));
+        assert!(check_query("DROP TABLE t", generic, false, true).is_ok());
+    }
+
+    #[test]
+    fn mysql_hash_comments_hide_nothing() {
+        let classification = classify_query("# cleanup\nDROP TABLE t", SqlDialect::MySQL);
+        assert_eq!(classification.risk, StatementRisk::Destructive);
+        assert_eq!(classification.statements[0].keyword, "DROP");
+    }
+
+    #[test]
+    fn mysql_executable_comments_are_classified_as_sql() {
+        let mysql = SqlDialect::MySQL;
+        assert_eq!(classify_query("/*!50000 DROP TABLE t */", mysql).risk, StatementRisk::Destructive);
+        assert_eq!(classify_query("/*M! DELETE FROM t */", mysql).risk, StatementRisk::Destructive);
+        assert_eq!(classify_query("SELECT 1 /*!, (SELECT 2) */", mysql).risk, StatementRisk::ReadOnly);
+        assert_eq!(classify_query("/*! SELECT 1; DROP TABLE t */", mysql).statements.len(), 2);
+        assert!(check_query("SELECT 1 /*!; UPDATE t SET a = 1 WHERE id = 2 */", mysql, true, false).is_err());
+        // Plain comments are still ignored
+        assert_eq!(classify_query("/* DROP TABLE t */ SELECT 1", mysql).risk, StatementRisk::ReadOnly);
+    }
+
+    #[test]
+    fn tsql_batches_without_semicolons_are_checked() {
+        assert!(check_tsql_read_only("SELECT * FROM t WHERE a IN (SELECT b FROM u) UNION SELECT 1").is_ok());
+        assert!(check_tsql_read_only("SELECT 1 DELETE FROM t").is_err());
+        assert!(check_tsql_read_only("SELECT 1 SELECT 2").is_err());
+        assert!(check_tsql_read_only("SELECT * INTO backup FROM t").is_err());
+        assert!(check_tsql_read_only("EXEC sp_who").is_err());
+        // Keywords inside literals, identifiers and nested comments don't count
+        assert!(check_tsql_read_only("SELECT 'DELETE', [drop] FROM t /* a /* DELETE */ b */").is_ok());
+    }
+
+    #[test]
+    fn mongodb_read_only_commands() {
+        assert!(check_mongodb_command(r#"{"find": "users"}"#, true).is_ok());
+        assert!(check_mongodb_command(r#"{"delete": "users", "deletes": []}"#, true).is_err());
+        assert!(check_mongodb_command(r#"{"aggregate": "users", "pipeline": [{"$out": "copy"}]}"#, true).is_err());
+        assert!(check_mongodb_command(r#"{"delete": "users", "deletes": []}"#, false).is_ok());
+    }
+}
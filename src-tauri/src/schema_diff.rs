@@ -0,0 +1,758 @@
+use crate::database::{
+    quote_identifier, quote_mssql_identifier, ColumnInfo, DatabaseConnection, DatabaseSchema, DatabaseType, ForeignKeyInfo, IndexInfo, TableInfo,
+};
+use crate::error::AppError;
+use crate::snapshots::{self, SchemaSnapshot, SchemaSnapshots};
+use serde::{Deserialize, Serialize};
+
+// Where one side of a comparison comes from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum SchemaSource {
+    // The active connection
+    Current,
+    // A second connection, opened only for the comparison
+    Connection(crate::DatabaseConfig),
+    // A JSON file containing `get_schema` output or an exported snapshot
+    File(String),
+    // A snapshot id from local snapshot storage
+    Snapshot(i64),
+    Schema(DatabaseSchema),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ColumnChange {
+    pub name: String,
+    pub from: ColumnInfo,
+    pub to: ColumnInfo,
+    pub type_changed: bool,
+    pub nullability_changed: bool,
+    pub default_changed: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TableDiff {
+    pub name: String,
+    pub added_columns: Vec<ColumnInfo>,
+    pub removed_columns: Vec<ColumnInfo>,
+    pub changed_columns: Vec<ColumnChange>,
+    pub primary_key_changed: bool,
+    pub added_indexes: Vec<IndexInfo>,
+    pub removed_indexes: Vec<IndexInfo>,
+    pub added_foreign_keys: Vec<ForeignKeyInfo>,
+    pub removed_foreign_keys: Vec<ForeignKeyInfo>,
+}
+
+// Differences that turn `from` into `to`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SchemaDiff {
+    pub added_tables: Vec<TableInfo>,
+    pub removed_tables: Vec<TableInfo>,
+    pub changed_tables: Vec<TableDiff>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MigrationScript {
+    pub dialect: String,
+    pub statements: Vec<String>,
+    // Changes the dialect can't express as plain DDL
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SchemaComparison {
+    pub identical: bool,
+    pub diff: SchemaDiff,
+    pub migration: Option<MigrationScript>,
+}
+
+impl SchemaDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added_tables.is_empty() && self.removed_tables.is_empty() && self.changed_tables.is_empty()
+    }
+}
+
+impl TableDiff {
+    fn is_empty(&self) -> bool {
+        self.added_columns.is_empty()
+            && self.removed_columns.is_empty()
+            && self.changed_columns.is_empty()
+            && !self.primary_key_changed
+            && self.added_indexes.is_empty()
+            && self.removed_indexes.is_empty()
+            && self.added_foreign_keys.is_empty()
+            && self.removed_foreign_keys.is_empty()
+    }
+}
+
+// Resolve a source to a schema and, when known, the backend it came from
+pub async fn load_schema(
+    source: &SchemaSource,
+    current: Option<&DatabaseConnection>,
+    snapshots: Option<&SchemaSnapshots>,
+) -> Result<(DatabaseSchema, Option<DatabaseType>), AppError> {
+    match source {
+        SchemaSource::Current => {
+            let connection = current.ok_or(AppError::DatabaseNotConnected)?;
+            Ok((connection.get_schema().await?, Some(connection.db_type())))
+        }
+        SchemaSource::Connection(config) => {
+            let connection = DatabaseConnection::new(config).await?;
+            let schema = connection.get_schema().await;
+            connection.close().await;
+            Ok((schema?, Some(config.db_type.clone())))
+        }
+        SchemaSource::File(path) => {
+            let content = std::fs::read_to_string(path)?;
+            if let Ok(snapshot) = serde_json::from_str::<SchemaSnapshot>(&content) {
+                return Ok((snapshot.schema, snapshots::parse_db_type(&snapshot.summary.db_type)));
+            }
+            let schema = serde_json::from_str(&content)
+                .map_err(|e| AppError::InvalidConfiguration(format!("Invalid schema file {}: {}", path, e)))?;
+            Ok((schema, None))
+        }
+        SchemaSource::Snapshot(id) => {
+            let store = snapshots.ok_or_else(|| AppError::InternalError("Schema snapshots are not available".to_string()))?;
+            let snapshot = store.get(*id).await?;
+            Ok((snapshot.schema, snapshots::parse_db_type(&snapshot.summary.db_type)))
+        }
+        SchemaSource::Schema(schema) => Ok((schema.clone(), None)),
+    }
+}
+
+pub async fn compare(
+    from: &SchemaSource,
+    to: &SchemaSource,
+    dialect: Option<DatabaseType>,
+    current: Option<&DatabaseConnection>,
+    snapshots: Option<&SchemaSnapshots>,
+) -> Result<SchemaComparison, AppError> {
+    let (from_schema, from_type) = load_schema(from, current, snapshots).await?;
+    let (to_schema, to_type) = load_schema(to, current, snapshots).await?;
+    let diff = diff_schemas(&from_schema, &to_schema);
+
+    // The migration runs against `from`, so its dialect wins
+    let migration = match dialect.or(from_type).or(to_type) {
+        Some(DatabaseType::MongoDB | DatabaseType::Redis) | None => None,
+        Some(db_type) => Some(migration_sql(&diff, &from_schema, &to_schema, &db_type)?),
+    };
+
+    Ok(SchemaComparison {
+        identical: diff.is_empty(),
+        diff,
+        migration,
+    })
+}
+
+fn column_type(column: &ColumnInfo) -> &str {
+    column.column_type.as_deref().unwrap_or(&column.data_type)
+}
+
+// EXTRA also flags expression defaults and generated columns, which MODIFY can't restate as
+// attributes; auto_increment, on update and INVISIBLE are kept
+fn mysql_column_attributes(extra: &str) -> String {
+    let mut attributes = extra.to_string();
+    for flag in ["DEFAULT_GENERATED", "VIRTUAL GENERATED", "STORED GENERATED", "PERSISTENT GENERATED"] {
+        attributes = attributes.replace(flag, "");
+    }
+    attributes.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn normalize(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
+fn primary_key(table: &TableInfo) -> Vec<&str> {
+    table.columns.iter().filter(|c| c.is_primary_key).map(|c| c.name.as_str()).collect()
+}
+
+fn same_index(a: &IndexInfo, b: &IndexInfo) -> bool {
+    a.columns == b.columns && a.is_unique == b.is_unique
+}
+
+// Foreign keys are matched by what they reference since SQLite keys have no names
+fn fk_signature(fk: &ForeignKeyInfo) -> (Vec<String>, String) {
+    (
+        fk.columns.iter().map(|c| c.to_lowercase()).collect(),
+        fk.referenced_table.to_lowercase(),
+    )
+}
+
+fn same_foreign_key(a: &ForeignKeyInfo, b: &ForeignKeyInfo) -> bool {
+    let action = |v: &Option<String>| v.as_deref().map(normalize).unwrap_or_else(|| "no action".to_string());
+    a.referenced_columns == b.referenced_columns
+        && action(&a.on_delete) == action(&b.on_delete)
+        && action(&a.on_update) == action(&b.on_update)
+}
+
+pub fn diff_table(from: &TableInfo, to: &TableInfo) -> TableDiff {
+    let added_columns = to
+        .columns
+        .iter()
+        .filter(|c| !from.columns.iter().any(|f| f.name == c.name))
+        .cloned()
+        .collect();
+    let removed_columns = from
+        .columns
+        .iter()
+        .filter(|c| !to.columns.iter().any(|t| t.name == c.name))
+        .cloned()
+        .collect();
+
+    let mut changed_columns = Vec::new();
+    for old in &from.columns {
+        if let Some(new) = to.columns.iter().find(|c| c.name == old.name) {
+            let type_changed = normalize(column_type(old)) != normalize(column_type(new));
+            let nullability_changed = old.is_nullable != new.is_nullable;
+            let default_changed = old.default_value.as_deref().map(normalize) != new.default_value.as_deref().map(normalize);
+            if type_changed || nullability_changed || default_changed {
+                changed_columns.push(ColumnChange {
+                    name: old.name.clone(),
+                    from: old.clone(),
+                    to: new.clone(),
+                    type_changed,
+                    nullability_changed,
+                    default_changed,
+                });
+            }
+        }
+    }
+
+    // Primary key indexes are covered by primary_key_changed; a changed index is dropped and recreated
+    let mut added_indexes = Vec::new();
+    let mut removed_indexes = Vec::new();
+    for index in to.indexes.iter().filter(|i| !i.is_primary) {
+        match from.indexes.iter().find(|i| i.name == index.name) {
+            Some(old) if same_index(old, index) => {}
+            Some(old) => {
+                removed_indexes.push(old.clone());
+                added_indexes.push(index.clone());
+            }
+            None => added_indexes.push(index.clone()),
+        }
+    }
+    for index in from.indexes.iter().filter(|i| !i.is_primary) {
+        if !to.indexes.iter().any(|i| i.name == index.name) {
+            removed_indexes.push(index.clone());
+        }
+    }
+
+    let mut added_foreign_keys = Vec::new();
+    let mut removed_foreign_keys = Vec::new();
+    for fk in &to.foreign_keys {
+        match from.foreign_keys.iter().find(|f| fk_signature(f) == fk_signature(fk)) {
+            Some(old) if same_foreign_key(old, fk) => {}
+            Some(old) => {
+                removed_foreign_keys.push(old.clone());
+                added_foreign_keys.push(fk.clone());
+            }
+            None => added_foreign_keys.push(fk.clone()),
+        }
+    }
+    for fk in &from.foreign_keys {
+        if !to.foreign_keys.iter().any(|f| fk_signature(f) == fk_signature(fk)) {
+            removed_foreign_keys.push(fk.clone());
+        }
+    }
+
+    TableDiff {
+        name: to.name.clone(),
+        added_columns,
+        removed_columns,
+        changed_columns,
+        primary_key_changed: primary_key(from) != primary_key(to),
+        added_indexes,
+        removed_indexes,
+        added_foreign_keys,
+        removed_foreign_keys,
+    }
+}
+
+pub fn diff_schemas(from: &DatabaseSchema, to: &DatabaseSchema) -> SchemaDiff {
+    let added_tables = to
+        .tables
+        .iter()
+        .filter(|t| !from.tables.iter().any(|f| f.name == t.name))
+        .cloned()
+        .collect();
+    let removed_tables = from
+        .tables
+        .iter()
+        .filter(|t| !to.tables.iter().any(|n| n.name == t.name))
+        .cloned()
+        .collect();
+    let changed_tables = from
+        .tables
+        .iter()
+        .filter_map(|old| to.tables.iter().find(|t| t.name == old.name).map(|new| diff_table(old, new)))
+        .filter(|diff| !diff.is_empty())
+        .collect();
+
+    SchemaDiff {
+        added_tables,
+        removed_tables,
+        changed_tables,
+    }
+}
+
+// Generates DDL for one dialect from a SchemaDiff
+struct MigrationWriter {
+    db_type: DatabaseType,
+    statements: Vec<String>,
+    warnings: Vec<String>,
+}
+
+impl MigrationWriter {
+    fn quote(&self, name: &str) -> String {
+        match self.db_type {
+            DatabaseType::MySQL => quote_identifier(name, '`'),
+            DatabaseType::SQLServer => quote_mssql_identifier(name),
+            DatabaseType::PostgreSQL | DatabaseType::SQLite | DatabaseType::DuckDB => quote_identifier(name, '"'),
+            DatabaseType::MongoDB | DatabaseType::Redis => unreachable!(),
+        }
+    }
+
+    // SQLite and DuckDB can't add or drop foreign keys on an existing table
+    fn inline_foreign_keys(&self) -> bool {
+        matches!(self.db_type, DatabaseType::SQLite | DatabaseType::DuckDB)
+    }
+
+    fn rebuild_warning(&mut self, action: String) {
+        self.warnings.push(format!("{} can't {}; the table must be rebuilt", self.db_type.dialect_name(), action));
+    }
+
+    fn quote_list(&self, names: &[String]) -> String {
+        names.iter().map(|n| self.quote(n)).collect::<Vec<_>>().join(", ")
+    }
+
+    fn column_definition(&self, column: &ColumnInfo) -> String {
+        let mut data_type = column_type(column).to_string();
+        let mut default_value = column.default_value.clone();
+
+        // Sequence-backed defaults name a sequence that may not exist on the other side
+        if let (DatabaseType::PostgreSQL, Some(default)) = (&self.db_type, &default_value) {
+            if default.starts_with("nextval(") {
+                let serial = match data_type.as_str() {
+                    "smallint" => Some("smallserial"),
+                    "integer" => Some("serial"),
+                    "bigint" => Some("bigserial"),
+                    _ => None,
+                };
+                if let Some(serial) = serial {
+                    data_type = serial.to_string();
+                    default_value = None;
+                }
+            }
+        }
+
+        let mut definition = format!("{} {}", self.quote(&column.name), data_type);
+        // A collation implies its character set
+        if let (DatabaseType::MySQL, Some(collation)) = (&self.db_type, &column.collation) {
+            definition.push_str(&format!(" COLLATE {}", collation));
+        }
+        if !column.is_nullable {
+            definition.push_str(" NOT NULL");
+        }
+        if let Some(default_value) = default_value {
+            definition.push_str(&format!(" DEFAULT {}", default_value));
+        }
+        if let DatabaseType::MySQL = self.db_type {
+            if let Some(extra) = column.extra.as_deref().map(mysql_column_attributes).filter(|e| !e.is_empty()) {
+                definition.push_str(&format!(" {}", extra));
+            }
+            if let Some(comment) = &column.comment {
+                definition.push_str(&format!(" COMMENT '{}'", comment.replace('\\', "\\\\").replace('\'', "''")));
+            }
+        }
+        definition
+    }
+
+    fn foreign_key_clause(&self, fk: &ForeignKeyInfo) -> String {
+        let mut clause = format!(
+            "FOREIGN KEY ({}) REFERENCES {} ({})",
+            self.quote_list(&fk.columns),
+            self.quote(&fk.referenced_table),
+            self.quote_list(&fk.referenced_columns)
+        );
+        for (action, rule) in [("DELETE", &fk.on_delete), ("UPDATE", &fk.on_update)] {
+            if let Some(rule) = rule.as_deref().filter(|r| !r.eq_ignore_ascii_case("NO ACTION")) {
+                clause.push_str(&format!(" ON {} {}", action, rule));
+            }
+        }
+        clause
+    }
+
+    fn create_index(&mut self, table: &str, index: &IndexInfo) {
+        if index.columns.iter().any(|c| c == "<expression>") {
+            self.warnings.push(format!("Index {} on {} uses an expression and must be recreated by hand", index.name, table));
+            return;
+        }
+        // Names of SQLite's implicit UNIQUE constraint indexes are reserved
+        let name = if index.name.starts_with("sqlite_autoindex_") {
+            format!("{}_{}_key", table, index.columns.join("_"))
+        } else {
+            index.name.clone()
+        };
+        self.statements.push(format!(
+            "CREATE {}INDEX {} ON {} ({});",
+            if index.is_unique { "UNIQUE " } else { "" },
+            self.quote(&name),
+            self.quote(table),
+            self.quote_list(&index.columns)
+        ));
+    }
+
+    fn drop_index(&mut self, table: &str, index: &IndexInfo) {
+        if index.name.starts_with("sqlite_autoindex_") {
+            self.warnings.push(format!("Index {} backs a UNIQUE constraint on {}; the table must be rebuilt", index.name, table));
+            return;
+        }
+        self.statements.push(match self.db_type {
+            DatabaseType::MySQL | DatabaseType::SQLServer => format!("DROP INDEX {} ON {};", self.quote(&index.name), self.quote(table)),
+            DatabaseType::PostgreSQL | DatabaseType::SQLite | DatabaseType::DuckDB => format!("DROP INDEX {};", self.quote(&index.name)),
+            DatabaseType::MongoDB | DatabaseType::Redis => unreachable!(),
+        });
+    }
+
+    fn create_table(&mut self, table: &TableInfo) {
+        let mut lines: Vec<String> = table.columns.iter().map(|c| format!("    {}", self.column_definition(c))).collect();
+        let primary_key: Vec<String> = primary_key(table).iter().map(|c| c.to_string()).collect();
+        if !primary_key.is_empty() {
+            lines.push(format!("    PRIMARY KEY ({})", self.quote_list(&primary_key)));
+        }
+        if self.inline_foreign_keys() {
+            for fk in &table.foreign_keys {
+                lines.push(format!("    {}", self.foreign_key_clause(fk)));
+            }
+        }
+        self.statements.push(format!("CREATE TABLE {} (\n{}\n);", self.quote(&table.name), lines.join(",\n")));
+        for index in table.indexes.iter().filter(|i| !i.is_primary) {
+            self.create_index(&table.name, index);
+        }
+    }
+
+    fn add_foreign_key(&mut self, table: &str, fk: &ForeignKeyInfo) {
+        if self.inline_foreign_keys() {
+            self.rebuild_warning(format!("add foreign key {} to existing table {}", fk.name, table));
+            return;
+        }
+        self.statements.push(format!(
+            "ALTER TABLE {} ADD CONSTRAINT {} {};",
+            self.quote(table),
+            self.quote(&fk.name),
+            self.foreign_key_clause(fk)
+        ));
+    }
+
+    fn drop_foreign_key(&mut self, table: &str, fk: &ForeignKeyInfo) {
+        match self.db_type {
+            DatabaseType::MySQL => self.statements.push(format!("ALTER TABLE {} DROP FOREIGN KEY {};", self.quote(table), self.quote(&fk.name))),
+            DatabaseType::PostgreSQL | DatabaseType::SQLServer => {
+                self.statements.push(format!("ALTER TABLE {} DROP CONSTRAINT {};", self.quote(table), self.quote(&fk.name)))
+            }
+            DatabaseType::SQLite | DatabaseType::DuckDB => {
+                self.rebuild_warning(format!("drop foreign key {} from table {}", fk.name, table))
+            }
+            DatabaseType::MongoDB | DatabaseType::Redis => unreachable!(),
+        }
+    }
+
+    fn alter_column(&mut self, table: &str, change: &ColumnChange) {
+        let quoted_table = self.quote(table);
+        let column = self.quote(&change.name);
+        match self.db_type {
+            // MODIFY restates the full definition
+            DatabaseType::MySQL => self.statements.push(format!(
+                "ALTER TABLE {} MODIFY COLUMN {};",
+                quoted_table,
+                self.column_definition(&change.to)
+            )),
+            // ALTER COLUMN restates type and nullability; defaults are separately named constraints
+            DatabaseType::SQLServer => {
+                if change.type_changed || change.nullability_changed {
+                    self.statements.push(format!(
+                        "ALTER TABLE {} ALTER COLUMN {} {} {};",
+                        quoted_table,
+                        column,
+                        column_type(&change.to),
+                        if change.to.is_nullable { "NULL" } else { "NOT NULL" }
+                    ));
+                }
+                if change.default_changed {
+                    self.warnings.push(format!(
+                        "The default of {}.{} is a named constraint in SQL Server and must be changed by hand",
+                        table, change.name
+                    ));
+                }
+            }
+            DatabaseType::PostgreSQL | DatabaseType::DuckDB => {
+                if change.type_changed {
+                    let new_type = column_type(&change.to);
+                    self.statements.push(format!(
+                        "ALTER TABLE {} ALTER COLUMN {} TYPE {} USING {}::{};",
+                        quoted_table, column, new_type, column, new_type
+                    ));
+                }
+                if change.nullability_changed {
+                    let action = if change.to.is_nullable { "DROP NOT NULL" } else { "SET NOT NULL" };
+                    self.statements.push(format!("ALTER TABLE {} ALTER COLUMN {} {};", quoted_table, column, action));
+                }
+                if change.default_changed {
+                    self.statements.push(match &change.to.default_value {
+                        Some(default_value) => format!("ALTER TABLE {} ALTER COLUMN {} SET DEFAULT {};", quoted_table, column, default_value),
+                        None => format!("ALTER TABLE {} ALTER COLUMN {} DROP DEFAULT;", quoted_table, column),
+                    });
+                }
+            }
+            DatabaseType::SQLite => self.rebuild_warning(format!("alter column {}.{}", table, change.name)),
+            DatabaseType::MongoDB | DatabaseType::Redis => unreachable!(),
+        }
+    }
+
+    fn change_primary_key(&mut self, table: &TableDiff, from: &TableInfo, to: &TableInfo) {
+        let columns: Vec<String> = primary_key(to).iter().map(|c| c.to_string()).collect();
+        let quoted_table = self.quote(&table.name);
+        match self.db_type {
+            DatabaseType::MySQL => {
+                let mut parts = Vec::new();
+                if !primary_key(from).is_empty() {
+                    parts.push("DROP PRIMARY KEY".to_string());
+                }
+                if !columns.is_empty() {
+                    parts.push(format!("ADD PRIMARY KEY ({})", self.quote_list(&columns)));
+                }
+                self.statements.push(format!("ALTER TABLE {} {};", quoted_table, parts.join(", ")));
+            }
+            DatabaseType::PostgreSQL | DatabaseType::SQLServer => {
+                if let Some(index) = from.indexes.iter().find(|i| i.is_primary) {
+                    self.statements.push(format!("ALTER TABLE {} DROP CONSTRAINT {};", quoted_table, self.quote(&index.name)));
+                }
+                if !columns.is_empty() {
+                    self.statements.push(format!("ALTER TABLE {} ADD PRIMARY KEY ({});", quoted_table, self.quote_list(&columns)));
+                }
+            }
+            DatabaseType::SQLite | DatabaseType::DuckDB => {
+                self.rebuild_warning(format!("change the primary key of {}", table.name))
+            }
+            DatabaseType::MongoDB | DatabaseType::Redis => unreachable!(),
+        }
+    }
+}
+
+// DDL that migrates `from` to `to`, ordered so dependencies are satisfied:
+// foreign keys and indexes are dropped first and recreated last
+pub fn migration_sql(diff: &SchemaDiff, from: &DatabaseSchema, to: &DatabaseSchema, db_type: &DatabaseType) -> Result<MigrationScript, AppError> {
+    if let DatabaseType::MongoDB | DatabaseType::Redis = db_type {
+        return Err(AppError::InvalidConfiguration(format!("Migration DDL can't be generated for {}", db_type.dialect_name())));
+    }
+
+    let mut writer = MigrationWriter {
+        db_type: db_type.clone(),
+        statements: Vec::new(),
+        warnings: Vec::new(),
+    };
+    let find = |schema: &DatabaseSchema, name: &str| schema.tables.iter().find(|t| t.name == name).cloned();
+
+    for table in &diff.changed_tables {
+        for fk in &table.removed_foreign_keys {
+            writer.drop_foreign_key(&table.name, fk);
+        }
+        for index in &table.removed_indexes {
+            writer.drop_index(&table.name, index);
+        }
+    }
+    if !writer.inline_foreign_keys() {
+        for table in &diff.removed_tables {
+            for fk in &table.foreign_keys {
+                writer.drop_foreign_key(&table.name, fk);
+            }
+        }
+    }
+    for table in &diff.removed_tables {
+        writer.statements.push(format!("DROP TABLE {};", writer.quote(&table.name)));
+    }
+
+    for table in &diff.added_tables {
+        writer.create_table(table);
+    }
+
+    for table in &diff.changed_tables {
+        for column in &table.added_columns {
+            let definition = writer.column_definition(column);
+            // T-SQL has no COLUMN keyword in ADD
+            let add = if let DatabaseType::SQLServer = db_type { "ADD" } else { "ADD COLUMN" };
+            writer.statements.push(format!("ALTER TABLE {} {} {};", writer.quote(&table.name), add, definition));
+        }
+        for change in &table.changed_columns {
+            writer.alter_column(&table.name, change);
+        }
+        if table.primary_key_changed {
+            if let (Some(old), Some(new)) = (find(from, &table.name), find(to, &table.name)) {
+                writer.change_primary_key(table, &old, &new);
+            }
+        }
+        for column in &table.removed_columns {
+            writer.statements.push(format!("ALTER TABLE {} DROP COLUMN {};", writer.quote(&table.name), writer.quote(&column.name)));
+        }
+        for index in &table.added_indexes {
+            writer.create_index(&table.name, index);
+        }
+    }
+
+    if !writer.inline_foreign_keys() {
+        for table in &diff.added_tables {
+            for fk in &table.foreign_keys {
+                writer.add_foreign_key(&table.name, fk);
+            }
+        }
+    }
+    for table in &diff.changed_tables {
+        for fk in &table.added_foreign_keys {
+            writer.add_foreign_key(&table.name, fk);
+        }
+    }
+
+    Ok(MigrationScript {
+        dialect: db_type.as_str().to_string(),
+        statements: writer.statements,
+        warnings: writer.warnings,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn column(name: &str, data_type: &str, nullable: bool) -> ColumnInfo {
+        ColumnInfo {
+            name: name.to_string(),
+            data_type: data_type.to_string(),
+            is_nullable: nullable,
+            is_primary_key: false,
+            column_type: None,
+            default_value: None,
+            extra: None,
+            comment: None,
+            collation: None,
+        }
+    }
+
+    fn key(name: &str) -> ColumnInfo {
+        ColumnInfo {
+            is_primary_key: true,
+            ..column(name, "integer", false)
+        }
+    }
+
+    fn index(table: &str, name: &str, columns: &[&str], unique: bool) -> IndexInfo {
+        IndexInfo {
+            name: name.to_string(),
+            table: table.to_string(),
+            columns: columns.iter().map(|c| c.to_string()).collect(),
+            is_unique: unique,
+            is_primary: false,
+        }
+    }
+
+    fn foreign_key(name: &str, column: &str, referenced_table: &str, on_delete: Option<&str>) -> ForeignKeyInfo {
+        ForeignKeyInfo {
+            name: name.to_string(),
+            columns: vec![column.to_string()],
+            referenced_table: referenced_table.to_string(),
+            referenced_columns: vec!["id".to_string()],
+            on_delete: on_delete.map(str::to_string),
+            on_update: None,
+        }
+    }
+
+    fn table(name: &str, columns: Vec<ColumnInfo>) -> TableInfo {
+        TableInfo {
+            name: name.to_string(),
+            columns,
+            indexes: Vec::new(),
+            foreign_keys: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn columns_are_compared_after_normalizing() {
+        let from = table("t", vec![key("id"), column("name", "VARCHAR(20)", true), column("age", "integer", true)]);
+        let mut to = table("t", vec![key("id"), column("name", "varchar(20)", true), column("age", "bigint", false)]);
+        to.columns.push(column("email", "text", true));
+
+        let diff = diff_table(&from, &to);
+        assert_eq!(diff.added_columns.len(), 1);
+        assert!(diff.removed_columns.is_empty());
+        assert_eq!(diff.changed_columns.len(), 1);
+        let change = &diff.changed_columns[0];
+        assert_eq!(change.name, "age");
+        assert!(change.type_changed && change.nullability_changed && !change.default_changed);
+        assert!(!diff.primary_key_changed);
+    }
+
+    #[test]
+    fn indexes_and_foreign_keys_are_matched_by_shape() {
+        let mut from = table("orders", vec![key("id"), column("customer_id", "integer", false)]);
+        from.indexes = vec![index("orders", "by_customer", &["customer_id"], false)];
+        from.foreign_keys = vec![foreign_key("fk_1", "customer_id", "customers", Some("NO ACTION"))];
+        let mut to = from.clone();
+        // A renamed foreign key with the same meaning is no change; a changed index is recreated
+        to.foreign_keys = vec![foreign_key("orders_customer", "customer_id", "Customers", None)];
+        to.indexes = vec![index("orders", "by_customer", &["customer_id"], true)];
+
+        let diff = diff_table(&from, &to);
+        assert!(diff.added_foreign_keys.is_empty() && diff.removed_foreign_keys.is_empty());
+        assert_eq!(diff.removed_indexes.len(), 1);
+        assert_eq!(diff.added_indexes.len(), 1);
+
+        to.foreign_keys[0].on_delete = Some("CASCADE".to_string());
+        let diff = diff_table(&from, &to);
+        assert_eq!(diff.removed_foreign_keys[0].name, "fk_1");
+        assert_eq!(diff.added_foreign_keys[0].name, "orders_customer");
+    }
+
+    #[test]
+    fn postgres_migration_orders_dependencies() {
+        let customers = table("customers", vec![key("id")]);
+        let mut old_orders = table("orders", vec![key("id"), column("customer_id", "integer", false)]);
+        old_orders.foreign_keys = vec![foreign_key("orders_customer", "customer_id", "customers", None)];
+        let mut legacy = table("legacy", vec![key("id"), column("order_id", "integer", true)]);
+        legacy.foreign_keys = vec![foreign_key("legacy_order", "order_id", "orders", None)];
+        let mut new_orders = old_orders.clone();
+        new_orders.columns[1].is_nullable = true;
+        new_orders.foreign_keys = vec![foreign_key("orders_customer", "customer_id", "customers", Some("SET NULL"))];
+        let from = DatabaseSchema { tables: vec![customers.clone(), old_orders, legacy] };
+        let to = DatabaseSchema { tables: vec![customers, new_orders] };
+
+        let script = migration_sql(&diff_schemas(&from, &to), &from, &to, &DatabaseType::PostgreSQL).unwrap();
+        assert_eq!(
+            script.statements,
+            vec![
+                "ALTER TABLE \"orders\" DROP CONSTRAINT \"orders_customer\";",
+                "ALTER TABLE \"legacy\" DROP CONSTRAINT \"legacy_order\";",
+                "DROP TABLE \"legacy\";",
+                "ALTER TABLE \"orders\" ALTER COLUMN \"customer_id\" DROP NOT NULL;",
+                "ALTER TABLE \"orders\" ADD CONSTRAINT \"orders_customer\" FOREIGN KEY (\"customer_id\") REFERENCES \"customers\" (\"id\") ON DELETE SET NULL;",
+            ]
+        );
+        assert!(script.warnings.is_empty());
+    }
+
+    #[test]
+    fn column_changes_follow_the_dialect() {
+        let from = DatabaseSchema { tables: vec![table("t", vec![key("id"), column("n", "integer", true)])] };
+        let to = DatabaseSchema { tables: vec![table("t", vec![key("id"), column("n", "text", true)])] };
+        let diff = diff_schemas(&from, &to);
+
+        let sqlite = migration_sql(&diff, &from, &to, &DatabaseType::SQLite).unwrap();
+        assert!(sqlite.statements.is_empty());
+        assert_eq!(sqlite.warnings, vec!["SQLite can't alter column t.n; the table must be rebuilt"]);
+
+        let mssql = migration_sql(&diff, &from, &to, &DatabaseType::SQLServer).unwrap();
+        assert_eq!(mssql.statements, vec!["ALTER TABLE [t] ALTER COLUMN [n] text NULL;"]);
+
+        let duckdb = migration_sql(&diff, &from, &to, &DatabaseType::DuckDB).unwrap();
+        assert_eq!(duckdb.statements, vec!["ALTER TABLE \"t\" ALTER COLUMN \"n\" TYPE text USING \"n\"::text;"]);
+
+        assert!(migration_sql(&diff, &from, &to, &DatabaseType::Redis).is_err());
+    }
+}
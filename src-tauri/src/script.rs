@@ -0,0 +1,502 @@
+use crate::database::DatabaseConnection;
+use crate::error::AppError;
+use crate::splitter::{SplitStatement, SqlDialect, StatementSplitter};
+use flate2::read::MultiGzDecoder;
+use serde::{Deserialize, Serialize};
+use sqlx::pool::PoolConnection;
+use sqlx::{Executor, MySql, Postgres, Sqlite};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+const DEFAULT_BATCH_SIZE: usize = 500;
+const PROGRESS_INTERVAL: u64 = 100;
+const MAX_REPORTED_ERRORS: usize = 1000;
+// Statement text included in error reports
+const MAX_STATEMENT_PREVIEW: usize = 500;
+const COPY_CHUNK_SIZE: usize = 64 * 1024;
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunSqlFileOptions {
+    pub path: String,
+    // Detected from the gzip magic bytes when omitted
+    pub gzip: Option<bool>,
+    // Wrap statements in transactions of `batch_size` statements
+    #[serde(default = "default_true")]
+    pub use_transaction: bool,
+    pub batch_size: Option<usize>,
+    #[serde(default = "default_true")]
+    pub stop_on_error: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SqlFileProgress {
+    pub path: String,
+    // Bytes read from disk, compressed bytes for gzipped files
+    pub bytes_read: u64,
+    pub total_bytes: u64,
+    pub statements_executed: u64,
+    pub statements_failed: u64,
+    pub finished: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SqlStatementError {
+    pub path: String,
+    // 1-based position of the statement in the file
+    pub statement_number: u64,
+    pub line: usize,
+    pub statement: String,
+    pub error: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SqlFileSummary {
+    pub statements_executed: u64,
+    pub statements_failed: u64,
+    pub rows_affected: u64,
+    pub errors: Vec<SqlStatementError>,
+    // True when execution stopped at the first error
+    pub stopped: bool,
+    pub duration_ms: u64,
+}
+
+fn script_error(err: impl std::fmt::Display) -> AppError {
+    AppError::FileOperationFailed(format!("SQL file failed: {}", err))
+}
+
+// Counts bytes pulled from the file so progress can be reported against its size
+struct CountingReader<R> {
+    inner: R,
+    count: Arc<AtomicU64>,
+}
+
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.count.fetch_add(n as u64, Ordering::Relaxed);
+        Ok(n)
+    }
+}
+
+fn open_script(options: &RunSqlFileOptions) -> Result<(Box<dyn BufRead + Send>, Arc<AtomicU64>), AppError> {
+    let gzip = match options.gzip {
+        Some(gzip) => gzip,
+        None => {
+            let mut magic = [0u8; 2];
+            let read = File::open(&options.path)?.read(&mut magic)?;
+            read == 2 && magic == [0x1f, 0x8b]
+        }
+    };
+
+    let count = Arc::new(AtomicU64::new(0));
+    let file = CountingReader {
+        inner: File::open(&options.path)?,
+        count: count.clone(),
+    };
+    let reader: Box<dyn BufRead + Send> = if gzip {
+        Box::new(BufReader::new(MultiGzDecoder::new(BufReader::new(file))))
+    } else {
+        Box::new(BufReader::new(file))
+    };
+    Ok((reader, count))
+}
+
+// Scripts run on one pooled connection so session state and transactions carry over
+enum ScriptConnection {
+    MySQL(PoolConnection<MySql>),
+    PostgreSQL(PoolConnection<Postgres>),
+    SQLite(PoolConnection<Sqlite>),
+}
+
+impl ScriptConnection {
+    async fn execute(&mut self, sql: &str) -> Result<u64, sqlx::Error> {
+        match self {
+            ScriptConnection::MySQL(conn) => (&mut **conn).execute(sql).await.map(|r| r.rows_affected()),
+            ScriptConnection::PostgreSQL(conn) => (&mut **conn).execute(sql).await.map(|r| r.rows_affected()),
+            ScriptConnection::SQLite(conn) => (&mut **conn).execute(sql).await.map(|r| r.rows_affected()),
+        }
+    }
+
+    // Closed rather than returned to the pool: the script may have left a transaction open or
+    // changed session state (USE, SET search_path, PRAGMA foreign_keys, ...)
+    async fn close(self) {
+        let _ = match self {
+            ScriptConnection::MySQL(conn) => conn.close().await,
+            ScriptConnection::PostgreSQL(conn) => conn.close().await,
+            ScriptConnection::SQLite(conn) => conn.close().await,
+        };
+    }
+}
+
+fn leading_words(sql: &str, count: usize) -> Vec<String> {
+    sql.split(|c: char| c.is_whitespace() || c == ';' || c == '(')
+        .filter(|w| !w.is_empty())
+        .take(count)
+        .map(|w| w.to_uppercase())
+        .collect()
+}
+
+// Some(true) for statements that open a transaction, Some(false) for ones that end it
+fn transaction_control(words: &[String]) -> Option<bool> {
+    let first = words.first().map(|w| w.as_str()).unwrap_or_default();
+    let second = words.get(1).map(|w| w.as_str()).unwrap_or_default();
+    match first {
+        "BEGIN" => Some(true),
+        "START" if second == "TRANSACTION" => Some(true),
+        "COMMIT" | "END" => Some(false),
+        "ROLLBACK" if second != "TO" => Some(false),
+        _ => None,
+    }
+}
+
+// Statements the backend refuses to run, or silently ignores, inside a transaction
+fn runs_outside_transaction(dialect: SqlDialect, words: &[String], sql: &str) -> bool {
+    let first = words.first().map(|w| w.as_str()).unwrap_or_default();
+    let second = words.get(1).map(|w| w.as_str()).unwrap_or_default();
+    match dialect {
+        SqlDialect::SQLite => matches!(first, "PRAGMA" | "VACUUM" | "ATTACH" | "DETACH"),
+        SqlDialect::PostgreSQL => {
+            matches!(first, "VACUUM")
+                || matches!((first, second), ("CREATE", "DATABASE") | ("DROP", "DATABASE") | ("ALTER", "SYSTEM") | ("CREATE", "TABLESPACE") | ("DROP", "TABLESPACE"))
+                || (matches!(first, "CREATE" | "DROP" | "REINDEX") && sql.len() < 4096 && sql.to_uppercase().contains("CONCURRENTLY"))
+        }
+        _ => false,
+    }
+}
+
+fn is_copy_from_stdin(sql: &str) -> bool {
+    sql.get(..4).map(|p| p.eq_ignore_ascii_case("COPY")).unwrap_or(false)
+        && sql.to_uppercase().contains("FROM STDIN")
+}
+
+struct ScriptRunner<'a, F, E> {
+    conn: ScriptConnection,
+    options: &'a RunSqlFileOptions,
+    dialect: SqlDialect,
+    batch_size: usize,
+    // A transaction opened by the runner around a batch of statements
+    batch_open: bool,
+    batch_statements: usize,
+    // A transaction opened by the script itself (BEGIN ... COMMIT)
+    script_transaction: bool,
+    // A ROLLBACK failed, so the connection can't safely go back to the pool
+    statement_number: u64,
+    statements_executed: u64,
+    statements_failed: u64,
+    rows_affected: u64,
+    errors: Vec<SqlStatementError>,
+    stopped: bool,
+    bytes_read: Arc<AtomicU64>,
+    total_bytes: u64,
+    on_progress: F,
+    on_error: E,
+}
+
+impl<F, E> ScriptRunner<'_, F, E>
+where
+    F: FnMut(SqlFileProgress) + Send,
+    E: FnMut(SqlStatementError) + Send,
+{
+    fn progress(&mut self, finished: bool) {
+        (self.on_progress)(SqlFileProgress {
+            path: self.options.path.clone(),
+            bytes_read: self.bytes_read.load(Ordering::Relaxed),
+            total_bytes: self.total_bytes,
+            statements_executed: self.statements_executed,
+            statements_failed: self.statements_failed,
+            finished,
+        });
+    }
+
+    fn record_error(&mut self, statement: &SplitStatement, error: String) {
+        self.statements_failed += 1;
+        let preview: String = statement.sql.chars().take(MAX_STATEMENT_PREVIEW).collect();
+        let error = SqlStatementError {
+            path: self.options.path.clone(),
+            statement_number: self.statement_number,
+            line: statement.line,
+            statement: preview,
+            error,
+        };
+        (self.on_error)(error.clone());
+        if self.errors.len() < MAX_REPORTED_ERRORS {
+            self.errors.push(error);
+        }
+    }
+
+    async fn commit_batch(&mut self) -> Result<(), AppError> {
+        if self.batch_open {
+            self.conn.execute("COMMIT").await?;
+            self.batch_open = false;
+            self.batch_statements = 0;
+        }
+        Ok(())
+    }
+
+    async fn rollback_open_transaction(&mut self) {
+        if self.batch_open || self.script_transaction {
+            let _ = self.conn.execute("ROLLBACK").await;
+            self.batch_open = false;
+            self.script_transaction = false;
+        }
+    }
+
+    // PostgreSQL aborts the whole transaction on error, so statements that may
+    // fail without stopping the script run under a savepoint
+    fn use_savepoint(&self) -> bool {
+        self.dialect == SqlDialect::PostgreSQL
+            && !self.options.stop_on_error
+            && (self.batch_open || self.script_transaction)
+    }
+
+    async fn run_statement(&mut self, statement: &SplitStatement, reader: &mut (dyn BufRead + Send), splitter: &mut StatementSplitter) -> Result<(), AppError> {
+        self.statement_number += 1;
+        let words = leading_words(&statement.sql, 2);
+
+        if let Some(opens) = transaction_control(&words) {
+            // The script manages its own transaction from here on
+            self.commit_batch().await?;
+            match self.conn.execute(&statement.sql).await {
+                Ok(_) => {
+                    self.statements_executed += 1;
+                    self.script_transaction = opens;
+                }
+                Err(e) => {
+                    self.record_error(statement, e.to_string());
+                    if self.options.stop_on_error {
+                        self.stopped = true;
+                        self.rollback_open_transaction().await;
+                    }
+                }
+            }
+            return Ok(());
+        }
+
+        let standalone = runs_outside_transaction(self.dialect, &words, &statement.sql);
+        if standalone {
+            self.commit_batch().await?;
+        } else if self.options.use_transaction && !self.batch_open && !self.script_transaction {
+            self.conn.execute("BEGIN").await?;
+            self.batch_open = true;
+        }
+
+        let savepoint = !standalone && self.use_savepoint();
+        if savepoint {
+            self.conn.execute("SAVEPOINT titania_statement").await?;
+        }
+
+        let result = match (&mut self.conn, is_copy_from_stdin(&statement.sql)) {
+            (ScriptConnection::PostgreSQL(conn), true) => copy_from_script(conn, &statement.sql, reader, splitter).await,
+            _ => self.conn.execute(&statement.sql).await.map_err(|e| e.to_string()),
+        };
+
+        match result {
+            Ok(rows) => {
+                self.statements_executed += 1;
+                self.rows_affected += rows;
+                if savepoint {
+                    self.conn.execute("RELEASE SAVEPOINT titania_statement").await?;
+                }
+            }
+            Err(error) => {
+                self.record_error(statement, error);
+                if savepoint {
+                    self.conn.execute("ROLLBACK TO SAVEPOINT titania_statement").await?;
+                }
+                if self.options.stop_on_error {
+                    self.stopped = true;
+                    self.rollback_open_transaction().await;
+                    return Ok(());
+                }
+            }
+        }
+
+        if self.batch_open {
+            self.batch_statements += 1;
+            if self.batch_statements >= self.batch_size {
+                self.commit_batch().await?;
+            }
+        }
+
+        if self.statement_number.is_multiple_of(PROGRESS_INTERVAL) {
+            self.progress(false);
+        }
+        Ok(())
+    }
+
+    async fn run_script(&mut self, reader: &mut (dyn BufRead + Send), splitter: &mut StatementSplitter) -> Result<(), AppError> {
+        let mut line = Vec::new();
+        'lines: loop {
+            line.clear();
+            let read = reader.read_until(b'\n', &mut line).map_err(script_error)?;
+            let statements: Vec<SplitStatement> = if read == 0 {
+                splitter.finish().into_iter().collect()
+            } else {
+                splitter.push_line(&String::from_utf8_lossy(&line))
+            };
+
+            for statement in statements {
+                self.run_statement(&statement, reader, splitter).await?;
+                if self.stopped {
+                    break 'lines;
+                }
+            }
+
+            if read == 0 {
+                break;
+            }
+        }
+
+        if !self.stopped {
+            self.commit_batch().await?;
+            // Like psql, a transaction the script never closed is rolled back
+            if self.script_transaction {
+                self.rollback_open_transaction().await;
+                let statement = SplitStatement {
+                    sql: String::new(),
+                    line: splitter.line(),
+                };
+                self.record_error(&statement, "script ended inside an open transaction; it was rolled back".to_string());
+            }
+        }
+        Ok(())
+    }
+}
+
+// Stream the data block following COPY ... FROM stdin, terminated by a \. line
+async fn copy_from_script(
+    conn: &mut PoolConnection<Postgres>,
+    statement: &str,
+    reader: &mut (dyn BufRead + Send),
+    splitter: &mut StatementSplitter,
+) -> Result<u64, String> {
+    // Whatever fails, the whole data block is still consumed so it isn't parsed as SQL
+    let (mut copy, mut failure) = match conn.copy_in_raw(statement).await {
+        Ok(copy) => (Some(copy), None),
+        Err(e) => (None, Some(e.to_string())),
+    };
+    let mut chunk = Vec::with_capacity(COPY_CHUNK_SIZE);
+    let mut line = Vec::new();
+
+    loop {
+        line.clear();
+        let read = match reader.read_until(b'\n', &mut line) {
+            Ok(read) => read,
+            Err(e) => {
+                failure = Some(e.to_string());
+                break;
+            }
+        };
+        if read == 0 {
+            failure.get_or_insert_with(|| "unexpected end of file in COPY data".to_string());
+            break;
+        }
+        splitter.skip_line();
+        if line.trim_ascii_end() == b"\\." {
+            break;
+        }
+        let Some(active) = copy.as_mut().filter(|_| failure.is_none()) else {
+            continue;
+        };
+        chunk.extend_from_slice(&line);
+        if chunk.len() >= COPY_CHUNK_SIZE {
+            if let Err(e) = active.send(std::mem::take(&mut chunk)).await {
+                failure = Some(e.to_string());
+            }
+        }
+    }
+
+    let mut copy = match (copy, failure) {
+        (Some(copy), None) => copy,
+        (copy, failure) => {
+            let failure = failure.unwrap_or_default();
+            if let Some(copy) = copy {
+                let _ = copy.abort(failure.clone()).await;
+            }
+            return Err(failure);
+        }
+    };
+    if !chunk.is_empty() {
+        copy.send(chunk).await.map_err(|e| e.to_string())?;
+    }
+    copy.finish().await.map_err(|e| e.to_string())
+}
+
+pub async fn run_sql_file<F, E>(
+    connection: &DatabaseConnection,
+    options: &RunSqlFileOptions,
+    on_progress: F,
+    on_error: E,
+) -> Result<SqlFileSummary, AppError>
+where
+    F: FnMut(SqlFileProgress) + Send,
+    E: FnMut(SqlStatementError) + Send,
+{
+    let started = Instant::now();
+    let conn = match connection {
+        DatabaseConnection::MySQL(pool, _) => ScriptConnection::MySQL(pool.acquire().await?),
+        DatabaseConnection::PostgreSQL(pool, _) => ScriptConnection::PostgreSQL(pool.acquire().await?),
+        DatabaseConnection::SQLite(pool, _) => ScriptConnection::SQLite(pool.acquire().await?),
+        DatabaseConnection::MongoDB(..) => {
+            return Err(AppError::InvalidConfiguration("SQL files can't be run against MongoDB".to_string()))
+        }
+        DatabaseConnection::SQLServer(_) => {
+            return Err(AppError::InvalidConfiguration("Running SQL files isn't supported for SQL Server yet".to_string()))
+        }
+        DatabaseConnection::DuckDB(_) => {
+            return Err(AppError::InvalidConfiguration("Running SQL files isn't supported for DuckDB yet".to_string()))
+        }
+        DatabaseConnection::Redis(_) => {
+            return Err(AppError::InvalidConfiguration("SQL files can't be run against Redis".to_string()))
+        }
+    };
+
+    let dialect = SqlDialect::from(&connection.db_type());
+    let total_bytes = std::fs::metadata(&options.path)?.len();
+    let (mut reader, bytes_read) = open_script(options)?;
+    let mut splitter = StatementSplitter::new(dialect);
+
+    let mut runner = ScriptRunner {
+        conn,
+        options,
+        dialect,
+        batch_size: options.batch_size.unwrap_or(DEFAULT_BATCH_SIZE).max(1),
+        batch_open: false,
+        batch_statements: 0,
+        script_transaction: false,
+        statement_number: 0,
+        statements_executed: 0,
+        statements_failed: 0,
+        rows_affected: 0,
+        errors: Vec::new(),
+        stopped: false,
+        bytes_read,
+        total_bytes,
+        on_progress,
+        on_error,
+    };
+
+    if let Err(e) = runner.run_script(reader.as_mut(), &mut splitter).await {
+        runner.rollback_open_transaction().await;
+        runner.conn.close().await;
+        return Err(e);
+    }
+    runner.progress(true);
+    runner.conn.close().await;
+
+    Ok(SqlFileSummary {
+        statements_executed: runner.statements_executed,
+        statements_failed: runner.statements_failed,
+        rows_affected: runner.rows_affected,
+        errors: runner.errors,
+        stopped: runner.stopped,
+        duration_ms: started.elapsed().as_millis() as u64,
+    })
+}
@@ -0,0 +1,174 @@
+use crate::database::{DatabaseSchema, DatabaseType};
+use crate::error::AppError;
+use chrono::{SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
+use sqlx::{Row, SqlitePool};
+use std::path::Path;
+
+// Snapshot metadata; the schema itself is only loaded on demand
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotSummary {
+    pub id: i64,
+    pub connection: String,
+    pub db_type: String,
+    pub label: Option<String>,
+    pub captured_at: String,
+    pub table_count: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SchemaSnapshot {
+    #[serde(flatten)]
+    pub summary: SnapshotSummary,
+    pub schema: DatabaseSchema,
+}
+
+#[derive(Debug, Clone)]
+pub struct SchemaSnapshots {
+    pool: SqlitePool,
+}
+
+pub fn parse_db_type(name: &str) -> Option<DatabaseType> {
+    match name {
+        "mysql" => Some(DatabaseType::MySQL),
+        "postgresql" => Some(DatabaseType::PostgreSQL),
+        "sqlite" => Some(DatabaseType::SQLite),
+        "mongodb" => Some(DatabaseType::MongoDB),
+        "mssql" => Some(DatabaseType::SQLServer),
+        "redis" => Some(DatabaseType::Redis),
+        "duckdb" => Some(DatabaseType::DuckDB),
+        _ => None,
+    }
+}
+
+fn summary_from_row(row: &sqlx::sqlite::SqliteRow) -> SnapshotSummary {
+    SnapshotSummary {
+        id: row.get("id"),
+        connection: row.get("connection"),
+        db_type: row.get("db_type"),
+        label: row.get("label"),
+        captured_at: row.get("captured_at"),
+        table_count: row.get("table_count"),
+    }
+}
+
+impl SchemaSnapshots {
+    pub async fn open(path: &Path) -> Result<Self, AppError> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| AppError::InternalError(format!("Failed to create snapshot directory: {}", e)))?;
+        }
+
+        let options = SqliteConnectOptions::new()
+            .filename(path)
+            .create_if_missing(true);
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect_with(options)
+            .await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS schema_snapshots (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                connection TEXT NOT NULL,
+                db_type TEXT NOT NULL,
+                label TEXT,
+                captured_at TEXT NOT NULL,
+                table_count INTEGER NOT NULL,
+                schema TEXT NOT NULL
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await?;
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_schema_snapshots_connection ON schema_snapshots (connection, captured_at)")
+            .execute(&pool)
+            .await?;
+
+        Ok(SchemaSnapshots { pool })
+    }
+
+    pub async fn capture(
+        &self,
+        connection: &str,
+        db_type: &DatabaseType,
+        label: Option<&str>,
+        schema: &DatabaseSchema,
+    ) -> Result<SnapshotSummary, AppError> {
+        let json = serde_json::to_string(schema)
+            .map_err(|e| AppError::InternalError(format!("Failed to serialize schema: {}", e)))?;
+        let captured_at = Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true);
+
+        let id = sqlx::query(
+            r#"
+            INSERT INTO schema_snapshots (connection, db_type, label, captured_at, table_count, schema)
+            VALUES (?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(connection)
+        .bind(db_type.as_str())
+        .bind(label)
+        .bind(&captured_at)
+        .bind(schema.tables.len() as i64)
+        .bind(json)
+        .execute(&self.pool)
+        .await?
+        .last_insert_rowid();
+
+        Ok(SnapshotSummary {
+            id,
+            connection: connection.to_string(),
+            db_type: db_type.as_str().to_string(),
+            label: label.map(|l| l.to_string()),
+            captured_at,
+            table_count: schema.tables.len() as i64,
+        })
+    }
+
+    // Newest first, optionally limited to one connection
+    pub async fn list(&self, connection: Option<&str>) -> Result<Vec<SnapshotSummary>, AppError> {
+        let rows = sqlx::query(
+            r#"
+            SELECT id, connection, db_type, label, captured_at, table_count
+            FROM schema_snapshots
+            WHERE ? IS NULL OR connection = ?
+            ORDER BY captured_at DESC, id DESC
+            "#,
+        )
+        .bind(connection)
+        .bind(connection)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(summary_from_row).collect())
+    }
+
+    pub async fn get(&self, id: i64) -> Result<SchemaSnapshot, AppError> {
+        let row = sqlx::query(
+            "SELECT id, connection, db_type, label, captured_at, table_count, schema FROM schema_snapshots WHERE id = ?",
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::InvalidConfiguration(format!("Schema snapshot {} not found", id)))?;
+
+        let json: String = row.get("schema");
+        let schema = serde_json::from_str(&json)
+            .map_err(|e| AppError::InternalError(format!("Corrupt schema snapshot {}: {}", id, e)))?;
+
+        Ok(SchemaSnapshot {
+            summary: summary_from_row(&row),
+            schema,
+        })
+    }
+
+    pub async fn delete(&self, id: i64) -> Result<(), AppError> {
+        sqlx::query("DELETE FROM schema_snapshots WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,385 @@
+use crate::database::DatabaseType;
+
+// Lexical rules that affect where a statement ends
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SqlDialect {
+    Generic,
+    MySQL,
+    PostgreSQL,
+    SQLite,
+    TSql,
+}
+
+impl From<&DatabaseType> for SqlDialect {
+    fn from(db_type: &DatabaseType) -> Self {
+        match db_type {
+            DatabaseType::MySQL => SqlDialect::MySQL,
+            // DuckDB follows PostgreSQL's quoting, including dollar-quoted strings
+            DatabaseType::PostgreSQL | DatabaseType::DuckDB => SqlDialect::PostgreSQL,
+            DatabaseType::SQLite => SqlDialect::SQLite,
+            DatabaseType::SQLServer => SqlDialect::TSql,
+            DatabaseType::MongoDB | DatabaseType::Redis => SqlDialect::Generic,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SplitStatement {
+    pub sql: String,
+    // 1-based line where the statement starts
+    pub line: usize,
+}
+
+enum State {
+    Normal,
+    Quoted { quote: char, escapes: bool },
+    LineComment,
+    // MySQL /*! ... */ comments are executable and kept in the statement; depth counts
+    // nested comments, which PostgreSQL and T-SQL allow
+    BlockComment { keep: bool, depth: usize },
+    DollarQuoted(String),
+}
+
+// Incremental statement splitter fed one line at a time, so scripts of any
+// size can be split without holding the whole file in memory
+pub struct StatementSplitter {
+    dialect: SqlDialect,
+    state: State,
+    current: String,
+    start_line: Option<usize>,
+    line: usize,
+    delimiter: String,
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '$'
+}
+
+// Uppercased words outside string literals, stopping after `limit` words
+fn words(sql: &str, limit: usize) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut quote: Option<char> = None;
+    for c in sql.chars() {
+        if let Some(q) = quote {
+            if c == q {
+                quote = None;
+            }
+            continue;
+        }
+        if c.is_alphanumeric() || c == '_' {
+            word.push(c.to_ascii_uppercase());
+            continue;
+        }
+        if !word.is_empty() {
+            words.push(std::mem::take(&mut word));
+            if words.len() >= limit {
+                return words;
+            }
+        }
+        if c == '\'' || c == '"' || c == '`' {
+            quote = Some(c);
+        }
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    words
+}
+
+// SQLite trigger bodies contain semicolons between BEGIN and END
+fn inside_trigger_body(sql: &str) -> bool {
+    let leading = words(sql, 3);
+    let is_trigger = leading.first().map(|w| w == "CREATE").unwrap_or(false)
+        && leading.iter().skip(1).any(|w| w == "TRIGGER");
+    if !is_trigger {
+        return false;
+    }
+
+    let mut depth = 0i32;
+    let mut seen_begin = false;
+    for word in words(sql, usize::MAX) {
+        match word.as_str() {
+            "BEGIN" => {
+                seen_begin = true;
+                depth += 1;
+            }
+            "CASE" => depth += 1,
+            "END" => depth -= 1,
+            _ => {}
+        }
+    }
+    seen_begin && depth > 0
+}
+
+impl StatementSplitter {
+    pub fn new(dialect: SqlDialect) -> Self {
+        StatementSplitter {
+            dialect,
+            state: State::Normal,
+            current: String::new(),
+            start_line: None,
+            line: 0,
+            delimiter: ";".to_string(),
+        }
+    }
+
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    // Account for lines consumed outside the splitter (COPY data blocks)
+    pub fn skip_line(&mut self) {
+        self.line += 1;
+    }
+
+    fn push(&mut self, text: &str) {
+        if self.start_line.is_none() && !text.trim().is_empty() {
+            self.start_line = Some(self.line);
+        }
+        self.current.push_str(text);
+    }
+
+    fn take_statement(&mut self) -> Option<SplitStatement> {
+        let sql = self.current.trim().to_string();
+        let line = self.start_line.take().unwrap_or(self.line);
+        self.current.clear();
+        if sql.is_empty() {
+            None
+        } else {
+            Some(SplitStatement { sql, line })
+        }
+    }
+
+    // Feed one line including its trailing newline; returns the statements it completes
+    pub fn push_line(&mut self, line: &str) -> Vec<SplitStatement> {
+        self.line += 1;
+        let mut statements = Vec::new();
+
+        // sqlcmd batch separator: GO alone on a line, optionally with a repeat count
+        if self.dialect == SqlDialect::TSql && matches!(self.state, State::Normal) {
+            let trimmed = line.trim();
+            let is_go = trimmed.get(..2).map(|p| p.eq_ignore_ascii_case("GO")).unwrap_or(false)
+                && trimmed[2..].trim().chars().all(|ch| ch.is_ascii_digit());
+            if is_go {
+                statements.extend(self.take_statement());
+                return statements;
+            }
+        }
+
+        if matches!(self.state, State::Normal) && self.start_line.is_none() {
+            let trimmed = line.trim();
+            // mysql client directive used around procedure and trigger bodies
+            if self.dialect == SqlDialect::MySQL
+                && trimmed.get(..10).map(|p| p.eq_ignore_ascii_case("DELIMITER ")).unwrap_or(false)
+            {
+                self.delimiter = trimmed[10..].trim().to_string();
+                self.current.clear();
+                return statements;
+            }
+            // psql meta-commands such as \connect or \restrict can't run on the server
+            if self.dialect == SqlDialect::PostgreSQL && trimmed.starts_with('\\') {
+                self.current.clear();
+                return statements;
+            }
+        }
+
+        let mut i = 0;
+        while i < line.len() {
+            let rest = &line[i..];
+            let c = rest.chars().next().unwrap_or_default();
+            let next = rest[c.len_utf8()..].chars().next();
+            let mut advance = c.len_utf8();
+
+            match &self.state {
+                State::Quoted { quote, escapes } => {
+                    let (quote, escapes) = (*quote, *escapes);
+                    self.push(&rest[..advance]);
+                    if escapes && c == '\\' {
+                        if let Some(next) = next {
+                            self.push(&rest[advance..advance + next.len_utf8()]);
+                            advance += next.len_utf8();
+                        }
+                    } else if c == quote {
+                        // Doubled quote is an escaped quote
+                        if next == Some(quote) {
+                            self.push(&rest[advance..advance + 1]);
+                            advance += 1;
+                        } else {
+                            self.state = State::Normal;
+                        }
+                    }
+                }
+                State::LineComment => {
+                    if c == '\n' {
+                        self.push("\n");
+                        self.state = State::Normal;
+                    }
+                }
+                State::BlockComment { keep, depth } => {
+                    let (keep, depth) = (*keep, *depth);
+                    if rest.starts_with("*/") {
+                        if keep {
+                            self.push("*/");
+                        }
+                        advance = 2;
+                        self.state = match depth {
+                            0 => State::Normal,
+                            _ => State::BlockComment { keep, depth: depth - 1 },
+                        };
+                    } else if rest.starts_with("/*") && matches!(self.dialect, SqlDialect::PostgreSQL | SqlDialect::TSql) {
+                        advance = 2;
+                        self.state = State::BlockComment { keep, depth: depth + 1 };
+                    } else if keep {
+                        self.push(&rest[..advance]);
+                    }
+                }
+                State::DollarQuoted(tag) => {
+                    if rest.starts_with(tag.as_str()) {
+                        let tag = tag.clone();
+                        advance = tag.len();
+                        self.push(&tag);
+                        self.state = State::Normal;
+                    } else {
+                        self.push(&rest[..advance]);
+                    }
+                }
+                State::Normal => {
+                    let previous = self.current.chars().last();
+                    if !self.delimiter.is_empty() && rest.starts_with(self.delimiter.as_str()) {
+                        if self.dialect == SqlDialect::SQLite && inside_trigger_body(&self.current) {
+                            self.push(";");
+                        } else {
+                            advance = self.delimiter.len();
+                            if let Some(statement) = self.take_statement() {
+                                statements.push(statement);
+                            }
+                        }
+                    } else if (c == '-' && next == Some('-')) || (c == '#' && self.dialect == SqlDialect::MySQL) {
+                        self.state = State::LineComment;
+                    } else if c == '/' && next == Some('*') {
+                        advance = 2;
+                        // MariaDB's /*M! ... */ is executable as well
+                        if self.dialect == SqlDialect::MySQL && (rest[2..].starts_with('!') || rest[2..].starts_with("M!")) {
+                            self.push("/*");
+                            self.state = State::BlockComment { keep: true, depth: 0 };
+                        } else {
+                            self.push(" ");
+                            self.state = State::BlockComment { keep: false, depth: 0 };
+                        }
+                    } else if c == '$' && self.dialect == SqlDialect::PostgreSQL && !previous.map(is_ident_char).unwrap_or(false) {
+                        // $tag$ opens a dollar-quoted body; $1 is a parameter
+                        let tag_len = rest[1..].find(|ch: char| !(ch.is_alphanumeric() || ch == '_')).unwrap_or(rest.len() - 1);
+                        let starts_with_digit = rest[1..].starts_with(|ch: char| ch.is_ascii_digit());
+                        if !starts_with_digit && rest[1 + tag_len..].starts_with('$') {
+                            let tag = rest[..tag_len + 2].to_string();
+                            advance = tag.len();
+                            self.push(&tag);
+                            self.state = State::DollarQuoted(tag);
+                        } else {
+                            self.push("$");
+                        }
+                    } else if c == '[' && self.dialect == SqlDialect::TSql {
+                        // [bracketed] identifiers; ]] is an escaped bracket
+                        self.push("[");
+                        self.state = State::Quoted { quote: ']', escapes: false };
+                    } else if c == '\'' || c == '"' || (c == '`' && self.dialect != SqlDialect::PostgreSQL) {
+                        let escapes = match (self.dialect, c) {
+                            (SqlDialect::Generic, '\'') | (SqlDialect::Generic, '`') => true,
+                            (SqlDialect::MySQL, '\'') | (SqlDialect::MySQL, '"') => true,
+                            // E'...' escape strings; standard strings treat backslash literally
+                            (SqlDialect::PostgreSQL, '\'') => {
+                                matches!(previous, Some('E') | Some('e'))
+                                    && !self.current[..self.current.len() - 1].chars().last().map(is_ident_char).unwrap_or(false)
+                            }
+                            _ => false,
+                        };
+                        self.push(&rest[..advance]);
+                        self.state = State::Quoted { quote: c, escapes };
+                    } else {
+                        self.push(&rest[..advance]);
+                    }
+                }
+            }
+
+            i += advance;
+        }
+
+        statements
+    }
+
+    // Flush whatever follows the last delimiter
+    pub fn finish(&mut self) -> Option<SplitStatement> {
+        self.state = State::Normal;
+        self.take_statement()
+    }
+}
+
+pub fn split_sql(sql: &str, dialect: SqlDialect) -> Vec<SplitStatement> {
+    let mut splitter = StatementSplitter::new(dialect);
+    let mut statements = Vec::new();
+    for line in sql.split_inclusive('\n') {
+        statements.extend(splitter.push_line(line));
+    }
+    statements.extend(splitter.finish());
+    statements
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn split(sql: &str, dialect: SqlDialect) -> Vec<String> {
+        split_sql(sql, dialect).into_iter().map(|s| s.sql).collect()
+    }
+
+    #[test]
+    fn dollar_quoted_bodies_keep_their_semicolons() {
+        let sql = "CREATE FUNCTION f() RETURNS int AS $body$ SELECT 1; SELECT 2; $body$ LANGUAGE sql;\nSELECT $1;";
+        let statements = split(sql, SqlDialect::PostgreSQL);
+        assert_eq!(statements.len(), 2);
+        assert!(statements[0].ends_with("$body$ LANGUAGE sql"));
+        assert_eq!(statements[1], "SELECT $1");
+    }
+
+    #[test]
+    fn nested_block_comments_hide_semicolons() {
+        let sql = "SELECT 1 /* outer /* inner */ still comment; DROP TABLE t; */;\nSELECT 2;";
+        assert_eq!(split(sql, SqlDialect::PostgreSQL), vec!["SELECT 1", "SELECT 2"]);
+        assert_eq!(split(sql, SqlDialect::TSql).len(), 2);
+        // MySQL comments don't nest, so the first */ closes it
+        assert_eq!(split("SELECT 1 /* a /* b */ ;\nSELECT 2;", SqlDialect::MySQL), vec!["SELECT 1", "SELECT 2"]);
+    }
+
+    #[test]
+    fn mysql_delimiter_directive_changes_the_terminator() {
+        let sql = "DELIMITER //\nCREATE PROCEDURE p() BEGIN SELECT 1; SELECT 2; END //\nDELIMITER ;\nSELECT 3;";
+        let statements = split(sql, SqlDialect::MySQL);
+        assert_eq!(statements.len(), 2);
+        assert!(statements[0].starts_with("CREATE PROCEDURE") && statements[0].ends_with("END"));
+        assert_eq!(statements[1], "SELECT 3");
+    }
+
+    #[test]
+    fn quoted_delimiters_do_not_split() {
+        assert_eq!(split("SELECT 'a;b', \"c;d\";", SqlDialect::PostgreSQL), vec!["SELECT 'a;b', \"c;d\""]);
+        assert_eq!(split("SELECT 'it\\'s;';", SqlDialect::MySQL), vec!["SELECT 'it\\'s;'"]);
+        assert_eq!(split("SELECT E'\\';'; SELECT 2;", SqlDialect::PostgreSQL), vec!["SELECT E'\\';'", "SELECT 2"]);
+    }
+
+    #[test]
+    fn tsql_go_separates_batches() {
+        let sql = "CREATE TABLE t (id int)\ngo\nSELECT [a;b] FROM t\nGO 2\nSELECT 'GO'\n";
+        assert_eq!(split(sql, SqlDialect::TSql), vec!["CREATE TABLE t (id int)", "SELECT [a;b] FROM t", "SELECT 'GO'"]);
+        // GO inside a comment or string isn't a separator
+        assert_eq!(split("SELECT 1 /*\nGO\n*/\nSELECT 2", SqlDialect::TSql).len(), 1);
+    }
+
+    #[test]
+    fn sqlite_trigger_bodies_stay_whole() {
+        let sql = "CREATE TRIGGER tr AFTER INSERT ON t BEGIN UPDATE t SET a = 1; DELETE FROM u; END;\nSELECT 1;";
+        let statements = split(sql, SqlDialect::SQLite);
+        assert_eq!(statements.len(), 2);
+        assert!(statements[0].ends_with("END"));
+    }
+}
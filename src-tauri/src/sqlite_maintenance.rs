@@ -0,0 +1,498 @@
+use crate::database::{quote_identifier, DatabaseConnection};
+use crate::error::AppError;
+use libsqlite3_sys as ffi;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::ffi::{CStr, CString};
+use std::path::{Path, PathBuf};
+use std::ptr;
+use std::time::{Duration, Instant};
+
+// Integrity problems reported before SQLite stops checking
+const MAX_INTEGRITY_ERRORS: i64 = 100;
+// Pages copied per backup step; writers can get in between steps
+const BACKUP_PAGES_PER_STEP: i32 = 256;
+// How long a backup keeps retrying while another connection holds a lock
+const BACKUP_BUSY_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IntegrityReport {
+    pub schema: String,
+    pub ok: bool,
+    pub problems: Vec<String>,
+    // quick_check skips index consistency checks
+    pub quick: bool,
+    pub duration_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ForeignKeyViolation {
+    pub table: String,
+    // None for WITHOUT ROWID tables
+    pub rowid: Option<i64>,
+    pub parent: String,
+    pub columns: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ForeignKeyReport {
+    pub schema: String,
+    pub violations: Vec<ForeignKeyViolation>,
+    pub duration_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VacuumSummary {
+    pub schema: String,
+    // Set for VACUUM INTO, which leaves the original untouched
+    pub destination: Option<String>,
+    pub size_before: Option<i64>,
+    pub size_after: Option<i64>,
+    pub duration_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnalyzeSummary {
+    // Table, schema or everything when None
+    pub target: Option<String>,
+    pub duration_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CheckpointSummary {
+    pub mode: String,
+    pub journal_mode: String,
+    // A reader or writer prevented the checkpoint from completing
+    pub busy: bool,
+    // Frames in the WAL and how many were copied into the database; -1 outside WAL mode
+    pub wal_frames: i64,
+    pub checkpointed_frames: i64,
+    pub duration_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupProgress {
+    pub total_pages: i64,
+    pub remaining_pages: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupSummary {
+    pub schema: String,
+    pub destination: String,
+    // "backup_api" for files, "vacuum_into" for in-memory databases
+    pub method: String,
+    pub pages: i64,
+    pub size_bytes: Option<i64>,
+    pub duration_ms: u64,
+}
+
+fn require_sqlite(connection: &DatabaseConnection) -> Result<(), AppError> {
+    match connection {
+        DatabaseConnection::SQLite(..) => Ok(()),
+        _ => Err(AppError::InvalidConfiguration("Maintenance operations are only available for SQLite".to_string())),
+    }
+}
+
+fn text(value: Option<&Value>) -> Option<String> {
+    match value? {
+        Value::Null => None,
+        Value::String(s) => Some(s.clone()),
+        other => Some(other.to_string()),
+    }
+}
+
+fn number(value: Option<&Value>) -> Option<i64> {
+    match value? {
+        Value::Number(n) => n.as_i64(),
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+fn sql_string(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "''"))
+}
+
+fn elapsed_ms(started: Instant) -> u64 {
+    started.elapsed().as_millis() as u64
+}
+
+// (name, file) of main and attached databases; file is empty for in-memory ones
+async fn database_list(connection: &DatabaseConnection) -> Result<Vec<(String, String)>, AppError> {
+    Ok(connection
+        .execute_query("PRAGMA database_list")
+        .await?
+        .rows
+        .iter()
+        .map(|r| (text(r.get(1)).unwrap_or_default(), text(r.get(2)).unwrap_or_default()))
+        .collect())
+}
+
+// Validated schema name, "main" by default
+async fn schema_name(connection: &DatabaseConnection, schema: Option<&str>) -> Result<String, AppError> {
+    let schema = schema.map(str::trim).filter(|s| !s.is_empty()).unwrap_or("main");
+    if !database_list(connection).await?.iter().any(|(name, _)| name == schema) {
+        return Err(AppError::InvalidConfiguration(format!("No attached database named {}", schema)));
+    }
+    Ok(schema.to_string())
+}
+
+async fn database_size(connection: &DatabaseConnection, schema: &str) -> Option<i64> {
+    let schema = quote_identifier(schema, '"');
+    let pages = connection.execute_query(&format!("PRAGMA {}.page_count", schema)).await.ok()?;
+    let size = connection.execute_query(&format!("PRAGMA {}.page_size", schema)).await.ok()?;
+    Some(number(pages.rows.first()?.first())? * number(size.rows.first()?.first())?)
+}
+
+// Absolute path of a file that may not exist yet
+fn resolve_path(path: &Path) -> Option<PathBuf> {
+    if let Ok(resolved) = path.canonicalize() {
+        return Some(resolved);
+    }
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.canonicalize().ok()?,
+        _ => std::env::current_dir().ok()?,
+    };
+    Some(parent.join(path.file_name()?))
+}
+
+// Validates the destination and returns a temporary file next to it to write into,
+// so an existing file is only replaced once the new copy is complete
+async fn check_destination(connection: &DatabaseConnection, destination: &str, overwrite: bool) -> Result<PathBuf, AppError> {
+    if destination.trim().is_empty() {
+        return Err(AppError::InvalidConfiguration("A destination file is required".to_string()));
+    }
+    let path = Path::new(destination);
+    let resolved = resolve_path(path)
+        .ok_or_else(|| AppError::InvalidConfiguration(format!("{} is not a valid destination", destination)))?;
+    for (name, file) in database_list(connection).await? {
+        if !file.is_empty() && resolve_path(Path::new(&file)).as_ref() == Some(&resolved) {
+            return Err(AppError::InvalidConfiguration(format!(
+                "{} is the open database {}; choose another destination",
+                destination, name
+            )));
+        }
+    }
+    if path.exists() && !overwrite {
+        return Err(AppError::InvalidConfiguration(format!("{} already exists", destination)));
+    }
+
+    let file_name = resolved.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+    let temp = resolved.with_file_name(format!(".{}.{}.partial", file_name, std::process::id()));
+    if temp.exists() {
+        std::fs::remove_file(&temp)?;
+    }
+    Ok(temp)
+}
+
+// Moves a finished copy into place, or discards it if the copy failed
+fn finish_destination<T>(result: Result<T, AppError>, temp: &Path, destination: &str) -> Result<T, AppError> {
+    match result {
+        Ok(value) => {
+            std::fs::rename(temp, destination)?;
+            Ok(value)
+        }
+        Err(error) => {
+            let _ = std::fs::remove_file(temp);
+            Err(error)
+        }
+    }
+}
+
+pub async fn integrity_check(connection: &DatabaseConnection, schema: Option<&str>, quick: bool) -> Result<IntegrityReport, AppError> {
+    require_sqlite(connection)?;
+    let started = Instant::now();
+    let schema = schema_name(connection, schema).await?;
+    let pragma = if quick { "quick_check" } else { "integrity_check" };
+    let result = connection
+        .execute_query(&format!("PRAGMA {}.{}({})", quote_identifier(&schema, '"'), pragma, MAX_INTEGRITY_ERRORS))
+        .await?;
+    let problems: Vec<String> = result
+        .rows
+        .iter()
+        .filter_map(|r| text(r.first()))
+        .filter(|message| message != "ok")
+        .collect();
+    Ok(IntegrityReport {
+        schema,
+        ok: problems.is_empty(),
+        problems,
+        quick,
+        duration_ms: elapsed_ms(started),
+    })
+}
+
+pub async fn foreign_key_check(connection: &DatabaseConnection, schema: Option<&str>) -> Result<ForeignKeyReport, AppError> {
+    require_sqlite(connection)?;
+    let started = Instant::now();
+    let schema = schema_name(connection, schema).await?;
+    let quoted = quote_identifier(&schema, '"');
+    let result = connection.execute_query(&format!("PRAGMA {}.foreign_key_check", quoted)).await?;
+
+    let mut violations = Vec::new();
+    // Constraint columns per (table, fkid), looked up once per table
+    let mut constraints: HashMap<String, Vec<(i64, String)>> = HashMap::new();
+    for row in &result.rows {
+        let table = text(row.first()).unwrap_or_default();
+        let fkid = number(row.get(3));
+        if !constraints.contains_key(&table) {
+            let list = connection
+                .execute_query(&format!("PRAGMA {}.foreign_key_list({})", quoted, quote_identifier(&table, '"')))
+                .await
+                .map(|r| r.rows)
+                .unwrap_or_default();
+            let columns = list
+                .iter()
+                .filter_map(|r| Some((number(r.first())?, text(r.get(3))?)))
+                .collect();
+            constraints.insert(table.clone(), columns);
+        }
+        let columns = constraints[&table]
+            .iter()
+            .filter(|(id, _)| Some(*id) == fkid)
+            .map(|(_, column)| column.clone())
+            .collect();
+        violations.push(ForeignKeyViolation {
+            rowid: number(row.get(1)),
+            parent: text(row.get(2)).unwrap_or_default(),
+            table,
+            columns,
+        });
+    }
+    Ok(ForeignKeyReport {
+        schema,
+        violations,
+        duration_ms: elapsed_ms(started),
+    })
+}
+
+// VACUUM rebuilds the database in place; with a destination it writes a compacted copy instead
+pub async fn vacuum(
+    connection: &DatabaseConnection,
+    schema: Option<&str>,
+    destination: Option<&str>,
+    overwrite: bool,
+) -> Result<VacuumSummary, AppError> {
+    require_sqlite(connection)?;
+    let started = Instant::now();
+    let schema = schema_name(connection, schema).await?;
+    let size_before = database_size(connection, &schema).await;
+    let destination = destination.map(str::trim).filter(|d| !d.is_empty());
+
+    // VACUUM can't run inside a transaction
+    match destination {
+        Some(destination) => {
+            let temp = check_destination(connection, destination, overwrite).await?;
+            let statement = format!(
+                "VACUUM {} INTO {}",
+                quote_identifier(&schema, '"'),
+                sql_string(&temp.to_string_lossy())
+            );
+            let result = connection.execute_ddl(&[statement], false).await;
+            finish_destination(result, &temp, destination)?;
+        }
+        None => {
+            connection
+                .execute_ddl(&[format!("VACUUM {}", quote_identifier(&schema, '"'))], false)
+                .await?;
+        }
+    }
+
+    let size_after = match destination {
+        Some(destination) => std::fs::metadata(destination).ok().map(|m| m.len() as i64),
+        None => database_size(connection, &schema).await,
+    };
+    Ok(VacuumSummary {
+        schema,
+        destination: destination.map(|d| d.to_string()),
+        size_before,
+        size_after,
+        duration_ms: elapsed_ms(started),
+    })
+}
+
+pub async fn analyze(connection: &DatabaseConnection, target: Option<&str>) -> Result<AnalyzeSummary, AppError> {
+    require_sqlite(connection)?;
+    let started = Instant::now();
+    let target = target.map(str::trim).filter(|t| !t.is_empty());
+    let statement = match target {
+        // "alias.table" targets a table of an attached database
+        Some(target) => match target.split_once('.') {
+            Some((schema, table)) if database_list(connection).await?.iter().any(|(name, _)| name == schema) => {
+                format!("ANALYZE {}.{}", quote_identifier(schema, '"'), quote_identifier(table, '"'))
+            }
+            _ => format!("ANALYZE {}", quote_identifier(target, '"')),
+        },
+        None => "ANALYZE".to_string(),
+    };
+    connection.execute_ddl(&[statement], false).await?;
+    Ok(AnalyzeSummary {
+        target: target.map(|t| t.to_string()),
+        duration_ms: elapsed_ms(started),
+    })
+}
+
+pub async fn wal_checkpoint(connection: &DatabaseConnection, mode: Option<&str>) -> Result<CheckpointSummary, AppError> {
+    require_sqlite(connection)?;
+    let started = Instant::now();
+    let mode = mode.map(|m| m.trim().to_uppercase()).filter(|m| !m.is_empty()).unwrap_or_else(|| "PASSIVE".to_string());
+    if !matches!(mode.as_str(), "PASSIVE" | "FULL" | "RESTART" | "TRUNCATE") {
+        return Err(AppError::InvalidConfiguration(format!(
+            "Unknown checkpoint mode {}; use PASSIVE, FULL, RESTART or TRUNCATE",
+            mode
+        )));
+    }
+    let journal_mode = connection
+        .execute_query("PRAGMA journal_mode")
+        .await?
+        .rows
+        .first()
+        .and_then(|r| text(r.first()))
+        .unwrap_or_default();
+    let result = connection.execute_query(&format!("PRAGMA wal_checkpoint({})", mode)).await?;
+    let row = result.rows.first().cloned().unwrap_or_default();
+    Ok(CheckpointSummary {
+        mode,
+        journal_mode,
+        busy: number(row.first()) == Some(1),
+        wal_frames: number(row.get(1)).unwrap_or(-1),
+        checkpointed_frames: number(row.get(2)).unwrap_or(-1),
+        duration_ms: elapsed_ms(started),
+    })
+}
+
+fn sqlite_error(db: *mut ffi::sqlite3, what: &str) -> AppError {
+    // SAFETY: sqlite3_errmsg returns a NUL-terminated string owned by the connection
+    let message = unsafe { CStr::from_ptr(ffi::sqlite3_errmsg(db)).to_string_lossy().into_owned() };
+    AppError::QueryExecutionFailed(format!("{}: {}", what, message))
+}
+
+// Copy a database file page by page with the SQLite backup API on dedicated connections,
+// so writers on the app's pool are only blocked for one step at a time
+fn backup_file(source: &str, destination: &str, mut on_progress: impl FnMut(BackupProgress)) -> Result<i64, AppError> {
+    let source = CString::new(source).map_err(|e| AppError::InvalidConfiguration(e.to_string()))?;
+    let destination = CString::new(destination).map_err(|e| AppError::InvalidConfiguration(e.to_string()))?;
+    let main = CString::new("main").unwrap();
+
+    // SAFETY: handles are opened, used and closed on this thread only, and closed on every path
+    unsafe {
+        let mut src: *mut ffi::sqlite3 = ptr::null_mut();
+        let mut dst: *mut ffi::sqlite3 = ptr::null_mut();
+        let close = |src: *mut ffi::sqlite3, dst: *mut ffi::sqlite3| {
+            ffi::sqlite3_close(src);
+            ffi::sqlite3_close(dst);
+        };
+
+        if ffi::sqlite3_open_v2(source.as_ptr(), &mut src, ffi::SQLITE_OPEN_READONLY, ptr::null()) != ffi::SQLITE_OK {
+            let error = sqlite_error(src, "Couldn't open the source database");
+            close(src, dst);
+            return Err(error);
+        }
+        let flags = ffi::SQLITE_OPEN_READWRITE | ffi::SQLITE_OPEN_CREATE;
+        if ffi::sqlite3_open_v2(destination.as_ptr(), &mut dst, flags, ptr::null()) != ffi::SQLITE_OK {
+            let error = sqlite_error(dst, "Couldn't create the backup file");
+            close(src, dst);
+            return Err(error);
+        }
+
+        let backup = ffi::sqlite3_backup_init(dst, main.as_ptr(), src, main.as_ptr());
+        if backup.is_null() {
+            let error = sqlite_error(dst, "Couldn't start the backup");
+            close(src, dst);
+            return Err(error);
+        }
+
+        let mut failure = None;
+        let mut busy_since: Option<Instant> = None;
+        loop {
+            let rc = ffi::sqlite3_backup_step(backup, BACKUP_PAGES_PER_STEP);
+            on_progress(BackupProgress {
+                total_pages: ffi::sqlite3_backup_pagecount(backup) as i64,
+                remaining_pages: ffi::sqlite3_backup_remaining(backup) as i64,
+            });
+            match rc {
+                ffi::SQLITE_DONE => break,
+                ffi::SQLITE_OK => {
+                    busy_since = None;
+                    ffi::sqlite3_sleep(1);
+                }
+                // Another connection holds a lock; wait and retry the step for a while
+                ffi::SQLITE_BUSY | ffi::SQLITE_LOCKED => {
+                    if busy_since.get_or_insert_with(Instant::now).elapsed() >= BACKUP_BUSY_TIMEOUT {
+                        failure = Some(AppError::QueryExecutionFailed(format!(
+                            "Backup gave up after the database stayed locked for {} seconds",
+                            BACKUP_BUSY_TIMEOUT.as_secs()
+                        )));
+                        break;
+                    }
+                    ffi::sqlite3_sleep(100);
+                }
+                _ => {
+                    failure = Some(sqlite_error(dst, "Backup step failed"));
+                    break;
+                }
+            }
+        }
+        let pages = ffi::sqlite3_backup_pagecount(backup) as i64;
+        if ffi::sqlite3_backup_finish(backup) != ffi::SQLITE_OK && failure.is_none() {
+            failure = Some(sqlite_error(dst, "Backup failed"));
+        }
+        close(src, dst);
+        match failure {
+            Some(error) => Err(error),
+            None => Ok(pages),
+        }
+    }
+}
+
+pub async fn backup(
+    connection: &DatabaseConnection,
+    schema: Option<&str>,
+    destination: &str,
+    overwrite: bool,
+    on_progress: impl FnMut(BackupProgress) + Send + 'static,
+) -> Result<BackupSummary, AppError> {
+    require_sqlite(connection)?;
+    let started = Instant::now();
+    let schema = schema_name(connection, schema).await?;
+    let destination = destination.trim().to_string();
+    let temp = check_destination(connection, &destination, overwrite).await?;
+    let file = database_list(connection)
+        .await?
+        .into_iter()
+        .find(|(name, _)| *name == schema)
+        .map(|(_, file)| file)
+        .unwrap_or_default();
+
+    let result = if file.is_empty() {
+        // In-memory databases can't be opened from another connection
+        let quoted = quote_identifier(&schema, '"');
+        let statement = format!("VACUUM {} INTO {}", quoted, sql_string(&temp.to_string_lossy()));
+        match connection.execute_ddl(&[statement], false).await {
+            Ok(_) => connection
+                .execute_query(&format!("PRAGMA {}.page_count", quoted))
+                .await
+                .map(|count| ("vacuum_into", count.rows.first().and_then(|r| number(r.first())).unwrap_or(0))),
+            Err(error) => Err(error),
+        }
+    } else {
+        let target = temp.to_string_lossy().into_owned();
+        tokio::task::spawn_blocking(move || backup_file(&file, &target, on_progress))
+            .await
+            .map_err(|e| AppError::InternalError(e.to_string()))
+            .and_then(|pages| pages.map(|pages| ("backup_api", pages)))
+    };
+    let (method, pages) = finish_destination(result, &temp, &destination)?;
+
+    let size_bytes = std::fs::metadata(&destination).ok().map(|m| m.len() as i64);
+    Ok(BackupSummary {
+        schema,
+        destination,
+        method: method.to_string(),
+        pages,
+        size_bytes,
+        duration_ms: elapsed_ms(started),
+    })
+}
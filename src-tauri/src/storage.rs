@@ -0,0 +1,345 @@
+use crate::database::{quote_identifier, DatabaseConnection};
+use crate::error::AppError;
+use bson::{doc, Bson, Document};
+use futures::TryStreamExt;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DatabaseStorage {
+    pub name: String,
+    pub size_bytes: Option<i64>,
+    pub current: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TableStorage {
+    pub table: String,
+    pub row_estimate: Option<i64>,
+    pub data_bytes: Option<i64>,
+    pub index_bytes: Option<i64>,
+    // PostgreSQL out-of-line storage for large values
+    pub toast_bytes: Option<i64>,
+    pub total_bytes: Option<i64>,
+    // Allocated but unused space: MySQL DATA_FREE, MongoDB freeStorageSize
+    pub free_bytes: Option<i64>,
+    pub dead_rows: Option<i64>,
+    // Share of dead rows (PostgreSQL) or free space (MySQL, MongoDB); high values suggest VACUUM/OPTIMIZE
+    pub bloat_ratio: Option<f64>,
+    pub last_vacuum: Option<String>,
+    pub last_analyze: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StorageStats {
+    pub databases: Vec<DatabaseStorage>,
+    // Largest first
+    pub tables: Vec<TableStorage>,
+    pub warnings: Vec<String>,
+}
+
+fn text(value: Option<&Value>) -> Option<String> {
+    match value? {
+        Value::Null => None,
+        Value::String(s) => Some(s.clone()),
+        other => Some(other.to_string()),
+    }
+}
+
+fn number(value: Option<&Value>) -> Option<i64> {
+    match value? {
+        Value::Number(n) => n.as_i64().or_else(|| n.as_f64().map(|f| f as i64)),
+        Value::String(s) => s.trim().parse::<f64>().ok().map(|f| f as i64),
+        _ => None,
+    }
+}
+
+fn ratio(part: Option<i64>, whole: Option<i64>) -> Option<f64> {
+    match (part, whole) {
+        (Some(part), Some(whole)) if whole > 0 => Some(part as f64 / whole as f64),
+        _ => None,
+    }
+}
+
+async fn rows(connection: &DatabaseConnection, query: &str, what: &str, warnings: &mut Vec<String>) -> Vec<Vec<Value>> {
+    match connection.execute_query(query).await {
+        Ok(result) => result.rows,
+        Err(e) => {
+            warnings.push(format!("Couldn't read {}: {}", what, e));
+            Vec::new()
+        }
+    }
+}
+
+// Latest manual or automatic run of vacuum/analyze
+fn pg_time(operation: &str) -> String {
+    format!("to_char(GREATEST(s.last_{0}, s.last_auto{0}) AT TIME ZONE 'UTC', 'YYYY-MM-DD\"T\"HH24:MI:SS\"Z\"')", operation)
+}
+
+async fn postgres_storage(connection: &DatabaseConnection, warnings: &mut Vec<String>) -> (Vec<DatabaseStorage>, Vec<TableStorage>) {
+    let databases = rows(
+        connection,
+        "SELECT datname::text, CASE WHEN has_database_privilege(datname, 'CONNECT') THEN pg_database_size(datname) END, \
+         datname = current_database() FROM pg_database WHERE NOT datistemplate ORDER BY datname",
+        "database sizes",
+        warnings,
+    )
+    .await
+    .into_iter()
+    .map(|r| DatabaseStorage {
+        name: text(r.first()).unwrap_or_default(),
+        size_bytes: number(r.get(1)),
+        current: matches!(r.get(2), Some(Value::Bool(true))),
+    })
+    .collect();
+
+    // reltuples is -1 until the table is first analyzed
+    let query = format!(
+        "SELECT c.relname::text, CASE WHEN c.reltuples >= 0 THEN c.reltuples::bigint END, \
+         pg_relation_size(c.oid), pg_indexes_size(c.oid), \
+         CASE WHEN c.reltoastrelid <> 0 THEN pg_total_relation_size(c.reltoastrelid) ELSE 0 END, \
+         pg_total_relation_size(c.oid), s.n_live_tup, s.n_dead_tup, {}, {} \
+         FROM pg_class c JOIN pg_namespace n ON n.oid = c.relnamespace \
+         LEFT JOIN pg_stat_user_tables s ON s.relid = c.oid \
+         WHERE n.nspname = 'public' AND c.relkind IN ('r', 'p', 'm') \
+         ORDER BY pg_total_relation_size(c.oid) DESC",
+        pg_time("vacuum"),
+        pg_time("analyze")
+    );
+    let tables = rows(connection, &query, "table sizes", warnings)
+        .await
+        .into_iter()
+        .map(|r| {
+            let live = number(r.get(6));
+            let dead = number(r.get(7));
+            TableStorage {
+                table: text(r.first()).unwrap_or_default(),
+                row_estimate: number(r.get(1)).or(live),
+                data_bytes: number(r.get(2)),
+                index_bytes: number(r.get(3)),
+                toast_bytes: number(r.get(4)),
+                total_bytes: number(r.get(5)),
+                free_bytes: None,
+                dead_rows: dead,
+                bloat_ratio: ratio(dead, live.zip(dead).map(|(l, d)| l + d)),
+                last_vacuum: text(r.get(8)),
+                last_analyze: text(r.get(9)),
+            }
+        })
+        .collect();
+
+    (databases, tables)
+}
+
+async fn mysql_storage(connection: &DatabaseConnection, warnings: &mut Vec<String>) -> (Vec<DatabaseStorage>, Vec<TableStorage>) {
+    let databases = rows(
+        connection,
+        "SELECT s.SCHEMA_NAME, CAST(SUM(t.DATA_LENGTH + t.INDEX_LENGTH) AS SIGNED), s.SCHEMA_NAME = DATABASE() \
+         FROM information_schema.SCHEMATA s LEFT JOIN information_schema.TABLES t ON t.TABLE_SCHEMA = s.SCHEMA_NAME \
+         GROUP BY s.SCHEMA_NAME ORDER BY s.SCHEMA_NAME",
+        "database sizes",
+        warnings,
+    )
+    .await
+    .into_iter()
+    .map(|r| DatabaseStorage {
+        name: text(r.first()).unwrap_or_default(),
+        size_bytes: number(r.get(1)),
+        current: number(r.get(2)) == Some(1),
+    })
+    .collect();
+
+    let mut tables: Vec<TableStorage> = rows(
+        connection,
+        "SELECT TABLE_NAME, CAST(TABLE_ROWS AS SIGNED), CAST(DATA_LENGTH AS SIGNED), CAST(INDEX_LENGTH AS SIGNED), \
+         CAST(DATA_FREE AS SIGNED) FROM information_schema.TABLES \
+         WHERE TABLE_SCHEMA = DATABASE() AND TABLE_TYPE = 'BASE TABLE' \
+         ORDER BY DATA_LENGTH + INDEX_LENGTH DESC",
+        "table sizes",
+        warnings,
+    )
+    .await
+    .into_iter()
+    .map(|r| {
+        let data = number(r.get(2));
+        let index = number(r.get(3));
+        let free = number(r.get(4));
+        let total = data.zip(index).map(|(d, i)| d + i);
+        TableStorage {
+            table: text(r.first()).unwrap_or_default(),
+            row_estimate: number(r.get(1)),
+            data_bytes: data,
+            index_bytes: index,
+            total_bytes: total,
+            free_bytes: free,
+            bloat_ratio: ratio(free, total.zip(free).map(|(t, f)| t + f)),
+            ..Default::default()
+        }
+    })
+    .collect();
+
+    // Persistent InnoDB statistics record when the table was last analyzed
+    let analyzed = rows(
+        connection,
+        "SELECT table_name, DATE_FORMAT(CONVERT_TZ(last_update, @@session.time_zone, '+00:00'), '%Y-%m-%dT%H:%i:%sZ') \
+         FROM mysql.innodb_table_stats WHERE database_name = DATABASE()",
+        "analyze times (mysql.innodb_table_stats)",
+        warnings,
+    )
+    .await;
+    for row in analyzed {
+        if let Some(table) = tables.iter_mut().find(|t| Some(&t.table) == text(row.first()).as_ref()) {
+            table.last_analyze = text(row.get(1));
+        }
+    }
+    warnings.push("MySQL sizes come from cached information_schema statistics; run ANALYZE TABLE for fresh numbers".to_string());
+
+    (databases, tables)
+}
+
+async fn sqlite_storage(connection: &DatabaseConnection, warnings: &mut Vec<String>) -> Result<(Vec<DatabaseStorage>, Vec<TableStorage>), AppError> {
+    let mut databases = Vec::new();
+    for schema in rows(connection, "PRAGMA database_list", "attached databases", warnings).await {
+        let Some(name) = text(schema.get(1)) else { continue };
+        let quoted = quote_identifier(&name, '"');
+        let pages = rows(connection, &format!("PRAGMA {}.page_count", quoted), "page counts", warnings).await;
+        let page_size = rows(connection, &format!("PRAGMA {}.page_size", quoted), "page sizes", warnings).await;
+        let size = number(pages.first().and_then(|r| r.first()))
+            .zip(number(page_size.first().and_then(|r| r.first())))
+            .map(|(pages, size)| pages * size);
+        databases.push(DatabaseStorage {
+            current: name == "main",
+            name,
+            size_bytes: size,
+        });
+    }
+
+    // dbstat is only present when SQLite was built with SQLITE_ENABLE_DBSTAT_VTAB
+    let mut sizes: HashMap<String, (Option<i64>, Option<i64>)> = HashMap::new();
+    let objects = rows(
+        connection,
+        "SELECT m.tbl_name, m.type, SUM(d.pgsize), SUM(d.unused) FROM sqlite_master m JOIN dbstat d ON d.name = m.name \
+         WHERE m.type IN ('table', 'index') GROUP BY m.tbl_name, m.type",
+        "object sizes (dbstat)",
+        warnings,
+    )
+    .await;
+    let mut free: HashMap<String, i64> = HashMap::new();
+    for row in objects {
+        let Some(table) = text(row.first()) else { continue };
+        let entry = sizes.entry(table.clone()).or_default();
+        match text(row.get(1)).as_deref() {
+            Some("table") => entry.0 = number(row.get(2)),
+            _ => entry.1 = number(row.get(2)),
+        }
+        *free.entry(table).or_default() += number(row.get(3)).unwrap_or(0);
+    }
+
+    let mut tables = Vec::new();
+    for table in connection.list_collections().await? {
+        let (data, index) = sizes.get(&table).cloned().unwrap_or_default();
+        let total = match (data, index) {
+            (None, None) => None,
+            (data, index) => Some(data.unwrap_or(0) + index.unwrap_or(0)),
+        };
+        let unused = free.get(&table).copied();
+        tables.push(TableStorage {
+            row_estimate: connection.estimate_row_count(&table).await?,
+            data_bytes: data,
+            index_bytes: index.or(data.map(|_| 0)),
+            total_bytes: total,
+            free_bytes: unused,
+            bloat_ratio: ratio(unused, total),
+            table,
+            ..Default::default()
+        });
+    }
+    Ok((databases, tables))
+}
+
+fn bson_number(value: Option<&Bson>) -> Option<i64> {
+    match value? {
+        Bson::Int32(v) => Some(*v as i64),
+        Bson::Int64(v) => Some(*v),
+        Bson::Double(v) => Some(*v as i64),
+        _ => None,
+    }
+}
+
+async fn mongodb_storage(
+    client: &mongodb::Client,
+    database: &mongodb::Database,
+    warnings: &mut Vec<String>,
+) -> Result<(Vec<DatabaseStorage>, Vec<TableStorage>), AppError> {
+    let databases = match client.list_databases(None, None).await {
+        Ok(list) => list
+            .into_iter()
+            .map(|d| DatabaseStorage {
+                current: d.name == database.name(),
+                size_bytes: Some(d.size_on_disk as i64),
+                name: d.name,
+            })
+            .collect(),
+        Err(e) => {
+            warnings.push(format!("Couldn't list databases: {}", e));
+            Vec::new()
+        }
+    };
+
+    let mut tables = Vec::new();
+    for name in database.list_collection_names(None).await? {
+        let collection = database.collection::<Document>(&name);
+        let stats: Vec<Document> = match collection.aggregate(vec![doc! { "$collStats": { "storageStats": {} } }], None).await {
+            Ok(cursor) => cursor.try_collect().await.unwrap_or_default(),
+            Err(e) => {
+                warnings.push(format!("Couldn't read storage stats for {}: {}", name, e));
+                Vec::new()
+            }
+        };
+        let storage = stats.first().and_then(|s| s.get_document("storageStats").ok());
+        let field = |key: &str| storage.and_then(|s| bson_number(s.get(key)));
+        let data = field("storageSize");
+        let free = field("freeStorageSize");
+        tables.push(TableStorage {
+            table: name,
+            row_estimate: field("count"),
+            data_bytes: data,
+            index_bytes: field("totalIndexSize"),
+            total_bytes: field("totalSize").or(data.zip(field("totalIndexSize")).map(|(d, i)| d + i)),
+            free_bytes: free,
+            bloat_ratio: ratio(free, data),
+            ..Default::default()
+        });
+    }
+    Ok((databases, tables))
+}
+
+pub async fn storage_stats(connection: &DatabaseConnection) -> Result<StorageStats, AppError> {
+    let mut warnings = Vec::new();
+    let (databases, mut tables) = match connection {
+        DatabaseConnection::PostgreSQL(..) => postgres_storage(connection, &mut warnings).await,
+        DatabaseConnection::MySQL(..) => mysql_storage(connection, &mut warnings).await,
+        DatabaseConnection::SQLite(..) => sqlite_storage(connection, &mut warnings).await?,
+        DatabaseConnection::MongoDB(client, database) => mongodb_storage(client, database, &mut warnings).await?,
+        DatabaseConnection::SQLServer(_) => {
+            warnings.push("Storage statistics aren't supported for SQL Server yet".to_string());
+            (Vec::new(), Vec::new())
+        }
+        DatabaseConnection::DuckDB(_) => {
+            warnings.push("Storage statistics aren't supported for DuckDB yet".to_string());
+            (Vec::new(), Vec::new())
+        }
+        DatabaseConnection::Redis(_) => {
+            warnings.push("Storage statistics aren't supported for Redis yet".to_string());
+            (Vec::new(), Vec::new())
+        }
+    };
+    tables.sort_by_key(|t| std::cmp::Reverse(t.total_bytes));
+
+    Ok(StorageStats {
+        databases,
+        tables,
+        warnings,
+    })
+}
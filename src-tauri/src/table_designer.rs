@@ -0,0 +1,789 @@
+use crate::database::{
+    quote_identifier, quote_mssql_identifier, ColumnInfo, DatabaseConnection, DatabaseSchema, DatabaseType, ForeignKeyInfo, IndexInfo, TableInfo,
+};
+use crate::error::AppError;
+use crate::schema_diff::{self, SchemaDiff, TableDiff};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ColumnDesign {
+    pub name: String,
+    // Name in the existing table; None for new columns
+    pub original_name: Option<String>,
+    // Full declared type, e.g. varchar(255)
+    pub data_type: String,
+    pub nullable: bool,
+    // SQL expression, e.g. 'active' or CURRENT_TIMESTAMP
+    pub default_value: Option<String>,
+    #[serde(default)]
+    pub primary_key: bool,
+    #[serde(default)]
+    pub auto_increment: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexDesign {
+    pub name: String,
+    pub columns: Vec<String>,
+    #[serde(default)]
+    pub unique: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TableDesign {
+    pub name: String,
+    // Name of the table being altered; None creates a new table
+    pub original_name: Option<String>,
+    pub columns: Vec<ColumnDesign>,
+    #[serde(default)]
+    pub indexes: Vec<IndexDesign>,
+    #[serde(default)]
+    pub foreign_keys: Vec<ForeignKeyInfo>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TableDdl {
+    pub dialect: String,
+    pub statements: Vec<String>,
+    pub warnings: Vec<String>,
+    // SQLite only: the table is recreated and its rows copied over
+    pub rebuild: bool,
+}
+
+fn quote(db_type: &DatabaseType, name: &str) -> String {
+    match db_type {
+        DatabaseType::MySQL => quote_identifier(name, '`'),
+        DatabaseType::SQLServer => quote_mssql_identifier(name),
+        DatabaseType::PostgreSQL | DatabaseType::SQLite | DatabaseType::DuckDB => quote_identifier(name, '"'),
+        DatabaseType::MongoDB | DatabaseType::Redis => unreachable!(),
+    }
+}
+
+fn check_supported(db_type: &DatabaseType) -> Result<(), AppError> {
+    match db_type {
+        DatabaseType::MongoDB | DatabaseType::Redis => Err(AppError::InvalidConfiguration(format!(
+            "The table designer doesn't support {}",
+            db_type.dialect_name()
+        ))),
+        _ => Ok(()),
+    }
+}
+
+fn sql_string(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "''"))
+}
+
+// Columns whose values come from AUTO_INCREMENT, a serial sequence, an identity or the SQLite rowid
+async fn auto_increment_columns(connection: &DatabaseConnection, table: &TableInfo) -> Result<HashSet<String>, AppError> {
+    let query = match connection {
+        DatabaseConnection::SQLServer(_) => format!(
+            "SELECT name FROM sys.identity_columns \
+             WHERE object_id = OBJECT_ID(QUOTENAME(SCHEMA_NAME()) + '.' + QUOTENAME({}))",
+            sql_string(&table.name)
+        ),
+        DatabaseConnection::MySQL(..) => format!(
+            "SELECT COLUMN_NAME FROM information_schema.COLUMNS WHERE TABLE_SCHEMA = DATABASE() AND TABLE_NAME = {} \
+             AND EXTRA LIKE '%auto_increment%'",
+            sql_string(&table.name)
+        ),
+        DatabaseConnection::PostgreSQL(..) => format!(
+            "SELECT column_name FROM information_schema.columns WHERE table_schema = current_schema() AND table_name = {} \
+             AND (is_identity = 'YES' OR column_default LIKE 'nextval(%')",
+            sql_string(&table.name)
+        ),
+        DatabaseConnection::SQLite(..) => {
+            let keys: Vec<&ColumnInfo> = table.columns.iter().filter(|c| c.is_primary_key).collect();
+            return Ok(match keys.as_slice() {
+                [key] if key.data_type.eq_ignore_ascii_case("integer") => HashSet::from([key.name.clone()]),
+                _ => HashSet::new(),
+            });
+        }
+        // Sequence defaults stay ordinary defaults in the designer
+        DatabaseConnection::DuckDB(_) => return Ok(HashSet::new()),
+        DatabaseConnection::MongoDB(..) | DatabaseConnection::Redis(_) => unreachable!(),
+    };
+    let rows = connection.execute_query(&query).await?.rows;
+    Ok(rows
+        .into_iter()
+        .filter_map(|r| r.into_iter().next().and_then(|v| v.as_str().map(|s| s.to_string())))
+        .collect())
+}
+
+async fn find_table(connection: &DatabaseConnection, name: &str) -> Result<Option<TableInfo>, AppError> {
+    Ok(connection.get_schema().await?.tables.into_iter().find(|t| t.name == name))
+}
+
+fn design_from_table(table: &TableInfo, db_type: &DatabaseType, auto_increment: &HashSet<String>) -> TableDesign {
+    TableDesign {
+        name: table.name.clone(),
+        original_name: Some(table.name.clone()),
+        columns: table
+            .columns
+            .iter()
+            .map(|c| {
+                let auto = auto_increment.contains(&c.name);
+                ColumnDesign {
+                    name: c.name.clone(),
+                    original_name: Some(c.name.clone()),
+                    data_type: c.column_type.clone().unwrap_or_else(|| c.data_type.clone()),
+                    nullable: c.is_nullable,
+                    // The sequence default is implied by auto_increment
+                    default_value: if auto && matches!(db_type, DatabaseType::PostgreSQL) { None } else { c.default_value.clone() },
+                    primary_key: c.is_primary_key,
+                    auto_increment: auto,
+                }
+            })
+            .collect(),
+        indexes: table
+            .indexes
+            .iter()
+            .filter(|i| !i.is_primary)
+            .map(|i| IndexDesign {
+                name: i.name.clone(),
+                columns: i.columns.clone(),
+                unique: i.is_unique,
+            })
+            .collect(),
+        foreign_keys: table.foreign_keys.clone(),
+    }
+}
+
+fn postgres_serial(data_type: &str) -> Option<&'static str> {
+    match data_type.to_lowercase().as_str() {
+        "smallint" | "int2" => Some("smallserial"),
+        "integer" | "int" | "int4" => Some("serial"),
+        "bigint" | "int8" => Some("bigserial"),
+        _ => None,
+    }
+}
+
+// `creating` encodes PostgreSQL and SQL Server auto increment in the type; on existing tables it is
+// altered separately
+fn table_from_design(design: &TableDesign, db_type: &DatabaseType, creating: bool) -> TableInfo {
+    let columns = design
+        .columns
+        .iter()
+        .map(|c| {
+            let column_type = match db_type {
+                DatabaseType::MySQL if c.auto_increment => format!("{} AUTO_INCREMENT", c.data_type),
+                DatabaseType::SQLServer if c.auto_increment && creating => format!("{} IDENTITY(1,1)", c.data_type),
+                DatabaseType::PostgreSQL if c.auto_increment && creating => match postgres_serial(&c.data_type) {
+                    Some(serial) => serial.to_string(),
+                    None => format!("{} GENERATED BY DEFAULT AS IDENTITY", c.data_type),
+                },
+                _ => c.data_type.clone(),
+            };
+            ColumnInfo {
+                name: c.name.clone(),
+                data_type: c.data_type.clone(),
+                is_nullable: c.nullable && !c.primary_key,
+                is_primary_key: c.primary_key,
+                column_type: Some(column_type),
+                default_value: c.default_value.clone().filter(|d| !d.trim().is_empty()),
+                extra: None,
+                comment: None,
+                collation: None,
+            }
+        })
+        .collect();
+
+    TableInfo {
+        name: design.name.clone(),
+        columns,
+        indexes: design
+            .indexes
+            .iter()
+            .map(|i| IndexInfo {
+                name: i.name.clone(),
+                table: design.name.clone(),
+                columns: i.columns.clone(),
+                is_unique: i.unique,
+                is_primary: false,
+            })
+            .collect(),
+        foreign_keys: design.foreign_keys.clone(),
+    }
+}
+
+fn validate(design: &TableDesign, db_type: &DatabaseType) -> Result<Vec<String>, AppError> {
+    let invalid = |message: String| Err(AppError::InvalidConfiguration(message));
+    if design.name.trim().is_empty() {
+        return invalid("Table name is required".to_string());
+    }
+    if design.columns.is_empty() {
+        return invalid(format!("Table {} needs at least one column", design.name));
+    }
+
+    let mut names = HashSet::new();
+    for column in &design.columns {
+        if column.name.trim().is_empty() || column.data_type.trim().is_empty() {
+            return invalid("Every column needs a name and a type".to_string());
+        }
+        if !names.insert(column.name.to_lowercase()) {
+            return invalid(format!("Column {} is defined twice", column.name));
+        }
+    }
+    let known = |name: &String| names.contains(&name.to_lowercase());
+    for index in &design.indexes {
+        if index.columns.is_empty() || !index.columns.iter().all(|c| c == "<expression>" || known(c)) {
+            return invalid(format!("Index {} must list existing columns", index.name));
+        }
+    }
+    for fk in &design.foreign_keys {
+        if fk.columns.is_empty() || fk.columns.len() != fk.referenced_columns.len() || !fk.columns.iter().all(known) {
+            return invalid(format!("Foreign key {} must pair existing columns with referenced columns", fk.name));
+        }
+    }
+
+    let mut warnings = Vec::new();
+    let keys: Vec<&ColumnDesign> = design.columns.iter().filter(|c| c.primary_key).collect();
+    if keys.is_empty() {
+        warnings.push(format!("Table {} has no primary key", design.name));
+    }
+    for column in design.columns.iter().filter(|c| c.auto_increment) {
+        if !column.primary_key {
+            return invalid(format!("Auto increment column {} must be part of the primary key", column.name));
+        }
+        if matches!(db_type, DatabaseType::DuckDB) {
+            return invalid(format!(
+                "DuckDB has no auto increment columns; give {} a nextval() default instead",
+                column.name
+            ));
+        }
+        // Only a lone INTEGER PRIMARY KEY aliases the rowid
+        if matches!(db_type, DatabaseType::SQLite) && (keys.len() > 1 || !column.data_type.eq_ignore_ascii_case("integer")) {
+            return invalid(format!(
+                "SQLite auto increments only a single INTEGER primary key; change {} to INTEGER",
+                column.name
+            ));
+        }
+    }
+    Ok(warnings)
+}
+
+fn migration(diff: SchemaDiff, from: Option<&TableInfo>, to: &TableInfo, db_type: &DatabaseType) -> Result<(Vec<String>, Vec<String>), AppError> {
+    let from = DatabaseSchema {
+        tables: from.into_iter().cloned().collect(),
+    };
+    let to = DatabaseSchema { tables: vec![to.clone()] };
+    let script = schema_diff::migration_sql(&diff, &from, &to, db_type)?;
+    Ok((script.statements, script.warnings))
+}
+
+// ALTER TABLE ADD/DROP COLUMN is all SQLite offers; anything else recreates the table
+fn sqlite_needs_rebuild(diff: &TableDiff, from: &TableInfo) -> bool {
+    let in_key = |name: &str| {
+        from.columns.iter().any(|c| c.name == name && c.is_primary_key)
+            || from.indexes.iter().any(|i| i.columns.iter().any(|c| c == name))
+            || from.foreign_keys.iter().any(|fk| fk.columns.iter().any(|c| c == name))
+    };
+    let constant_default = |default: &str| {
+        let upper = default.trim().to_uppercase();
+        !default.contains('(') && !upper.starts_with("CURRENT_")
+    };
+
+    !diff.changed_columns.is_empty()
+        || diff.primary_key_changed
+        || !diff.added_foreign_keys.is_empty()
+        || !diff.removed_foreign_keys.is_empty()
+        || diff.removed_indexes.iter().any(|i| i.name.starts_with("sqlite_autoindex_"))
+        || diff.removed_columns.iter().any(|c| in_key(&c.name))
+        || diff.added_columns.iter().any(|c| {
+            c.is_primary_key
+                || match &c.default_value {
+                    Some(default) => !constant_default(default),
+                    None => !c.is_nullable,
+                }
+        })
+}
+
+// The documented SQLite procedure: create the new shape, copy rows, swap names
+async fn sqlite_rebuild(
+    connection: &DatabaseConnection,
+    design: &TableDesign,
+    original: &TableInfo,
+    to: &TableInfo,
+) -> Result<(Vec<String>, Vec<String>), AppError> {
+    let db_type = DatabaseType::SQLite;
+    let temporary = format!("new_{}", design.name);
+    let mut warnings = Vec::new();
+
+    let mut shell = to.clone();
+    shell.name = temporary.clone();
+    shell.indexes.clear();
+    let (create, create_warnings) = migration(
+        SchemaDiff {
+            added_tables: vec![shell],
+            ..Default::default()
+        },
+        None,
+        to,
+        &db_type,
+    )?;
+    warnings.extend(create_warnings);
+
+    // New columns keep their defaults; existing ones are copied from their original names
+    let copied: Vec<(&ColumnDesign, &str)> = design
+        .columns
+        .iter()
+        .filter_map(|c| {
+            let source = c.original_name.as_deref()?;
+            original.columns.iter().any(|o| o.name == source).then_some((c, source))
+        })
+        .collect();
+    for column in design.columns.iter().filter(|c| !copied.iter().any(|(d, _)| d.name == c.name)) {
+        if !column.nullable && column.default_value.is_none() && !column.primary_key {
+            warnings.push(format!(
+                "New column {} is NOT NULL without a default; copying existing rows will fail if the table has data",
+                column.name
+            ));
+        }
+    }
+
+    let mut statements = vec!["PRAGMA foreign_keys = OFF;".to_string(), "BEGIN;".to_string()];
+    statements.extend(create);
+    if !copied.is_empty() {
+        let targets: Vec<String> = copied.iter().map(|(c, _)| quote(&db_type, &c.name)).collect();
+        let sources: Vec<String> = copied.iter().map(|(_, source)| quote(&db_type, source)).collect();
+        statements.push(format!(
+            "INSERT INTO {} ({}) SELECT {} FROM {};",
+            quote(&db_type, &temporary),
+            targets.join(", "),
+            sources.join(", "),
+            quote(&db_type, &original.name)
+        ));
+    }
+    statements.push(format!("DROP TABLE {};", quote(&db_type, &original.name)));
+    statements.push(format!("ALTER TABLE {} RENAME TO {};", quote(&db_type, &temporary), quote(&db_type, &design.name)));
+
+    let (indexes, index_warnings) = migration(
+        SchemaDiff {
+            changed_tables: vec![TableDiff {
+                name: design.name.clone(),
+                added_indexes: to.indexes.clone(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        },
+        Some(to),
+        to,
+        &db_type,
+    )?;
+    statements.extend(indexes);
+    warnings.extend(index_warnings);
+
+    // Triggers are dropped with the table and recreated from their original SQL
+    let triggers = connection
+        .execute_query(&format!(
+            "SELECT name, sql FROM sqlite_master WHERE type = 'trigger' AND tbl_name = {}",
+            sql_string(&original.name)
+        ))
+        .await?
+        .rows;
+    for trigger in triggers {
+        let (Some(Value::String(name)), Some(Value::String(sql))) = (trigger.first(), trigger.get(1)) else {
+            continue;
+        };
+        if original.name == design.name {
+            statements.push(format!("{};", sql.trim_end_matches(';')));
+        } else {
+            warnings.push(format!("Trigger {} references the old table name and must be recreated by hand", name));
+        }
+    }
+
+    // Fails the rebuild if the copied rows left dangling references
+    statements.push("PRAGMA foreign_key_check;".to_string());
+    statements.push("COMMIT;".to_string());
+    statements.push("PRAGMA foreign_keys = ON;".to_string());
+    warnings.push(format!("Views that reference {} are not updated by the rebuild", original.name));
+    Ok((statements, warnings))
+}
+
+// PostgreSQL auto increment is switched with a sequence or identity rather than a type change
+fn postgres_auto_increment(design: &TableDesign, original: &TableInfo, auto_before: &HashSet<String>) -> Vec<String> {
+    let db_type = DatabaseType::PostgreSQL;
+    let table = quote(&db_type, &design.name);
+    let mut statements = Vec::new();
+    for column in &design.columns {
+        let Some(source) = column.original_name.as_deref() else { continue };
+        let was_auto = auto_before.contains(source);
+        let name = quote(&db_type, &column.name);
+        if column.auto_increment && !was_auto {
+            let sequence = quote(&db_type, &format!("{}_{}_seq", design.name, column.name));
+            statements.push(format!("CREATE SEQUENCE IF NOT EXISTS {} OWNED BY {}.{};", sequence, table, name));
+            statements.push(format!(
+                "ALTER TABLE {} ALTER COLUMN {} SET DEFAULT nextval({});",
+                table,
+                name,
+                sql_string(&sequence)
+            ));
+            statements.push(format!(
+                "SELECT setval({}, COALESCE((SELECT MAX({}) FROM {}), 0) + 1, false);",
+                sql_string(&sequence),
+                name,
+                table
+            ));
+        } else if !column.auto_increment && was_auto {
+            let serial = original
+                .columns
+                .iter()
+                .any(|c| c.name == source && c.default_value.as_deref().map(|d| d.starts_with("nextval(")).unwrap_or(false));
+            statements.push(if serial {
+                format!("ALTER TABLE {} ALTER COLUMN {} DROP DEFAULT;", table, name)
+            } else {
+                format!("ALTER TABLE {} ALTER COLUMN {} DROP IDENTITY IF EXISTS;", table, name)
+            });
+        }
+    }
+    statements
+}
+
+pub async fn get_table_design(connection: &DatabaseConnection, table: &str) -> Result<TableDesign, AppError> {
+    let db_type = connection.db_type();
+    check_supported(&db_type)?;
+    let info = find_table(connection, table)
+        .await?
+        .ok_or_else(|| AppError::InvalidConfiguration(format!("Table {} not found", table)))?;
+    let auto_increment = auto_increment_columns(connection, &info).await?;
+    Ok(design_from_table(&info, &db_type, &auto_increment))
+}
+
+// CREATE TABLE and index statements for a new table
+fn create_ddl(design: &TableDesign, db_type: &DatabaseType) -> Result<(Vec<String>, Vec<String>), AppError> {
+    let to = table_from_design(design, db_type, true);
+    migration(
+        SchemaDiff {
+            added_tables: vec![to.clone()],
+            ..Default::default()
+        },
+        None,
+        &to,
+        db_type,
+    )
+}
+
+enum AlterPlan {
+    // SQLite can't alter in place; the table is rebuilt into this shape
+    Rebuild(TableInfo),
+    Statements(Vec<String>, Vec<String>),
+}
+
+// Statements that alter `original` to match the design
+fn alter_ddl(
+    design: &TableDesign,
+    original: &TableInfo,
+    auto_before: &HashSet<String>,
+    db_type: &DatabaseType,
+) -> Result<AlterPlan, AppError> {
+    // Compare against the current table as if the renames had already happened
+    let renames: HashMap<&str, &str> = design
+        .columns
+        .iter()
+        .filter_map(|c| Some((c.original_name.as_deref()?, c.name.as_str())))
+        .collect();
+    for source in renames.keys() {
+        if !original.columns.iter().any(|c| &c.name == source) {
+            return Err(AppError::InvalidConfiguration(format!("Table {} has no column {}", original.name, source)));
+        }
+    }
+    let renamed = |name: &String| renames.get(name.as_str()).map(|n| n.to_string()).unwrap_or_else(|| name.clone());
+    let mut current = design_from_table(original, db_type, auto_before);
+    current.name = design.name.clone();
+    for column in current.columns.iter_mut() {
+        column.name = renamed(&column.name);
+    }
+    for index in current.indexes.iter_mut() {
+        index.columns = index.columns.iter().map(renamed).collect();
+    }
+    for fk in current.foreign_keys.iter_mut() {
+        fk.columns = fk.columns.iter().map(renamed).collect();
+    }
+    let mut from = table_from_design(&current, db_type, false);
+    // PostgreSQL drops the primary key by constraint name
+    from.indexes.extend(original.indexes.iter().filter(|i| i.is_primary).cloned());
+    let to = table_from_design(design, db_type, false);
+    let diff = schema_diff::diff_table(&from, &to);
+
+    if matches!(db_type, DatabaseType::SQLite) && sqlite_needs_rebuild(&diff, &from) {
+        return Ok(AlterPlan::Rebuild(to));
+    }
+
+    let mut statements = Vec::new();
+    let mut warnings = Vec::new();
+    if original.name != design.name {
+        statements.push(match db_type {
+            DatabaseType::MySQL => format!("RENAME TABLE {} TO {};", quote(db_type, &original.name), quote(db_type, &design.name)),
+            // sp_rename takes the new name unquoted
+            DatabaseType::SQLServer => format!("EXEC sp_rename {}, {};", sql_string(&original.name), sql_string(&design.name)),
+            _ => format!("ALTER TABLE {} RENAME TO {};", quote(db_type, &original.name), quote(db_type, &design.name)),
+        });
+    }
+    for column in &design.columns {
+        let Some(source) = column.original_name.as_deref().filter(|s| *s != column.name) else { continue };
+        statements.push(match db_type {
+            DatabaseType::SQLServer => format!(
+                "EXEC sp_rename {}, {}, 'COLUMN';",
+                sql_string(&format!("{}.{}", design.name, source)),
+                sql_string(&column.name)
+            ),
+            _ => format!(
+                "ALTER TABLE {} RENAME COLUMN {} TO {};",
+                quote(db_type, &design.name),
+                quote(db_type, source),
+                quote(db_type, &column.name)
+            ),
+        });
+    }
+    let (alter, alter_warnings) = migration(
+        SchemaDiff {
+            changed_tables: vec![diff],
+            ..Default::default()
+        },
+        Some(&from),
+        &to,
+        db_type,
+    )?;
+    statements.extend(alter);
+    warnings.extend(alter_warnings);
+    match db_type {
+        DatabaseType::PostgreSQL => statements.extend(postgres_auto_increment(design, original, auto_before)),
+        DatabaseType::SQLServer => {
+            for column in &design.columns {
+                let was_auto = column.original_name.as_deref().is_some_and(|s| auto_before.contains(s));
+                if column.original_name.is_some() && column.auto_increment != was_auto {
+                    warnings.push(format!(
+                        "SQL Server can't change IDENTITY on existing column {}; the table must be rebuilt",
+                        column.name
+                    ));
+                }
+            }
+        }
+        _ => {}
+    }
+    Ok(AlterPlan::Statements(statements, warnings))
+}
+
+// DDL that creates the designed table, or alters `original_name` to match it
+pub async fn generate_ddl(connection: &DatabaseConnection, design: &TableDesign) -> Result<TableDdl, AppError> {
+    let db_type = connection.db_type();
+    check_supported(&db_type)?;
+    let mut warnings = validate(design, &db_type)?;
+
+    let Some(original_name) = design.original_name.as_deref() else {
+        if find_table(connection, &design.name).await?.is_some() {
+            return Err(AppError::InvalidConfiguration(format!("Table {} already exists", design.name)));
+        }
+        let (statements, create_warnings) = create_ddl(design, &db_type)?;
+        warnings.extend(create_warnings);
+        return Ok(TableDdl {
+            dialect: db_type.as_str().to_string(),
+            statements,
+            warnings,
+            rebuild: false,
+        });
+    };
+
+    let original = find_table(connection, original_name)
+        .await?
+        .ok_or_else(|| AppError::InvalidConfiguration(format!("Table {} not found", original_name)))?;
+    let auto_before = auto_increment_columns(connection, &original).await?;
+
+    let (statements, rebuild) = match alter_ddl(design, &original, &auto_before, &db_type)? {
+        AlterPlan::Rebuild(to) => {
+            let (statements, rebuild_warnings) = sqlite_rebuild(connection, design, &original, &to).await?;
+            warnings.extend(rebuild_warnings);
+            (statements, true)
+        }
+        AlterPlan::Statements(statements, alter_warnings) => {
+            warnings.extend(alter_warnings);
+            if statements.is_empty() {
+                warnings.push(format!("Table {} already matches the design", design.name));
+            }
+            (statements, false)
+        }
+    };
+
+    Ok(TableDdl {
+        dialect: db_type.as_str().to_string(),
+        statements,
+        warnings,
+        rebuild,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    fn column(name: &str, data_type: &str, nullable: bool) -> ColumnDesign {
+        ColumnDesign {
+            name: name.to_string(),
+            original_name: None,
+            data_type: data_type.to_string(),
+            nullable,
+            default_value: None,
+            primary_key: false,
+            auto_increment: false,
+        }
+    }
+
+    fn key(name: &str, data_type: &str) -> ColumnDesign {
+        ColumnDesign {
+            primary_key: true,
+            auto_increment: true,
+            ..column(name, data_type, false)
+        }
+    }
+
+    fn orders() -> TableDesign {
+        TableDesign {
+            name: "orders".to_string(),
+            original_name: None,
+            columns: vec![
+                key("id", "integer"),
+                column("customer_id", "integer", false),
+                ColumnDesign {
+                    default_value: Some("'new'".to_string()),
+                    ..column("status", "varchar(20)", true)
+                },
+            ],
+            indexes: vec![IndexDesign {
+                name: "orders_status".to_string(),
+                columns: vec!["status".to_string()],
+                unique: false,
+            }],
+            foreign_keys: vec![ForeignKeyInfo {
+                name: "orders_customer".to_string(),
+                columns: vec!["customer_id".to_string()],
+                referenced_table: "customers".to_string(),
+                referenced_columns: vec!["id".to_string()],
+                on_delete: Some("CASCADE".to_string()),
+                on_update: None,
+            }],
+        }
+    }
+
+    #[test]
+    fn mysql_create_table() {
+        let (statements, warnings) = create_ddl(&orders(), &DatabaseType::MySQL).unwrap();
+        assert_eq!(
+            statements,
+            vec![
+                "CREATE TABLE `orders` (\n    `id` integer AUTO_INCREMENT NOT NULL,\n    `customer_id` integer NOT NULL,\n    \
+                 `status` varchar(20) DEFAULT 'new',\n    PRIMARY KEY (`id`)\n);",
+                "CREATE INDEX `orders_status` ON `orders` (`status`);",
+                "ALTER TABLE `orders` ADD CONSTRAINT `orders_customer` FOREIGN KEY (`customer_id`) REFERENCES `customers` (`id`) \
+                 ON DELETE CASCADE;",
+            ]
+        );
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn postgres_create_table() {
+        let (statements, _) = create_ddl(&orders(), &DatabaseType::PostgreSQL).unwrap();
+        assert_eq!(
+            statements,
+            vec![
+                "CREATE TABLE \"orders\" (\n    \"id\" serial NOT NULL,\n    \"customer_id\" integer NOT NULL,\n    \
+                 \"status\" varchar(20) DEFAULT 'new',\n    PRIMARY KEY (\"id\")\n);",
+                "CREATE INDEX \"orders_status\" ON \"orders\" (\"status\");",
+                "ALTER TABLE \"orders\" ADD CONSTRAINT \"orders_customer\" FOREIGN KEY (\"customer_id\") REFERENCES \"customers\" (\"id\") \
+                 ON DELETE CASCADE;",
+            ]
+        );
+    }
+
+    #[test]
+    fn sqlite_create_table_declares_foreign_keys_inline() {
+        let (statements, _) = create_ddl(&orders(), &DatabaseType::SQLite).unwrap();
+        assert_eq!(
+            statements,
+            vec![
+                "CREATE TABLE \"orders\" (\n    \"id\" integer NOT NULL,\n    \"customer_id\" integer NOT NULL,\n    \
+                 \"status\" varchar(20) DEFAULT 'new',\n    PRIMARY KEY (\"id\"),\n    \
+                 FOREIGN KEY (\"customer_id\") REFERENCES \"customers\" (\"id\") ON DELETE CASCADE\n);",
+                "CREATE INDEX \"orders_status\" ON \"orders\" (\"status\");",
+            ]
+        );
+    }
+
+    #[test]
+    fn postgres_alter_renames_then_alters() {
+        let original = table_from_design(&orders(), &DatabaseType::PostgreSQL, false);
+        let mut design = design_from_table(&original, &DatabaseType::PostgreSQL, &HashSet::from(["id".to_string()]));
+        design.columns[2].name = "state".to_string();
+        design.columns[2].nullable = false;
+        design.indexes.clear();
+        let AlterPlan::Statements(statements, _) =
+            alter_ddl(&design, &original, &HashSet::from(["id".to_string()]), &DatabaseType::PostgreSQL).unwrap()
+        else {
+            panic!("PostgreSQL alters in place");
+        };
+        assert_eq!(
+            statements,
+            vec![
+                "ALTER TABLE \"orders\" RENAME COLUMN \"status\" TO \"state\";",
+                "DROP INDEX \"orders_status\";",
+                "ALTER TABLE \"orders\" ALTER COLUMN \"state\" SET NOT NULL;",
+            ]
+        );
+    }
+
+    #[test]
+    fn mysql_alter_adds_columns() {
+        let original = table_from_design(&orders(), &DatabaseType::MySQL, false);
+        let mut design = design_from_table(&original, &DatabaseType::MySQL, &HashSet::from(["id".to_string()]));
+        design.columns.push(column("note", "text", true));
+        let AlterPlan::Statements(statements, _) =
+            alter_ddl(&design, &original, &HashSet::from(["id".to_string()]), &DatabaseType::MySQL).unwrap()
+        else {
+            panic!("MySQL alters in place");
+        };
+        assert_eq!(statements, vec!["ALTER TABLE `orders` ADD COLUMN `note` text;"]);
+    }
+
+    async fn sqlite_connection() -> DatabaseConnection {
+        // One connection, so every query sees the same in-memory database
+        let pool = SqlitePoolOptions::new().max_connections(1).connect("sqlite::memory:").await.unwrap();
+        let connection = DatabaseConnection::SQLite(pool, Vec::new());
+        connection
+            .execute_ddl(
+                &[
+                    "CREATE TABLE customers (id INTEGER PRIMARY KEY);".to_string(),
+                    "CREATE TABLE orders (id INTEGER PRIMARY KEY, customer_id INTEGER NOT NULL, status TEXT);".to_string(),
+                    "INSERT INTO customers VALUES (1);".to_string(),
+                    "INSERT INTO orders VALUES (1, 1, 'new'), (2, 7, 'new');".to_string(),
+                ],
+                false,
+            )
+            .await
+            .unwrap();
+        connection
+    }
+
+    #[tokio::test]
+    async fn sqlite_rebuild_checks_foreign_keys_before_commit() {
+        let connection = sqlite_connection().await;
+        let mut design = get_table_design(&connection, "orders").await.unwrap();
+        design.foreign_keys = orders().foreign_keys;
+
+        let ddl = generate_ddl(&connection, &design).await.unwrap();
+        assert!(ddl.rebuild);
+        let tail: Vec<&str> = ddl.statements.iter().rev().take(3).map(String::as_str).rev().collect();
+        assert_eq!(tail, vec!["PRAGMA foreign_key_check;", "COMMIT;", "PRAGMA foreign_keys = ON;"]);
+
+        // Order 2 references a missing customer, so the rebuild is rolled back
+        let error = connection.execute_ddl(&ddl.statements, true).await.unwrap_err();
+        assert!(error.to_string().contains("foreign key"));
+        let schema = connection.get_schema().await.unwrap();
+        let table = schema.tables.iter().find(|t| t.name == "orders").unwrap();
+        assert!(table.foreign_keys.is_empty());
+
+        connection.execute_ddl(&["DELETE FROM orders WHERE id = 2;".to_string()], false).await.unwrap();
+        connection.execute_ddl(&ddl.statements, true).await.unwrap();
+    }
+}
@@ -0,0 +1,627 @@
+use crate::database::{quote_identifier, DatabaseConnection, DatabaseType};
+use crate::error::AppError;
+use bson::{doc, Bson, Document};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+// Shown in previews in place of the real password
+const MASKED_PASSWORD: &str = "********";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserInfo {
+    pub name: String,
+    // MySQL account host
+    pub host: Option<String>,
+    // MongoDB database the user is defined in
+    pub database: Option<String>,
+    pub can_login: bool,
+    pub superuser: bool,
+    // Roles granted to this user or role
+    pub member_of: Vec<String>,
+    // Other flags such as CREATEDB, REPLICATION or LOCKED
+    pub attributes: Vec<String>,
+    pub valid_until: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GrantInfo {
+    pub grantee: String,
+    pub host: Option<String>,
+    // Table, `db`.* / *.* for MySQL, or the role's database for MongoDB
+    pub object: String,
+    // Privilege type, or role name for MongoDB
+    pub privilege: String,
+    pub grantable: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserReport {
+    pub users: Vec<UserInfo>,
+    pub grants: Vec<GrantInfo>,
+    // Catalogs that couldn't be read, e.g. for lack of privileges
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "scope", rename_all = "snake_case")]
+pub enum GrantTarget {
+    // Server-wide: *.* on MySQL, the admin database on MongoDB
+    Global,
+    // The database itself; defaults to the connected one
+    Database { name: Option<String> },
+    // Every table in the connected database (PostgreSQL: schema public)
+    AllTables,
+    Table { name: String },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum UserChange {
+    CreateUser {
+        name: String,
+        password: Option<String>,
+        // MySQL account host, '%' when empty
+        host: Option<String>,
+        // Roles to grant right away; MongoDB roles apply to the connected database
+        #[serde(default)]
+        roles: Vec<String>,
+        // MongoDB database to create the user in; defaults to the connected one
+        auth_database: Option<String>,
+    },
+    Grant {
+        user: String,
+        host: Option<String>,
+        // Privilege types such as SELECT or ALL PRIVILEGES; role names for MongoDB
+        privileges: Vec<String>,
+        target: GrantTarget,
+        #[serde(default)]
+        with_grant_option: bool,
+        auth_database: Option<String>,
+    },
+    Revoke {
+        user: String,
+        host: Option<String>,
+        privileges: Vec<String>,
+        target: GrantTarget,
+        auth_database: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserDdl {
+    pub dialect: String,
+    // Passwords are masked; MongoDB changes are shown as shell commands
+    pub statements: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+fn text(value: Option<&Value>) -> Option<String> {
+    match value? {
+        Value::Null => None,
+        Value::String(s) => Some(s.clone()),
+        other => Some(other.to_string()),
+    }
+}
+
+fn flag(value: Option<&Value>) -> bool {
+    match value {
+        Some(Value::Bool(b)) => *b,
+        Some(Value::Number(n)) => n.as_i64() == Some(1),
+        Some(Value::String(s)) => matches!(s.as_str(), "Y" | "YES" | "t" | "true" | "1"),
+        _ => false,
+    }
+}
+
+fn list(value: Option<&Value>) -> Vec<String> {
+    text(value)
+        .map(|s| s.split(',').filter(|r| !r.is_empty()).map(|r| r.to_string()).collect())
+        .unwrap_or_default()
+}
+
+fn sql_string(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "''"))
+}
+
+// MySQL also treats backslashes as escapes unless NO_BACKSLASH_ESCAPES is set
+fn mysql_string(text: &str) -> String {
+    format!("'{}'", text.replace('\\', "\\\\").replace('\'', "''"))
+}
+
+async fn rows(connection: &DatabaseConnection, query: &str, what: &str, warnings: &mut Vec<String>) -> Vec<Vec<Value>> {
+    match connection.execute_query(query).await {
+        Ok(result) => result.rows,
+        Err(e) => {
+            warnings.push(format!("Couldn't read {}: {}", what, e));
+            Vec::new()
+        }
+    }
+}
+
+async fn postgres_users(connection: &DatabaseConnection, warnings: &mut Vec<String>) -> (Vec<UserInfo>, Vec<GrantInfo>) {
+    let users = rows(
+        connection,
+        "SELECT r.rolname::text, r.rolcanlogin, r.rolsuper, r.rolcreatedb, r.rolcreaterole, r.rolreplication, r.rolbypassrls, \
+         to_char(r.rolvaliduntil AT TIME ZONE 'UTC', 'YYYY-MM-DD\"T\"HH24:MI:SS\"Z\"'), \
+         array_to_string(ARRAY(SELECT b.rolname FROM pg_auth_members m JOIN pg_roles b ON b.oid = m.roleid \
+         WHERE m.member = r.oid ORDER BY b.rolname), ',') \
+         FROM pg_roles r WHERE r.rolname NOT LIKE 'pg\\_%' ORDER BY r.rolname",
+        "roles (pg_roles)",
+        warnings,
+    )
+    .await
+    .into_iter()
+    .map(|r| {
+        let attributes = [(3, "CREATEDB"), (4, "CREATEROLE"), (5, "REPLICATION"), (6, "BYPASSRLS")]
+            .iter()
+            .filter(|(i, _)| flag(r.get(*i)))
+            .map(|(_, name)| name.to_string())
+            .collect();
+        UserInfo {
+            name: text(r.first()).unwrap_or_default(),
+            host: None,
+            database: None,
+            can_login: flag(r.get(1)),
+            superuser: flag(r.get(2)),
+            member_of: list(r.get(8)),
+            attributes,
+            valid_until: text(r.get(7)),
+        }
+    })
+    .collect();
+
+    // Only grants where the current user is grantor or grantee, or a member of one of them, are visible
+    let grants = rows(
+        connection,
+        "SELECT grantee::text, table_name::text, privilege_type::text, is_grantable::text \
+         FROM information_schema.role_table_grants WHERE table_schema = 'public' \
+         ORDER BY grantee, table_name, privilege_type",
+        "table grants (information_schema.role_table_grants)",
+        warnings,
+    )
+    .await
+    .into_iter()
+    .map(|r| GrantInfo {
+        grantee: text(r.first()).unwrap_or_default(),
+        host: None,
+        object: text(r.get(1)).unwrap_or_default(),
+        privilege: text(r.get(2)).unwrap_or_default(),
+        grantable: flag(r.get(3)),
+    })
+    .collect();
+
+    (users, grants)
+}
+
+// Split on commas outside parentheses, e.g. "SELECT (a, b), INSERT"
+fn split_privileges(privileges: &str) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut depth = 0;
+    let mut current = String::new();
+    for c in privileges.chars() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ',' if depth == 0 => {
+                parts.push(current.trim().to_string());
+                current.clear();
+                continue;
+            }
+            _ => {}
+        }
+        current.push(c);
+    }
+    parts.push(current.trim().to_string());
+    parts.into_iter().filter(|p| !p.is_empty()).collect()
+}
+
+// Parse one line of SHOW GRANTS into privilege grants, or role names for role grants
+fn parse_mysql_grant(line: &str, user: &str, host: &str, grants: &mut Vec<GrantInfo>, roles: &mut Vec<String>) {
+    let Some(rest) = line.strip_prefix("GRANT ") else { return };
+    let Some(to) = rest.rfind(" TO ") else { return };
+    let (head, tail) = rest.split_at(to);
+    match head.find(" ON ") {
+        Some(on) => {
+            let object = head[on + 4..].trim().to_string();
+            let grantable = tail.contains("WITH GRANT OPTION");
+            for privilege in split_privileges(&head[..on]) {
+                grants.push(GrantInfo {
+                    grantee: user.to_string(),
+                    host: Some(host.to_string()),
+                    object: object.clone(),
+                    privilege,
+                    grantable,
+                });
+            }
+        }
+        // MySQL 8 role grants: GRANT `role`@`%` TO `user`@`host`
+        None => roles.extend(split_privileges(head).into_iter().map(|r| r.replace('`', ""))),
+    }
+}
+
+async fn mysql_users(connection: &DatabaseConnection, warnings: &mut Vec<String>) -> (Vec<UserInfo>, Vec<GrantInfo>) {
+    let mut accounts: Vec<(String, String, bool, bool)> = rows(
+        connection,
+        "SELECT User, Host, Super_priv, account_locked FROM mysql.user ORDER BY User, Host",
+        "accounts (mysql.user)",
+        warnings,
+    )
+    .await
+    .into_iter()
+    .map(|r| (text(r.first()).unwrap_or_default(), text(r.get(1)).unwrap_or_default(), flag(r.get(2)), flag(r.get(3))))
+    .collect();
+    // Without access to mysql.user, at least show the connected account
+    if accounts.is_empty() {
+        let current = rows(connection, "SELECT CURRENT_USER()", "current user", warnings).await;
+        if let Some(account) = current.first().and_then(|r| text(r.first())) {
+            if let Some((user, host)) = account.rsplit_once('@') {
+                accounts.push((user.to_string(), host.to_string(), false, false));
+            }
+        }
+    }
+
+    let mut users = Vec::new();
+    let mut grants = Vec::new();
+    for (user, host, superuser, locked) in accounts {
+        let query = format!("SHOW GRANTS FOR {}@{}", mysql_string(&user), mysql_string(&host));
+        let mut roles = Vec::new();
+        for row in rows(connection, &query, &format!("grants for {}@{}", user, host), warnings).await {
+            if let Some(line) = text(row.first()) {
+                parse_mysql_grant(&line, &user, &host, &mut grants, &mut roles);
+            }
+        }
+        users.push(UserInfo {
+            name: user,
+            host: Some(host),
+            database: None,
+            can_login: !locked,
+            superuser,
+            member_of: roles,
+            attributes: if locked { vec!["LOCKED".to_string()] } else { Vec::new() },
+            valid_until: None,
+        });
+    }
+    (users, grants)
+}
+
+async fn mongodb_users(client: &mongodb::Client, database: &mongodb::Database, warnings: &mut Vec<String>) -> (Vec<UserInfo>, Vec<GrantInfo>) {
+    let result = match client.database("admin").run_command(doc! { "usersInfo": { "forAllDBs": true } }, None).await {
+        Ok(result) => result,
+        Err(e) => {
+            warnings.push(format!("Couldn't list users of all databases, showing {} only: {}", database.name(), e));
+            match database.run_command(doc! { "usersInfo": 1 }, None).await {
+                Ok(result) => result,
+                Err(e) => {
+                    warnings.push(format!("Couldn't run usersInfo: {}", e));
+                    return (Vec::new(), Vec::new());
+                }
+            }
+        }
+    };
+
+    let mut users = Vec::new();
+    let mut grants = Vec::new();
+    for user in result.get_array("users").map(|u| u.iter().filter_map(|u| u.as_document()).collect::<Vec<_>>()).unwrap_or_default() {
+        let name = user.get_str("user").unwrap_or_default().to_string();
+        let mut member_of = Vec::new();
+        let mut superuser = false;
+        for role in user.get_array("roles").map(|r| r.iter().filter_map(|r| r.as_document()).collect::<Vec<_>>()).unwrap_or_default() {
+            let role_name = role.get_str("role").unwrap_or_default().to_string();
+            let role_db = role.get_str("db").unwrap_or_default().to_string();
+            superuser |= role_name == "root" && role_db == "admin";
+            member_of.push(format!("{}@{}", role_name, role_db));
+            grants.push(GrantInfo {
+                grantee: name.clone(),
+                host: None,
+                object: role_db,
+                privilege: role_name,
+                grantable: false,
+            });
+        }
+        users.push(UserInfo {
+            name,
+            host: None,
+            database: user.get_str("db").ok().map(|db| db.to_string()),
+            can_login: true,
+            superuser,
+            member_of,
+            attributes: user
+                .get_array("mechanisms")
+                .map(|m| m.iter().filter_map(|m| m.as_str().map(|m| m.to_string())).collect())
+                .unwrap_or_default(),
+            valid_until: None,
+        });
+    }
+    (users, grants)
+}
+
+fn unsupported(connection: &DatabaseConnection) -> Result<(), AppError> {
+    let message = match connection {
+        DatabaseConnection::PostgreSQL(..) | DatabaseConnection::MySQL(..) | DatabaseConnection::MongoDB(..) => return Ok(()),
+        DatabaseConnection::SQLite(..) => "SQLite has no users or privileges",
+        DatabaseConnection::SQLServer(_) => "User management isn't supported for SQL Server yet",
+        DatabaseConnection::Redis(_) => "User management isn't supported for Redis yet",
+        DatabaseConnection::DuckDB(_) => "DuckDB has no users or privileges",
+    };
+    Err(AppError::InvalidConfiguration(message.to_string()))
+}
+
+pub async fn list_users(connection: &DatabaseConnection) -> Result<UserReport, AppError> {
+    unsupported(connection)?;
+    let mut warnings = Vec::new();
+    let (users, grants) = match connection {
+        DatabaseConnection::MongoDB(client, database) => mongodb_users(client, database, &mut warnings).await,
+        DatabaseConnection::PostgreSQL(..) => postgres_users(connection, &mut warnings).await,
+        _ => mysql_users(connection, &mut warnings).await,
+    };
+    Ok(UserReport { users, grants, warnings })
+}
+
+// Privilege keywords are spliced into SQL, so only plain words are accepted
+fn sql_privileges(privileges: &[String]) -> Result<String, AppError> {
+    if privileges.is_empty() {
+        return Err(AppError::InvalidConfiguration("Choose at least one privilege".to_string()));
+    }
+    let mut cleaned = Vec::new();
+    for privilege in privileges {
+        let privilege = privilege.split_whitespace().collect::<Vec<_>>().join(" ").to_uppercase();
+        if privilege.is_empty() || !privilege.chars().all(|c| c.is_ascii_alphabetic() || c == ' ' || c == '_') {
+            return Err(AppError::InvalidConfiguration(format!("Invalid privilege: {}", privilege)));
+        }
+        cleaned.push(privilege);
+    }
+    Ok(cleaned.join(", "))
+}
+
+fn check_name(name: &str, what: &str) -> Result<(), AppError> {
+    if name.trim().is_empty() {
+        return Err(AppError::InvalidConfiguration(format!("{} name is required", what)));
+    }
+    Ok(())
+}
+
+async fn current_database(connection: &DatabaseConnection, query: &str) -> Result<String, AppError> {
+    connection
+        .execute_query(query)
+        .await?
+        .rows
+        .first()
+        .and_then(|r| text(r.first()))
+        .ok_or_else(|| AppError::InvalidConfiguration("No database selected".to_string()))
+}
+
+fn mysql_account(user: &str, host: &Option<String>) -> String {
+    let host = host.as_deref().map(str::trim).filter(|h| !h.is_empty()).unwrap_or("%");
+    format!("{}@{}", mysql_string(user), mysql_string(host))
+}
+
+async fn postgres_object(connection: &DatabaseConnection, target: &GrantTarget) -> Result<String, AppError> {
+    Ok(match target {
+        GrantTarget::Global => {
+            return Err(AppError::InvalidConfiguration(
+                "PostgreSQL has no server-wide privileges; grant a role or use ALTER ROLE instead".to_string(),
+            ))
+        }
+        GrantTarget::Database { name } => {
+            let name = match name.as_deref().filter(|n| !n.trim().is_empty()) {
+                Some(name) => name.to_string(),
+                None => current_database(connection, "SELECT current_database()").await?,
+            };
+            format!("DATABASE {}", quote_identifier(&name, '"'))
+        }
+        GrantTarget::AllTables => "ALL TABLES IN SCHEMA public".to_string(),
+        GrantTarget::Table { name } => format!("TABLE {}", quote_identifier(name, '"')),
+    })
+}
+
+async fn mysql_object(connection: &DatabaseConnection, target: &GrantTarget) -> Result<String, AppError> {
+    let database = match target {
+        GrantTarget::Global => return Ok("*.*".to_string()),
+        GrantTarget::Database { name: Some(name) } if !name.trim().is_empty() => name.clone(),
+        _ => current_database(connection, "SELECT DATABASE()").await?,
+    };
+    Ok(match target {
+        GrantTarget::Table { name } => format!("{}.{}", quote_identifier(&database, '`'), quote_identifier(name, '`')),
+        _ => format!("{}.*", quote_identifier(&database, '`')),
+    })
+}
+
+// (database to run in, command) pairs
+type MongoCommands = Vec<(String, Document)>;
+
+fn mongodb_roles(privileges: &[String], target: &GrantTarget, current: &str) -> Result<Vec<Bson>, AppError> {
+    let database = match target {
+        GrantTarget::Global => "admin".to_string(),
+        GrantTarget::Database { name: Some(name) } if !name.trim().is_empty() => name.clone(),
+        GrantTarget::Database { .. } | GrantTarget::AllTables => current.to_string(),
+        GrantTarget::Table { .. } => {
+            return Err(AppError::InvalidConfiguration(
+                "MongoDB grants roles per database; collection privileges need a custom role".to_string(),
+            ))
+        }
+    };
+    if privileges.iter().all(|p| p.trim().is_empty()) {
+        return Err(AppError::InvalidConfiguration("Choose at least one role".to_string()));
+    }
+    Ok(privileges
+        .iter()
+        .map(|p| p.trim())
+        .filter(|p| !p.is_empty())
+        .map(|role| Bson::Document(doc! { "role": role, "db": database.as_str() }))
+        .collect())
+}
+
+fn mongodb_changes(database: &mongodb::Database, change: &UserChange, warnings: &mut Vec<String>) -> Result<MongoCommands, AppError> {
+    let auth_database = |auth: &Option<String>| {
+        auth.as_deref()
+            .filter(|db| !db.trim().is_empty())
+            .unwrap_or(database.name())
+            .to_string()
+    };
+    let host_ignored = |host: &Option<String>, warnings: &mut Vec<String>| {
+        if host.as_deref().is_some_and(|h| !h.trim().is_empty()) {
+            warnings.push("MongoDB users have no host; it is ignored".to_string());
+        }
+    };
+    Ok(match change {
+        UserChange::CreateUser { name, password, host, roles, auth_database: auth } => {
+            check_name(name, "User")?;
+            host_ignored(host, warnings);
+            let roles: Vec<Bson> = roles
+                .iter()
+                .filter(|r| !r.trim().is_empty())
+                .map(|r| Bson::Document(doc! { "role": r.trim(), "db": database.name() }))
+                .collect();
+            let mut command = doc! { "createUser": name.as_str() };
+            match password {
+                Some(password) => {
+                    command.insert("pwd", password.as_str());
+                }
+                None => warnings.push("Without a password the user can only authenticate externally, e.g. with x.509".to_string()),
+            }
+            command.insert("roles", roles);
+            vec![(auth_database(auth), command)]
+        }
+        UserChange::Grant { user, host, privileges, target, with_grant_option, auth_database: auth } => {
+            check_name(user, "User")?;
+            host_ignored(host, warnings);
+            if *with_grant_option {
+                warnings.push("MongoDB has no grant option; grant userAdmin to let a user manage roles".to_string());
+            }
+            let roles = mongodb_roles(privileges, target, database.name())?;
+            vec![(auth_database(auth), doc! { "grantRolesToUser": user.as_str(), "roles": roles })]
+        }
+        UserChange::Revoke { user, host, privileges, target, auth_database: auth } => {
+            check_name(user, "User")?;
+            host_ignored(host, warnings);
+            let roles = mongodb_roles(privileges, target, database.name())?;
+            vec![(auth_database(auth), doc! { "revokeRolesFromUser": user.as_str(), "roles": roles })]
+        }
+    })
+}
+
+fn mongodb_preview(database: &str, command: &Document) -> String {
+    let mut shown = command.clone();
+    if shown.contains_key("pwd") {
+        shown.insert("pwd", MASKED_PASSWORD);
+    }
+    format!("db.getSiblingDB({:?}).runCommand({})", database, shown)
+}
+
+// SQL statements with the real password, plus the password literal so it can be masked
+async fn sql_changes(
+    connection: &DatabaseConnection,
+    change: &UserChange,
+    warnings: &mut Vec<String>,
+) -> Result<(Vec<String>, Option<String>), AppError> {
+    let db_type = connection.db_type();
+    let postgres = matches!(db_type, DatabaseType::PostgreSQL);
+    let quote_role = |name: &str, host: &Option<String>| {
+        if postgres {
+            quote_identifier(name, '"')
+        } else {
+            mysql_account(name, host)
+        }
+    };
+    if postgres {
+        let host = match change {
+            UserChange::CreateUser { host, .. } | UserChange::Grant { host, .. } | UserChange::Revoke { host, .. } => host,
+        };
+        if host.as_deref().is_some_and(|h| !h.trim().is_empty()) {
+            warnings.push("PostgreSQL roles have no host; restrict clients in pg_hba.conf instead".to_string());
+        }
+    }
+
+    let mut statements = Vec::new();
+    let mut password_literal = None;
+    match change {
+        UserChange::CreateUser { name, password, host, roles, .. } => {
+            check_name(name, "User")?;
+            let literal = password.as_deref().map(|p| if postgres { sql_string(p) } else { mysql_string(p) });
+            let account = quote_role(name, host);
+            statements.push(match (&literal, postgres) {
+                (Some(literal), true) => format!("CREATE ROLE {} WITH LOGIN PASSWORD {};", account, literal),
+                (None, true) => format!("CREATE ROLE {} WITH LOGIN;", account),
+                (Some(literal), false) => format!("CREATE USER {} IDENTIFIED BY {};", account, literal),
+                (None, false) => format!("CREATE USER {};", account),
+            });
+            if literal.is_none() {
+                warnings.push(format!("{} is created without a password", name));
+            }
+            password_literal = literal;
+            for role in roles.iter().map(|r| r.trim()).filter(|r| !r.is_empty()) {
+                let role = if postgres { quote_identifier(role, '"') } else { mysql_account(role, &None) };
+                statements.push(format!("GRANT {} TO {};", role, account));
+            }
+        }
+        UserChange::Grant { user, host, privileges, target, with_grant_option, .. } => {
+            check_name(user, "User")?;
+            let object = if postgres { postgres_object(connection, target).await? } else { mysql_object(connection, target).await? };
+            statements.push(format!(
+                "GRANT {} ON {} TO {}{};",
+                sql_privileges(privileges)?,
+                object,
+                quote_role(user, host),
+                if *with_grant_option { " WITH GRANT OPTION" } else { "" }
+            ));
+        }
+        UserChange::Revoke { user, host, privileges, target, .. } => {
+            check_name(user, "User")?;
+            let object = if postgres { postgres_object(connection, target).await? } else { mysql_object(connection, target).await? };
+            statements.push(format!(
+                "REVOKE {} ON {} FROM {};",
+                sql_privileges(privileges)?,
+                object,
+                quote_role(user, host)
+            ));
+        }
+    }
+    Ok((statements, password_literal))
+}
+
+fn mask(statement: &str, password_literal: &Option<String>) -> String {
+    match password_literal {
+        Some(literal) => statement.replace(literal.as_str(), &format!("'{}'", MASKED_PASSWORD)),
+        None => statement.to_string(),
+    }
+}
+
+// Statements for the change with passwords masked
+pub async fn user_ddl(connection: &DatabaseConnection, change: &UserChange) -> Result<UserDdl, AppError> {
+    unsupported(connection)?;
+    let mut warnings = Vec::new();
+    let statements = match connection {
+        DatabaseConnection::MongoDB(_client, database) => mongodb_changes(database, change, &mut warnings)?
+            .iter()
+            .map(|(db, command)| mongodb_preview(db, command))
+            .collect(),
+        _ => {
+            let (statements, password) = sql_changes(connection, change, &mut warnings).await?;
+            statements.iter().map(|s| mask(s, &password)).collect()
+        }
+    };
+    Ok(UserDdl {
+        dialect: connection.db_type().as_str().to_string(),
+        statements,
+        warnings,
+    })
+}
+
+pub async fn apply_user_change(connection: &DatabaseConnection, change: &UserChange) -> Result<UserDdl, AppError> {
+    let ddl = user_ddl(connection, change).await?;
+    match connection {
+        DatabaseConnection::MongoDB(client, database) => {
+            for (db, command) in mongodb_changes(database, change, &mut Vec::new())? {
+                client.database(&db).run_command(command, None).await?;
+            }
+        }
+        _ => {
+            let (statements, password) = sql_changes(connection, change, &mut Vec::new()).await?;
+            // Failed statements are echoed in the error, so keep the password out of it
+            connection.execute_ddl(&statements, true).await.map_err(|e| match e {
+                AppError::QueryExecutionFailed(message) => AppError::QueryExecutionFailed(mask(&message, &password)),
+                other => other,
+            })?;
+        }
+    }
+    Ok(ddl)
+}
@@ -0,0 +1,291 @@
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use serde_json::Value;
+use sqlx::database::HasArguments;
+use sqlx::query::Query;
+use sqlx::{Database, Encode, Type};
+
+// Broad storage class of a column, derived from its declared data type
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueCategory {
+    Integer,
+    Float,
+    // Exact numerics, kept as text so no precision is lost on the way in
+    Decimal,
+    Boolean,
+    Date,
+    DateTime,
+    TimestampTz,
+    Json,
+    Text,
+}
+
+// A JSON value converted to the Rust type that matches its target column
+#[derive(Debug, Clone)]
+pub enum SqlValue {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Decimal(String),
+    Text(String),
+    Date(NaiveDate),
+    DateTime(NaiveDateTime),
+    TimestampTz(DateTime<Utc>),
+    Json(Value),
+}
+
+// Type names are matched word by word so interval, point and similar don't count as int
+const INTEGER_TYPES: &[&str] = &[
+    "int", "int2", "int4", "int8", "integer", "bigint", "smallint", "tinyint", "mediumint",
+    "serial", "serial2", "serial4", "serial8", "smallserial", "bigserial",
+    "hugeint", "uhugeint", "ubigint", "uinteger", "usmallint", "utinyint",
+];
+const FLOAT_TYPES: &[&str] = &["float", "float4", "float8", "double", "real"];
+const DECIMAL_TYPES: &[&str] = &["decimal", "numeric", "dec", "number"];
+
+pub fn categorize(data_type: &str) -> ValueCategory {
+    let data_type = data_type.to_lowercase();
+    // "int(11) unsigned" or "unsigned big int" -> ["int", "unsigned"] or ["unsigned", "big", "int"]
+    let base = data_type.split('(').next().unwrap_or_default();
+    let is_any = |types: &[&str]| base.split_whitespace().any(|word| types.contains(&word));
+    // DuckDB nested types, e.g. integer[], struct(a integer) or map(varchar, integer)
+    if data_type.ends_with(']') || ["struct(", "map(", "union("].iter().any(|t| data_type.starts_with(t)) {
+        ValueCategory::Json
+    // SQL Server has no boolean type; bit stands in for it
+    } else if data_type.contains("bool") || data_type == "bit" {
+        ValueCategory::Boolean
+    } else if is_any(INTEGER_TYPES) {
+        ValueCategory::Integer
+    } else if is_any(FLOAT_TYPES) {
+        ValueCategory::Float
+    } else if is_any(DECIMAL_TYPES) {
+        ValueCategory::Decimal
+    } else if data_type.contains("with time zone") || data_type == "timestamptz" || data_type == "datetimeoffset" {
+        ValueCategory::TimestampTz
+    } else if data_type.contains("timestamp") || data_type.contains("datetime") {
+        ValueCategory::DateTime
+    } else if data_type == "date" {
+        ValueCategory::Date
+    } else if data_type.contains("json") {
+        ValueCategory::Json
+    } else {
+        ValueCategory::Text
+    }
+}
+
+pub fn parse_datetime(text: &str) -> Option<NaiveDateTime> {
+    if let Ok(value) = DateTime::parse_from_rfc3339(text) {
+        return Some(value.naive_utc());
+    }
+    ["%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(text, format).ok())
+}
+
+pub fn parse_date(text: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(text, "%Y-%m-%d").ok()
+}
+
+pub fn parse_bool(text: &str) -> Option<bool> {
+    match text.to_lowercase().as_str() {
+        "true" | "t" | "yes" | "y" | "1" => Some(true),
+        "false" | "f" | "no" | "n" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+fn as_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+// Values that don't fit the category are passed through as text and left for
+// the database to accept or reject
+pub fn coerce(value: &Value, category: ValueCategory) -> SqlValue {
+    if value.is_null() {
+        return SqlValue::Null;
+    }
+
+    let text = as_text(value);
+    let trimmed = text.trim();
+
+    match category {
+        ValueCategory::Integer => match value {
+            Value::Number(n) if n.is_i64() => SqlValue::Int(n.as_i64().unwrap_or_default()),
+            Value::Number(n) => SqlValue::Float(n.as_f64().unwrap_or_default()),
+            Value::Bool(b) => SqlValue::Int(*b as i64),
+            _ => trimmed
+                .parse::<i64>()
+                .map(SqlValue::Int)
+                .or_else(|_| trimmed.parse::<f64>().map(SqlValue::Float))
+                .unwrap_or(SqlValue::Text(text)),
+        },
+        ValueCategory::Float => match value {
+            Value::Number(n) => SqlValue::Float(n.as_f64().unwrap_or_default()),
+            _ => trimmed
+                .parse::<f64>()
+                .map(SqlValue::Float)
+                .unwrap_or(SqlValue::Text(text)),
+        },
+        ValueCategory::Decimal => match value {
+            Value::Number(n) => SqlValue::Decimal(n.to_string()),
+            Value::Bool(b) => SqlValue::Decimal((*b as i64).to_string()),
+            _ if trimmed.parse::<f64>().is_ok() => SqlValue::Decimal(trimmed.to_string()),
+            _ => SqlValue::Text(text),
+        },
+        ValueCategory::Boolean => match value {
+            Value::Bool(b) => SqlValue::Bool(*b),
+            Value::Number(n) => SqlValue::Bool(n.as_f64().unwrap_or_default() != 0.0),
+            _ => parse_bool(trimmed).map(SqlValue::Bool).unwrap_or(SqlValue::Text(text)),
+        },
+        ValueCategory::Date => parse_date(trimmed)
+            .or_else(|| parse_datetime(trimmed).map(|dt| dt.date()))
+            .map(SqlValue::Date)
+            .unwrap_or(SqlValue::Text(text)),
+        ValueCategory::DateTime => parse_datetime(trimmed)
+            .or_else(|| parse_date(trimmed).and_then(|d| d.and_hms_opt(0, 0, 0)))
+            .map(SqlValue::DateTime)
+            .unwrap_or(SqlValue::Text(text)),
+        ValueCategory::TimestampTz => parse_datetime(trimmed)
+            .map(|dt| SqlValue::TimestampTz(dt.and_utc()))
+            .unwrap_or(SqlValue::Text(text)),
+        ValueCategory::Json => match value {
+            Value::String(s) => SqlValue::Json(serde_json::from_str(s).unwrap_or_else(|_| value.clone())),
+            other => SqlValue::Json(other.clone()),
+        },
+        ValueCategory::Text => SqlValue::Text(text),
+    }
+}
+
+pub fn bind_value<'q, DB>(
+    query: Query<'q, DB, <DB as HasArguments<'q>>::Arguments>,
+    value: SqlValue,
+) -> Query<'q, DB, <DB as HasArguments<'q>>::Arguments>
+where
+    DB: Database,
+    Option<String>: Encode<'q, DB> + Type<DB>,
+    bool: Encode<'q, DB> + Type<DB>,
+    i64: Encode<'q, DB> + Type<DB>,
+    f64: Encode<'q, DB> + Type<DB>,
+    String: Encode<'q, DB> + Type<DB>,
+    NaiveDate: Encode<'q, DB> + Type<DB>,
+    NaiveDateTime: Encode<'q, DB> + Type<DB>,
+    DateTime<Utc>: Encode<'q, DB> + Type<DB>,
+    Value: Encode<'q, DB> + Type<DB>,
+{
+    match value {
+        SqlValue::Null => query.bind(None::<String>),
+        SqlValue::Bool(v) => query.bind(v),
+        SqlValue::Int(v) => query.bind(v),
+        SqlValue::Float(v) => query.bind(v),
+        SqlValue::Decimal(v) | SqlValue::Text(v) => query.bind(v),
+        SqlValue::Date(v) => query.bind(v),
+        SqlValue::DateTime(v) => query.bind(v),
+        SqlValue::TimestampTz(v) => query.bind(v),
+        SqlValue::Json(v) => query.bind(v),
+    }
+}
+
+// tiberius binds parameters on its own Query type rather than through sqlx
+pub fn bind_mssql(query: &mut tiberius::Query<'_>, value: SqlValue) {
+    match value {
+        SqlValue::Null => query.bind(None::<String>),
+        SqlValue::Bool(v) => query.bind(v),
+        SqlValue::Int(v) => query.bind(v),
+        SqlValue::Float(v) => query.bind(v),
+        SqlValue::Decimal(v) | SqlValue::Text(v) => query.bind(v),
+        SqlValue::Date(v) => query.bind(v),
+        SqlValue::DateTime(v) => query.bind(v),
+        SqlValue::TimestampTz(v) => query.bind(v),
+        SqlValue::Json(v) => query.bind(v.to_string()),
+    }
+}
+
+pub fn to_duckdb(value: SqlValue) -> duckdb::types::Value {
+    use duckdb::types::{TimeUnit, Value as V};
+    match value {
+        SqlValue::Null => V::Null,
+        SqlValue::Bool(v) => V::Boolean(v),
+        SqlValue::Int(v) => V::BigInt(v),
+        SqlValue::Float(v) => V::Double(v),
+        // Inserts cast text to the column's DECIMAL type
+        SqlValue::Decimal(v) | SqlValue::Text(v) => V::Text(v),
+        // NaiveDate::default() is the Unix epoch
+        SqlValue::Date(v) => V::Date32(v.signed_duration_since(NaiveDate::default()).num_days() as i32),
+        SqlValue::DateTime(v) => V::Timestamp(TimeUnit::Microsecond, v.and_utc().timestamp_micros()),
+        SqlValue::TimestampTz(v) => V::Timestamp(TimeUnit::Microsecond, v.timestamp_micros()),
+        // DuckDB casts JSON text to LIST, STRUCT and MAP columns
+        SqlValue::Json(v) => V::Text(v.to_string()),
+    }
+}
+
+pub fn to_bson(value: SqlValue) -> bson::Bson {
+    match value {
+        SqlValue::Null => bson::Bson::Null,
+        SqlValue::Bool(v) => bson::Bson::Boolean(v),
+        SqlValue::Int(v) => bson::Bson::Int64(v),
+        SqlValue::Float(v) => bson::Bson::Double(v),
+        SqlValue::Decimal(v) => v.parse().map(bson::Bson::Decimal128).unwrap_or(bson::Bson::String(v)),
+        SqlValue::Text(v) => bson::Bson::String(v),
+        SqlValue::Date(v) => bson::Bson::DateTime(bson::DateTime::from_millis(
+            v.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc().timestamp_millis(),
+        )),
+        SqlValue::DateTime(v) => bson::Bson::DateTime(bson::DateTime::from_millis(v.and_utc().timestamp_millis())),
+        SqlValue::TimestampTz(v) => bson::Bson::DateTime(bson::DateTime::from_millis(v.timestamp_millis())),
+        SqlValue::Json(v) => bson::to_bson(&v).unwrap_or(bson::Bson::Null),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn categories_match_type_names_word_by_word() {
+        assert_eq!(categorize("int(11) unsigned"), ValueCategory::Integer);
+        assert_eq!(categorize("UNSIGNED BIG INT"), ValueCategory::Integer);
+        assert_eq!(categorize("bigserial"), ValueCategory::Integer);
+        assert_eq!(categorize("interval"), ValueCategory::Text);
+        assert_eq!(categorize("point"), ValueCategory::Text);
+        assert_eq!(categorize("double precision"), ValueCategory::Float);
+        assert_eq!(categorize("numeric(20,6)"), ValueCategory::Decimal);
+        assert_eq!(categorize("bit"), ValueCategory::Boolean);
+        assert_eq!(categorize("timestamp with time zone"), ValueCategory::TimestampTz);
+        assert_eq!(categorize("datetime2"), ValueCategory::DateTime);
+        assert_eq!(categorize("integer[]"), ValueCategory::Json);
+        assert_eq!(categorize("struct(a integer)"), ValueCategory::Json);
+        assert_eq!(categorize("jsonb"), ValueCategory::Json);
+    }
+
+    #[test]
+    fn integers_keep_full_precision() {
+        assert!(matches!(coerce(&json!("9007199254740993"), ValueCategory::Integer), SqlValue::Int(9007199254740993)));
+        assert!(matches!(coerce(&json!(true), ValueCategory::Integer), SqlValue::Int(1)));
+        assert!(matches!(coerce(&json!(" 42 "), ValueCategory::Integer), SqlValue::Int(42)));
+        assert!(matches!(coerce(&json!("abc"), ValueCategory::Integer), SqlValue::Text(t) if t == "abc"));
+    }
+
+    #[test]
+    fn decimals_stay_as_text() {
+        let SqlValue::Decimal(text) = coerce(&json!("12345678901234567890.123456789"), ValueCategory::Decimal) else {
+            panic!("expected a decimal");
+        };
+        assert_eq!(text, "12345678901234567890.123456789");
+        assert!(matches!(coerce(&json!(1.5), ValueCategory::Decimal), SqlValue::Decimal(t) if t == "1.5"));
+        assert!(matches!(coerce(&json!("n/a"), ValueCategory::Decimal), SqlValue::Text(_)));
+    }
+
+    #[test]
+    fn booleans_dates_and_json_are_parsed() {
+        assert!(matches!(coerce(&json!("Yes"), ValueCategory::Boolean), SqlValue::Bool(true)));
+        assert!(matches!(coerce(&json!(0), ValueCategory::Boolean), SqlValue::Bool(false)));
+        assert!(matches!(coerce(&json!("2024-02-29"), ValueCategory::DateTime), SqlValue::DateTime(_)));
+        assert!(matches!(coerce(&json!("2024-02-29T10:00:00Z"), ValueCategory::Date), SqlValue::Date(_)));
+        assert!(matches!(coerce(&json!("2024-02-30"), ValueCategory::Date), SqlValue::Text(_)));
+        assert!(matches!(coerce(&json!("{\"a\": 1}"), ValueCategory::Json), SqlValue::Json(v) if v["a"] == 1));
+        assert!(matches!(coerce(&Value::Null, ValueCategory::Integer), SqlValue::Null));
+    }
+}
@@ -58,7 +58,9 @@ export function DatabaseConfigForm({ onConnect }: DatabaseConfigFormProps) {
 
     if (
       config.db_type !== "SQLite" &&
+      config.db_type !== "DuckDB" &&
       config.db_type !== "MongoDB" &&
+      config.db_type !== "Redis" &&
       !config.username
     ) {
       setError("Username is required for this database type");
@@ -116,6 +118,12 @@ export function DatabaseConfigForm({ onConnect }: DatabaseConfigFormProps) {
         return 0;
       case "MongoDB":
         return 27017;
+      case "SQLServer":
+        return 1433;
+      case "Redis":
+        return 6379;
+      case "DuckDB":
+        return 0;
       default:
         return 3306;
     }
@@ -133,7 +141,7 @@ export function DatabaseConfigForm({ onConnect }: DatabaseConfigFormProps) {
   };
 
   const loadAvailableDatabases = async () => {
-    if (config.db_type === "SQLite") return; // SQLite doesn't have multiple databases
+    if (config.db_type === "SQLite" || config.db_type === "DuckDB") return; // Embedded databases are single files
 
     setIsLoadingDatabases(true);
     setError(null);
@@ -182,6 +190,9 @@ export function DatabaseConfigForm({ onConnect }: DatabaseConfigFormProps) {
               <option value="PostgreSQL">PostgreSQL</option>
               <option value="SQLite">SQLite</option>
               <option value="MongoDB">MongoDB</option>
+              <option value="SQLServer">SQL Server</option>
+              <option value="Redis">Redis</option>
+              <option value="DuckDB">DuckDB</option>
             </Select>
           </div>
           <div>
@@ -192,13 +203,13 @@ export function DatabaseConfigForm({ onConnect }: DatabaseConfigFormProps) {
               onChange={(e) =>
                 handleInputChange("port", parseInt(e.target.value) || 0)
               }
-              disabled={isConnected || config.db_type === "SQLite"}
+              disabled={isConnected || config.db_type === "SQLite" || config.db_type === "DuckDB"}
               placeholder="3306"
             />
           </div>
         </div>
 
-        {config.db_type !== "SQLite" && config.db_type !== "MongoDB" && (
+        {config.db_type !== "SQLite" && config.db_type !== "DuckDB" && config.db_type !== "MongoDB" && (
           <>
             <div>
               <label className="text-sm font-medium">Host</label>
@@ -250,7 +261,7 @@ export function DatabaseConfigForm({ onConnect }: DatabaseConfigFormProps) {
               placeholder="database_name (leave empty to connect without specific database)"
               className="flex-1"
             />
-            {config.db_type !== "SQLite" && (
+            {config.db_type !== "SQLite" && config.db_type !== "DuckDB" && (
               <Button
                 type="button"
                 onClick={loadAvailableDatabases}
@@ -7,7 +7,7 @@ export interface DatabaseConfig {
   db_type: DatabaseType;
 }
 
-export type DatabaseType = "MySQL" | "PostgreSQL" | "SQLite" | "MongoDB";
+export type DatabaseType = "MySQL" | "PostgreSQL" | "SQLite" | "MongoDB" | "SQLServer" | "Redis" | "DuckDB";
 
 export interface DatabaseSchema {
   tables: TableInfo[];